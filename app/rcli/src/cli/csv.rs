@@ -1,31 +1,352 @@
-use crate::CmdExector;
+use crate::{CmdExector, CsvDialect, SortOpts};
 
 use super::verify_file;
-use clap::Parser;
-use std::{fmt, str::FromStr};
+use clap::{ArgAction, Args, Parser};
+use encoding_rs::Encoding;
+use enum_dispatch::enum_dispatch;
+use std::{fmt, path::Path, str::FromStr};
+
+// `rcli csv -i input.csv` converts CSV as it always did, the subcommands do the rest
+#[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct CsvCommand {
+    #[command(subcommand)]
+    pub cmd: Option<CsvSubCommand>,
+
+    // not an Option: clap can't tell whether flattened args holding a flatten were given
+    #[command(flatten)]
+    pub convert: CsvOpts,
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExector)]
+pub enum CsvSubCommand {
+    #[command(name = "from", about = "Convert JSON, NDJSON or YAML records to CSV")]
+    From(CsvFromOpts),
+    #[command(name = "query", about = "Run SQL over CSV files, each file is a table")]
+    Query(CsvQueryOpts),
+    #[command(
+        name = "stats",
+        about = "Show the type, nulls, range and cardinality of each column"
+    )]
+    Stats(CsvStatsOpts),
+    #[command(name = "head", about = "Show the first records")]
+    Head(CsvHeadOpts),
+    #[command(name = "tail", about = "Show the last records")]
+    Tail(CsvTailOpts),
+    #[command(name = "select", about = "Keep some columns, by name")]
+    Select(CsvSelectOpts),
+    #[command(
+        name = "sort",
+        about = "Sort by some columns, files larger than memory included"
+    )]
+    Sort(CsvSortOpts),
+    #[command(name = "dedupe", about = "Drop duplicate records")]
+    Dedupe(CsvDedupeOpts),
+    #[command(name = "validate", about = "Check records against a JSON Schema")]
+    Validate(CsvValidateOpts),
+}
 
 #[derive(Debug, Clone, Copy)]
 pub enum OutputFormat {
     Json,
     Yaml,
+    Toml,
+    Ndjson,
+    MsgPack,
+    Parquet,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum InputFormat {
+    Json,
+    Ndjson,
+    Yaml,
 }
 
 #[derive(Debug, Parser)]
 pub struct CsvOpts {
-    #[arg(short, long, value_parser = verify_file)]
-    pub input: String,
+    // only ever None with a subcommand
+    #[arg(
+        short,
+        long,
+        required = true,
+        value_parser = verify_file,
+        help = "Input file, - for stdin"
+    )]
+    pub input: Option<String>,
 
-    #[arg(short, long)] // "output.json".into()
+    #[arg(
+        short,
+        long,
+        help = "Output file, - for stdout [default: output.<format>]"
+    )]
     pub output: Option<String>,
 
-    #[arg(long, value_parser = parse_format, default_value = "json")]
+    #[arg(
+        long,
+        value_parser = parse_format,
+        default_value = "json",
+        help = "Output format: json, yaml, toml, ndjson, msgpack or parquet"
+    )]
     pub format: OutputFormat,
 
-    #[arg(short, long, default_value_t = ',')]
-    pub delimiter: char,
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+}
+
+// how the input CSV is written, shared by the subcommands reading CSV
+#[derive(Debug, Args)]
+pub struct CsvReaderOpts {
+    #[arg(short, long, value_parser = parse_char, default_value = ",")]
+    pub delimiter: u8,
 
-    #[arg(long, default_value_t = true)]
+    // `--header` alone, or `--header false` for a file without a header row
+    #[arg(
+        long,
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        default_value_t = true,
+        default_missing_value = "true"
+    )]
     pub header: bool,
+
+    #[arg(long, value_parser = parse_char, default_value = "\"")]
+    pub quote: u8,
+
+    #[arg(long, value_parser = parse_char, help = "Escape character for quotes, e.g. \\")]
+    pub escape: Option<u8>,
+
+    #[arg(
+        long,
+        value_parser = parse_char,
+        help = "Lines starting with this character are skipped"
+    )]
+    pub comment: Option<u8>,
+
+    #[arg(
+        long,
+        value_parser = parse_encoding,
+        help = "Input encoding, e.g. latin1 or gbk [default: utf-8]"
+    )]
+    pub encoding: Option<&'static Encoding>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvFromOpts {
+    #[arg(short, long, value_parser = verify_file, help = "Input file, - for stdin")]
+    pub input: String,
+
+    #[arg(
+        short,
+        long,
+        default_value = "output.csv",
+        help = "Output file, - for stdout"
+    )]
+    pub output: String,
+
+    #[arg(
+        long,
+        value_parser = parse_input_format,
+        help = "Input format: json, ndjson or yaml [default: from the file extension]"
+    )]
+    pub format: Option<InputFormat>,
+
+    #[arg(short, long, value_parser = parse_char, default_value = ",")]
+    pub delimiter: u8,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvQueryOpts {
+    #[arg(help = "The SQL to run, e.g. SELECT * FROM players WHERE kit > 10")]
+    pub sql: String,
+
+    // a table is named after its file, players.csv is players, unless given as name=path
+    #[arg(
+        short = 'i',
+        long = "input",
+        value_parser = parse_table,
+        required = true,
+        help = "Input file, [name=]path, - for stdin; repeat for more tables"
+    )]
+    pub tables: Vec<(String, String)>,
+
+    #[arg(short, long, default_value = "-", help = "Output file, - for stdout")]
+    pub output: String,
+
+    #[arg(
+        long,
+        value_parser = parse_format,
+        default_value = "json",
+        help = "Output format: json, yaml, toml, ndjson, msgpack or parquet"
+    )]
+    pub format: OutputFormat,
+
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvStatsOpts {
+    #[arg(short, long, value_parser = verify_file, help = "Input file, - for stdin")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-", help = "Output file, - for stdout")]
+    pub output: String,
+
+    #[arg(
+        long,
+        value_parser = parse_format,
+        default_value = "json",
+        help = "Output format: json, yaml, toml, ndjson, msgpack or parquet"
+    )]
+    pub format: OutputFormat,
+
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvHeadOpts {
+    #[arg(short, long, value_parser = verify_file, help = "Input file, - for stdin")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-", help = "Output file, - for stdout")]
+    pub output: String,
+
+    #[arg(short = 'n', long, default_value_t = 10)]
+    pub lines: usize,
+
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvTailOpts {
+    #[arg(short, long, value_parser = verify_file, help = "Input file, - for stdin")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-", help = "Output file, - for stdout")]
+    pub output: String,
+
+    #[arg(short = 'n', long, default_value_t = 10)]
+    pub lines: usize,
+
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvSelectOpts {
+    #[arg(short, long, value_parser = verify_file, help = "Input file, - for stdin")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-", help = "Output file, - for stdout")]
+    pub output: String,
+
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        required = true,
+        help = "Columns to keep, e.g. name,kit"
+    )]
+    pub columns: Vec<String>,
+
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvSortOpts {
+    #[arg(short, long, value_parser = verify_file, help = "Input file, - for stdin")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-", help = "Output file, - for stdout")]
+    pub output: String,
+
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        help = "Columns to sort by [default: all of them]"
+    )]
+    pub columns: Vec<String>,
+
+    #[arg(short, long, help = "Sort in descending order")]
+    pub reverse: bool,
+
+    #[arg(
+        long,
+        default_value_t = 100_000,
+        help = "Records sorted in memory before spilling to a temp file"
+    )]
+    pub buffer_rows: usize,
+
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvDedupeOpts {
+    #[arg(short, long, value_parser = verify_file, help = "Input file, - for stdin")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-", help = "Output file, - for stdout")]
+    pub output: String,
+
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        help = "Columns making a record unique [default: all of them]"
+    )]
+    pub columns: Vec<String>,
+
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvValidateOpts {
+    #[arg(short, long, value_parser = verify_file, help = "Input file, - for stdin")]
+    pub input: String,
+
+    #[arg(short, long, value_parser = verify_file, help = "JSON Schema of a record")]
+    pub schema: String,
+
+    #[arg(
+        short,
+        long,
+        default_value = "-",
+        help = "Output file for the errors, - for stdout"
+    )]
+    pub output: String,
+
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+}
+
+impl CsvReaderOpts {
+    pub fn dialect(&self) -> CsvDialect {
+        CsvDialect {
+            delimiter: self.delimiter,
+            header: self.header,
+            quote: self.quote,
+            escape: self.escape,
+            comment: self.comment,
+            encoding: self.encoding,
+        }
+    }
+}
+
+impl CmdExector for CsvCommand {
+    async fn execute(self) -> anyhow::Result<()> {
+        match self.cmd {
+            Some(cmd) => cmd.execute().await,
+            None => self.convert.execute().await,
+        }
+    }
 }
 
 impl CmdExector for CsvOpts {
@@ -35,10 +356,132 @@ impl CmdExector for CsvOpts {
         } else {
             format!("output.{}", self.format)
         };
-        crate::process_csv(&self.input, output, self.format)
+        let input = self
+            .input
+            .expect("clap requires the input without a subcommand");
+        crate::process_csv(&input, &self.reader.dialect(), &output, self.format)
+    }
+}
+
+impl CmdExector for CsvFromOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let format = match self.format {
+            Some(format) => format,
+            None => InputFormat::from_path(&self.input)?,
+        };
+        crate::process_csv_from(&self.input, format, &self.output, self.delimiter)
+    }
+}
+
+impl CmdExector for CsvQueryOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_csv_query(
+            &self.sql,
+            &self.tables,
+            &self.reader.dialect(),
+            &self.output,
+            self.format,
+        )
+    }
+}
+
+impl CmdExector for CsvStatsOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let dialect = self.reader.dialect();
+        crate::process_csv_stats(&self.input, &dialect, &self.output, self.format)
+    }
+}
+
+impl CmdExector for CsvHeadOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let dialect = self.reader.dialect();
+        crate::process_csv_head(&self.input, &dialect, &self.output, self.lines)
+    }
+}
+
+impl CmdExector for CsvTailOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let dialect = self.reader.dialect();
+        crate::process_csv_tail(&self.input, &dialect, &self.output, self.lines)
+    }
+}
+
+impl CmdExector for CsvSelectOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let dialect = self.reader.dialect();
+        crate::process_csv_select(&self.input, &dialect, &self.output, &self.columns)
+    }
+}
+
+impl CmdExector for CsvSortOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let opts = SortOpts {
+            columns: &self.columns,
+            reverse: self.reverse,
+            buffer_rows: self.buffer_rows,
+        };
+        let dialect = self.reader.dialect();
+        crate::process_csv_sort(&self.input, &dialect, &self.output, &opts)
+    }
+}
+
+impl CmdExector for CsvDedupeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let dialect = self.reader.dialect();
+        crate::process_csv_dedupe(&self.input, &dialect, &self.output, &self.columns)
+    }
+}
+
+impl CmdExector for CsvValidateOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let dialect = self.reader.dialect();
+        crate::process_csv_validate(&self.input, &dialect, &self.schema, &self.output)
+    }
+}
+
+// name=path, or a path alone for a table named after the file stem
+fn parse_table(s: &str) -> Result<(String, String), &'static str> {
+    let (name, path) = match s.split_once('=') {
+        Some((name, path)) if !Path::new(s).exists() => (name.to_string(), path),
+        _ => (table_name(s), s),
+    };
+    if name.is_empty() {
+        return Err("Table name is empty");
+    }
+    Ok((name, verify_file(path)?))
+}
+
+// players.csv is players, and my-players.2024.csv my_players_2024
+fn table_name(path: &str) -> String {
+    if path == "-" {
+        return "stdin".to_string();
+    }
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    stem.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+// a single ASCII character, or \t for a tab
+fn parse_char(s: &str) -> Result<u8, &'static str> {
+    match s.as_bytes() {
+        b"\\t" => Ok(b'\t'),
+        [c] if c.is_ascii() => Ok(*c),
+        _ => Err("Must be a single ASCII character"),
     }
 }
 
+fn parse_encoding(label: &str) -> Result<&'static Encoding, &'static str> {
+    Encoding::for_label(label.as_bytes()).ok_or("Unknown encoding")
+}
+
+fn parse_input_format(format: &str) -> Result<InputFormat, anyhow::Error> {
+    format.parse()
+}
+
 fn parse_format(format: &str) -> Result<OutputFormat, anyhow::Error> {
     format.parse()
 }
@@ -48,6 +491,10 @@ impl From<OutputFormat> for &'static str {
         match format {
             OutputFormat::Json => "json",
             OutputFormat::Yaml => "yaml",
+            OutputFormat::Toml => "toml",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::MsgPack => "msgpack",
+            OutputFormat::Parquet => "parquet",
         }
     }
 }
@@ -59,6 +506,10 @@ impl FromStr for OutputFormat {
         match s {
             "json" => Ok(OutputFormat::Json),
             "yaml" => Ok(OutputFormat::Yaml),
+            "toml" => Ok(OutputFormat::Toml),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "msgpack" => Ok(OutputFormat::MsgPack),
+            "parquet" => Ok(OutputFormat::Parquet),
             _ => Err(anyhow::anyhow!("Invalid format")),
         }
     }
@@ -69,3 +520,31 @@ impl fmt::Display for OutputFormat {
         write!(f, "{}", Into::<&str>::into(*self))
     }
 }
+
+impl InputFormat {
+    fn from_path(path: &str) -> anyhow::Result<Self> {
+        let ext = Path::new(path).extension().and_then(|ext| ext.to_str());
+        match ext {
+            Some("jsonl") => Ok(InputFormat::Ndjson),
+            Some("yml") => Ok(InputFormat::Yaml),
+            Some(ext) => ext.parse(),
+            None => Err(anyhow::anyhow!(
+                "Can't tell the format of {}, use --format",
+                path
+            )),
+        }
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(InputFormat::Json),
+            "ndjson" => Ok(InputFormat::Ndjson),
+            "yaml" => Ok(InputFormat::Yaml),
+            _ => Err(anyhow::anyhow!("Invalid format")),
+        }
+    }
+}
@@ -1,7 +1,10 @@
-use crate::CmdExector;
+use crate::{CmdExector, PasswordKind};
 use clap::Parser;
+use serde_json::json;
 use zxcvbn::zxcvbn;
 
+use super::verify_file;
+
 #[derive(Debug, Parser)]
 pub struct GenPassOpts {
     #[arg(short, long, default_value_t = 16)]
@@ -18,22 +21,91 @@ pub struct GenPassOpts {
 
     #[arg(long, default_value_t = true)]
     pub symbol: bool,
+
+    #[arg(
+        long,
+        help = "Leave out characters easily mistaken for others: I, O, l and 0"
+    )]
+    pub exclude_ambiguous: bool,
+
+    #[arg(
+        short,
+        long,
+        conflicts_with = "words",
+        help = "A template, e.g. Cvccvc99!: C/c consonant, V/v vowel, A/a letter, 9 digit, ! symbol, * any, \\x x itself"
+    )]
+    pub pattern: Option<String>,
+
+    #[arg(short, long, help = "Generate a passphrase of this many words instead")]
+    pub words: Option<usize>,
+
+    #[arg(long, default_value = "-", requires = "words")]
+    pub separator: String,
+
+    #[arg(
+        long,
+        value_parser = verify_file,
+        requires = "words",
+        help = "Word list, one word per line as in the EFF lists [default: the EFF large list]"
+    )]
+    pub wordlist: Option<String>,
+
+    #[arg(short = 'n', long, default_value_t = 1, help = "How many to generate")]
+    pub count: usize,
+
+    #[arg(
+        long,
+        help = "Print the passwords with their strength and entropy as JSON"
+    )]
+    pub json: bool,
 }
 
 impl CmdExector for GenPassOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let ret = crate::process_genpass(
-            self.length,
-            self.uppercase,
-            self.lowercase,
-            self.number,
-            self.symbol,
-        )?;
-        println!("{}", ret);
+        let wordlist = match &self.wordlist {
+            Some(path) => Some(std::fs::read_to_string(path)?),
+            None => None,
+        };
+        let kind = match (&self.pattern, self.words) {
+            (Some(pattern), _) => PasswordKind::Pattern(pattern),
+            (None, Some(words)) => PasswordKind::Passphrase {
+                words,
+                separator: &self.separator,
+                wordlist: wordlist.as_deref(),
+            },
+            (None, None) => PasswordKind::Chars {
+                length: self.length,
+                upper: self.uppercase,
+                lower: self.lowercase,
+                number: self.number,
+                symbol: self.symbol,
+            },
+        };
+        let passwords = crate::process_genpass(&kind, self.exclude_ambiguous, self.count)?;
+
+        if self.json {
+            let mut reports = Vec::with_capacity(passwords.len());
+            for password in passwords {
+                let estimate = zxcvbn(&password.password, &[])?;
+                reports.push(json!({
+                    "password": password.password,
+                    "entropy": (password.entropy * 100.0).round() / 100.0,
+                    "score": estimate.score(),
+                }));
+            }
+            println!("{}", serde_json::to_string_pretty(&reports)?);
+            return Ok(());
+        }
 
+        for password in &passwords {
+            println!("{}", password.password);
+        }
         // output password strength in stderr
-        let estimate = zxcvbn(&ret, &[])?;
-        eprintln!("Password strength: {}", estimate.score());
+        if let [password] = passwords.as_slice() {
+            let estimate = zxcvbn(&password.password, &[])?;
+            eprintln!("Password strength: {}", estimate.score());
+            eprintln!("Entropy: {:.1} bits", password.entropy);
+        }
         Ok(())
     }
 }
@@ -1,44 +1,234 @@
-use anyhow::Result;
-use csv::Reader;
-use serde::{Deserialize, Serialize};
-use std::fs;
-
-use crate::cli::OutputFormat;
-
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "PascalCase")]
-struct Player {
-    name: String,
-    position: String,
-    #[serde(rename = "DOB")]
-    dob: String,
-    nationality: String,
-    #[serde(rename = "Kit Number")]
-    kit: u8,
+use anyhow::{anyhow, Result};
+use csv::{Reader, ReaderBuilder, StringRecord, Writer, WriterBuilder};
+use encoding_rs::Encoding;
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use std::io::{self, BufWriter, Read, Write};
+use tempfile::NamedTempFile;
+
+use super::csv_schema::{Schema, INFER_ROWS};
+use super::csv_writer::record_writer;
+use crate::{cli::OutputFormat, get_reader, get_writer};
+
+// how the input CSV is written
+#[derive(Debug, Clone)]
+pub struct CsvDialect {
+    pub delimiter: u8,
+    // without a header row the columns are named column1, column2...
+    pub header: bool,
+    pub quote: u8,
+    pub escape: Option<u8>,
+    pub comment: Option<u8>,
+    // None is UTF-8; a byte order mark, if any, wins over the encoding
+    pub encoding: Option<&'static Encoding>,
 }
 
-pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<()> {
-    let mut reader = Reader::from_path(input)?;
-    let mut ret = Vec::with_capacity(128);
-    let headers = reader.headers()?.clone();
-    for result in reader.records() {
-        let record = result?;
-        // headers.iter() -> 使用 headers 的迭代器
-        // record.iter() -> 使用 record 的迭代器
-        // zip() -> 将两个迭代器合并为一个元组的迭代器 [(header, record), ..]
-        // collect::<Value>() -> 将元组的迭代器转换为 JSON Value
-        let json_value = headers
-            .iter()
-            .zip(record.iter())
-            .collect::<serde_json::Value>();
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            header: true,
+            quote: b'"',
+            escape: None,
+            comment: None,
+            encoding: None,
+        }
+    }
+}
 
-        ret.push(json_value);
+impl CsvDialect {
+    // a reader of `input`, a file or "-" for stdin, decoded to UTF-8
+    pub fn reader(&self, input: &str) -> Result<Reader<Box<dyn Read>>> {
+        let decoded = DecodeReaderBytesBuilder::new()
+            .encoding(self.encoding)
+            .build(get_reader(input)?);
+        let reader = ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(self.header)
+            .quote(self.quote)
+            .escape(self.escape)
+            .comment(self.comment)
+            .from_reader(Box::new(decoded) as Box<dyn Read>);
+        Ok(reader)
     }
 
-    let content = match format {
-        OutputFormat::Json => serde_json::to_string_pretty(&ret)?,
-        OutputFormat::Yaml => serde_yaml::to_string(&ret)?,
+    // a writer of CSV in the same dialect, to a file or "-" for stdout, always in UTF-8
+    pub fn writer(&self, output: &str) -> Result<Writer<Box<dyn Write + Send>>> {
+        let writer = WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .from_writer(get_writer(output)?);
+        Ok(writer)
+    }
+
+    // the column names, read from the header or made up from the first record
+    pub fn headers<R: Read>(&self, reader: &mut Reader<R>) -> Result<StringRecord> {
+        if self.header {
+            return Ok(reader.headers()?.clone());
+        }
+        let len = reader.headers()?.len();
+        Ok((1..=len).map(|i| format!("column{}", i)).collect())
+    }
+}
+
+// records are streamed to the output: only the first INFER_ROWS are buffered, to infer
+// the type of each column
+pub fn process_csv(
+    input: &str,
+    dialect: &CsvDialect,
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    if let OutputFormat::Parquet = format {
+        return process_csv_parquet(input, dialect, output);
+    }
+    let mut reader = dialect.reader(input)?;
+    let headers = dialect.headers(&mut reader)?;
+    let mut records = reader.records();
+
+    let sample = records
+        .by_ref()
+        .take(INFER_ROWS)
+        .collect::<Result<Vec<StringRecord>, _>>()?;
+    let schema = Schema::infer(&headers, &sample);
+
+    let output = BufWriter::new(get_writer(output)?);
+    let mut writer = record_writer(format, schema.clone(), output)?;
+    for record in sample {
+        writer.write(&schema.parse(&record))?;
+    }
+    for record in records {
+        writer.write(&schema.parse(&record?))?;
+    }
+    writer.finish()
+}
+
+// the types of parquet columns are fixed once the file is started, so a value that doesn't
+// fit the types inferred from the first records can't be written: they are inferred from
+// every record instead, reading the input twice, stdin being copied to a temp file first
+fn process_csv_parquet(input: &str, dialect: &CsvDialect, output: &str) -> Result<()> {
+    let stdin = if input == "-" {
+        let mut file = NamedTempFile::new()?;
+        io::copy(&mut get_reader(input)?, &mut file)?;
+        Some(file)
+    } else {
+        None
     };
-    fs::write(output, content)?;
-    Ok(())
+    let input = match &stdin {
+        Some(file) => file
+            .path()
+            .to_str()
+            .ok_or_else(|| anyhow!("temp file path is not UTF-8"))?,
+        None => input,
+    };
+
+    let mut reader = dialect.reader(input)?;
+    let mut schema = Schema::untyped(&dialect.headers(&mut reader)?);
+    for record in reader.records() {
+        schema.widen(&record?);
+    }
+
+    let mut reader = dialect.reader(input)?;
+    let output = BufWriter::new(get_writer(output)?);
+    let mut writer = record_writer(OutputFormat::Parquet, schema.clone(), output)?;
+    for record in reader.records() {
+        writer.write(&schema.parse(&record?))?;
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::test_utils::TestDir;
+
+    fn convert(data: &[u8], dialect: &CsvDialect) -> Result<serde_json::Value> {
+        let dir = TestDir::new()?;
+        let (input, output) = (dir.write("input.csv", data)?, dir.path("output.json"));
+        process_csv(&input, dialect, &output, OutputFormat::Json)?;
+        Ok(serde_json::from_slice(&dir.read("output.json")?)?)
+    }
+
+    #[test]
+    fn test_process_csv_dialect() -> Result<()> {
+        let dialect = CsvDialect {
+            delimiter: b';',
+            quote: b'\'',
+            escape: Some(b'\\'),
+            comment: Some(b'#'),
+            ..Default::default()
+        };
+        let data = b"# players\nname;kit\n'Buffon\\'s; \"Gigi\"';77\n";
+        assert_eq!(
+            convert(data, &dialect)?,
+            serde_json::json!([{"name": "Buffon's; \"Gigi\"", "kit": 77}])
+        );
+
+        let dialect = CsvDialect {
+            header: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            convert(b"a,1\nb,2\n", &dialect)?,
+            serde_json::json!([
+                {"column1": "a", "column2": 1},
+                {"column1": "b", "column2": 2}
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_csv_encoding() -> Result<()> {
+        // "café" in latin-1
+        let dialect = CsvDialect {
+            encoding: Encoding::for_label(b"latin1"),
+            ..Default::default()
+        };
+        assert_eq!(
+            convert(b"name\ncaf\xe9\n", &dialect)?,
+            serde_json::json!([{"name": "café"}])
+        );
+        // a UTF-8 byte order mark isn't part of the first column name
+        assert_eq!(
+            convert(b"\xef\xbb\xbfname\nx\n", &CsvDialect::default())?,
+            serde_json::json!([{"name": "x"}])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_csv_parquet() -> Result<()> {
+        use parquet::{basic::Type, file::reader::FileReader, file::reader::SerializedFileReader};
+
+        // the kit column is empty and the name column holds ints for the first INFER_ROWS
+        // records, the values after them still fit the columns
+        let mut data = "name,kit\n".to_string();
+        for i in 0..INFER_ROWS {
+            data.push_str(&format!("{},\n", i));
+        }
+        data.push_str("Buffon,1\nTotti,10.5\n");
+
+        let dir = TestDir::new()?;
+        let (input, output) = (dir.write("input.csv", data)?, dir.path("output.parquet"));
+        process_csv(
+            &input,
+            &CsvDialect::default(),
+            &output,
+            OutputFormat::Parquet,
+        )?;
+        let reader = SerializedFileReader::new(std::fs::File::open(&output)?)?;
+
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), INFER_ROWS as i64 + 2);
+        let types = metadata
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|column| column.physical_type())
+            .collect::<Vec<_>>();
+        assert_eq!(types, [Type::BYTE_ARRAY, Type::DOUBLE]);
+        let last = reader.get_row_iter(None)?.last().unwrap()?;
+        assert_eq!(last.to_string(), "{name: \"Totti\", kit: 10.5}");
+        Ok(())
+    }
 }
@@ -0,0 +1,201 @@
+use anyhow::{anyhow, Result};
+use csv::WriterBuilder;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead, BufReader, Read},
+};
+
+use crate::{cli::InputFormat, get_reader, get_writer};
+
+type Record = HashMap<String, String>;
+
+// converts records, JSON objects, to CSV: the header is the union of the keys of all the
+// records, in the order they first appear, and a record lacking a column gets an empty cell
+pub fn process_csv_from(
+    input: &str,
+    format: InputFormat,
+    output: &str,
+    delimiter: u8,
+) -> Result<()> {
+    let reader = get_reader(input)?;
+    let values = match format {
+        InputFormat::Json => match serde_json::from_reader(reader)? {
+            Value::Array(values) => values,
+            _ => return Err(anyhow!("expected an array of objects")),
+        },
+        InputFormat::Ndjson => read_ndjson(reader)?,
+        InputFormat::Yaml => match serde_yaml::from_reader(reader)? {
+            Value::Array(values) => values,
+            _ => return Err(anyhow!("expected a sequence of mappings")),
+        },
+    };
+
+    // each record's cells, null ones as None, and the JSON path of each column
+    let mut flattened = Vec::with_capacity(values.len());
+    let mut paths: HashMap<String, Vec<String>> = HashMap::new();
+    let mut parents = HashSet::new();
+    for (i, value) in values.into_iter().enumerate() {
+        let Value::Object(_) = value else {
+            return Err(anyhow!("record {} is not an object", i + 1));
+        };
+        let mut cells = Vec::new();
+        let mut error = None;
+        flatten(&mut Vec::new(), value, &mut |path, cell| {
+            let key = path.join(".");
+            match paths.get(&key) {
+                Some(seen) if seen != path => {
+                    error.get_or_insert_with(|| {
+                        anyhow!(
+                            "record {}: {} and {} both flatten to column {}",
+                            i + 1,
+                            pointer(seen),
+                            pointer(path),
+                            key
+                        )
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    paths.insert(key.clone(), path.to_vec());
+                }
+            }
+            for len in 1..path.len() {
+                parents.insert(path[..len].join("."));
+            }
+            cells.push((key, cell));
+        });
+        if let Some(error) = error {
+            return Err(error);
+        }
+        flattened.push(cells);
+    }
+
+    // a null object, {"club": null} next to {"club": {"name": ...}}, is empty cells in the
+    // nested columns rather than a column of its own
+    let mut headers = Vec::new();
+    let mut seen = HashSet::new();
+    let mut records = Vec::with_capacity(flattened.len());
+    for cells in flattened {
+        let mut record = Record::new();
+        for (key, cell) in cells {
+            if cell.is_none() && parents.contains(&key) {
+                continue;
+            }
+            if seen.insert(key.clone()) {
+                headers.push(key.clone());
+            }
+            record.insert(key, cell.unwrap_or_default());
+        }
+        records.push(record);
+    }
+
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(get_writer(output)?);
+    if !headers.is_empty() {
+        writer.write_record(&headers)?;
+    }
+    for mut record in records {
+        let row = headers
+            .iter()
+            .map(|key| record.remove(key).unwrap_or_default());
+        writer.write_record(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_ndjson(reader: impl Read) -> Result<Vec<Value>> {
+    let mut values = Vec::new();
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            values.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(values)
+}
+
+// nested objects become dotted column names, {"a": {"b": 1}} is a.b = 1; arrays are kept
+// as JSON and null is None, an empty cell
+fn flatten(path: &mut Vec<String>, value: Value, emit: &mut impl FnMut(&[String], Option<String>)) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                path.push(key);
+                flatten(path, value, emit);
+                path.pop();
+            }
+        }
+        Value::Null => emit(path, None),
+        Value::String(s) => emit(path, Some(s)),
+        Value::Array(_) | Value::Bool(_) | Value::Number(_) => emit(path, Some(value.to_string())),
+    }
+}
+
+// a path as a JSON pointer, /a.b is the key "a.b" and /a/b the key "b" in "a"
+fn pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::test_utils::TestDir;
+
+    fn convert(data: &str, format: InputFormat, delimiter: u8) -> Result<String> {
+        let dir = TestDir::new()?;
+        let (input, output) = (dir.write("input", data)?, dir.path("output.csv"));
+        process_csv_from(&input, format, &output, delimiter)?;
+        dir.read_to_string("output.csv")
+    }
+
+    #[test]
+    fn test_process_csv_from() -> Result<()> {
+        let json = r#"[
+            {"name": "Buffon", "kit": 77, "club": {"name": "Juventus", "since": 2001}},
+            {"name": "Perin", "tags": ["a", "b"], "club": null, "active": true}
+        ]"#;
+        assert_eq!(
+            convert(json, InputFormat::Json, b',')?,
+            "name,kit,club.name,club.since,tags,active\n\
+             Buffon,77,Juventus,2001,,\n\
+             Perin,,,,\"[\"\"a\"\",\"\"b\"\"]\",true\n"
+        );
+        // a null is a column of its own only where no record nests under the key
+        let json = r#"[{"club": null, "coach": null}, {"club": {"name": "Roma"}}]"#;
+        assert_eq!(
+            convert(json, InputFormat::Json, b',')?,
+            "coach,club.name\n,\n,Roma\n"
+        );
+
+        let ndjson = "{\"a\": 1, \"b\": \"x;y\"}\n\n{\"b\": \"z\"}\n";
+        assert_eq!(
+            convert(ndjson, InputFormat::Ndjson, b';')?,
+            "a;b\n1;\"x;y\"\n;z\n"
+        );
+
+        let yaml = "- a: 1\n  b:\n    c: text\n- a: 2\n";
+        assert_eq!(
+            convert(yaml, InputFormat::Yaml, b',')?,
+            "a,b.c\n1,text\n2,\n"
+        );
+
+        assert!(convert("{\"a\": 1}", InputFormat::Json, b',').is_err());
+        assert!(convert("[1]", InputFormat::Json, b',').is_err());
+        // a dotted key would be mistaken for a nested one
+        let json = r#"[{"a": {"b": 1}}, {"a.b": 2}]"#;
+        assert_eq!(
+            convert(json, InputFormat::Json, b',')
+                .unwrap_err()
+                .to_string(),
+            "record 2: /a/b and /a.b both flatten to column a.b"
+        );
+        let json = r#"[{"a.b": 1, "a": {"b": 2}}]"#;
+        assert!(convert(json, InputFormat::Json, b',').is_err());
+        Ok(())
+    }
+}
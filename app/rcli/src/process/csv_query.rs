@@ -0,0 +1,263 @@
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use rusqlite::{
+    types::{Value, ValueRef},
+    Connection,
+};
+use std::{collections::HashSet, io::BufWriter};
+
+use super::csv_convert::CsvDialect;
+use super::csv_schema::{ColumnType, Field, Schema, DATE_FORMAT, INFER_ROWS};
+use super::csv_writer::record_writer;
+use crate::{cli::OutputFormat, get_writer};
+
+// loads each CSV file into a table of an in-memory SQLite database, with the column types
+// inferred as for a conversion, and writes the rows the SQL returns
+pub fn process_csv_query(
+    sql: &str,
+    tables: &[(String, String)],
+    dialect: &CsvDialect,
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let mut conn = Connection::open_in_memory()?;
+    let mut names = HashSet::new();
+    for (name, input) in tables {
+        if !names.insert(name.to_lowercase()) {
+            return Err(anyhow!("table {} is given twice, use name=path", name));
+        }
+        load_table(&mut conn, name, input, dialect)?;
+    }
+
+    let mut stmt = conn.prepare(sql)?;
+    let columns = stmt
+        .columns()
+        .iter()
+        .map(|column| {
+            let decl_type = column.decl_type().map(|t| t.to_ascii_uppercase());
+            (column.name().to_string(), decl_type)
+        })
+        .collect::<Vec<_>>();
+
+    // the result's columns are typed from all of its rows, as a converted CSV file's are:
+    // the query runs once to infer the types, then once more to write the rows
+    let mut types = vec![ColumnType::Null; columns.len()];
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        for (t, field) in types.iter_mut().zip(read_row(row, &columns)?) {
+            *t = t.merge(field.column_type());
+        }
+    }
+    drop(rows);
+    let names = columns.iter().map(|(name, _)| name.clone());
+    let schema = Schema {
+        columns: names.zip(types).collect(),
+    };
+
+    let output = BufWriter::new(get_writer(output)?);
+    let mut writer = record_writer(format, schema.clone(), output)?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let fields = read_row(row, &columns)?;
+        writer.write(&coerce(&schema, fields))?;
+    }
+    writer.finish()
+}
+
+fn load_table(conn: &mut Connection, name: &str, input: &str, dialect: &CsvDialect) -> Result<()> {
+    let mut reader = dialect.reader(input)?;
+    let headers = dialect.headers(&mut reader)?;
+    let mut records = reader.records();
+    let sample = records
+        .by_ref()
+        .take(INFER_ROWS)
+        .collect::<Result<Vec<_>, _>>()?;
+    let schema = Schema::infer(&headers, &sample);
+
+    let columns = schema
+        .columns
+        .iter()
+        .map(|(name, t)| format!("{} {}", quote(name), sql_type(*t)))
+        .collect::<Vec<_>>();
+    let params = vec!["?"; columns.len()].join(", ");
+    let tx = conn.transaction()?;
+    tx.execute(
+        &format!("CREATE TABLE {} ({})", quote(name), columns.join(", ")),
+        [],
+    )?;
+    {
+        let insert = format!("INSERT INTO {} VALUES ({})", quote(name), params);
+        let mut stmt = tx.prepare(&insert)?;
+        for record in sample {
+            stmt.execute(rusqlite::params_from_iter(to_values(schema.parse(&record))))?;
+        }
+        for record in records {
+            let fields = schema.parse(&record?);
+            stmt.execute(rusqlite::params_from_iter(to_values(fields)))?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+// the declared type keeps a column's type in the results: BOOLEAN and DATE are stored as
+// integers and text, and turned back into booleans and dates
+fn sql_type(t: ColumnType) -> &'static str {
+    match t {
+        ColumnType::Null => "",
+        ColumnType::Bool => "BOOLEAN",
+        ColumnType::Int => "INTEGER",
+        ColumnType::Float => "REAL",
+        ColumnType::Date => "DATE",
+        ColumnType::String => "TEXT",
+    }
+}
+
+fn to_values(fields: Vec<Field>) -> impl Iterator<Item = Value> {
+    fields.into_iter().map(|field| match field {
+        Field::Null => Value::Null,
+        Field::Bool(b) => Value::Integer(b as i64),
+        Field::Int(n) => Value::Integer(n),
+        Field::Float(f) => Value::Real(f),
+        Field::Date(d) => Value::Text(d.format(DATE_FORMAT).to_string()),
+        Field::String(s) => Value::Text(s),
+    })
+}
+
+fn read_row(row: &rusqlite::Row, columns: &[(String, Option<String>)]) -> Result<Vec<Field>> {
+    let mut fields = Vec::with_capacity(columns.len());
+    for (i, (_, decl_type)) in columns.iter().enumerate() {
+        let field = match (row.get_ref(i)?, decl_type.as_deref()) {
+            (ValueRef::Null, _) => Field::Null,
+            (ValueRef::Integer(n), Some("BOOLEAN")) => Field::Bool(n != 0),
+            (ValueRef::Integer(n), _) => Field::Int(n),
+            (ValueRef::Real(f), _) => Field::Float(f),
+            (ValueRef::Text(s), decl_type) => {
+                let s = String::from_utf8_lossy(s);
+                match NaiveDate::parse_from_str(&s, DATE_FORMAT) {
+                    Ok(d) if decl_type == Some("DATE") => Field::Date(d),
+                    _ => Field::String(s.into_owned()),
+                }
+            }
+            (ValueRef::Blob(b), _) => Field::String(String::from_utf8_lossy(b).into_owned()),
+        };
+        fields.push(field);
+    }
+    Ok(fields)
+}
+
+// a value of a mixed column takes the column's type: 1 in a REAL column is 1.0, and
+// anything in a TEXT column is text
+fn coerce(schema: &Schema, fields: Vec<Field>) -> Vec<Field> {
+    let types = schema.columns.iter().map(|(_, t)| *t);
+    fields
+        .into_iter()
+        .zip(types)
+        .map(|(field, t)| match (field, t) {
+            (Field::Int(n), ColumnType::Float) => Field::Float(n as f64),
+            (Field::Null, _) => Field::Null,
+            (Field::String(s), _) => Field::String(s),
+            (field, ColumnType::String) => Field::String(field.to_string()),
+            (field, _) => field,
+        })
+        .collect()
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::test_utils::TestDir;
+
+    fn query(sql: &str, files: &[(&str, &str)]) -> Result<serde_json::Value> {
+        let dir = TestDir::new()?;
+        let mut tables = Vec::new();
+        for (name, data) in files {
+            let path = dir.write(&format!("{}.csv", name), data)?;
+            tables.push((name.to_string(), path));
+        }
+        let output = dir.path("output.json");
+        let dialect = CsvDialect::default();
+        process_csv_query(sql, &tables, &dialect, &output, OutputFormat::Json)?;
+        Ok(serde_json::from_slice(&dir.read("output.json")?)?)
+    }
+
+    const PLAYERS: &str = "name,club,kit,retired,born\n\
+                           Buffon,1,1,true,1978-01-28\n\
+                           Pirlo,1,21,true,1979-05-19\n\
+                           Totti,2,10,true,1976-09-27\n\
+                           Perin,1,,false,1992-11-10\n";
+    const CLUBS: &str = "id,club\n1,Juventus\n2,Roma\n";
+
+    #[test]
+    fn test_process_csv_query() -> Result<()> {
+        let sql = "SELECT name, retired, born FROM players \
+                   WHERE kit > 5 AND born < '1979-01-01' ORDER BY kit";
+        assert_eq!(
+            query(sql, &[("players", PLAYERS)])?,
+            serde_json::json!([{"name": "Totti", "retired": true, "born": "1976-09-27"}])
+        );
+
+        let sql = "SELECT c.club, COUNT(*) AS players, AVG(p.kit) AS kit FROM players p \
+                   JOIN clubs c ON p.club = c.id GROUP BY c.club ORDER BY players DESC";
+        assert_eq!(
+            query(sql, &[("players", PLAYERS), ("clubs", CLUBS)])?,
+            serde_json::json!([
+                {"club": "Juventus", "players": 3, "kit": 11.0},
+                {"club": "Roma", "players": 1, "kit": 10.0}
+            ])
+        );
+
+        assert_eq!(
+            query("SELECT * FROM players WHERE 0", &[("players", PLAYERS)])?,
+            serde_json::json!([])
+        );
+        assert!(query("SELECT * FROM nope", &[("players", PLAYERS)]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_csv_query_parquet() -> Result<()> {
+        use parquet::{basic::Type, file::reader::FileReader, file::reader::SerializedFileReader};
+
+        // the first INFER_ROWS values are ints, the last one is a float
+        let sql = format!(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i <= {}) \
+             SELECT CASE WHEN i <= {} THEN i ELSE i + 0.5 END AS v FROM n",
+            INFER_ROWS, INFER_ROWS
+        );
+        let dir = TestDir::new()?;
+        let tables = [("clubs".to_string(), dir.write("clubs.csv", CLUBS)?)];
+        let output = dir.path("output.parquet");
+        let dialect = CsvDialect::default();
+        process_csv_query(&sql, &tables, &dialect, &output, OutputFormat::Parquet)?;
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&output)?)?;
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), INFER_ROWS as i64 + 1);
+        assert_eq!(
+            metadata.schema_descr().column(0).physical_type(),
+            Type::DOUBLE
+        );
+        let last = reader.get_row_iter(None)?.last().unwrap()?;
+        assert_eq!(last.to_string(), format!("{{v: {}.5}}", INFER_ROWS + 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_coerce() {
+        let schema = Schema {
+            columns: vec![
+                ("a".to_string(), ColumnType::Float),
+                ("b".to_string(), ColumnType::String),
+            ],
+        };
+        assert_eq!(
+            coerce(&schema, vec![Field::Int(1), Field::Bool(true)]),
+            vec![Field::Float(1.0), Field::String("true".to_string())]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::{anyhow, Result};
+use csv::StringRecord;
+use std::collections::{HashSet, VecDeque};
+
+use super::csv_convert::CsvDialect;
+
+// the first `n` records
+pub fn process_csv_head(input: &str, dialect: &CsvDialect, output: &str, n: usize) -> Result<()> {
+    let mut reader = dialect.reader(input)?;
+    let headers = dialect.headers(&mut reader)?;
+    let mut writer = dialect.writer(output)?;
+    if dialect.header {
+        writer.write_record(&headers)?;
+    }
+    for record in reader.records().take(n) {
+        writer.write_record(&record?)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// the last `n` records, only those are kept in memory
+pub fn process_csv_tail(input: &str, dialect: &CsvDialect, output: &str, n: usize) -> Result<()> {
+    let mut reader = dialect.reader(input)?;
+    let headers = dialect.headers(&mut reader)?;
+    let mut last = VecDeque::with_capacity(n);
+    for record in reader.records() {
+        if last.len() == n {
+            last.pop_front();
+        }
+        if n > 0 {
+            last.push_back(record?);
+        }
+    }
+
+    let mut writer = dialect.writer(output)?;
+    if dialect.header {
+        writer.write_record(&headers)?;
+    }
+    for record in last {
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// the given columns, in the given order
+pub fn process_csv_select(
+    input: &str,
+    dialect: &CsvDialect,
+    output: &str,
+    columns: &[String],
+) -> Result<()> {
+    let mut reader = dialect.reader(input)?;
+    let headers = dialect.headers(&mut reader)?;
+    let indices = column_indices(&headers, columns)?;
+    let mut writer = dialect.writer(output)?;
+    if dialect.header {
+        writer.write_record(indices.iter().map(|&i| &headers[i]))?;
+    }
+    for record in reader.records() {
+        let record = record?;
+        writer.write_record(indices.iter().map(|&i| record.get(i).unwrap_or_default()))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// drops the records equal to an earlier one, on the given columns or on all of them
+pub fn process_csv_dedupe(
+    input: &str,
+    dialect: &CsvDialect,
+    output: &str,
+    columns: &[String],
+) -> Result<()> {
+    let mut reader = dialect.reader(input)?;
+    let headers = dialect.headers(&mut reader)?;
+    let indices = if columns.is_empty() {
+        (0..headers.len()).collect()
+    } else {
+        column_indices(&headers, columns)?
+    };
+    let mut writer = dialect.writer(output)?;
+    if dialect.header {
+        writer.write_record(&headers)?;
+    }
+    let mut seen = HashSet::new();
+    for record in reader.records() {
+        let record = record?;
+        let key = indices
+            .iter()
+            .map(|&i| record.get(i).unwrap_or_default().to_string())
+            .collect::<Vec<_>>();
+        if seen.insert(key) {
+            writer.write_record(&record)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// the position of each column, by name
+pub(super) fn column_indices(headers: &StringRecord, columns: &[String]) -> Result<Vec<usize>> {
+    columns
+        .iter()
+        .map(|column| {
+            headers
+                .iter()
+                .position(|name| name == column)
+                .ok_or_else(|| anyhow!("no column named {}", column))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::test_utils::TestDir;
+
+    type Process<'a> = dyn Fn(&str, &CsvDialect, &str) -> Result<()> + 'a;
+
+    fn run(data: &str, dialect: &CsvDialect, process: &Process<'_>) -> Result<String> {
+        let dir = TestDir::new()?;
+        let (input, output) = (dir.write("input.csv", data)?, dir.path("output.csv"));
+        process(&input, dialect, &output)?;
+        dir.read_to_string("output.csv")
+    }
+
+    const PLAYERS: &str = "name,club,kit\nBuffon,Juventus,1\nTotti,Roma,10\n\
+                           Pirlo,Juventus,21\nBuffon,Parma,1\n";
+
+    #[test]
+    fn test_head_tail() -> Result<()> {
+        let dialect = CsvDialect::default();
+        let head = run(PLAYERS, &dialect, &|i, d, o| process_csv_head(i, d, o, 2))?;
+        assert_eq!(head, "name,club,kit\nBuffon,Juventus,1\nTotti,Roma,10\n");
+        let tail = run(PLAYERS, &dialect, &|i, d, o| process_csv_tail(i, d, o, 1))?;
+        assert_eq!(tail, "name,club,kit\nBuffon,Parma,1\n");
+        let tail = run(PLAYERS, &dialect, &|i, d, o| process_csv_tail(i, d, o, 0))?;
+        assert_eq!(tail, "name,club,kit\n");
+
+        // without a header, the first line is a record
+        let dialect = CsvDialect {
+            delimiter: b';',
+            header: false,
+            ..Default::default()
+        };
+        let head = run("a;1\nb;2\n", &dialect, &|i, d, o| {
+            process_csv_head(i, d, o, 1)
+        })?;
+        assert_eq!(head, "a;1\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_dedupe() -> Result<()> {
+        let dialect = CsvDialect::default();
+        let columns = ["kit".to_string(), "name".to_string()];
+        let select = run(PLAYERS, &dialect, &|i, d, o| {
+            process_csv_select(i, d, o, &columns)
+        })?;
+        assert_eq!(select, "kit,name\n1,Buffon\n10,Totti\n21,Pirlo\n1,Buffon\n");
+        let missing = ["shirt".to_string()];
+        let select = run(PLAYERS, &dialect, &|i, d, o| {
+            process_csv_select(i, d, o, &missing)
+        });
+        assert!(select.is_err());
+
+        let dedupe = run(PLAYERS, &dialect, &|i, d, o| {
+            process_csv_dedupe(i, d, o, &columns)
+        })?;
+        assert_eq!(
+            dedupe,
+            "name,club,kit\nBuffon,Juventus,1\nTotti,Roma,10\nPirlo,Juventus,21\n"
+        );
+        let dedupe = run(PLAYERS, &dialect, &|i, d, o| {
+            process_csv_dedupe(i, d, o, &[])
+        })?;
+        assert_eq!(dedupe, PLAYERS);
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+use chrono::NaiveDate;
+use csv::StringRecord;
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use std::fmt;
+
+// column types are inferred from this many records, the rest is streamed
+pub const INFER_ROWS: usize = 1000;
+
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    // every value is empty
+    Null,
+    Bool,
+    Int,
+    Float,
+    Date,
+    String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Date(NaiveDate),
+    String(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    pub columns: Vec<(String, ColumnType)>,
+}
+
+// a record along with the names of its columns, serialized as a map in column order
+pub struct Row<'a> {
+    pub schema: &'a Schema,
+    pub fields: &'a [Field],
+}
+
+impl ColumnType {
+    // the narrowest type of a single value
+    pub fn of(value: &str) -> Self {
+        if value.is_empty() {
+            ColumnType::Null
+        } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            ColumnType::Bool
+        } else if parse_int(value).is_some() {
+            ColumnType::Int
+        } else if parse_float(value).is_some() {
+            ColumnType::Float
+        } else if NaiveDate::parse_from_str(value, DATE_FORMAT).is_ok() {
+            ColumnType::Date
+        } else {
+            ColumnType::String
+        }
+    }
+
+    // the narrowest type holding the values of both
+    pub fn merge(self, other: Self) -> Self {
+        use ColumnType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Null, t) | (t, Null) => t,
+            (Int, Float) | (Float, Int) => Float,
+            _ => String,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColumnType::Null => "null",
+            ColumnType::Bool => "bool",
+            ColumnType::Int => "int",
+            ColumnType::Float => "float",
+            ColumnType::Date => "date",
+            ColumnType::String => "string",
+        }
+    }
+
+    // a value of the column, one that doesn't fit the type is kept as a string
+    pub fn parse(self, value: &str) -> Field {
+        if value.is_empty() {
+            return Field::Null;
+        }
+        let field = match self {
+            ColumnType::Bool => match value.to_ascii_lowercase().as_str() {
+                "true" => Some(Field::Bool(true)),
+                "false" => Some(Field::Bool(false)),
+                _ => None,
+            },
+            ColumnType::Int => parse_int(value).map(Field::Int),
+            ColumnType::Float => parse_float(value).map(Field::Float),
+            ColumnType::Date => NaiveDate::parse_from_str(value, DATE_FORMAT)
+                .ok()
+                .map(Field::Date),
+            ColumnType::Null | ColumnType::String => None,
+        };
+        field.unwrap_or_else(|| Field::String(value.to_string()))
+    }
+}
+
+impl Field {
+    pub fn column_type(&self) -> ColumnType {
+        match self {
+            Field::Null => ColumnType::Null,
+            Field::Bool(_) => ColumnType::Bool,
+            Field::Int(_) => ColumnType::Int,
+            Field::Float(_) => ColumnType::Float,
+            Field::Date(_) => ColumnType::Date,
+            Field::String(_) => ColumnType::String,
+        }
+    }
+}
+
+impl Schema {
+    pub fn infer(headers: &StringRecord, records: &[StringRecord]) -> Self {
+        let mut schema = Self::untyped(headers);
+        for record in records {
+            schema.widen(record);
+        }
+        schema
+    }
+
+    // every column is Null until widened by the records
+    pub fn untyped(headers: &StringRecord) -> Self {
+        let columns = headers
+            .iter()
+            .map(|name| (name.to_string(), ColumnType::Null))
+            .collect();
+        Schema { columns }
+    }
+
+    // the narrowest types holding the values of the record as well
+    pub fn widen(&mut self, record: &StringRecord) {
+        for ((_, t), value) in self.columns.iter_mut().zip(record.iter()) {
+            *t = t.merge(ColumnType::of(value));
+        }
+    }
+
+    pub fn parse(&self, record: &StringRecord) -> Vec<Field> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, (_, t))| t.parse(record.get(i).unwrap_or_default()))
+            .collect()
+    }
+}
+
+impl Serialize for Field {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Field::Null => serializer.serialize_none(),
+            Field::Bool(b) => serializer.serialize_bool(*b),
+            Field::Int(n) => serializer.serialize_i64(*n),
+            Field::Float(f) => serializer.serialize_f64(*f),
+            Field::Date(d) => serializer.collect_str(&d.format(DATE_FORMAT)),
+            Field::String(s) => serializer.serialize_str(s),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Null => Ok(()),
+            Field::Bool(b) => write!(f, "{}", b),
+            Field::Int(n) => write!(f, "{}", n),
+            Field::Float(x) => write!(f, "{}", x),
+            Field::Date(d) => write!(f, "{}", d.format(DATE_FORMAT)),
+            Field::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl Serialize for Row<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for ((name, _), field) in self.schema.columns.iter().zip(self.fields) {
+            map.serialize_entry(name, field)?;
+        }
+        map.end()
+    }
+}
+
+// leading zeros are kept, "007" is an identifier rather than a number
+fn parse_int(value: &str) -> Option<i64> {
+    let digits = value.strip_prefix(['-', '+']).unwrap_or(value);
+    if digits.len() > 1 && digits.starts_with('0') {
+        return None;
+    }
+    value.parse().ok()
+}
+
+// only decimal notation, not "inf" or "NaN"
+fn parse_float(value: &str) -> Option<f64> {
+    let numeric = value
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'));
+    if !numeric || !value.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_schema() {
+        let headers = StringRecord::from(vec!["id", "score", "ok", "day", "name", "empty"]);
+        let records = vec![
+            StringRecord::from(vec!["1", "1.5", "true", "2024-01-31", "a", ""]),
+            StringRecord::from(vec!["2", "3", "False", "", "007", ""]),
+            StringRecord::from(vec!["", "-2e3", "", "2024-02-29", "c", ""]),
+        ];
+        let schema = Schema::infer(&headers, &records);
+        let types = schema.columns.iter().map(|(_, t)| *t).collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                ColumnType::Int,
+                ColumnType::Float,
+                ColumnType::Bool,
+                ColumnType::Date,
+                ColumnType::String,
+                ColumnType::Null
+            ]
+        );
+        assert_eq!(
+            schema.parse(&records[1]),
+            vec![
+                Field::Int(2),
+                Field::Float(3.0),
+                Field::Bool(false),
+                Field::Null,
+                Field::String("007".to_string()),
+                Field::Null
+            ]
+        );
+        // a value after the inferred rows that doesn't fit stays a string
+        assert_eq!(
+            ColumnType::Int.parse("n/a"),
+            Field::String("n/a".to_string())
+        );
+        assert_eq!(ColumnType::of("inf"), ColumnType::String);
+    }
+
+    #[test]
+    fn test_row_serialize() -> anyhow::Result<()> {
+        let schema = Schema {
+            columns: vec![
+                ("b".to_string(), ColumnType::Int),
+                ("a".to_string(), ColumnType::Date),
+            ],
+        };
+        let fields = [
+            Field::Null,
+            Field::Date(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()),
+        ];
+        let row = Row {
+            schema: &schema,
+            fields: &fields,
+        };
+        // columns keep their order
+        assert_eq!(
+            serde_json::to_string(&row)?,
+            r#"{"b":null,"a":"2024-01-31"}"#
+        );
+        Ok(())
+    }
+}
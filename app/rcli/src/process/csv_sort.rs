@@ -0,0 +1,333 @@
+use anyhow::Result;
+use csv::{Reader, ReaderBuilder, StringRecord, Writer, WriterBuilder};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    fs::File,
+    io::{BufReader, BufWriter, Seek, SeekFrom},
+};
+
+use super::csv_convert::CsvDialect;
+use super::csv_rows::column_indices;
+use super::csv_schema::{Field, Schema, INFER_ROWS};
+
+// the most runs merged at once, more are first merged in groups into longer runs
+const MAX_MERGE_RUNS: usize = 64;
+
+pub struct SortOpts<'a> {
+    // the columns to sort by, all of them if empty
+    pub columns: &'a [String],
+    pub reverse: bool,
+    // records sorted in memory at once, each batch is spilled to a temp file and the files
+    // are merged
+    pub buffer_rows: usize,
+}
+
+// a sorted batch of records spilled to disk
+struct Run {
+    reader: Reader<BufReader<File>>,
+}
+
+// the smallest record of a run, waiting to be merged
+struct Head {
+    key: Vec<Field>,
+    run: usize,
+    record: StringRecord,
+    reverse: bool,
+}
+
+// sorts by the key columns, compared with the types inferred as for a conversion: 9 comes
+// before 10 in an int column, and empty values come first; records with the same key keep
+// their order
+pub fn process_csv_sort(
+    input: &str,
+    dialect: &CsvDialect,
+    output: &str,
+    opts: &SortOpts,
+) -> Result<()> {
+    let mut reader = dialect.reader(input)?;
+    let headers = dialect.headers(&mut reader)?;
+    let indices = if opts.columns.is_empty() {
+        (0..headers.len()).collect()
+    } else {
+        column_indices(&headers, opts.columns)?
+    };
+    let mut records = reader.records();
+    let sample = records
+        .by_ref()
+        .take(INFER_ROWS)
+        .collect::<Result<Vec<_>, _>>()?;
+    let schema = Schema::infer(&headers, &sample);
+    let types = indices
+        .iter()
+        .map(|&i| schema.columns[i].1)
+        .collect::<Vec<_>>();
+    let key = |record: &StringRecord| -> Vec<Field> {
+        let values = indices.iter().map(|&i| record.get(i).unwrap_or_default());
+        types.iter().zip(values).map(|(t, v)| t.parse(v)).collect()
+    };
+
+    let buffer_rows = opts.buffer_rows.max(1);
+    let mut batch = Vec::new();
+    let mut runs = Vec::new();
+    for record in sample.into_iter().map(Ok).chain(records) {
+        let record = record?;
+        batch.push((key(&record), record));
+        if batch.len() == buffer_rows {
+            runs.push(spill(sort_batch(batch, opts.reverse))?);
+            batch = Vec::new();
+        }
+    }
+
+    let mut writer = dialect.writer(output)?;
+    if dialect.header {
+        writer.write_record(&headers)?;
+    }
+    if runs.is_empty() {
+        for record in sort_batch(batch, opts.reverse) {
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+        return Ok(());
+    }
+    if !batch.is_empty() {
+        runs.push(spill(sort_batch(batch, opts.reverse))?);
+    }
+
+    // groups of consecutive runs keep the records with the same key in order
+    while runs.len() > MAX_MERGE_RUNS {
+        let mut merged = Vec::new();
+        let mut rest = runs.into_iter();
+        loop {
+            let group = rest.by_ref().take(MAX_MERGE_RUNS).collect::<Vec<_>>();
+            if group.is_empty() {
+                break;
+            }
+            merged.push(spill_with(|writer| {
+                merge(group, &key, opts.reverse, |record| {
+                    Ok(writer.write_record(record)?)
+                })
+            })?);
+        }
+        runs = merged;
+    }
+    merge(runs, &key, opts.reverse, |record| {
+        Ok(writer.write_record(record)?)
+    })?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn merge(
+    mut runs: Vec<Run>,
+    key: &impl Fn(&StringRecord) -> Vec<Field>,
+    reverse: bool,
+    mut write: impl FnMut(&StringRecord) -> Result<()>,
+) -> Result<()> {
+    let mut heap = BinaryHeap::with_capacity(runs.len());
+    for (i, run) in runs.iter_mut().enumerate() {
+        if let Some(record) = run.next()? {
+            heap.push(Reverse(Head {
+                key: key(&record),
+                run: i,
+                record,
+                reverse,
+            }));
+        }
+    }
+    while let Some(Reverse(head)) = heap.pop() {
+        write(&head.record)?;
+        if let Some(record) = runs[head.run].next()? {
+            heap.push(Reverse(Head {
+                key: key(&record),
+                record,
+                ..head
+            }));
+        }
+    }
+    Ok(())
+}
+
+fn sort_batch(mut batch: Vec<(Vec<Field>, StringRecord)>, reverse: bool) -> Vec<StringRecord> {
+    batch.sort_by(|(a, _), (b, _)| compare_keys(a, b, reverse));
+    batch.into_iter().map(|(_, record)| record).collect()
+}
+
+fn spill(records: Vec<StringRecord>) -> Result<Run> {
+    spill_with(|writer| {
+        for record in records {
+            writer.write_record(&record)?;
+        }
+        Ok(())
+    })
+}
+
+fn spill_with(write: impl FnOnce(&mut Writer<BufWriter<File>>) -> Result<()>) -> Result<Run> {
+    let mut writer = WriterBuilder::new()
+        .flexible(true)
+        .from_writer(BufWriter::new(tempfile::tempfile()?));
+    write(&mut writer)?;
+    let mut file = writer.into_inner()?.into_inner()?;
+    file.seek(SeekFrom::Start(0))?;
+    let reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(BufReader::new(file));
+    Ok(Run { reader })
+}
+
+impl Run {
+    fn next(&mut self) -> Result<Option<StringRecord>> {
+        let mut record = StringRecord::new();
+        Ok(self.reader.read_record(&mut record)?.then_some(record))
+    }
+}
+
+fn compare_keys(a: &[Field], b: &[Field], reverse: bool) -> Ordering {
+    let ordering = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| compare_fields(a, b))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal);
+    if reverse {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+// a total order: empty values first, then the values of the column's type, then the values
+// that didn't fit it, which are parsed as strings
+fn compare_fields(a: &Field, b: &Field) -> Ordering {
+    match (a, b) {
+        (Field::Bool(a), Field::Bool(b)) => a.cmp(b),
+        (Field::Int(a), Field::Int(b)) => a.cmp(b),
+        (Field::Float(a), Field::Float(b)) => a.total_cmp(b),
+        (Field::Date(a), Field::Date(b)) => a.cmp(b),
+        (Field::String(a), Field::String(b)) => a.cmp(b),
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+// a column holds nulls, strings and values of at most one other variant
+fn rank(field: &Field) -> u8 {
+    match field {
+        Field::Null => 0,
+        Field::Bool(_) => 1,
+        Field::Int(_) => 2,
+        Field::Float(_) => 3,
+        Field::Date(_) => 4,
+        Field::String(_) => 5,
+    }
+}
+
+// ties are broken by run, the earlier batch holds the earlier records
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_keys(&self.key, &other.key, self.reverse).then(self.run.cmp(&other.run))
+    }
+}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Head {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::test_utils::TestDir;
+
+    fn sort(data: &str, columns: &[&str], reverse: bool, buffer_rows: usize) -> Result<String> {
+        let dir = TestDir::new()?;
+        let (input, output) = (dir.write("input.csv", data)?, dir.path("output.csv"));
+        let columns = columns.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let opts = SortOpts {
+            columns: &columns,
+            reverse,
+            buffer_rows,
+        };
+        process_csv_sort(&input, &CsvDialect::default(), &output, &opts)?;
+        dir.read_to_string("output.csv")
+    }
+
+    const PLAYERS: &str = "name,kit\nTotti,10\nPirlo,21\nPerin,\nBuffon,1\nDel Piero,10\n";
+
+    #[test]
+    fn test_process_csv_sort() -> Result<()> {
+        // ints compare as numbers, empty values come first and ties keep their order
+        let sorted = "name,kit\nPerin,\nBuffon,1\nTotti,10\nDel Piero,10\nPirlo,21\n";
+        assert_eq!(sort(PLAYERS, &["kit"], false, 1000)?, sorted);
+        // the same once merged from batches of two
+        assert_eq!(sort(PLAYERS, &["kit"], false, 2)?, sorted);
+        assert_eq!(
+            sort(PLAYERS, &["kit", "name"], true, 2)?,
+            "name,kit\nPirlo,21\nTotti,10\nDel Piero,10\nBuffon,1\nPerin,\n"
+        );
+        assert_eq!(
+            sort(PLAYERS, &[], false, 3)?,
+            "name,kit\nBuffon,1\nDel Piero,10\nPerin,\nPirlo,21\nTotti,10\n"
+        );
+        assert!(sort(PLAYERS, &["shirt"], false, 2).is_err());
+
+        // the type is inferred from the first rows, the values after them that don't fit
+        // are sorted after the others rather than as text among them
+        let mut data = "kit\n".to_string();
+        for i in (0..INFER_ROWS).rev() {
+            data.push_str(&format!("{}\n", i % 100));
+        }
+        data.push_str("5x\n1000\n10\n9\n");
+        let sorted = sort(&data, &["kit"], false, 300)?;
+        let kits = sorted.lines().skip(1).collect::<Vec<_>>();
+        assert_eq!(kits[kits.len() - 3..], ["99", "1000", "5x"]);
+        let ints = kits[..kits.len() - 1]
+            .iter()
+            .map(|kit| kit.parse::<i64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        assert!(ints.windows(2).all(|pair| pair[0] <= pair[1]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_csv_sort_many_runs() -> Result<()> {
+        // a run per record, more than are merged at once
+        let rows = MAX_MERGE_RUNS * 3 + 5;
+        let mut data = "kit,order\n".to_string();
+        let mut expected = Vec::new();
+        for i in 0..rows {
+            let kit = (rows - i) % 7;
+            data.push_str(&format!("{},{}\n", kit, i));
+            expected.push((kit, i));
+        }
+        expected.sort_by_key(|(kit, _)| *kit);
+        let sorted = sort(&data, &["kit"], false, 1)?;
+        let mut lines = sorted.lines();
+        assert_eq!(lines.next(), Some("kit,order"));
+        let expected = expected
+            .iter()
+            .map(|(kit, i)| format!("{},{}", kit, i))
+            .collect::<Vec<_>>();
+        assert_eq!(lines.collect::<Vec<_>>(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare_fields() {
+        assert!(compare_fields(&Field::Int(9), &Field::Int(10)).is_lt());
+        assert!(compare_fields(&Field::Null, &Field::Int(-1)).is_lt());
+        // a value that didn't fit an int column comes after all the ints
+        assert!(compare_fields(&Field::Int(10), &Field::String("5x".into())).is_lt());
+        assert!(compare_fields(&Field::String("5x".into()), &Field::Int(9)).is_gt());
+        assert!(compare_fields(&Field::Float(2.5), &Field::Float(-0.5)).is_gt());
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::Result;
+use std::{collections::HashSet, io::BufWriter};
+
+use super::csv_convert::CsvDialect;
+use super::csv_schema::{ColumnType, Field, Schema};
+use super::csv_writer::record_writer;
+use crate::{cli::OutputFormat, get_writer};
+
+// what is known of a column after reading every record, unlike a conversion the type is
+// inferred from all the values
+#[derive(Debug, Default)]
+struct ColumnStats {
+    kind: Option<ColumnType>,
+    nulls: usize,
+    distinct: HashSet<String>,
+    // kept for every value that parses as a number, used if the column turns out numeric
+    min_num: Option<f64>,
+    max_num: Option<f64>,
+    sum: f64,
+    numbers: usize,
+    min_str: Option<String>,
+    max_str: Option<String>,
+}
+
+// one record per column: its type, how many values are empty, the smallest and largest
+// value, the mean of a numeric column and the number of distinct values
+pub fn process_csv_stats(
+    input: &str,
+    dialect: &CsvDialect,
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let mut reader = dialect.reader(input)?;
+    let headers = dialect.headers(&mut reader)?;
+    let mut stats = headers
+        .iter()
+        .map(|_| ColumnStats::default())
+        .collect::<Vec<_>>();
+    for record in reader.records() {
+        let record = record?;
+        for (column, value) in stats.iter_mut().zip(record.iter()) {
+            column.add(value);
+        }
+    }
+
+    let schema = Schema {
+        columns: vec![
+            ("column".to_string(), ColumnType::String),
+            ("type".to_string(), ColumnType::String),
+            ("nulls".to_string(), ColumnType::Int),
+            ("min".to_string(), ColumnType::String),
+            ("max".to_string(), ColumnType::String),
+            ("mean".to_string(), ColumnType::Float),
+            ("cardinality".to_string(), ColumnType::Int),
+        ],
+    };
+    let output = BufWriter::new(get_writer(output)?);
+    let mut writer = record_writer(format, schema, output)?;
+    for (name, column) in headers.iter().zip(stats) {
+        writer.write(&column.fields(name))?;
+    }
+    writer.finish()
+}
+
+impl ColumnStats {
+    fn add(&mut self, value: &str) {
+        let kind = ColumnType::of(value);
+        self.kind = Some(self.kind.map_or(kind, |t| t.merge(kind)));
+        if value.is_empty() {
+            self.nulls += 1;
+            return;
+        }
+        if let Field::Float(n) = ColumnType::Float.parse(value) {
+            self.min_num = Some(self.min_num.map_or(n, |min| min.min(n)));
+            self.max_num = Some(self.max_num.map_or(n, |max| max.max(n)));
+            self.sum += n;
+            self.numbers += 1;
+        }
+        if self.min_str.as_deref().is_none_or(|min| value < min) {
+            self.min_str = Some(value.to_string());
+        }
+        if self.max_str.as_deref().is_none_or(|max| value > max) {
+            self.max_str = Some(value.to_string());
+        }
+        if !self.distinct.contains(value) {
+            self.distinct.insert(value.to_string());
+        }
+    }
+
+    fn fields(self, name: &str) -> Vec<Field> {
+        let kind = self.kind.unwrap_or(ColumnType::Null);
+        let numeric = matches!(kind, ColumnType::Int | ColumnType::Float);
+        let (min, max, mean) = if numeric {
+            let format = |n: f64| match kind {
+                ColumnType::Int => (n as i64).to_string(),
+                _ => n.to_string(),
+            };
+            (
+                self.min_num.map(format),
+                self.max_num.map(format),
+                Some(self.sum / self.numbers as f64),
+            )
+        } else {
+            (self.min_str, self.max_str, None)
+        };
+        vec![
+            Field::String(name.to_string()),
+            Field::String(kind.name().to_string()),
+            Field::Int(self.nulls as i64),
+            min.map_or(Field::Null, Field::String),
+            max.map_or(Field::Null, Field::String),
+            mean.map_or(Field::Null, Field::Float),
+            Field::Int(self.distinct.len() as i64),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_stats() {
+        let mut stats = ColumnStats::default();
+        for value in ["3", "", "-1", "10", "3"] {
+            stats.add(value);
+        }
+        assert_eq!(
+            stats.fields("kit"),
+            vec![
+                Field::String("kit".to_string()),
+                Field::String("int".to_string()),
+                Field::Int(1),
+                Field::String("-1".to_string()),
+                Field::String("10".to_string()),
+                Field::Float(3.75),
+                Field::Int(3),
+            ]
+        );
+
+        // "10" is smaller than "9" once the column holds text
+        let mut stats = ColumnStats::default();
+        for value in ["9", "10", "n/a"] {
+            stats.add(value);
+        }
+        let fields = stats.fields("score");
+        assert_eq!(fields[1], Field::String("string".to_string()));
+        assert_eq!(fields[3], Field::String("10".to_string()));
+        assert_eq!(fields[4], Field::String("n/a".to_string()));
+        assert_eq!(fields[5], Field::Null);
+
+        let fields = ColumnStats::default().fields("empty");
+        assert_eq!(fields[1], Field::String("null".to_string()));
+        assert_eq!(fields[3], Field::Null);
+    }
+}
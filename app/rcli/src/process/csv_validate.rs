@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::io::Write;
+
+use super::csv_convert::CsvDialect;
+use super::csv_schema::{ColumnType, Row, Schema, INFER_ROWS};
+use crate::{get_reader, get_writer};
+
+// checks each record, as `rcli csv` would convert it to a JSON object, against a JSON
+// Schema; a column the schema gives a type to is parsed as that type rather than inferred,
+// so "007" in a string column stays a string. Each error is written to the output as
+// `row <n>: <path>: <message>`, and any error fails the command
+pub fn process_csv_validate(
+    input: &str,
+    dialect: &CsvDialect,
+    schema: &str,
+    output: &str,
+) -> Result<()> {
+    let json_schema: Value = serde_json::from_reader(get_reader(schema)?)?;
+    let validator = jsonschema::validator_for(&json_schema)
+        .map_err(|e| anyhow!("invalid schema {}: {}", schema, e))?;
+
+    let mut reader = dialect.reader(input)?;
+    let headers = dialect.headers(&mut reader)?;
+    let mut records = reader.records();
+    let sample = records
+        .by_ref()
+        .take(INFER_ROWS)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut schema = Schema::infer(&headers, &sample);
+    for (name, t) in schema.columns.iter_mut() {
+        if let Some(declared) = declared_type(&json_schema, name) {
+            *t = declared;
+        }
+    }
+
+    let mut output = get_writer(output)?;
+    let (mut rows, mut invalid) = (0, 0);
+    for record in sample.into_iter().map(Ok).chain(records) {
+        let fields = schema.parse(&record?);
+        let row = serde_json::to_value(Row {
+            schema: &schema,
+            fields: &fields,
+        })?;
+        rows += 1;
+        let mut errors = validator.iter_errors(&row).peekable();
+        if errors.peek().is_some() {
+            invalid += 1;
+        }
+        for error in errors {
+            // rows are numbered from 1, as a spreadsheet would, not counting the header
+            writeln!(
+                output,
+                "row {}: {}: {}",
+                rows,
+                path(&error.instance_path),
+                error
+            )?;
+        }
+    }
+    output.flush()?;
+    match invalid {
+        0 => Ok(()),
+        _ => Err(anyhow!("{} of {} rows are invalid", invalid, rows)),
+    }
+}
+
+// the type of a property, when it's a single one or a single one and null
+fn declared_type(schema: &Value, column: &str) -> Option<ColumnType> {
+    let types = match &schema["properties"][column]["type"] {
+        Value::String(t) => vec![t.as_str()],
+        Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+        _ => return None,
+    };
+    let mut types = types.into_iter().filter(|&t| t != "null");
+    let t = match types.next()? {
+        "boolean" => ColumnType::Bool,
+        "integer" => ColumnType::Int,
+        "number" => ColumnType::Float,
+        "string" => ColumnType::String,
+        _ => return None,
+    };
+    types.next().is_none().then_some(t)
+}
+
+fn path(location: &jsonschema::paths::Location) -> String {
+    match location.to_string() {
+        path if path.is_empty() => "/".to_string(),
+        path => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::test_utils::TestDir;
+
+    const SCHEMA: &str = r#"{
+        "type": "object",
+        "required": ["name", "kit"],
+        "properties": {
+            "name": {"type": "string", "minLength": 1},
+            "kit": {"type": "integer", "minimum": 1, "maximum": 99},
+            "zip": {"type": ["string", "null"], "pattern": "^[0-9]{5}$"}
+        }
+    }"#;
+
+    fn validate(data: &str) -> Result<(String, Result<()>)> {
+        let dir = TestDir::new()?;
+        let (input, schema) = (
+            dir.write("input.csv", data)?,
+            dir.write("schema.json", SCHEMA)?,
+        );
+        let output = dir.path("errors.txt");
+        let ret = process_csv_validate(&input, &CsvDialect::default(), &schema, &output);
+        Ok((dir.read_to_string("errors.txt")?, ret))
+    }
+
+    #[test]
+    fn test_process_csv_validate() -> Result<()> {
+        // the zip code is inferred as an int, the schema makes it a string
+        let (errors, ret) = validate("name,kit,zip\nBuffon,1,00100\nTotti,10,\n")?;
+        assert_eq!(errors, "");
+        assert!(ret.is_ok());
+
+        // an empty value is null, as in a conversion
+        let (errors, ret) = validate("name,kit,zip\n,1,00100\nTotti,100,1234\n")?;
+        assert_eq!(
+            errors,
+            "row 1: /name: null is not of type \"string\"\n\
+             row 2: /kit: 100 is greater than the maximum of 99\n\
+             row 2: /zip: \"1234\" does not match \"^[0-9]{5}$\"\n"
+        );
+        assert_eq!(ret.unwrap_err().to_string(), "2 of 2 rows are invalid");
+        Ok(())
+    }
+
+    #[test]
+    fn test_declared_type() -> Result<()> {
+        let schema: Value = serde_json::from_str(SCHEMA)?;
+        assert_eq!(declared_type(&schema, "kit"), Some(ColumnType::Int));
+        assert_eq!(declared_type(&schema, "zip"), Some(ColumnType::String));
+        assert_eq!(declared_type(&schema, "club"), None);
+        Ok(())
+    }
+}
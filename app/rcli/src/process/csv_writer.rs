@@ -0,0 +1,450 @@
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use parquet::{
+    basic::{LogicalType, Repetition, Type as PhysicalType},
+    column::writer::ColumnWriter,
+    data_type::ByteArray,
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::types::Type,
+};
+use std::{io::Write, sync::Arc};
+
+use super::csv_schema::{ColumnType, Field, Row, Schema, DATE_FORMAT};
+use crate::cli::OutputFormat;
+
+// rows buffered per parquet row group
+const ROW_GROUP_SIZE: usize = 64 * 1024;
+
+// writes records one at a time, nothing but the current record (or parquet row group) is
+// kept in memory
+pub trait RecordWriter {
+    fn write(&mut self, fields: &[Field]) -> Result<()>;
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+pub fn record_writer<W: Write + Send + 'static>(
+    format: OutputFormat,
+    schema: Schema,
+    output: W,
+) -> Result<Box<dyn RecordWriter>> {
+    let writer: Box<dyn RecordWriter> = match format {
+        OutputFormat::Json => Box::new(JsonWriter::new(schema, output)),
+        OutputFormat::Yaml => Box::new(YamlWriter::new(schema, output)),
+        OutputFormat::Toml => Box::new(TomlWriter { schema, output }),
+        OutputFormat::Ndjson => Box::new(NdjsonWriter { schema, output }),
+        OutputFormat::MsgPack => Box::new(MsgPackWriter { schema, output }),
+        OutputFormat::Parquet => Box::new(ParquetWriter::new(schema, output)?),
+    };
+    Ok(writer)
+}
+
+// a pretty printed array, the same as serializing a Vec of all the records
+struct JsonWriter<W> {
+    schema: Schema,
+    output: W,
+    count: usize,
+}
+
+impl<W: Write> JsonWriter<W> {
+    fn new(schema: Schema, output: W) -> Self {
+        Self {
+            schema,
+            output,
+            count: 0,
+        }
+    }
+}
+
+impl<W: Write> RecordWriter for JsonWriter<W> {
+    fn write(&mut self, fields: &[Field]) -> Result<()> {
+        let row = Row {
+            schema: &self.schema,
+            fields,
+        };
+        let json = serde_json::to_string_pretty(&row)?;
+        let sep = if self.count == 0 { "[\n" } else { ",\n" };
+        self.output.write_all(sep.as_bytes())?;
+        self.output.write_all(indent(&json).as_bytes())?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        let end = if self.count == 0 { "[]" } else { "\n]" };
+        self.output.write_all(end.as_bytes())?;
+        Ok(self.output.flush()?)
+    }
+}
+
+// a sequence of mappings
+struct YamlWriter<W> {
+    schema: Schema,
+    output: W,
+    count: usize,
+}
+
+impl<W: Write> YamlWriter<W> {
+    fn new(schema: Schema, output: W) -> Self {
+        Self {
+            schema,
+            output,
+            count: 0,
+        }
+    }
+}
+
+impl<W: Write> RecordWriter for YamlWriter<W> {
+    fn write(&mut self, fields: &[Field]) -> Result<()> {
+        let row = Row {
+            schema: &self.schema,
+            fields,
+        };
+        let yaml = serde_yaml::to_string(&row)?;
+        // the item marker takes the place of the indentation of the first line
+        let item = indent(yaml.trim_end_matches('\n'));
+        writeln!(self.output, "- {}", &item[2..])?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        if self.count == 0 {
+            self.output.write_all(b"[]\n")?;
+        }
+        Ok(self.output.flush()?)
+    }
+}
+
+// an array of tables; TOML has no null, so empty fields are left out
+struct TomlWriter<W> {
+    schema: Schema,
+    output: W,
+}
+
+impl<W: Write> RecordWriter for TomlWriter<W> {
+    fn write(&mut self, fields: &[Field]) -> Result<()> {
+        let mut table = toml::Table::new();
+        for ((name, _), field) in self.schema.columns.iter().zip(fields) {
+            let value = match field {
+                Field::Null => continue,
+                Field::Bool(b) => toml::Value::Boolean(*b),
+                Field::Int(n) => toml::Value::Integer(*n),
+                Field::Float(f) => toml::Value::Float(*f),
+                Field::Date(d) => toml::Value::Datetime(d.format(DATE_FORMAT).to_string().parse()?),
+                Field::String(s) => toml::Value::String(s.clone()),
+            };
+            table.insert(name.clone(), value);
+        }
+        writeln!(self.output, "[[records]]\n{}", toml::to_string(&table)?)?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        Ok(self.output.flush()?)
+    }
+}
+
+// one JSON object per line
+struct NdjsonWriter<W> {
+    schema: Schema,
+    output: W,
+}
+
+impl<W: Write> RecordWriter for NdjsonWriter<W> {
+    fn write(&mut self, fields: &[Field]) -> Result<()> {
+        let row = Row {
+            schema: &self.schema,
+            fields,
+        };
+        serde_json::to_writer(&mut self.output, &row)?;
+        self.output.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        Ok(self.output.flush()?)
+    }
+}
+
+// a stream of maps, one after the other like NDJSON: an enclosing array would need the
+// number of records upfront
+struct MsgPackWriter<W> {
+    schema: Schema,
+    output: W,
+}
+
+impl<W: Write> RecordWriter for MsgPackWriter<W> {
+    fn write(&mut self, fields: &[Field]) -> Result<()> {
+        let row = Row {
+            schema: &self.schema,
+            fields,
+        };
+        rmp_serde::encode::write_named(&mut self.output, &row)?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        Ok(self.output.flush()?)
+    }
+}
+
+// every column is optional, typed from the schema: int64, double, boolean, date or string
+struct ParquetWriter<W: Write + Send> {
+    schema: Schema,
+    writer: SerializedFileWriter<W>,
+    // the buffered row group, column by column
+    columns: Vec<Vec<Field>>,
+    rows: usize,
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    fn new(schema: Schema, output: W) -> Result<Self> {
+        let fields = schema
+            .columns
+            .iter()
+            .map(|(name, t)| {
+                let (physical, logical) = match t {
+                    ColumnType::Bool => (PhysicalType::BOOLEAN, None),
+                    ColumnType::Int => (PhysicalType::INT64, None),
+                    ColumnType::Float => (PhysicalType::DOUBLE, None),
+                    ColumnType::Date => (PhysicalType::INT32, Some(LogicalType::Date)),
+                    ColumnType::Null | ColumnType::String => {
+                        (PhysicalType::BYTE_ARRAY, Some(LogicalType::String))
+                    }
+                };
+                let field = Type::primitive_type_builder(name, physical)
+                    .with_repetition(Repetition::OPTIONAL)
+                    .with_logical_type(logical)
+                    .build()?;
+                Ok(Arc::new(field))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let parquet_schema = Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()?;
+        let props = WriterProperties::builder().build();
+        let writer = SerializedFileWriter::new(output, Arc::new(parquet_schema), Arc::new(props))?;
+        let columns = vec![Vec::new(); schema.columns.len()];
+        Ok(Self {
+            schema,
+            writer,
+            columns,
+            rows: 0,
+        })
+    }
+
+    fn flush_row_group(&mut self) -> Result<()> {
+        if self.rows == 0 {
+            return Ok(());
+        }
+        let mut row_group = self.writer.next_row_group()?;
+        let mut index = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            let (name, _) = &self.schema.columns[index];
+            let fields = std::mem::take(&mut self.columns[index]);
+            let defs = fields
+                .iter()
+                .map(|f| (*f != Field::Null) as i16)
+                .collect::<Vec<_>>();
+            match column.untyped() {
+                ColumnWriter::BoolColumnWriter(w) => {
+                    let values = column_values(name, &fields, |f| match f {
+                        Field::Bool(b) => Some(*b),
+                        _ => None,
+                    })?;
+                    w.write_batch(&values, Some(&defs), None)?;
+                }
+                ColumnWriter::Int64ColumnWriter(w) => {
+                    let values = column_values(name, &fields, |f| match f {
+                        Field::Int(n) => Some(*n),
+                        _ => None,
+                    })?;
+                    w.write_batch(&values, Some(&defs), None)?;
+                }
+                ColumnWriter::DoubleColumnWriter(w) => {
+                    let values = column_values(name, &fields, |f| match f {
+                        Field::Float(n) => Some(*n),
+                        Field::Int(n) => Some(*n as f64),
+                        _ => None,
+                    })?;
+                    w.write_batch(&values, Some(&defs), None)?;
+                }
+                ColumnWriter::Int32ColumnWriter(w) => {
+                    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).expect("valid date");
+                    let values = column_values(name, &fields, |f| match f {
+                        Field::Date(d) => Some((*d - epoch).num_days() as i32),
+                        _ => None,
+                    })?;
+                    w.write_batch(&values, Some(&defs), None)?;
+                }
+                ColumnWriter::ByteArrayColumnWriter(w) => {
+                    // any value fits a string column
+                    let values = column_values(name, &fields, |f| match f {
+                        Field::String(s) => Some(ByteArray::from(s.as_str())),
+                        f => Some(ByteArray::from(f.to_string().as_str())),
+                    })?;
+                    w.write_batch(&values, Some(&defs), None)?;
+                }
+                _ => unreachable!("no other column type in the schema"),
+            }
+            column.close()?;
+            index += 1;
+        }
+        row_group.close()?;
+        self.rows = 0;
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> RecordWriter for ParquetWriter<W> {
+    fn write(&mut self, fields: &[Field]) -> Result<()> {
+        for (column, field) in self.columns.iter_mut().zip(fields) {
+            column.push(field.clone());
+        }
+        self.rows += 1;
+        if self.rows == ROW_GROUP_SIZE {
+            self.flush_row_group()?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.flush_row_group()?;
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+// the non null values of a column, a value of another type than the column's can't be
+// written
+fn column_values<T>(
+    name: &str,
+    fields: &[Field],
+    value: impl Fn(&Field) -> Option<T>,
+) -> Result<Vec<T>> {
+    fields
+        .iter()
+        .filter(|f| **f != Field::Null)
+        .map(|f| {
+            value(f).ok_or_else(|| {
+                anyhow!(
+                    "column '{}': {:?} doesn't match the type of the column",
+                    name,
+                    f
+                )
+            })
+        })
+        .collect()
+}
+
+// indents every line by two spaces
+fn indent(s: &str) -> String {
+    s.lines()
+        .map(|line| format!("  {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::test_utils::TestDir;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use std::sync::Mutex;
+
+    // a writer whose output can be read once the record writer is finished
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn convert(format: OutputFormat, records: &[Vec<Field>]) -> Result<Vec<u8>> {
+        let schema = Schema {
+            columns: vec![
+                ("name".to_string(), ColumnType::String),
+                ("kit".to_string(), ColumnType::Int),
+            ],
+        };
+        let buffer = Buffer::default();
+        let mut writer = record_writer(format, schema, buffer.clone())?;
+        for record in records {
+            writer.write(record)?;
+        }
+        writer.finish()?;
+        let data = buffer.0.lock().unwrap().clone();
+        Ok(data)
+    }
+
+    fn records() -> Vec<Vec<Field>> {
+        vec![
+            vec![Field::String("Buffon".to_string()), Field::Int(77)],
+            vec![Field::String("Perin".to_string()), Field::Null],
+        ]
+    }
+
+    #[test]
+    fn test_text_formats() -> Result<()> {
+        let expected = serde_json::json!([
+            {"name": "Buffon", "kit": 77},
+            {"name": "Perin", "kit": null}
+        ]);
+        let json = String::from_utf8(convert(OutputFormat::Json, &records())?)?;
+        assert_eq!(
+            json,
+            "[\n  {\n    \"name\": \"Buffon\",\n    \"kit\": 77\n  },\n  \
+             {\n    \"name\": \"Perin\",\n    \"kit\": null\n  }\n]"
+        );
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&json)?, expected);
+        let yaml = convert(OutputFormat::Yaml, &records())?;
+        assert_eq!(
+            serde_yaml::from_slice::<serde_json::Value>(&yaml)?,
+            expected
+        );
+        assert_eq!(convert(OutputFormat::Json, &[])?, b"[]");
+        assert_eq!(convert(OutputFormat::Yaml, &[])?, b"[]\n");
+
+        let ndjson = String::from_utf8(convert(OutputFormat::Ndjson, &records())?)?;
+        assert_eq!(
+            ndjson,
+            "{\"name\":\"Buffon\",\"kit\":77}\n{\"name\":\"Perin\",\"kit\":null}\n"
+        );
+
+        let toml = String::from_utf8(convert(OutputFormat::Toml, &records())?)?;
+        let table: toml::Table = toml.parse()?;
+        let rows = table["records"].as_array().unwrap();
+        assert_eq!(rows[0]["kit"].as_integer(), Some(77));
+        assert!(rows[1].get("kit").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_formats() -> Result<()> {
+        let msgpack = convert(OutputFormat::MsgPack, &records())?;
+        let mut de = rmp_serde::Deserializer::new(&msgpack[..]);
+        let first: serde_json::Value = serde::Deserialize::deserialize(&mut de)?;
+        assert_eq!(first, serde_json::json!({"name": "Buffon", "kit": 77}));
+
+        let parquet = convert(OutputFormat::Parquet, &records())?;
+        let dir = TestDir::new()?;
+        let path = dir.write("output.parquet", parquet)?;
+        let reader = SerializedFileReader::new(std::fs::File::open(path)?)?;
+        let metadata = reader.metadata();
+        assert_eq!(metadata.file_metadata().num_rows(), 2);
+        assert_eq!(metadata.file_metadata().schema_descr().num_columns(), 2);
+
+        let mismatch = vec![vec![
+            Field::String("x".to_string()),
+            Field::String("y".to_string()),
+        ]];
+        assert!(convert(OutputFormat::Parquet, &mismatch).is_err());
+        Ok(())
+    }
+}
@@ -1,46 +1,357 @@
-use rand::seq::SliceRandom;
+use anyhow::{anyhow, Result};
+use rand::{seq::SliceRandom, Rng};
+use std::collections::HashSet;
 
-const UPPER: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
-const LOWER: &[u8] = b"abcdefghijkmnopqrstuvwxyz";
-const NUMBER: &[u8] = b"123456789";
+const UPPER: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWER: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const NUMBER: &[u8] = b"0123456789";
 const SYMBOL: &[u8] = b"!@#$%^&*_";
+const VOWELS: &[u8] = b"aeiouAEIOU";
+// easily mistaken for one another
+const AMBIGUOUS: &[u8] = b"IOl0";
+
+// the EFF large word list, 7776 words or 12.9 bits each, see assets/README.md
+const WORDLIST: &str = include_str!("../../assets/eff_large_wordlist.txt");
+
+pub enum PasswordKind<'a> {
+    // random characters, at least one of each enabled class
+    Chars {
+        length: u8,
+        upper: bool,
+        lower: bool,
+        number: bool,
+        symbol: bool,
+    },
+    // a template such as Cvccvc99!, see `pattern_class`
+    Pattern(&'a str),
+    // diceware: words drawn from a list, the default one if None
+    Passphrase {
+        words: usize,
+        separator: &'a str,
+        wordlist: Option<&'a str>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Password {
+    pub password: String,
+    // log2 of the number of passwords the generator could have drawn, all equally likely
+    pub entropy: f64,
+}
 
 pub fn process_genpass(
-    length: u8,
-    upper: bool,
-    lower: bool,
-    number: bool,
-    symbol: bool,
-) -> anyhow::Result<String> {
+    kind: &PasswordKind,
+    exclude_ambiguous: bool,
+    count: usize,
+) -> Result<Vec<Password>> {
     let mut rng = rand::thread_rng();
-    let mut password = Vec::new();
-    let mut chars = Vec::new();
+    let filter = |chars: &[u8]| -> Vec<u8> {
+        chars
+            .iter()
+            .copied()
+            .filter(|c| !exclude_ambiguous || !AMBIGUOUS.contains(c))
+            .collect()
+    };
+
+    match kind {
+        PasswordKind::Chars {
+            length,
+            upper,
+            lower,
+            number,
+            symbol,
+        } => {
+            let classes = [
+                (*upper, UPPER),
+                (*lower, LOWER),
+                (*number, NUMBER),
+                (*symbol, SYMBOL),
+            ]
+            .into_iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, chars)| filter(chars))
+            .collect::<Vec<_>>();
+            if classes.is_empty() {
+                return Err(anyhow!("At least one character class must be enabled"));
+            }
+            let length = *length as usize;
+            if length < classes.len() {
+                return Err(anyhow!(
+                    "Length must be at least {}, one character of each enabled class",
+                    classes.len()
+                ));
+            }
+            let entropy = chars_entropy(&classes, length);
+            let chars = classes.concat();
+            (0..count)
+                .map(|_| {
+                    let password = random_chars(&mut rng, &chars, &classes, length);
+                    Ok(Password {
+                        password: String::from_utf8(password)?,
+                        entropy,
+                    })
+                })
+                .collect()
+        }
+        PasswordKind::Pattern(pattern) => {
+            let template = parse_pattern(pattern, &filter)?;
+            let entropy = template
+                .iter()
+                .map(|chars| (chars.len() as f64).log2())
+                .sum();
+            (0..count)
+                .map(|_| {
+                    let password = template
+                        .iter()
+                        .map(|chars| *chars.choose(&mut rng).expect("classes aren't empty"))
+                        .collect();
+                    Ok(Password {
+                        password: String::from_utf8(password)?,
+                        entropy,
+                    })
+                })
+                .collect()
+        }
+        PasswordKind::Passphrase {
+            words,
+            separator,
+            wordlist,
+        } => {
+            if *words == 0 {
+                return Err(anyhow!("A passphrase needs at least one word"));
+            }
+            let list = parse_wordlist(wordlist.unwrap_or(WORDLIST))?;
+            let entropy = *words as f64 * (list.len() as f64).log2();
+            let passwords = (0..count).map(|_| {
+                let password = (0..*words)
+                    .map(|_| *list.choose(&mut rng).expect("the list isn't empty"))
+                    .collect::<Vec<_>>()
+                    .join(separator);
+                Password { password, entropy }
+            });
+            Ok(passwords.collect())
+        }
+    }
+}
+
+// draws until the password holds a character of each class, which keeps every such
+// password equally likely, unlike placing one of each and shuffling
+fn random_chars(rng: &mut impl Rng, chars: &[u8], classes: &[Vec<u8>], length: usize) -> Vec<u8> {
+    loop {
+        let password = (0..length)
+            .map(|_| {
+                *chars
+                    .choose(rng)
+                    .expect("chars won't be empty in this context")
+            })
+            .collect::<Vec<_>>();
+        let complete = classes
+            .iter()
+            .all(|class| password.iter().any(|c| class.contains(c)));
+        if complete {
+            return password;
+        }
+    }
+}
+
+// log2 of the number of passwords of `length` holding a character of each class, counted
+// by inclusion-exclusion over the classes left out, as a ratio to pool^length so that it
+// doesn't overflow
+fn chars_entropy(classes: &[Vec<u8>], length: usize) -> f64 {
+    let pool = classes.iter().map(Vec::len).sum::<usize>() as f64;
+    let mut ratio = 0.0;
+    for subset in 0..(1u32 << classes.len()) {
+        let missing = (0..classes.len())
+            .filter(|i| subset & (1 << i) != 0)
+            .map(|i| classes[i].len())
+            .sum::<usize>() as f64;
+        let sign = if subset.count_ones() % 2 == 0 {
+            1.0
+        } else {
+            -1.0
+        };
+        ratio += sign * ((pool - missing) / pool).powi(length as i32);
+    }
+    length as f64 * pool.log2() + ratio.log2()
+}
+
+// the characters each position of the pattern is drawn from:
+//   C / c  an upper / lower case consonant
+//   V / v  an upper / lower case vowel
+//   A / a  an upper / lower case letter
+//   9      a digit
+//   !      a symbol
+//   *      any of the above
+//   \x     x itself, as is any other character
+fn parse_pattern(pattern: &str, filter: &impl Fn(&[u8]) -> Vec<u8>) -> Result<Vec<Vec<u8>>> {
+    if pattern.is_empty() {
+        return Err(anyhow!("Pattern is empty"));
+    }
+    let mut template = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let class = match c {
+            '\\' => {
+                let c = chars
+                    .next()
+                    .ok_or_else(|| anyhow!("Pattern ends with an escape"))?;
+                vec![literal(c)?]
+            }
+            _ => match pattern_class(c) {
+                Some(class) => filter(&class),
+                None => vec![literal(c)?],
+            },
+        };
+        template.push(class);
+    }
+    Ok(template)
+}
+
+fn pattern_class(c: char) -> Option<Vec<u8>> {
+    let consonant = |c: &u8| !VOWELS.contains(c);
+    let class = match c {
+        'C' => UPPER.iter().copied().filter(consonant).collect(),
+        'c' => LOWER.iter().copied().filter(consonant).collect(),
+        'V' => UPPER
+            .iter()
+            .copied()
+            .filter(|c| VOWELS.contains(c))
+            .collect(),
+        'v' => LOWER
+            .iter()
+            .copied()
+            .filter(|c| VOWELS.contains(c))
+            .collect(),
+        'A' => UPPER.to_vec(),
+        'a' => LOWER.to_vec(),
+        '9' => NUMBER.to_vec(),
+        '!' => SYMBOL.to_vec(),
+        '*' => [UPPER, LOWER, NUMBER, SYMBOL].concat(),
+        _ => return None,
+    };
+    Some(class)
+}
 
-    if upper {
-        chars.extend_from_slice(UPPER);
-        password.push(*UPPER.choose(&mut rng).expect("UPPER won't be empty"));
+fn literal(c: char) -> Result<u8> {
+    if c.is_ascii() {
+        Ok(c as u8)
+    } else {
+        Err(anyhow!("Pattern must be ASCII, found {}", c))
     }
-    if lower {
-        chars.extend_from_slice(LOWER);
-        password.push(*LOWER.choose(&mut rng).expect("LOWER won't be empty"));
+}
+
+// one word per line, optionally after its dice roll as in the EFF lists ("11111\tabacus");
+// a word listed twice would only lower the entropy, so it's kept once
+fn parse_wordlist(text: &str) -> Result<Vec<&str>> {
+    let mut seen = HashSet::new();
+    let words = text
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .filter(|word| seen.insert(*word))
+        .collect::<Vec<_>>();
+    if words.len() < 2 {
+        return Err(anyhow!("Word list needs at least two words"));
     }
-    if number {
-        chars.extend_from_slice(NUMBER);
-        password.push(*NUMBER.choose(&mut rng).expect("NUMBER won't be empty"));
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(
+        length: u8,
+        upper: bool,
+        lower: bool,
+        number: bool,
+        symbol: bool,
+    ) -> PasswordKind<'static> {
+        PasswordKind::Chars {
+            length,
+            upper,
+            lower,
+            number,
+            symbol,
+        }
     }
-    if symbol {
-        chars.extend_from_slice(SYMBOL);
-        password.push(*SYMBOL.choose(&mut rng).expect("SYMBOL won't be empty"));
+
+    #[test]
+    fn test_genpass_chars() -> Result<()> {
+        let passwords = process_genpass(&chars(4, true, true, true, true), true, 20)?;
+        assert_eq!(passwords.len(), 20);
+        for Password { password, .. } in &passwords {
+            assert_eq!(password.len(), 4);
+            assert!(password.bytes().any(|c| c.is_ascii_uppercase()));
+            assert!(password.bytes().any(|c| c.is_ascii_lowercase()));
+            assert!(password.bytes().any(|c| c.is_ascii_digit()));
+            assert!(password.bytes().any(|c| SYMBOL.contains(&c)));
+            assert!(!password.bytes().any(|c| AMBIGUOUS.contains(&c)));
+        }
+
+        // used to overflow
+        assert!(process_genpass(&chars(3, true, true, true, true), false, 1).is_err());
+        assert!(process_genpass(&chars(8, false, false, false, false), false, 1).is_err());
+        Ok(())
     }
 
-    for _ in 0..(length - password.len() as u8) {
-        let c = chars
-            .choose(&mut rng)
-            .expect("chars won't be empty in this context");
-        password.push(*c);
+    #[test]
+    fn test_chars_entropy() {
+        // a single class is every string over it
+        assert_eq!(chars_entropy(&[NUMBER.to_vec()], 4), 4.0 * 10f64.log2());
+        // 2 digits from {0, 1} holding both: 01 and 10
+        assert!((chars_entropy(&[b"0".to_vec(), b"1".to_vec()], 2) - 1.0).abs() < 1e-9);
+        // a long password hardly loses anything to the classes
+        let classes = [
+            UPPER.to_vec(),
+            LOWER.to_vec(),
+            NUMBER.to_vec(),
+            SYMBOL.to_vec(),
+        ];
+        let entropy = chars_entropy(&classes, 16);
+        assert!(entropy < 16.0 * 71f64.log2() && entropy > 16.0 * 71f64.log2() - 1.0);
     }
 
-    password.shuffle(&mut rng);
+    #[test]
+    fn test_genpass_pattern() -> Result<()> {
+        let passwords = process_genpass(&PasswordKind::Pattern("Cvccvc99!-\\9"), false, 10)?;
+        for Password { password, entropy } in passwords {
+            let bytes = password.as_bytes();
+            assert_eq!(bytes.len(), 11);
+            assert!(bytes[0].is_ascii_uppercase() && !VOWELS.contains(&bytes[0]));
+            assert!(VOWELS.contains(&bytes[1]) && bytes[1].is_ascii_lowercase());
+            assert!(bytes[6].is_ascii_digit() && bytes[7].is_ascii_digit());
+            assert!(SYMBOL.contains(&bytes[8]));
+            assert_eq!(&password[9..], "-9");
+            let expected =
+                21f64.log2() * 4.0 + 5f64.log2() * 2.0 + 10f64.log2() * 2.0 + 9f64.log2();
+            assert!((entropy - expected).abs() < 1e-9);
+        }
+        assert!(process_genpass(&PasswordKind::Pattern("9\\"), false, 1).is_err());
+        assert!(process_genpass(&PasswordKind::Pattern(""), false, 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_genpass_passphrase() -> Result<()> {
+        let kind = PasswordKind::Passphrase {
+            words: 6,
+            separator: " ",
+            wordlist: None,
+        };
+        let passwords = process_genpass(&kind, false, 2)?;
+        let words = parse_wordlist(WORDLIST)?;
+        assert_eq!(words.len(), 7776);
+        assert_eq!(words[0], "abacus");
+        for Password { password, entropy } in passwords {
+            assert_eq!(password.split(' ').count(), 6);
+            assert!(password.split(' ').all(|word| words.contains(&word)));
+            assert!((entropy - 6.0 * 7776f64.log2()).abs() < 1e-9);
+        }
 
-    Ok(String::from_utf8(password)?)
+        // the format of the EFF lists
+        let words = parse_wordlist("11111\tabacus\n11112\tabdomen\n\n11113\tabacus\n")?;
+        assert_eq!(words, vec!["abacus", "abdomen"]);
+        assert!(parse_wordlist("abacus\n").is_err());
+        Ok(())
+    }
 }
@@ -1,13 +1,30 @@
 mod b64;
 mod csv_convert;
+mod csv_from;
+mod csv_query;
+mod csv_rows;
+mod csv_schema;
+mod csv_sort;
+mod csv_stats;
+mod csv_validate;
+mod csv_writer;
 mod gen_pass;
 mod http_serve;
 mod jwt;
 mod text;
 
+#[cfg(test)]
+mod test_utils;
+
 pub use b64::{process_decode, process_encode};
-pub use csv_convert::process_csv;
-pub use gen_pass::process_genpass;
+pub use csv_convert::{process_csv, CsvDialect};
+pub use csv_from::process_csv_from;
+pub use csv_query::process_csv_query;
+pub use csv_rows::{process_csv_dedupe, process_csv_head, process_csv_select, process_csv_tail};
+pub use csv_sort::{process_csv_sort, SortOpts};
+pub use csv_stats::process_csv_stats;
+pub use csv_validate::process_csv_validate;
+pub use gen_pass::{process_genpass, PasswordKind};
 pub use http_serve::process_http_serve;
 pub use jwt::{process_jwt_sign, process_jwt_verify};
 pub use text::{
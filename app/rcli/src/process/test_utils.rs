@@ -0,0 +1,32 @@
+use anyhow::Result;
+use std::fs;
+use tempfile::TempDir;
+
+// a temp dir for the input and output files of a test, removed when dropped
+pub struct TestDir(TempDir);
+
+impl TestDir {
+    pub fn new() -> Result<Self> {
+        Ok(Self(tempfile::tempdir()?))
+    }
+
+    // the path of a file in the dir, as the process functions take it
+    pub fn path(&self, name: &str) -> String {
+        self.0.path().join(name).to_str().unwrap().to_string()
+    }
+
+    // writes a file and returns its path
+    pub fn write(&self, name: &str, data: impl AsRef<[u8]>) -> Result<String> {
+        let path = self.path(name);
+        fs::write(&path, data)?;
+        Ok(path)
+    }
+
+    pub fn read(&self, name: &str) -> Result<Vec<u8>> {
+        Ok(fs::read(self.path(name))?)
+    }
+
+    pub fn read_to_string(&self, name: &str) -> Result<String> {
+        Ok(fs::read_to_string(self.path(name))?)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{process_genpass, TextEncryptFormat, TextSignFormat};
+use crate::{process_genpass, PasswordKind, TextEncryptFormat, TextSignFormat};
 use anyhow::Result;
 use chacha20poly1305::{
     aead::Aead, AeadCore, ChaCha20Poly1305, ChaChaPoly1305, Key, KeyInit, Nonce,
@@ -121,9 +121,16 @@ impl Blake3 {
     }
 
     fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
-        let key = process_genpass(32, true, true, true, true)?;
+        let kind = PasswordKind::Chars {
+            length: 32,
+            upper: true,
+            lower: true,
+            number: true,
+            symbol: true,
+        };
+        let key = process_genpass(&kind, false, 1)?.remove(0).password;
         let mut map = HashMap::new();
-        map.insert("blake3.txt", key.as_bytes().to_vec());
+        map.insert("blake3.txt", key.into_bytes());
         Ok(map)
     }
 }
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use criterion::{criterion_group, criterion_main, Criterion};
+use simple_redis::{client::Client, network, shard::Shards, Backend};
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+use tokio::{net::TcpListener, runtime::Runtime};
+
+// multi-client load: every client runs SET + GET on its own keys, over its own connection
+const CLIENTS: usize = 32;
+const OPS_PER_CLIENT: usize = 200;
+
+async fn start_shared() -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let backend = Backend::new();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            stream.set_nodelay(true).unwrap();
+            tokio::spawn(network::stream_handler(stream, backend.clone()));
+        }
+    });
+    Ok(addr)
+}
+
+async fn start_sharded(n: usize) -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let shards = Shards::new(n);
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            stream.set_nodelay(true).unwrap();
+            tokio::spawn(network::sharded_stream_handler(stream, shards.clone()));
+        }
+    });
+    Ok(addr)
+}
+
+async fn load(clients: &mut [Client]) -> Duration {
+    let start = Instant::now();
+    let tasks = clients
+        .iter_mut()
+        .enumerate()
+        .map(|(i, client)| async move {
+            for j in 0..OPS_PER_CLIENT {
+                let key = format!("key:{}:{}", i, j % 16);
+                client.set(&key, "value").await.unwrap();
+                client.get(&key).await.unwrap();
+            }
+        });
+    futures::future::join_all(tasks).await;
+    start.elapsed()
+}
+
+fn bench(c: &mut Criterion, name: &str, rt: &Runtime, addr: SocketAddr) {
+    let mut clients = rt.block_on(async {
+        let mut clients = Vec::with_capacity(CLIENTS);
+        for _ in 0..CLIENTS {
+            clients.push(Client::connect(addr).await.unwrap());
+        }
+        clients
+    });
+    c.bench_function(name, |b| {
+        b.iter_custom(|iters| (0..iters).map(|_| rt.block_on(load(&mut clients))).sum())
+    });
+}
+
+fn multi_client(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let addr = rt.block_on(start_shared()).unwrap();
+    bench(c, "shared backend", &rt, addr);
+
+    let shards = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let addr = rt.block_on(start_sharded(shards)).unwrap();
+    bench(c, &format!("{} shards", shards), &rt, addr);
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = multi_client
+}
+criterion_main!(benches);
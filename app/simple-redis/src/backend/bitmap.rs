@@ -0,0 +1,462 @@
+use super::Backend;
+
+// like redis proto-max-bulk-len, bit offsets address at most 512MB
+pub(crate) const MAX_BIT_OFFSET: u64 = 512 * 1024 * 1024 * 8 - 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitUnit {
+    Byte,
+    Bit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOpKind {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+// a BITFIELD integer type: i1..i64 or u1..u63
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitFieldType {
+    pub signed: bool,
+    pub bits: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    Wrap,
+    Sat,
+    Fail,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitFieldOp {
+    Get(BitFieldType, u64),
+    Set(BitFieldType, u64, i64),
+    IncrBy(BitFieldType, u64, i64),
+    Overflow(Overflow),
+}
+
+impl Backend {
+    // SETBIT, returns the previous bit
+    pub fn setbit(&self, key: &str, offset: u64, bit: bool) -> Result<bool, String> {
+        self.modify_bytes(key, "setbit", |data, _| {
+            let old = get_bit(data, offset);
+            let len = data.len();
+            set_bit(data, offset, bit);
+            Ok((old, old != bit || data.len() != len))
+        })
+    }
+
+    pub fn getbit(&self, key: &str, offset: u64) -> Result<bool, String> {
+        self.read_bytes(key, |data| get_bit(data.unwrap_or_default(), offset))
+    }
+
+    pub fn bitcount(&self, key: &str, range: Option<(i64, i64, BitUnit)>) -> Result<u64, String> {
+        self.read_bytes(key, |data| bit_count(data.unwrap_or_default(), range))
+    }
+
+    pub fn bitpos(
+        &self,
+        key: &str,
+        bit: bool,
+        start: Option<i64>,
+        end: Option<i64>,
+        unit: BitUnit,
+    ) -> Result<i64, String> {
+        self.read_bytes(key, |data| match data {
+            Some(data) => bit_pos(data, bit, start, end, unit),
+            // a missing key is an empty string, padded with zeros on the right
+            None if bit => -1,
+            None => 0,
+        })
+    }
+
+    // BITOP, returns the length of the destination string; an empty result deletes it
+    pub fn bitop(&self, op: BitOpKind, dest: &str, keys: &[String]) -> Result<usize, String> {
+        let srcs = keys
+            .iter()
+            .map(|key| Ok(self.get_bytes(key)?.unwrap_or_default()))
+            .collect::<Result<Vec<_>, String>>()?;
+        let result = bit_op(op, &srcs);
+        let len = result.len();
+        if result.is_empty() {
+            self.del(&[dest]);
+        } else {
+            self.modify_bytes(dest, "set", |data, _| {
+                *data = result;
+                Ok(((), true))
+            })?;
+        }
+        Ok(len)
+    }
+
+    // BITFIELD, one reply per GET / SET / INCRBY, None when an overflow FAILs
+    pub fn bitfield(&self, key: &str, ops: &[BitFieldOp]) -> Result<Vec<Option<i64>>, String> {
+        let writes = ops
+            .iter()
+            .any(|op| matches!(op, BitFieldOp::Set(..) | BitFieldOp::IncrBy(..)));
+        if !writes {
+            return self.read_bytes(key, |data| {
+                let data = data.unwrap_or_default();
+                ops.iter()
+                    .filter_map(|op| match *op {
+                        BitFieldOp::Get(ty, offset) => Some(Some(get_field(data, offset, ty))),
+                        _ => None,
+                    })
+                    .collect()
+            });
+        }
+        self.modify_bytes(key, "setbit", |data, _| {
+            let mut overflow = Overflow::Wrap;
+            let mut replies = Vec::new();
+            let mut modified = false;
+            for op in ops {
+                match *op {
+                    BitFieldOp::Overflow(o) => overflow = o,
+                    BitFieldOp::Get(ty, offset) => replies.push(Some(get_field(data, offset, ty))),
+                    BitFieldOp::Set(ty, offset, value) => {
+                        let old = get_field(data, offset, ty);
+                        let new = handle_overflow(value as i128, ty, overflow);
+                        if let Some(new) = new {
+                            set_field(data, offset, ty, new);
+                            modified = true;
+                        }
+                        replies.push(new.map(|_| old));
+                    }
+                    BitFieldOp::IncrBy(ty, offset, incr) => {
+                        let old = get_field(data, offset, ty);
+                        let new = handle_overflow(old as i128 + incr as i128, ty, overflow);
+                        if let Some(new) = new {
+                            set_field(data, offset, ty, new);
+                            modified = true;
+                        }
+                        replies.push(new);
+                    }
+                }
+            }
+            Ok((replies, modified))
+        })
+    }
+}
+
+impl BitFieldType {
+    // i1..i64 or u1..u63, case insensitive
+    pub fn parse(s: &str) -> Option<Self> {
+        let signed = match s.as_bytes().first()? {
+            b'i' | b'I' => true,
+            b'u' | b'U' => false,
+            _ => return None,
+        };
+        let bits: u8 = s[1..].parse().ok()?;
+        let max = if signed { 64 } else { 63 };
+        (1..=max).contains(&bits).then_some(Self { signed, bits })
+    }
+
+    fn range(&self) -> (i128, i128) {
+        if self.signed {
+            (-(1i128 << (self.bits - 1)), (1i128 << (self.bits - 1)) - 1)
+        } else {
+            (0, (1i128 << self.bits) - 1)
+        }
+    }
+}
+
+pub(crate) fn get_bit(data: &[u8], offset: u64) -> bool {
+    let byte = (offset / 8) as usize;
+    data.get(byte)
+        .is_some_and(|b| b & (0x80 >> (offset % 8)) != 0)
+}
+
+// the string grows as needed, padded with zeros
+pub(crate) fn set_bit(data: &mut Vec<u8>, offset: u64, bit: bool) {
+    let byte = (offset / 8) as usize;
+    if byte >= data.len() {
+        data.resize(byte + 1, 0);
+    }
+    let mask = 0x80 >> (offset % 8);
+    if bit {
+        data[byte] |= mask;
+    } else {
+        data[byte] &= !mask;
+    }
+}
+
+// redis range semantics: negative indexes count from the end, the range is clamped to
+// the string and `None` means it's empty
+fn normalize_range(start: i64, end: i64, len: i64) -> Option<(i64, i64)> {
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let end = if end < 0 { (len + end).max(0) } else { end };
+    let end = end.min(len - 1);
+    (len > 0 && start <= end).then_some((start, end))
+}
+
+// the bit range covered by a BITCOUNT / BITPOS range
+fn bit_range(data: &[u8], start: i64, end: i64, unit: BitUnit) -> Option<(u64, u64)> {
+    let len = data.len() as i64;
+    match unit {
+        BitUnit::Byte => {
+            normalize_range(start, end, len).map(|(s, e)| (s as u64 * 8, e as u64 * 8 + 7))
+        }
+        BitUnit::Bit => normalize_range(start, end, len * 8).map(|(s, e)| (s as u64, e as u64)),
+    }
+}
+
+pub(crate) fn bit_count(data: &[u8], range: Option<(i64, i64, BitUnit)>) -> u64 {
+    let (start, end) = match range {
+        Some((start, end, unit)) => match bit_range(data, start, end, unit) {
+            Some(range) => range,
+            None => return 0,
+        },
+        None if data.is_empty() => return 0,
+        None => (0, data.len() as u64 * 8 - 1),
+    };
+    let (first, last) = ((start / 8) as usize, (end / 8) as usize);
+    let mut count: u64 = data[first..=last]
+        .iter()
+        .map(|b| b.count_ones() as u64)
+        .sum();
+    // remove the bits of the partial first and last bytes that are out of the range
+    let head = (start % 8) as u32;
+    if head > 0 {
+        count -= (data[first] >> (8 - head)).count_ones() as u64;
+    }
+    let tail = 7 - (end % 8) as u32;
+    if tail > 0 {
+        count -= (data[last] & ((1u8 << tail) - 1)).count_ones() as u64;
+    }
+    count
+}
+
+pub(crate) fn bit_pos(
+    data: &[u8],
+    bit: bool,
+    start: Option<i64>,
+    end: Option<i64>,
+    unit: BitUnit,
+) -> i64 {
+    let end_given = end.is_some();
+    let Some((start, end)) = bit_range(data, start.unwrap_or(0), end.unwrap_or(-1), unit) else {
+        // an empty string is all zeros for BITPOS key 0 without an explicit end
+        return if !bit && !end_given && data.is_empty() {
+            0
+        } else {
+            -1
+        };
+    };
+
+    // whole bytes that can't contain the bit are skipped
+    let skip = if bit { 0x00 } else { 0xff };
+    let mut pos = start;
+    while pos <= end {
+        if pos % 8 == 0 && pos + 7 <= end && data[(pos / 8) as usize] == skip {
+            pos += 8;
+            continue;
+        }
+        if get_bit(data, pos) == bit {
+            return pos as i64;
+        }
+        pos += 1;
+    }
+    // looking for a clear bit without an explicit end: the string is zero padded
+    if !bit && !end_given {
+        return end as i64 + 1;
+    }
+    -1
+}
+
+// strings shorter than the longest one are zero padded
+pub(crate) fn bit_op(op: BitOpKind, srcs: &[Vec<u8>]) -> Vec<u8> {
+    let len = srcs.iter().map(Vec::len).max().unwrap_or(0);
+    if op == BitOpKind::Not {
+        return srcs
+            .first()
+            .map(|src| src.iter().map(|b| !b).collect())
+            .unwrap_or_default();
+    }
+    (0..len)
+        .map(|i| {
+            let mut bytes = srcs.iter().map(|src| src.get(i).copied().unwrap_or(0));
+            let first = bytes.next().unwrap_or(0);
+            bytes.fold(first, |acc, b| match op {
+                BitOpKind::And => acc & b,
+                BitOpKind::Or => acc | b,
+                BitOpKind::Xor => acc ^ b,
+                BitOpKind::Not => unreachable!(),
+            })
+        })
+        .collect()
+}
+
+fn get_field(data: &[u8], offset: u64, ty: BitFieldType) -> i64 {
+    let raw =
+        (0..ty.bits as u64).fold(0u64, |acc, i| (acc << 1) | get_bit(data, offset + i) as u64);
+    if ty.signed && ty.bits < 64 && raw & (1 << (ty.bits - 1)) != 0 {
+        // sign extension
+        (raw | (u64::MAX << ty.bits)) as i64
+    } else {
+        raw as i64
+    }
+}
+
+fn set_field(data: &mut Vec<u8>, offset: u64, ty: BitFieldType, value: i64) {
+    let value = value as u64;
+    for i in 0..ty.bits as u64 {
+        let bit = value & (1 << (ty.bits as u64 - 1 - i)) != 0;
+        set_bit(data, offset + i, bit);
+    }
+}
+
+// the value to store, or None if it overflows with OVERFLOW FAIL
+fn handle_overflow(value: i128, ty: BitFieldType, overflow: Overflow) -> Option<i64> {
+    let (min, max) = ty.range();
+    if (min..=max).contains(&value) {
+        return Some(value as i64);
+    }
+    match overflow {
+        Overflow::Fail => None,
+        Overflow::Sat => Some(value.clamp(min, max) as i64),
+        Overflow::Wrap => {
+            let wrapped = value.rem_euclid(1i128 << ty.bits);
+            if ty.signed && wrapped > max {
+                Some((wrapped - (1i128 << ty.bits)) as i64)
+            } else {
+                Some(wrapped as i64)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_get_bit() {
+        let mut data = Vec::new();
+        set_bit(&mut data, 7, true);
+        assert_eq!(data, vec![0x01]);
+        set_bit(&mut data, 9, true);
+        assert_eq!(data, vec![0x01, 0x40]);
+        assert!(get_bit(&data, 9));
+        assert!(!get_bit(&data, 100));
+    }
+
+    #[test]
+    fn test_bit_count() {
+        let data = b"foobar";
+        assert_eq!(bit_count(data, None), 26);
+        assert_eq!(bit_count(data, Some((0, 0, BitUnit::Byte))), 4);
+        assert_eq!(bit_count(data, Some((1, 1, BitUnit::Byte))), 6);
+        assert_eq!(bit_count(data, Some((1, 1, BitUnit::Byte))), 6);
+        assert_eq!(bit_count(data, Some((5, 30, BitUnit::Bit))), 17);
+        assert_eq!(bit_count(data, Some((-2, -1, BitUnit::Byte))), 7);
+        assert_eq!(bit_count(data, Some((3, 1, BitUnit::Byte))), 0);
+    }
+
+    #[test]
+    fn test_bit_pos() {
+        let data = [0xff, 0xf0, 0x00];
+        assert_eq!(bit_pos(&data, false, None, None, BitUnit::Byte), 12);
+        assert_eq!(
+            bit_pos(&[0x00, 0xff, 0xf0], true, Some(0), None, BitUnit::Byte),
+            8
+        );
+        assert_eq!(
+            bit_pos(&[0x00, 0xff, 0xf0], true, Some(2), None, BitUnit::Byte),
+            16
+        );
+        assert_eq!(
+            bit_pos(&[0x00, 0xff, 0xf0], true, Some(2), Some(-1), BitUnit::Byte),
+            16
+        );
+        assert_eq!(
+            bit_pos(&[0x00, 0xff, 0xf0], true, Some(7), Some(15), BitUnit::Bit),
+            8
+        );
+        assert_eq!(
+            bit_pos(&[0x00, 0x00, 0x00], true, None, None, BitUnit::Byte),
+            -1
+        );
+        // all ones: the first clear bit is right after the string, unless the end is given
+        assert_eq!(bit_pos(&[0xff, 0xff], false, None, None, BitUnit::Byte), 16);
+        assert_eq!(
+            bit_pos(&[0xff, 0xff], false, Some(0), Some(-1), BitUnit::Byte),
+            -1
+        );
+    }
+
+    #[test]
+    fn test_bit_op() {
+        let srcs = vec![b"abc".to_vec(), b"a".to_vec()];
+        assert_eq!(bit_op(BitOpKind::And, &srcs), vec![b'a', 0, 0]);
+        assert_eq!(bit_op(BitOpKind::Or, &srcs), b"abc".to_vec());
+        assert_eq!(bit_op(BitOpKind::Xor, &srcs), vec![0, b'b', b'c']);
+        assert_eq!(bit_op(BitOpKind::Not, &[vec![0x0f]]), vec![0xf0]);
+    }
+
+    #[test]
+    fn test_bitfield() {
+        let backend = Backend::new();
+        let u8t = BitFieldType::parse("u8").unwrap();
+        let i5 = BitFieldType::parse("i5").unwrap();
+        assert_eq!(BitFieldType::parse("u64"), None);
+        assert_eq!(BitFieldType::parse("i0"), None);
+
+        let ret = backend
+            .bitfield(
+                "bf",
+                &[
+                    BitFieldOp::IncrBy(i5, 100, 1),
+                    BitFieldOp::Get(u8t, 0),
+                    BitFieldOp::Set(u8t, 0, 255),
+                    BitFieldOp::IncrBy(u8t, 0, 10),
+                    BitFieldOp::Overflow(Overflow::Sat),
+                    BitFieldOp::IncrBy(u8t, 0, 300),
+                    BitFieldOp::Overflow(Overflow::Fail),
+                    BitFieldOp::IncrBy(i5, 100, 15),
+                    BitFieldOp::Overflow(Overflow::Wrap),
+                    BitFieldOp::IncrBy(i5, 100, 15),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            ret,
+            vec![
+                Some(1),
+                Some(0),
+                Some(0),
+                Some(9),
+                Some(255),
+                None,
+                Some(-16)
+            ]
+        );
+        assert_eq!(
+            backend.bitfield("bf", &[BitFieldOp::Get(i5, 100)]).unwrap(),
+            vec![Some(-16)]
+        );
+    }
+
+    #[test]
+    fn test_bitmap_wrong_type() {
+        let backend = Backend::new();
+        backend.hset(
+            "h".to_string(),
+            "f".to_string(),
+            crate::RespFrame::Integer(1),
+        );
+        assert!(backend.setbit("h", 1, true).is_err());
+        assert!(!backend.setbit("s", 1, true).unwrap());
+        assert!(backend.setbit("s", 1, false).unwrap());
+        assert_eq!(backend.bitop(BitOpKind::Or, "d", &["s".to_string()]), Ok(1));
+        assert_eq!(backend.bitop(BitOpKind::Or, "d", &["x".to_string()]), Ok(0));
+        assert_eq!(backend.get("d"), None);
+    }
+}
@@ -0,0 +1,602 @@
+use super::Backend;
+use crate::{cmd::command_keys, RespFrame, SimpleError};
+use rand::Rng;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::sync::atomic::Ordering;
+use std::sync::{Mutex, RwLock};
+
+pub const CLUSTER_SLOTS: usize = 16384;
+
+// the cluster bus port is always the client port + 10000, it's only advertised for
+// compatibility, nodes talk to each other over the client port (see `cluster::gossip`)
+const BUS_PORT_OFFSET: u32 = 10000;
+
+// cluster mode state. Every node is authoritative for the slots it serves: nodes
+// periodically pull `CLUSTER NODES` from their peers, learn about unknown nodes, and take
+// the slots each peer claims for itself. There are no replicas and no failover.
+#[derive(Debug)]
+pub struct Cluster {
+    myself: ClusterNode,
+    state: RwLock<ClusterState>,
+    // addresses given to CLUSTER MEET that haven't been contacted yet
+    meet: Mutex<BTreeSet<(String, u16)>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterNode {
+    pub id: String,
+    pub ip: String,
+    pub port: u16,
+}
+
+#[derive(Debug)]
+struct ClusterState {
+    nodes: BTreeMap<String, ClusterNode>,
+    // owner node id of every slot
+    slots: Vec<Option<String>>,
+    // slot -> target node id, set on the node the slot is moving away from
+    migrating: BTreeMap<u16, String>,
+    // slot -> source node id, set on the node the slot is moving to
+    importing: BTreeMap<u16, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetSlot {
+    Importing(String),
+    Migrating(String),
+    Node(String),
+    Stable,
+}
+
+// a node entry parsed from CLUSTER NODES output
+#[derive(Debug, PartialEq, Eq)]
+pub struct NodeInfo {
+    pub node: ClusterNode,
+    pub myself: bool,
+    pub slots: Vec<u16>,
+}
+
+impl Cluster {
+    pub fn new(ip: impl Into<String>, port: u16) -> Self {
+        let myself = ClusterNode {
+            id: random_node_id(),
+            ip: ip.into(),
+            port,
+        };
+        let nodes = BTreeMap::from([(myself.id.clone(), myself.clone())]);
+        Self {
+            myself,
+            state: RwLock::new(ClusterState {
+                nodes,
+                slots: vec![None; CLUSTER_SLOTS],
+                migrating: BTreeMap::new(),
+                importing: BTreeMap::new(),
+            }),
+            meet: Mutex::default(),
+        }
+    }
+
+    pub fn myself(&self) -> &ClusterNode {
+        &self.myself
+    }
+
+    pub fn nodes(&self) -> Vec<ClusterNode> {
+        self.state.read().unwrap().nodes.values().cloned().collect()
+    }
+
+    pub fn owner(&self, slot: u16) -> Option<ClusterNode> {
+        let state = self.state.read().unwrap();
+        state.slots[slot as usize]
+            .as_ref()
+            .and_then(|id| state.nodes.get(id))
+            .cloned()
+    }
+
+    // contiguous slot ranges with their owner, in slot order
+    pub fn slot_ranges(&self) -> Vec<(u16, u16, ClusterNode)> {
+        let state = self.state.read().unwrap();
+        slot_ranges(&state)
+            .into_iter()
+            .filter_map(|(start, end, id)| Some((start, end, state.nodes.get(id)?.clone())))
+            .collect()
+    }
+
+    pub fn add_slots(&self, slots: &[u16]) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        check_slots(slots)?;
+        if let Some(slot) = slots.iter().find(|&&s| state.slots[s as usize].is_some()) {
+            return Err(format!("ERR Slot {} is already busy", slot));
+        }
+        for &slot in slots {
+            state.slots[slot as usize] = Some(self.myself.id.clone());
+        }
+        Ok(())
+    }
+
+    pub fn del_slots(&self, slots: &[u16]) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        check_slots(slots)?;
+        if let Some(slot) = slots.iter().find(|&&s| state.slots[s as usize].is_none()) {
+            return Err(format!("ERR Slot {} is already unassigned", slot));
+        }
+        for &slot in slots {
+            state.slots[slot as usize] = None;
+            state.migrating.remove(&slot);
+            state.importing.remove(&slot);
+        }
+        Ok(())
+    }
+
+    // CLUSTER SETSLOT, the slot migration steps
+    pub fn set_slot(&self, slot: u16, action: SetSlot) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        check_slots(&[slot])?;
+        let known = |state: &ClusterState, id: &str| {
+            state
+                .nodes
+                .contains_key(id)
+                .then_some(())
+                .ok_or_else(|| format!("ERR I don't know about node {}", id))
+        };
+        let owned = state.slots[slot as usize].as_deref() == Some(self.myself.id.as_str());
+        match action {
+            SetSlot::Migrating(id) => {
+                known(&state, &id)?;
+                if !owned {
+                    return Err(format!("ERR I'm not the owner of hash slot {}", slot));
+                }
+                state.migrating.insert(slot, id);
+            }
+            SetSlot::Importing(id) => {
+                known(&state, &id)?;
+                if owned {
+                    return Err(format!("ERR I'm already the owner of hash slot {}", slot));
+                }
+                state.importing.insert(slot, id);
+            }
+            SetSlot::Node(id) => {
+                known(&state, &id)?;
+                state.slots[slot as usize] = Some(id);
+                state.migrating.remove(&slot);
+                state.importing.remove(&slot);
+            }
+            SetSlot::Stable => {
+                state.migrating.remove(&slot);
+                state.importing.remove(&slot);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn meet(&self, ip: &str, port: u16) {
+        let state = self.state.read().unwrap();
+        if state.nodes.values().any(|n| n.ip == ip && n.port == port) {
+            return;
+        }
+        self.meet.lock().unwrap().insert((ip.to_string(), port));
+    }
+
+    // addresses to pull the cluster state from: every known peer, and pending meets
+    pub fn peers(&self) -> Vec<(String, u16)> {
+        let mut peers: BTreeSet<(String, u16)> = self
+            .state
+            .read()
+            .unwrap()
+            .nodes
+            .values()
+            .filter(|n| n.id != self.myself.id)
+            .map(|n| (n.ip.clone(), n.port))
+            .collect();
+        peers.extend(self.meet.lock().unwrap().iter().cloned());
+        peers.into_iter().collect()
+    }
+
+    // merge the CLUSTER NODES output of a peer: it's authoritative for its own slots,
+    // the other nodes it knows about are only added to the node table
+    pub fn merge(&self, nodes: &[NodeInfo]) {
+        let mut state = self.state.write().unwrap();
+        for info in nodes {
+            let id = &info.node.id;
+            if *id == self.myself.id {
+                continue;
+            }
+            if !info.myself {
+                state
+                    .nodes
+                    .entry(id.clone())
+                    .or_insert_with(|| info.node.clone());
+                continue;
+            }
+
+            self.meet
+                .lock()
+                .unwrap()
+                .remove(&(info.node.ip.clone(), info.node.port));
+            state.nodes.insert(id.clone(), info.node.clone());
+            let claimed: BTreeSet<u16> = info.slots.iter().copied().collect();
+            for (slot, owner) in state.slots.iter_mut().enumerate() {
+                let mine = owner.as_deref() == Some(self.myself.id.as_str());
+                if claimed.contains(&(slot as u16)) {
+                    // until SETSLOT NODE hands it over, a slot we serve stays ours
+                    if !mine {
+                        *owner = Some(id.clone());
+                    }
+                } else if owner.as_deref() == Some(id.as_str()) {
+                    *owner = None;
+                }
+            }
+        }
+    }
+
+    // CLUSTER NODES
+    pub fn nodes_text(&self) -> String {
+        let state = self.state.read().unwrap();
+        let mut slots: BTreeMap<&str, Vec<(u16, u16)>> = BTreeMap::new();
+        for (start, end, node) in slot_ranges(&state) {
+            slots.entry(node).or_default().push((start, end));
+        }
+
+        let mut s = String::new();
+        for node in state.nodes.values() {
+            let myself = node.id == self.myself.id;
+            let _ = write!(
+                s,
+                "{} {}:{}@{} {} - 0 0 0 connected",
+                node.id,
+                node.ip,
+                node.port,
+                node.port as u32 + BUS_PORT_OFFSET,
+                if myself { "myself,master" } else { "master" }
+            );
+            for (start, end) in slots.get(node.id.as_str()).into_iter().flatten() {
+                if start == end {
+                    let _ = write!(s, " {}", start);
+                } else {
+                    let _ = write!(s, " {}-{}", start, end);
+                }
+            }
+            if myself {
+                for (slot, target) in &state.migrating {
+                    let _ = write!(s, " [{}->-{}]", slot, target);
+                }
+                for (slot, source) in &state.importing {
+                    let _ = write!(s, " [{}-<-{}]", slot, source);
+                }
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    // CLUSTER INFO
+    pub fn info_text(&self) -> String {
+        let state = self.state.read().unwrap();
+        let assigned = state.slots.iter().filter(|s| s.is_some()).count();
+        let masters = state
+            .slots
+            .iter()
+            .flatten()
+            .collect::<BTreeSet<&String>>()
+            .len();
+        let ok = if assigned == CLUSTER_SLOTS {
+            "ok"
+        } else {
+            "fail"
+        };
+        format!(
+            "cluster_enabled:1\r\ncluster_state:{}\r\ncluster_slots_assigned:{}\r\n\
+             cluster_slots_ok:{}\r\ncluster_slots_pfail:0\r\ncluster_slots_fail:0\r\n\
+             cluster_known_nodes:{}\r\ncluster_size:{}\r\n",
+            ok,
+            assigned,
+            assigned,
+            state.nodes.len(),
+            masters
+        )
+    }
+}
+
+impl Backend {
+    // turn on cluster mode, `ip` and `port` are the address advertised to clients
+    pub fn enable_cluster(&self, ip: impl Into<String>, port: u16) {
+        let _ = self.cluster.set(Cluster::new(ip, port));
+    }
+
+    pub fn cluster(&self) -> Option<&Cluster> {
+        self.cluster.get()
+    }
+
+    // ASKING: the next command may access a slot this node is importing
+    pub fn asking(&self) {
+        self.session.asking.store(true, Ordering::Relaxed);
+    }
+
+    // the redirection error to answer instead of executing the command, if any
+    pub fn cluster_redirect(&self, args: &[Vec<u8>]) -> Option<RespFrame> {
+        let cluster = self.cluster()?;
+        let name = args.first()?.to_ascii_lowercase();
+        if name == b"asking" {
+            return None;
+        }
+        // the ASKING flag only applies to the command that follows it
+        let asking = self.session.asking.swap(false, Ordering::Relaxed);
+
+        let keys = command_keys(args);
+        let slot = key_hash_slot(keys.first()?);
+        if keys.iter().any(|key| key_hash_slot(key) != slot) {
+            return Some(
+                SimpleError::new("CROSSSLOT Keys in request don't hash to the same slot").into(),
+            );
+        }
+
+        let state = cluster.state.read().unwrap();
+        let redirect = |kind: &str, id: &str| -> Option<RespFrame> {
+            let node = state.nodes.get(id)?;
+            Some(SimpleError::new(format!("{} {} {}:{}", kind, slot, node.ip, node.port)).into())
+        };
+        match state.slots[slot as usize].as_deref() {
+            None => Some(SimpleError::new("CLUSTERDOWN Hash slot not served").into()),
+            Some(owner) if owner == cluster.myself.id => {
+                let target = state.migrating.get(&slot)?;
+                // keys already migrated are served by the target
+                let db = self.db();
+                let missing = keys
+                    .iter()
+                    .filter(|key| !db.contains_key(&String::from_utf8_lossy(key)))
+                    .count();
+                match missing {
+                    0 => None,
+                    n if n == keys.len() => redirect("ASK", target),
+                    _ => Some(
+                        SimpleError::new("TRYAGAIN Multiple keys request during rehashing of slot")
+                            .into(),
+                    ),
+                }
+            }
+            Some(_) if asking && state.importing.contains_key(&slot) => None,
+            Some(owner) => redirect("MOVED", owner),
+        }
+    }
+
+    pub fn count_keys_in_slot(&self, slot: u16) -> usize {
+        let db = self.db();
+        db.map
+            .iter()
+            .map(|e| e.key().clone())
+            .chain(db.hmap.iter().map(|e| e.key().clone()))
+            .chain(db.zset.iter().map(|e| e.key().clone()))
+            .filter(|key| key_hash_slot(key.as_bytes()) == slot)
+            .count()
+    }
+
+    pub fn keys_in_slot(&self, slot: u16, count: usize) -> Vec<String> {
+        let db = self.db();
+        let mut keys: Vec<String> = db
+            .map
+            .iter()
+            .map(|e| e.key().clone())
+            .chain(db.hmap.iter().map(|e| e.key().clone()))
+            .chain(db.zset.iter().map(|e| e.key().clone()))
+            .filter(|key| key_hash_slot(key.as_bytes()) == slot)
+            .collect();
+        keys.sort();
+        keys.truncate(count);
+        keys
+    }
+}
+
+// the keys a command accesses
+// CRC16-CCITT (XMODEM), as specified by the redis cluster spec
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, &b| {
+        (0..8).fold(crc ^ ((b as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+// only the part between the first { and the next } is hashed, if it's not empty
+pub fn key_hash_slot(key: &[u8]) -> u16 {
+    let key = match key.iter().position(|&c| c == b'{') {
+        Some(start) => match key[start + 1..].iter().position(|&c| c == b'}') {
+            Some(len) if len > 0 => &key[start + 1..start + 1 + len],
+            _ => key,
+        },
+        None => key,
+    };
+    crc16(key) % CLUSTER_SLOTS as u16
+}
+
+// parse CLUSTER NODES output, lines that can't be parsed are skipped
+pub fn parse_nodes(text: &str) -> Vec<NodeInfo> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_ascii_whitespace();
+            let id = fields.next()?.to_string();
+            let addr = fields.next()?;
+            let addr = addr.split('@').next()?;
+            let (ip, port) = addr.rsplit_once(':')?;
+            let myself = fields.next()?.split(',').any(|flag| flag == "myself");
+            // master, ping-sent, pong-recv, config-epoch, link-state
+            let mut fields = fields.skip(5);
+            let mut slots = Vec::new();
+            for field in fields.by_ref() {
+                if field.starts_with('[') {
+                    continue;
+                }
+                let (start, end) = field.split_once('-').unwrap_or((field, field));
+                let (start, end) = (start.parse::<u16>().ok()?, end.parse::<u16>().ok()?);
+                slots.extend(start..=end);
+            }
+            Some(NodeInfo {
+                node: ClusterNode {
+                    id,
+                    ip: ip.to_string(),
+                    port: port.parse().ok()?,
+                },
+                myself,
+                slots,
+            })
+        })
+        .collect()
+}
+
+fn slot_ranges(state: &ClusterState) -> Vec<(u16, u16, &str)> {
+    let mut ranges: Vec<(u16, u16, &str)> = Vec::new();
+    for (slot, owner) in state.slots.iter().enumerate() {
+        let Some(id) = owner.as_deref() else {
+            continue;
+        };
+        let slot = slot as u16;
+        match ranges.last_mut() {
+            Some((_, end, last)) if *end + 1 == slot && *last == id => *end = slot,
+            _ => ranges.push((slot, slot, id)),
+        }
+    }
+    ranges
+}
+
+fn check_slots(slots: &[u16]) -> Result<(), String> {
+    match slots.iter().find(|&&s| s as usize >= CLUSTER_SLOTS) {
+        Some(_) => Err("ERR Invalid or out of range slot".to_string()),
+        None => Ok(()),
+    }
+}
+
+// 40 random hex characters, like redis node ids
+fn random_node_id() -> String {
+    let mut rng = rand::thread_rng();
+    (0..40)
+        .map(|_| char::from_digit(rng.gen_range(0..16), 16).expect("a hex digit"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_hash_slot() {
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+        assert_eq!(key_hash_slot(b"foo"), 12182);
+        assert_eq!(
+            key_hash_slot(b"{user1000}.following"),
+            key_hash_slot(b"user1000")
+        );
+        assert_eq!(key_hash_slot(b"foo{}{bar}"), crc16(b"foo{}{bar}") % 16384);
+        assert_ne!(key_hash_slot(b"foo{}{bar}"), key_hash_slot(b"bar"));
+        assert_eq!(key_hash_slot(b"foo{{bar}}zap"), key_hash_slot(b"{bar"));
+    }
+
+    #[test]
+    fn test_nodes_text_roundtrip() {
+        let cluster = Cluster::new("127.0.0.1", 7000);
+        cluster.add_slots(&(0..100).collect::<Vec<_>>()).unwrap();
+        cluster.add_slots(&[200]).unwrap();
+        assert!(cluster.add_slots(&[200]).is_err());
+        assert!(cluster.add_slots(&[16384]).is_err());
+
+        let nodes = parse_nodes(&cluster.nodes_text());
+        assert_eq!(nodes.len(), 1);
+        assert!(nodes[0].myself);
+        assert_eq!(nodes[0].node, *cluster.myself());
+        assert_eq!(nodes[0].slots.len(), 101);
+        assert_eq!(cluster.myself().id.len(), 40);
+    }
+
+    #[test]
+    fn test_merge_peer_slots() {
+        let a = Cluster::new("127.0.0.1", 7000);
+        let b = Cluster::new("127.0.0.1", 7001);
+        a.add_slots(&[1, 2]).unwrap();
+        b.add_slots(&[2, 3]).unwrap();
+
+        a.meet("127.0.0.1", 7001);
+        assert_eq!(a.peers(), vec![("127.0.0.1".to_string(), 7001)]);
+        a.merge(&parse_nodes(&b.nodes_text()));
+        assert_eq!(a.nodes().len(), 2);
+        // a keeps the slot it serves
+        assert_eq!(a.owner(2).unwrap().id, a.myself().id);
+        assert_eq!(a.owner(3).unwrap().id, b.myself().id);
+
+        b.del_slots(&[3]).unwrap();
+        a.merge(&parse_nodes(&b.nodes_text()));
+        assert_eq!(a.owner(3), None);
+        assert_eq!(a.slot_ranges().len(), 1);
+    }
+
+    #[test]
+    fn test_cluster_redirect() {
+        let backend = Backend::new();
+        backend.enable_cluster("127.0.0.1", 7000);
+        let cluster = backend.cluster().unwrap();
+        let other = Cluster::new("127.0.0.1", 7001);
+        cluster.merge(&parse_nodes(&other.nodes_text()));
+        let other_id = other.myself().id.clone();
+
+        let args = |args: &[&str]| -> Vec<Vec<u8>> {
+            args.iter().map(|arg| arg.as_bytes().to_vec()).collect()
+        };
+        let err = |s: &str| Some(SimpleError::new(s).into());
+        let slot = key_hash_slot(b"foo");
+
+        assert_eq!(
+            backend.cluster_redirect(&args(&["get", "foo"])),
+            err("CLUSTERDOWN Hash slot not served")
+        );
+        assert_eq!(
+            backend.cluster_redirect(&args(&["del", "foo", "bar"])),
+            err("CROSSSLOT Keys in request don't hash to the same slot")
+        );
+        assert_eq!(backend.cluster_redirect(&args(&["echo", "foo"])), None);
+
+        cluster.add_slots(&[slot]).unwrap();
+        assert_eq!(backend.cluster_redirect(&args(&["get", "foo"])), None);
+
+        // keys that are gone are asked to the target while the slot is migrating
+        backend.set("{foo}a".to_string(), RespFrame::Integer(1));
+        cluster
+            .set_slot(slot, SetSlot::Migrating(other_id.clone()))
+            .unwrap();
+        assert_eq!(backend.cluster_redirect(&args(&["get", "{foo}a"])), None);
+        assert_eq!(
+            backend.cluster_redirect(&args(&["get", "foo"])),
+            err(&format!("ASK {} 127.0.0.1:7001", slot))
+        );
+        assert_eq!(
+            backend.cluster_redirect(&args(&["del", "foo", "{foo}a"])),
+            err("TRYAGAIN Multiple keys request during rehashing of slot")
+        );
+
+        cluster.set_slot(slot, SetSlot::Node(other_id)).unwrap();
+        assert_eq!(
+            backend.cluster_redirect(&args(&["get", "foo"])),
+            err(&format!("MOVED {} 127.0.0.1:7001", slot))
+        );
+    }
+
+    #[test]
+    fn test_importing_requires_asking() {
+        let backend = Backend::new();
+        backend.enable_cluster("127.0.0.1", 7000);
+        let cluster = backend.cluster().unwrap();
+        let other = Cluster::new("127.0.0.1", 7001);
+        let slot = key_hash_slot(b"foo");
+        other.add_slots(&[slot]).unwrap();
+        cluster.merge(&parse_nodes(&other.nodes_text()));
+        cluster
+            .set_slot(slot, SetSlot::Importing(other.myself().id.clone()))
+            .unwrap();
+
+        let get = vec![b"get".to_vec(), b"foo".to_vec()];
+        assert!(backend.cluster_redirect(&get).is_some());
+        backend.asking();
+        assert_eq!(backend.cluster_redirect(&get), None);
+        assert!(backend.cluster_redirect(&get).is_some());
+    }
+}
@@ -0,0 +1,286 @@
+use super::Backend;
+use crate::glob_match;
+use std::sync::atomic::{AtomicI64, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+const DEFAULT_MAXCLIENTS: usize = 10000;
+const DEFAULT_SLOWLOG_LOG_SLOWER_THAN: i64 = 10000;
+const DEFAULT_SLOWLOG_MAX_LEN: usize = 128;
+const DEFAULT_HASH_MAX_LISTPACK_ENTRIES: usize = 128;
+const DEFAULT_HASH_MAX_LISTPACK_VALUE: usize = 64;
+
+// keyspace notification classes, see https://redis.io/docs/latest/develop/use/keyspace-notifications/
+pub(crate) const NOTIFY_KEYSPACE: u32 = 1 << 0; // K
+pub(crate) const NOTIFY_KEYEVENT: u32 = 1 << 1; // E
+pub(crate) const NOTIFY_GENERIC: u32 = 1 << 2; // g
+pub(crate) const NOTIFY_STRING: u32 = 1 << 3; // $
+pub(crate) const NOTIFY_LIST: u32 = 1 << 4; // l
+pub(crate) const NOTIFY_SET: u32 = 1 << 5; // s
+pub(crate) const NOTIFY_HASH: u32 = 1 << 6; // h
+pub(crate) const NOTIFY_ZSET: u32 = 1 << 7; // z
+pub(crate) const NOTIFY_EXPIRED: u32 = 1 << 8; // x
+pub(crate) const NOTIFY_EVICTED: u32 = 1 << 9; // e
+pub(crate) const NOTIFY_STREAM: u32 = 1 << 10; // t
+pub(crate) const NOTIFY_KEY_MISS: u32 = 1 << 11; // m
+pub(crate) const NOTIFY_MODULE: u32 = 1 << 12; // d
+pub(crate) const NOTIFY_NEW: u32 = 1 << 13; // n
+                                            // A is an alias for g$lshzxetd
+pub(crate) const NOTIFY_ALL: u32 = NOTIFY_GENERIC
+    | NOTIFY_STRING
+    | NOTIFY_LIST
+    | NOTIFY_SET
+    | NOTIFY_HASH
+    | NOTIFY_ZSET
+    | NOTIFY_EXPIRED
+    | NOTIFY_EVICTED
+    | NOTIFY_STREAM
+    | NOTIFY_MODULE;
+
+const NOTIFY_CLASSES: &[(char, u32)] = &[
+    ('g', NOTIFY_GENERIC),
+    ('$', NOTIFY_STRING),
+    ('l', NOTIFY_LIST),
+    ('s', NOTIFY_SET),
+    ('h', NOTIFY_HASH),
+    ('z', NOTIFY_ZSET),
+    ('x', NOTIFY_EXPIRED),
+    ('e', NOTIFY_EVICTED),
+    ('t', NOTIFY_STREAM),
+    ('d', NOTIFY_MODULE),
+    ('m', NOTIFY_KEY_MISS),
+    ('n', NOTIFY_NEW),
+    ('K', NOTIFY_KEYSPACE),
+    ('E', NOTIFY_KEYEVENT),
+];
+
+#[derive(Debug)]
+pub struct Config {
+    pub(crate) notify_keyspace_events: AtomicU32,
+    pub(crate) maxclients: AtomicUsize,
+    // idle seconds before a client is disconnected, 0 means never
+    pub(crate) timeout: AtomicU64,
+    // snapshot file, empty means persistence is disabled
+    pub(crate) dbfilename: RwLock<String>,
+    // microseconds, negative disables the slow log
+    pub(crate) slowlog_log_slower_than: AtomicI64,
+    pub(crate) slowlog_max_len: AtomicUsize,
+    // milliseconds, 0 disables the latency monitor
+    pub(crate) latency_monitor_threshold: AtomicU64,
+    // the largest hashes kept as listpacks, see `Hash`
+    pub(crate) hash_max_listpack_entries: AtomicUsize,
+    pub(crate) hash_max_listpack_value: AtomicUsize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            notify_keyspace_events: AtomicU32::default(),
+            maxclients: AtomicUsize::new(DEFAULT_MAXCLIENTS),
+            timeout: AtomicU64::default(),
+            dbfilename: RwLock::default(),
+            slowlog_log_slower_than: AtomicI64::new(DEFAULT_SLOWLOG_LOG_SLOWER_THAN),
+            slowlog_max_len: AtomicUsize::new(DEFAULT_SLOWLOG_MAX_LEN),
+            latency_monitor_threshold: AtomicU64::default(),
+            hash_max_listpack_entries: AtomicUsize::new(DEFAULT_HASH_MAX_LISTPACK_ENTRIES),
+            hash_max_listpack_value: AtomicUsize::new(DEFAULT_HASH_MAX_LISTPACK_VALUE),
+        }
+    }
+}
+
+impl Backend {
+    // CONFIG GET: all the parameters matching the glob pattern, sorted by name
+    pub fn config_get(&self, pattern: &str) -> Vec<(String, String)> {
+        let config = &self.config;
+        let params = [
+            ("databases", self.databases().to_string()),
+            ("dbfilename", config.dbfilename.read().unwrap().clone()),
+            (
+                "hash-max-listpack-entries",
+                config
+                    .hash_max_listpack_entries
+                    .load(Ordering::Relaxed)
+                    .to_string(),
+            ),
+            (
+                "hash-max-listpack-value",
+                config
+                    .hash_max_listpack_value
+                    .load(Ordering::Relaxed)
+                    .to_string(),
+            ),
+            (
+                "latency-monitor-threshold",
+                config
+                    .latency_monitor_threshold
+                    .load(Ordering::Relaxed)
+                    .to_string(),
+            ),
+            (
+                "maxclients",
+                config.maxclients.load(Ordering::Relaxed).to_string(),
+            ),
+            (
+                "notify-keyspace-events",
+                format_notify_flags(config.notify_keyspace_events.load(Ordering::Relaxed)),
+            ),
+            (
+                "slowlog-log-slower-than",
+                config
+                    .slowlog_log_slower_than
+                    .load(Ordering::Relaxed)
+                    .to_string(),
+            ),
+            (
+                "slowlog-max-len",
+                config.slowlog_max_len.load(Ordering::Relaxed).to_string(),
+            ),
+            (
+                "timeout",
+                config.timeout.load(Ordering::Relaxed).to_string(),
+            ),
+        ];
+        params
+            .into_iter()
+            .filter(|(name, _)| {
+                glob_match(pattern.to_ascii_lowercase().as_bytes(), name.as_bytes())
+            })
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+
+    // CONFIG SET: returns the redis error message on failure
+    pub fn config_set(&self, name: &str, value: &str) -> Result<(), String> {
+        let invalid = || {
+            format!(
+                "ERR Invalid argument '{}' for CONFIG SET '{}'",
+                value,
+                name.to_ascii_lowercase()
+            )
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "dbfilename" => {
+                *self.config.dbfilename.write().unwrap() = value.to_string();
+                Ok(())
+            }
+            "maxclients" => {
+                let n = value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?;
+                self.config.maxclients.store(n, Ordering::Relaxed);
+                Ok(())
+            }
+            "slowlog-log-slower-than" => {
+                let micros = value.parse().map_err(|_| invalid())?;
+                self.config
+                    .slowlog_log_slower_than
+                    .store(micros, Ordering::Relaxed);
+                Ok(())
+            }
+            "slowlog-max-len" => {
+                let n = value.parse().map_err(|_| invalid())?;
+                self.config.slowlog_max_len.store(n, Ordering::Relaxed);
+                Ok(())
+            }
+            "latency-monitor-threshold" => {
+                let millis = value.parse().map_err(|_| invalid())?;
+                self.config
+                    .latency_monitor_threshold
+                    .store(millis, Ordering::Relaxed);
+                Ok(())
+            }
+            "hash-max-listpack-entries" => {
+                let n = value.parse().map_err(|_| invalid())?;
+                self.config
+                    .hash_max_listpack_entries
+                    .store(n, Ordering::Relaxed);
+                Ok(())
+            }
+            "hash-max-listpack-value" => {
+                let n = value.parse().map_err(|_| invalid())?;
+                self.config
+                    .hash_max_listpack_value
+                    .store(n, Ordering::Relaxed);
+                Ok(())
+            }
+            "timeout" => {
+                let secs = value.parse().map_err(|_| invalid())?;
+                self.config.timeout.store(secs, Ordering::Relaxed);
+                Ok(())
+            }
+            "notify-keyspace-events" => {
+                let flags = parse_notify_flags(value).ok_or_else(invalid)?;
+                self.config
+                    .notify_keyspace_events
+                    .store(flags, Ordering::Relaxed);
+                Ok(())
+            }
+            "databases" => Err(format!(
+                "ERR CONFIG SET failed (possibly related to argument '{}') - can't set immutable config",
+                name
+            )),
+            _ => Err(format!("ERR Unknown option or number of arguments for CONFIG SET - '{}'", name)),
+        }
+    }
+}
+
+pub(crate) fn parse_notify_flags(s: &str) -> Option<u32> {
+    s.chars().try_fold(0, |flags, c| match c {
+        'A' => Some(flags | NOTIFY_ALL),
+        c => NOTIFY_CLASSES
+            .iter()
+            .find(|(class, _)| *class == c)
+            .map(|(_, flag)| flags | flag),
+    })
+}
+
+pub(crate) fn format_notify_flags(flags: u32) -> String {
+    let mut s = String::new();
+    let mut rest = flags;
+    if flags & NOTIFY_ALL == NOTIFY_ALL {
+        s.push('A');
+        rest &= !NOTIFY_ALL;
+    }
+    for (c, flag) in NOTIFY_CLASSES {
+        if rest & flag != 0 {
+            s.push(*c);
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notify_flags() {
+        assert_eq!(parse_notify_flags(""), Some(0));
+        assert_eq!(
+            parse_notify_flags("Kg$"),
+            Some(NOTIFY_KEYSPACE | NOTIFY_GENERIC | NOTIFY_STRING)
+        );
+        assert_eq!(parse_notify_flags("KQ"), None);
+        assert_eq!(format_notify_flags(parse_notify_flags("EA").unwrap()), "AE");
+        assert_eq!(
+            format_notify_flags(parse_notify_flags("hK$").unwrap()),
+            "$hK"
+        );
+    }
+
+    #[test]
+    fn test_config_get_set() {
+        let backend = Backend::new();
+        assert!(backend.config_set("notify-keyspace-events", "KEA").is_ok());
+        assert!(backend.config_set("notify-keyspace-events", "X").is_err());
+        assert!(backend.config_set("databases", "4").is_err());
+        assert_eq!(
+            backend.config_get("notify-*"),
+            vec![("notify-keyspace-events".to_string(), "AKE".to_string())]
+        );
+        assert!(backend.config_set("maxclients", "0").is_err());
+        assert!(backend.config_set("timeout", "30").is_ok());
+        assert_eq!(
+            backend.config_get("timeout"),
+            vec![("timeout".to_string(), "30".to_string())]
+        );
+        assert_eq!(backend.config_get("*").len(), 10);
+    }
+}
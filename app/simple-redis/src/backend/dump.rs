@@ -0,0 +1,349 @@
+use super::{
+    config::NOTIFY_GENERIC, object::Object, zset::SortedSet, Backend, Db, Hash, ListPackLimits,
+};
+use crate::{
+    client::{command, Client, ClientError, Pipeline},
+    BulkString, RespDecodeV2, RespEncode, RespFrame,
+};
+use bytes::BytesMut;
+use std::time::Duration;
+
+// A DUMP payload is the type of the value, its content, then a footer made of the format
+// version (2 bytes) and the CRC64 of everything before the checksum (8 bytes), both little
+// endian. Lengths and counts are 4 bytes little endian, values of strings and hash fields
+// are RESP frames.
+pub const DUMP_VERSION: u16 = 1;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_HASH: u8 = 1;
+const TYPE_ZSET: u8 = 2;
+const FOOTER_LEN: usize = 10;
+
+const BAD_PAYLOAD: &str = "ERR DUMP payload version or checksum are wrong";
+const CONNECT_ERROR: &str = "IOERR error or timeout connecting to the client";
+const IO_ERROR: &str = "IOERR error or timeout reading to target instance";
+
+#[derive(Debug)]
+enum DumpValue {
+    String(RespFrame),
+    Hash(Hash),
+    ZSet(SortedSet),
+}
+
+impl Backend {
+    // the serialized value of a key, None if it doesn't exist
+    pub fn dump(&self, key: &str) -> Option<Vec<u8>> {
+        self.read_key(key);
+        let db = self.db();
+        let payload = if let Some(v) = db.map.get(key) {
+            serialize_string(&v)
+        } else if let Some(v) = db.hmap.get(key) {
+            serialize_hash(&v)
+        } else if let Some(v) = db.zset.get(key) {
+            serialize_zset(&v)
+        } else {
+            return None;
+        };
+        Some(payload)
+    }
+
+    // recreate a key from a DUMP payload, an existing key is only overwritten with `replace`
+    pub fn restore(&self, key: &str, payload: &[u8], replace: bool) -> Result<(), String> {
+        let value = deserialize(payload, self.listpack_limits())?;
+        let db = self.db();
+        if db.contains_key(key) {
+            if !replace {
+                return Err("BUSYKEY Target key name already exists.".to_string());
+            }
+            db.map.remove(key);
+            db.hmap.remove(key);
+            db.zset.remove(key);
+        }
+        let key = key.to_string();
+        match value {
+            DumpValue::String(v) => {
+                db.map.insert(key.clone(), Object::new(v));
+            }
+            DumpValue::Hash(v) => {
+                db.hmap.insert(key.clone(), Object::new(v));
+            }
+            DumpValue::ZSet(v) => {
+                db.zset.insert(key.clone(), Object::new(v));
+            }
+        }
+        self.touch(&key, NOTIFY_GENERIC, "restore", true);
+        Ok(())
+    }
+
+    // send the keys to another instance with RESTORE, and delete them here unless `copy`.
+    // The keys aren't locked while they are sent: one written meanwhile is kept here, the
+    // write wins over the migration. Returns false if none of the keys exist.
+    pub async fn migrate(
+        &self,
+        addr: &str,
+        keys: &[String],
+        db: usize,
+        timeout: Duration,
+        copy: bool,
+        replace: bool,
+    ) -> Result<bool, String> {
+        let payloads: Vec<(&String, Vec<u8>)> = keys
+            .iter()
+            .filter_map(|key| self.dump(key).map(|payload| (key, payload)))
+            .collect();
+        if payloads.is_empty() {
+            return Ok(false);
+        }
+
+        // keys of a slot being migrated are only accepted with ASKING by the target
+        let asking = self.cluster().is_some();
+        let mut pipeline = Pipeline::new();
+        pipeline.cmd(command("select", [db.to_string().into()]));
+        for (key, payload) in &payloads {
+            if asking {
+                pipeline.cmd(command("asking", []));
+            }
+            let mut args: Vec<BulkString> =
+                vec![key.as_str().into(), "0".into(), payload.as_slice().into()];
+            if replace {
+                args.push("replace".into());
+            }
+            pipeline.cmd(command("restore", args));
+        }
+
+        let mut client = match tokio::time::timeout(timeout, Client::connect(addr)).await {
+            Ok(Ok(client)) => client,
+            _ => return Err(CONNECT_ERROR.to_string()),
+        };
+        let replies = match tokio::time::timeout(timeout, client.pipeline(pipeline)).await {
+            Ok(Ok(replies)) => replies,
+            Ok(Err(ClientError::Codec(e))) => {
+                return Err(format!("ERR invalid reply from target instance: {}", e))
+            }
+            _ => return Err(IO_ERROR.to_string()),
+        };
+        // the replies of ASKING are all OK, only the ones of SELECT and RESTORE matter
+        let mut errors = replies.into_iter().filter_map(|reply| match reply {
+            RespFrame::Error(e) => Some(e.to_string()),
+            _ => None,
+        });
+        if let Some(e) = errors.next() {
+            return Err(format!("ERR Target instance replied with error: {}", e));
+        }
+        if !copy {
+            let db = self.db();
+            for (key, payload) in &payloads {
+                if db.remove_unchanged(key, payload) {
+                    self.touch(key, NOTIFY_GENERIC, "del", false);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Db {
+    // deletes a key unless it changed since it was dumped as `payload`
+    fn remove_unchanged(&self, key: &str, payload: &[u8]) -> bool {
+        self.map
+            .remove_if(key, |_, v| serialize_string(v) == payload)
+            .is_some()
+            || self
+                .hmap
+                .remove_if(key, |_, v| serialize_hash(v) == payload)
+                .is_some()
+            || self
+                .zset
+                .remove_if(key, |_, v| serialize_zset(v) == payload)
+                .is_some()
+    }
+}
+
+fn serialize_string(value: &RespFrame) -> Vec<u8> {
+    let mut buf = vec![TYPE_STRING];
+    buf.extend(value.clone().encode());
+    with_footer(buf)
+}
+
+fn serialize_hash(hash: &Hash) -> Vec<u8> {
+    let mut buf = vec![TYPE_HASH];
+    buf.extend((hash.len() as u32).to_le_bytes());
+    for (field, value) in hash.iter() {
+        put_bytes(&mut buf, field.as_bytes());
+        buf.extend(value.clone().encode());
+    }
+    with_footer(buf)
+}
+
+fn serialize_zset(set: &SortedSet) -> Vec<u8> {
+    let mut buf = vec![TYPE_ZSET];
+    buf.extend((set.len() as u32).to_le_bytes());
+    for (member, score) in set.iter() {
+        put_bytes(&mut buf, member.as_bytes());
+        buf.extend(score.to_le_bytes());
+    }
+    with_footer(buf)
+}
+
+fn with_footer(mut buf: Vec<u8>) -> Vec<u8> {
+    buf.extend(DUMP_VERSION.to_le_bytes());
+    let crc = crc64(&buf);
+    buf.extend(crc.to_le_bytes());
+    buf
+}
+
+fn deserialize(payload: &[u8], limits: ListPackLimits) -> Result<DumpValue, String> {
+    if payload.len() < FOOTER_LEN + 1 {
+        return Err(BAD_PAYLOAD.to_string());
+    }
+    let (data, crc) = payload.split_at(payload.len() - 8);
+    let (body, version) = data.split_at(data.len() - 2);
+    if u16::from_le_bytes([version[0], version[1]]) != DUMP_VERSION
+        || crc64(data).to_le_bytes() != crc
+    {
+        return Err(BAD_PAYLOAD.to_string());
+    }
+
+    let bad = || "ERR Bad data format".to_string();
+    let mut reader = Reader { data: &body[1..] };
+    let value = match body[0] {
+        TYPE_STRING => DumpValue::String(reader.frame().ok_or_else(bad)?),
+        TYPE_HASH => {
+            let mut hash = Hash::default();
+            for _ in 0..reader.u32().ok_or_else(bad)? {
+                let field = reader.string().ok_or_else(bad)?;
+                hash.insert(field, reader.frame().ok_or_else(bad)?, limits);
+            }
+            DumpValue::Hash(hash)
+        }
+        TYPE_ZSET => {
+            let mut set = SortedSet::default();
+            for _ in 0..reader.u32().ok_or_else(bad)? {
+                let member = reader.string().ok_or_else(bad)?;
+                set.insert(member, reader.f64().ok_or_else(bad)?);
+            }
+            DumpValue::ZSet(set)
+        }
+        _ => return Err(bad()),
+    };
+    if !reader.data.is_empty() {
+        return Err(bad());
+    }
+    Ok(value)
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend((bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.data.len() < n {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        Some(f64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+
+    fn frame(&mut self) -> Option<RespFrame> {
+        let len = RespFrame::expect_length(self.data).ok()?;
+        let mut buf = BytesMut::from(self.take(len)?);
+        RespFrame::decode(&mut buf).ok()
+    }
+}
+
+// CRC-64/Jones as used by redis: reflected, polynomial 0xad93d23594c935a9, no final xor
+pub fn crc64(data: &[u8]) -> u64 {
+    data.iter().fold(0u64, |crc, &b| {
+        (0..8).fold(crc ^ b as u64, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0x95ac9329ac4bc9b5
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc64() {
+        assert_eq!(crc64(b"123456789"), 0xe9c6d914c4b8d9ca);
+    }
+
+    #[test]
+    fn test_dump_restore() {
+        let backend = Backend::new();
+        backend.set("s".to_string(), RespFrame::BulkString("hello".into()));
+        backend.hset("h".to_string(), "f".to_string(), RespFrame::Integer(7));
+        let place = vec![(13.361389, 38.115556, "Palermo".to_string())];
+        backend.geoadd("g", place, false, false, false).unwrap();
+        assert_eq!(backend.dump("missing"), None);
+
+        let target = Backend::new();
+        for key in ["s", "h", "g"] {
+            let payload = backend.dump(key).unwrap();
+            assert_eq!(target.restore(key, &payload, false), Ok(()));
+            assert_eq!(target.dump(key), Some(payload.clone()));
+            assert!(target
+                .restore(key, &payload, false)
+                .unwrap_err()
+                .starts_with("BUSYKEY"));
+        }
+        assert_eq!(target.get("s"), Some(RespFrame::BulkString("hello".into())));
+        assert_eq!(target.hget("h", "f"), Some(RespFrame::Integer(7)));
+        let palermo = ["Palermo".to_string()];
+        assert_eq!(target.geopos("g", &palermo), backend.geopos("g", &palermo));
+
+        // a string replaced by a hash is no longer a string
+        let payload = backend.dump("h").unwrap();
+        assert_eq!(target.restore("s", &payload, true), Ok(()));
+        assert_eq!(target.get("s"), None);
+        assert_eq!(target.hget("s", "f"), Some(RespFrame::Integer(7)));
+    }
+
+    #[test]
+    fn test_restore_bad_payload() {
+        let backend = Backend::new();
+        backend.set("s".to_string(), RespFrame::BulkString("hello".into()));
+        let mut payload = backend.dump("s").unwrap();
+        let len = payload.len();
+        payload[1] ^= 1;
+        assert_eq!(
+            backend.restore("t", &payload, false),
+            Err(BAD_PAYLOAD.to_string())
+        );
+        payload[1] ^= 1;
+        payload[len - FOOTER_LEN] = 2;
+        assert_eq!(
+            backend.restore("t", &payload, false),
+            Err(BAD_PAYLOAD.to_string())
+        );
+        assert_eq!(
+            backend.restore("t", b"short", false),
+            Err(BAD_PAYLOAD.to_string())
+        );
+    }
+}
@@ -0,0 +1,615 @@
+use super::{config::NOTIFY_ZSET, zset::SortedSet, Backend, WRONGTYPE};
+
+// the limits of EPSG:3857, the projection used by web maps
+pub const GEO_LONG_MIN: f64 = -180.0;
+pub const GEO_LONG_MAX: f64 = 180.0;
+pub const GEO_LAT_MIN: f64 = -85.05112878;
+pub const GEO_LAT_MAX: f64 = 85.05112878;
+
+// 26 bits per coordinate, a 52 bit hash fits exactly in the f64 score of a sorted set
+const GEO_STEP_MAX: u32 = 26;
+const EARTH_RADIUS_IN_METERS: f64 = 6372797.560856;
+const MERCATOR_MAX: f64 = 20037726.37;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoUnit {
+    Meters,
+    Kilometers,
+    Miles,
+    Feet,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoOrigin {
+    Member(String),
+    LonLat(f64, f64),
+}
+
+// radius, or width and height, in the search unit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoShape {
+    Radius(f64),
+    Box(f64, f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoQuery {
+    pub origin: GeoOrigin,
+    pub shape: GeoShape,
+    pub unit: GeoUnit,
+    pub order: Option<GeoOrder>,
+    // the number of results, and whether any of them will do (ANY)
+    pub count: Option<(usize, bool)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoMatch {
+    pub member: String,
+    // distance from the origin, in the search unit
+    pub dist: f64,
+    pub hash: u64,
+    pub lon: f64,
+    pub lat: f64,
+}
+
+impl GeoUnit {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "m" => Some(Self::Meters),
+            "km" => Some(Self::Kilometers),
+            "mi" => Some(Self::Miles),
+            "ft" => Some(Self::Feet),
+            _ => None,
+        }
+    }
+
+    pub fn to_meters(self) -> f64 {
+        match self {
+            Self::Meters => 1.0,
+            Self::Kilometers => 1000.0,
+            Self::Miles => 1609.34,
+            Self::Feet => 0.3048,
+        }
+    }
+}
+
+impl Backend {
+    // add or update members, returns the number of new members, or of new and updated
+    // members with `ch`; `nx` only adds and `xx` only updates
+    pub fn geoadd(
+        &self,
+        key: &str,
+        items: Vec<(f64, f64, String)>,
+        nx: bool,
+        xx: bool,
+        ch: bool,
+    ) -> Result<usize, String> {
+        if let Some((lon, lat, _)) = items.iter().find(|(lon, lat, _)| !valid_coord(*lon, *lat)) {
+            return Err(format!(
+                "ERR invalid longitude,latitude pair {:.6},{:.6}",
+                lon, lat
+            ));
+        }
+        let db = self.db();
+        if db.map.contains_key(key) || db.hmap.contains_key(key) {
+            return Err(WRONGTYPE.to_string());
+        }
+        let (added, changed, is_new) = {
+            let mut set = db.zset.entry(key.to_string()).or_default();
+            let is_new = set.is_empty();
+            let (mut added, mut changed) = (0, 0);
+            for (lon, lat, member) in items {
+                let score = geohash_encode(lon, lat) as f64;
+                match set.score(&member) {
+                    Some(_) if nx => {}
+                    Some(old) if old != score => {
+                        set.insert(member, score);
+                        changed += 1;
+                    }
+                    Some(_) => {}
+                    None if xx => {}
+                    None => {
+                        set.insert(member, score);
+                        added += 1;
+                    }
+                }
+            }
+            (added, changed, is_new)
+        };
+        // XX against a missing key leaves an empty set behind
+        db.zset.remove_if(key, |_, set| set.is_empty());
+        if added + changed > 0 {
+            self.touch(key, NOTIFY_ZSET, "zadd", is_new);
+        }
+        Ok(if ch { added + changed } else { added })
+    }
+
+    pub fn geopos(&self, key: &str, members: &[String]) -> Result<Vec<Option<(f64, f64)>>, String> {
+        self.read_zset(key, |set| {
+            members
+                .iter()
+                .map(|member| {
+                    set.and_then(|set| set.score(member))
+                        .map(|score| geohash_decode(score as u64))
+                })
+                .collect()
+        })
+    }
+
+    // distance between two members in `unit`, None if any of them is missing
+    pub fn geodist(
+        &self,
+        key: &str,
+        a: &str,
+        b: &str,
+        unit: GeoUnit,
+    ) -> Result<Option<f64>, String> {
+        self.read_zset(key, |set| {
+            let set = set?;
+            let (lon1, lat1) = geohash_decode(set.score(a)? as u64);
+            let (lon2, lat2) = geohash_decode(set.score(b)? as u64);
+            Some(geo_distance(lon1, lat1, lon2, lat2) / unit.to_meters())
+        })
+    }
+
+    pub fn geosearch(&self, key: &str, search: &GeoQuery) -> Result<Vec<GeoMatch>, String> {
+        self.read_zset(key, |set| {
+            let Some(set) = set else {
+                return Ok(vec![]);
+            };
+            let (lon, lat) = match &search.origin {
+                GeoOrigin::LonLat(lon, lat) => (*lon, *lat),
+                GeoOrigin::Member(member) => match set.score(member) {
+                    Some(score) => geohash_decode(score as u64),
+                    None => return Err("ERR could not decode requested zset member".to_string()),
+                },
+            };
+            let meters = search.unit.to_meters();
+            let (width, height) = match search.shape {
+                GeoShape::Radius(r) => (2.0 * r * meters, 2.0 * r * meters),
+                GeoShape::Box(w, h) => (w * meters, h * meters),
+            };
+            let mut matches = Vec::new();
+            'search: for (min, max) in search_ranges(lon, lat, width, height) {
+                for (member, score) in set.range_by_score(min as f64, max as f64) {
+                    let hash = score as u64;
+                    let (plon, plat) = geohash_decode(hash);
+                    let dist = match search.shape {
+                        GeoShape::Radius(_) => {
+                            let dist = geo_distance(lon, lat, plon, plat);
+                            if dist > width / 2.0 {
+                                continue;
+                            }
+                            dist
+                        }
+                        GeoShape::Box(..) => {
+                            if geo_distance(lon, lat, lon, plat) > height / 2.0
+                                || geo_distance(plon, plat, lon, plat) > width / 2.0
+                            {
+                                continue;
+                            }
+                            geo_distance(lon, lat, plon, plat)
+                        }
+                    };
+                    matches.push(GeoMatch {
+                        member: member.to_string(),
+                        dist: dist / meters,
+                        hash,
+                        lon: plon,
+                        lat: plat,
+                    });
+                    if matches!(search.count, Some((count, true)) if matches.len() >= count) {
+                        break 'search;
+                    }
+                }
+            }
+            // COUNT without ANY returns the closest ones
+            let order = match (search.order, search.count) {
+                (None, Some((_, false))) => Some(GeoOrder::Asc),
+                (order, _) => order,
+            };
+            match order {
+                Some(GeoOrder::Asc) => matches.sort_by(|a, b| a.dist.total_cmp(&b.dist)),
+                Some(GeoOrder::Desc) => matches.sort_by(|a, b| b.dist.total_cmp(&a.dist)),
+                None => {}
+            }
+            if let Some((count, _)) = search.count {
+                matches.truncate(count);
+            }
+            Ok(matches)
+        })?
+    }
+
+    fn read_zset<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(Option<&SortedSet>) -> T,
+    ) -> Result<T, String> {
+        self.read_key(key);
+        let db = self.db();
+        if db.map.contains_key(key) || db.hmap.contains_key(key) {
+            return Err(WRONGTYPE.to_string());
+        }
+        let set = db.zset.get(key);
+        Ok(f(set.as_deref().map(|set| &**set)))
+    }
+}
+
+fn valid_coord(lon: f64, lat: f64) -> bool {
+    (GEO_LONG_MIN..=GEO_LONG_MAX).contains(&lon) && (GEO_LAT_MIN..=GEO_LAT_MAX).contains(&lat)
+}
+
+// the 52 bit geohash of a position: latitude bits on even positions, longitude bits on odd ones
+pub fn geohash_encode(lon: f64, lat: f64) -> u64 {
+    let cells = (1u64 << GEO_STEP_MAX) as f64;
+    let max = (1u32 << GEO_STEP_MAX) - 1;
+    let lat_offset = (lat - GEO_LAT_MIN) / (GEO_LAT_MAX - GEO_LAT_MIN);
+    let lon_offset = (lon - GEO_LONG_MIN) / (GEO_LONG_MAX - GEO_LONG_MIN);
+    let lat_bits = ((lat_offset * cells) as u32).min(max);
+    let lon_bits = ((lon_offset * cells) as u32).min(max);
+    interleave(lat_bits, lon_bits)
+}
+
+// the center of the cell of a 52 bit geohash, as (longitude, latitude)
+pub fn geohash_decode(hash: u64) -> (f64, f64) {
+    let cells = (1u64 << GEO_STEP_MAX) as f64;
+    let lat_bits = squash(hash) as f64;
+    let lon_bits = squash(hash >> 1) as f64;
+    let lat_cell = (GEO_LAT_MAX - GEO_LAT_MIN) / cells;
+    let lon_cell = (GEO_LONG_MAX - GEO_LONG_MIN) / cells;
+    let lat = GEO_LAT_MIN + (lat_bits + 0.5) * lat_cell;
+    let lon = GEO_LONG_MIN + (lon_bits + 0.5) * lon_cell;
+    (
+        lon.clamp(GEO_LONG_MIN, GEO_LONG_MAX),
+        lat.clamp(GEO_LAT_MIN, GEO_LAT_MAX),
+    )
+}
+
+// haversine distance in meters
+pub fn geo_distance(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let u = ((lat2 - lat1) / 2.0).sin();
+    let v = ((lon2.to_radians() - lon1.to_radians()) / 2.0).sin();
+    2.0 * EARTH_RADIUS_IN_METERS * (u * u + lat1.cos() * lat2.cos() * v * v).sqrt().asin()
+}
+
+fn interleave(x: u32, y: u32) -> u64 {
+    spread(x) | (spread(y) << 1)
+}
+
+fn spread(v: u32) -> u64 {
+    let mut v = v as u64;
+    v = (v | (v << 16)) & 0x0000ffff0000ffff;
+    v = (v | (v << 8)) & 0x00ff00ff00ff00ff;
+    v = (v | (v << 4)) & 0x0f0f0f0f0f0f0f0f;
+    v = (v | (v << 2)) & 0x3333333333333333;
+    (v | (v << 1)) & 0x5555555555555555
+}
+
+fn squash(v: u64) -> u32 {
+    let mut v = v & 0x5555555555555555;
+    v = (v | (v >> 1)) & 0x3333333333333333;
+    v = (v | (v >> 2)) & 0x0f0f0f0f0f0f0f0f;
+    v = (v | (v >> 4)) & 0x00ff00ff00ff00ff;
+    v = (v | (v >> 8)) & 0x0000ffff0000ffff;
+    ((v | (v >> 16)) & 0x00000000ffffffff) as u32
+}
+
+// the coarsest geohash precision whose cells are still larger than the search radius
+fn estimate_steps(radius: f64, lat: f64) -> u32 {
+    if radius == 0.0 {
+        return GEO_STEP_MAX;
+    }
+    let mut radius = radius;
+    let mut step: i32 = 1;
+    while radius < MERCATOR_MAX {
+        radius *= 2.0;
+        step += 1;
+    }
+    step -= 2;
+    // cells get narrower towards the poles
+    if lat.abs() > 66.0 {
+        step -= 1;
+        if lat.abs() > 80.0 {
+            step -= 1;
+        }
+    }
+    step.clamp(1, GEO_STEP_MAX as i32) as u32
+}
+
+// the [min, max) hash ranges of the cell holding the origin and of its 8 neighbours, at a
+// precision where they cover the whole search box
+fn search_ranges(lon: f64, lat: f64, width: f64, height: f64) -> Vec<(u64, u64)> {
+    let lat_delta = (height / 2.0 / EARTH_RADIUS_IN_METERS).to_degrees();
+    let (lat_min, lat_max) = (
+        (lat - lat_delta).max(GEO_LAT_MIN),
+        (lat + lat_delta).min(GEO_LAT_MAX),
+    );
+    // widest at the latitude closest to a pole
+    let polar = (lat - lat_delta).abs().max((lat + lat_delta).abs());
+    let ratio = (width / 4.0 / EARTH_RADIUS_IN_METERS).sin() / polar.to_radians().cos();
+    let lon_delta = (polar < 90.0 && ratio < 1.0).then(|| (2.0 * ratio.asin()).to_degrees());
+
+    let radius = (width / 2.0).hypot(height / 2.0);
+    let mut step = estimate_steps(radius, lat);
+    let (x, y, cells) = loop {
+        if step <= 1 {
+            return vec![(0, 1 << (2 * GEO_STEP_MAX))];
+        }
+        let cells = 1i64 << step;
+        let lon_cell = (GEO_LONG_MAX - GEO_LONG_MIN) / cells as f64;
+        let lat_cell = (GEO_LAT_MAX - GEO_LAT_MIN) / cells as f64;
+        let x = (((lon - GEO_LONG_MIN) / lon_cell) as i64).clamp(0, cells - 1);
+        let y = (((lat - GEO_LAT_MIN) / lat_cell) as i64).clamp(0, cells - 1);
+        // longitudes outside [-180, 180] stand for the neighbours across the antimeridian
+        let covered = lon_delta.is_some_and(|delta| {
+            lon - delta >= GEO_LONG_MIN + (x - 1) as f64 * lon_cell
+                && lon + delta <= GEO_LONG_MIN + (x + 2) as f64 * lon_cell
+        }) && lat_min >= GEO_LAT_MIN + (y - 1) as f64 * lat_cell
+            && lat_max <= GEO_LAT_MIN + (y + 2) as f64 * lat_cell;
+        if covered {
+            break (x, y, cells);
+        }
+        step -= 1;
+    };
+
+    let shift = 2 * (GEO_STEP_MAX - step);
+    let mut ranges = Vec::with_capacity(9);
+    for dy in -1..=1 {
+        let ny = y + dy;
+        if !(0..cells).contains(&ny) {
+            continue;
+        }
+        for dx in -1..=1 {
+            let nx = (x + dx).rem_euclid(cells);
+            let hash = interleave(ny as u32, nx as u32);
+            ranges.push((hash << shift, (hash + 1) << shift));
+        }
+    }
+    ranges.sort_unstable();
+    ranges.dedup();
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sicily(backend: &Backend) -> Result<usize, String> {
+        backend.geoadd(
+            "Sicily",
+            vec![
+                (13.361389, 38.115556, "Palermo".to_string()),
+                (15.087269, 37.502669, "Catania".to_string()),
+            ],
+            false,
+            false,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_geohash() {
+        // the score redis stores for Palermo
+        let hash = geohash_encode(13.361389, 38.115556);
+        assert_eq!(hash, 3479099956230698);
+        let (lon, lat) = geohash_decode(hash);
+        assert!((lon - 13.361389).abs() < 1e-5 && (lat - 38.115556).abs() < 1e-5);
+        assert_eq!(geohash_encode(lon, lat), hash);
+        assert_eq!(squash(spread(0x3ffffff)), 0x3ffffff);
+        assert_eq!(geohash_encode(GEO_LONG_MAX, GEO_LAT_MAX), (1 << 52) - 1);
+    }
+
+    #[test]
+    fn test_geoadd_geodist_geopos() {
+        let backend = Backend::new();
+        assert_eq!(sicily(&backend), Ok(2));
+        assert_eq!(sicily(&backend), Ok(0));
+        let dist = backend
+            .geodist("Sicily", "Palermo", "Catania", GeoUnit::Kilometers)
+            .unwrap()
+            .unwrap();
+        assert!((dist - 166.2742).abs() < 1e-3);
+        assert_eq!(
+            backend.geodist("Sicily", "Palermo", "Rome", GeoUnit::Meters),
+            Ok(None)
+        );
+
+        let pos = backend
+            .geopos("Sicily", &["Catania".to_string(), "Rome".to_string()])
+            .unwrap();
+        let (lon, lat) = pos[0].unwrap();
+        assert!((lon - 15.087269).abs() < 1e-5 && (lat - 37.502669).abs() < 1e-5);
+        assert_eq!(pos[1], None);
+
+        // NX never updates, XX never adds, CH counts the updates
+        let moved = || vec![(13.0, 38.0, "Palermo".to_string())];
+        assert_eq!(backend.geoadd("Sicily", moved(), true, false, true), Ok(0));
+        assert_eq!(backend.geoadd("Sicily", moved(), false, true, true), Ok(1));
+        let other = vec![(13.0, 38.0, "x".to_string())];
+        assert_eq!(backend.geoadd("empty", other, false, true, false), Ok(0));
+        assert!(!backend.db().zset.contains_key("empty"));
+
+        assert!(backend
+            .geoadd(
+                "Sicily",
+                vec![(0.0, 86.0, "x".to_string())],
+                false,
+                false,
+                false
+            )
+            .unwrap_err()
+            .starts_with("ERR invalid longitude,latitude pair"));
+        backend.set("s".to_string(), crate::RespFrame::Integer(1));
+        assert_eq!(backend.geopos("s", &[]), Err(WRONGTYPE.to_string()));
+    }
+
+    #[test]
+    fn test_geosearch() {
+        let backend = Backend::new();
+        sicily(&backend).unwrap();
+        let search = |origin, shape, order, count| GeoQuery {
+            origin,
+            shape,
+            unit: GeoUnit::Kilometers,
+            order,
+            count,
+        };
+        let members = |matches: Vec<GeoMatch>| {
+            matches
+                .into_iter()
+                .map(|m| m.member)
+                .collect::<Vec<String>>()
+        };
+
+        let origin = GeoOrigin::LonLat(15.0, 37.0);
+        let ret = backend
+            .geosearch(
+                "Sicily",
+                &search(
+                    origin.clone(),
+                    GeoShape::Radius(200.0),
+                    Some(GeoOrder::Asc),
+                    None,
+                ),
+            )
+            .unwrap();
+        assert_eq!(members(ret.clone()), ["Catania", "Palermo"]);
+        assert!((ret[0].dist - 56.4413).abs() < 1e-3);
+        assert!((ret[1].dist - 190.4424).abs() < 1e-3);
+
+        let ret = backend
+            .geosearch(
+                "Sicily",
+                &search(origin.clone(), GeoShape::Radius(100.0), None, None),
+            )
+            .unwrap();
+        assert_eq!(members(ret), ["Catania"]);
+
+        let ret = backend
+            .geosearch(
+                "Sicily",
+                &search(
+                    origin.clone(),
+                    GeoShape::Box(400.0, 400.0),
+                    None,
+                    Some((1, false)),
+                ),
+            )
+            .unwrap();
+        assert_eq!(members(ret), ["Catania"]);
+
+        let ret = backend
+            .geosearch(
+                "Sicily",
+                &search(origin, GeoShape::Radius(200.0), Some(GeoOrder::Desc), None),
+            )
+            .unwrap();
+        assert_eq!(members(ret), ["Palermo", "Catania"]);
+
+        let from_member = GeoOrigin::Member("Palermo".to_string());
+        let ret = backend
+            .geosearch(
+                "Sicily",
+                &search(from_member, GeoShape::Radius(1.0), None, None),
+            )
+            .unwrap();
+        assert_eq!(members(ret), ["Palermo"]);
+
+        let missing = GeoOrigin::Member("Rome".to_string());
+        assert!(backend
+            .geosearch(
+                "Sicily",
+                &search(missing, GeoShape::Radius(1.0), None, None)
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_geosearch_across_antimeridian() {
+        let backend = Backend::new();
+        let points = vec![
+            (179.9, 0.0, "east".to_string()),
+            (-179.9, 0.0, "west".to_string()),
+            (0.0, 0.0, "far".to_string()),
+        ];
+        backend.geoadd("g", points, false, false, false).unwrap();
+        let search = GeoQuery {
+            origin: GeoOrigin::LonLat(180.0, 0.0),
+            shape: GeoShape::Radius(50.0),
+            unit: GeoUnit::Kilometers,
+            order: Some(GeoOrder::Asc),
+            count: None,
+        };
+        let mut ret: Vec<String> = backend
+            .geosearch("g", &search)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.member)
+            .collect();
+        ret.sort();
+        assert_eq!(ret, ["east", "west"]);
+    }
+
+    #[test]
+    fn test_geosearch_matches_full_scan() {
+        let backend = Backend::new();
+        // a small lcg is enough to scatter points around the globe
+        let mut seed = 42u64;
+        let mut next = |range: f64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5) * range
+        };
+        let points = (0..2000)
+            .map(|i| (next(360.0), next(170.0), i.to_string()))
+            .collect::<Vec<_>>();
+        backend.geoadd("g", points, false, false, false).unwrap();
+        for _ in 0..50 {
+            let (lon, lat) = (next(360.0), next(170.0));
+            let shape = if next(1.0) > 0.0 {
+                GeoShape::Radius(next(4000.0).abs())
+            } else {
+                GeoShape::Box(next(8000.0).abs(), next(8000.0).abs())
+            };
+            let query = GeoQuery {
+                origin: GeoOrigin::LonLat(lon, lat),
+                shape,
+                unit: GeoUnit::Kilometers,
+                order: None,
+                count: None,
+            };
+            let mut found: Vec<String> = backend
+                .geosearch("g", &query)
+                .unwrap()
+                .into_iter()
+                .map(|m| m.member)
+                .collect();
+            found.sort();
+            let set = backend.db().zset.get("g").unwrap().clone();
+            let mut expected: Vec<String> = set
+                .iter()
+                .filter(|(_, score)| {
+                    let (plon, plat) = geohash_decode(*score as u64);
+                    match shape {
+                        GeoShape::Radius(r) => geo_distance(lon, lat, plon, plat) <= r * 1000.0,
+                        GeoShape::Box(w, h) => {
+                            geo_distance(lon, lat, lon, plat) <= h * 500.0
+                                && geo_distance(plon, plat, lon, plat) <= w * 500.0
+                        }
+                    }
+                })
+                .map(|(member, _)| member.to_string())
+                .collect();
+            expected.sort();
+            assert_eq!(found, expected, "{:?} around {},{}", shape, lon, lat);
+        }
+    }
+}
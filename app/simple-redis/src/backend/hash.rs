@@ -0,0 +1,137 @@
+use super::{frame_len, Backend};
+use crate::RespFrame;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+
+// A hash value. Small hashes are a flat vector of field/value pairs searched linearly,
+// which saves the per entry overhead of a table (a listpack in redis); they are converted
+// to a hash table for good once they grow past the hash-max-listpack-* limits.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Hash {
+    ListPack(Vec<(String, RespFrame)>),
+    Table(HashMap<String, RespFrame>),
+}
+
+// the largest hash kept as a listpack: number of fields, and length of a field or value
+#[derive(Debug, Clone, Copy)]
+pub struct ListPackLimits {
+    pub entries: usize,
+    pub value: usize,
+}
+
+impl Default for Hash {
+    fn default() -> Self {
+        Hash::ListPack(Vec::new())
+    }
+}
+
+impl Hash {
+    pub fn len(&self) -> usize {
+        match self {
+            Hash::ListPack(pairs) => pairs.len(),
+            Hash::Table(table) => table.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, field: &str) -> Option<&RespFrame> {
+        match self {
+            Hash::ListPack(pairs) => pairs.iter().find(|(f, _)| f == field).map(|(_, v)| v),
+            Hash::Table(table) => table.get(field),
+        }
+    }
+
+    // returns true if the field is new
+    pub fn insert(&mut self, field: String, value: RespFrame, limits: ListPackLimits) -> bool {
+        let too_long = field.len() > limits.value || frame_len(&value) > limits.value;
+        let is_new = match self {
+            Hash::ListPack(pairs) => match pairs.iter_mut().find(|(f, _)| *f == field) {
+                Some((_, v)) => {
+                    *v = value;
+                    false
+                }
+                None => {
+                    pairs.push((field, value));
+                    true
+                }
+            },
+            Hash::Table(table) => table.insert(field, value).is_none(),
+        };
+        if let Hash::ListPack(pairs) = self {
+            if too_long || pairs.len() > limits.entries {
+                *self = Hash::Table(std::mem::take(pairs).into_iter().collect());
+            }
+        }
+        is_new
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &RespFrame)> {
+        let (pairs, table) = match self {
+            Hash::ListPack(pairs) => (Some(pairs.iter().map(|(f, v)| (f, v))), None),
+            Hash::Table(table) => (None, Some(table.iter())),
+        };
+        pairs
+            .into_iter()
+            .flatten()
+            .chain(table.into_iter().flatten())
+    }
+
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Hash::ListPack(_) => "listpack",
+            Hash::Table(_) => "hashtable",
+        }
+    }
+}
+
+impl Backend {
+    pub(crate) fn listpack_limits(&self) -> ListPackLimits {
+        ListPackLimits {
+            entries: self
+                .config
+                .hash_max_listpack_entries
+                .load(Ordering::Relaxed),
+            value: self.config.hash_max_listpack_value.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: ListPackLimits = ListPackLimits {
+        entries: 2,
+        value: 8,
+    };
+
+    #[test]
+    fn test_hash_listpack_conversion() {
+        let mut hash = Hash::default();
+        assert!(hash.insert("a".to_string(), RespFrame::Integer(1), LIMITS));
+        assert!(!hash.insert("a".to_string(), RespFrame::Integer(2), LIMITS));
+        assert!(hash.insert("b".to_string(), RespFrame::Integer(3), LIMITS));
+        assert_eq!(hash.encoding(), "listpack");
+        assert_eq!(hash.get("a"), Some(&RespFrame::Integer(2)));
+
+        // one field too many
+        assert!(hash.insert("c".to_string(), RespFrame::Integer(4), LIMITS));
+        assert_eq!(hash.encoding(), "hashtable");
+        assert_eq!(hash.len(), 3);
+        assert_eq!(hash.get("a"), Some(&RespFrame::Integer(2)));
+
+        // a value too long
+        let mut hash = Hash::default();
+        hash.insert("a".to_string(), RespFrame::BulkString("x".into()), LIMITS);
+        hash.insert(
+            "a".to_string(),
+            RespFrame::BulkString("123456789".into()),
+            LIMITS,
+        );
+        assert_eq!(hash.encoding(), "hashtable");
+        assert_eq!(hash.iter().count(), 1);
+    }
+}
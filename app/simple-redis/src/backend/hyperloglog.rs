@@ -0,0 +1,508 @@
+use super::Backend;
+
+// The redis HyperLogLog string format, so values can be copied from and to redis:
+//
+// +------+---+-----+----------+
+// | HYLL | E | N/U | Cardin.  |
+// +------+---+-----+----------+
+//
+// a 4 bytes magic, 1 byte encoding (dense or sparse), 3 unused bytes, and the cached
+// cardinality as 8 bytes little endian, the most significant bit set when it's stale.
+//
+// - dense: 16384 registers of 6 bits, least significant bits first
+// - sparse: run length encoded registers, with the opcodes
+//   ZERO  00xxxxxx          1-64 zero registers
+//   XZERO 01xxxxxx yyyyyyyy 1-16384 zero registers
+//   VAL   1vvvvvxx          1-4 registers set to 1-32
+const HLL_P: u32 = 14;
+const HLL_Q: usize = 64 - HLL_P as usize;
+const HLL_REGISTERS: usize = 1 << HLL_P;
+const HLL_BITS: usize = 6;
+const HLL_REGISTER_MAX: u8 = (1 << HLL_BITS) - 1;
+const HLL_HDR_SIZE: usize = 16;
+const HLL_DENSE_SIZE: usize = HLL_HDR_SIZE + (HLL_REGISTERS * HLL_BITS).div_ceil(8);
+const HLL_DENSE: u8 = 0;
+const HLL_SPARSE: u8 = 1;
+const HLL_SPARSE_VAL_MAX_VALUE: u8 = 32;
+const HLL_SPARSE_VAL_MAX_LEN: usize = 4;
+const HLL_SPARSE_ZERO_MAX_LEN: usize = 64;
+const HLL_SPARSE_XZERO_MAX_LEN: usize = 16384;
+// like redis hll-sparse-max-bytes, larger sparse values are promoted to dense
+const HLL_SPARSE_MAX_BYTES: usize = 3000;
+const HLL_ALPHA_INF: f64 = 0.721_347_520_444_481_7;
+const HLL_HASH_SEED: u64 = 0xadc8_3b19;
+
+const HLL_INVALID: &str = "WRONGTYPE Key is not a valid HyperLogLog string value.";
+const HLL_CORRUPTED: &str = "INVALIDOBJ Corrupted HLL object detected";
+
+impl Backend {
+    // PFADD, true if the estimated cardinality may have changed; the registers are
+    // updated in place, a value isn't decoded unless it's promoted to dense
+    pub fn pfadd(&self, key: &str, elements: &[Vec<u8>]) -> Result<bool, String> {
+        self.modify_bytes(key, "pfadd", |data, exists| {
+            if exists {
+                check(data)?;
+            } else {
+                *data = encode(&[0; HLL_REGISTERS], false);
+            }
+            let mut updated = !exists;
+            for element in elements {
+                let (index, count) = pattern_len(element);
+                updated |= set_register(data, index, count);
+            }
+            if updated {
+                // the cached cardinality is stale
+                data[15] |= 0x80;
+            }
+            Ok((updated, updated))
+        })
+    }
+
+    // PFCOUNT, the cardinality of the union of the keys; with a single key the estimate
+    // is cached in the value
+    pub fn pfcount(&self, keys: &[String]) -> Result<u64, String> {
+        if let [key] = keys {
+            let cached = self.read_bytes(key, |data| {
+                let Some(data) = data else {
+                    return Ok(Some(0));
+                };
+                check(data)?;
+                let cached = u64::from_le_bytes(data[8..16].try_into().unwrap_or_default());
+                Ok::<_, String>((data[15] & 0x80 == 0).then_some(cached))
+            })??;
+            if let Some(card) = cached {
+                return Ok(card);
+            }
+            // refreshing the cache isn't a change of the value: no notification
+            return self.modify_bytes(key, "pfcount", |data, exists| {
+                if !exists {
+                    return Ok((0, false));
+                }
+                let card = count(&decode(data)?);
+                data[8..16].copy_from_slice(&card.to_le_bytes());
+                Ok((card, false))
+            });
+        }
+
+        let mut union = vec![0; HLL_REGISTERS];
+        for key in keys {
+            if let Some(data) = self.get_bytes(key)? {
+                merge(&mut union, &decode(&data)?);
+            }
+        }
+        Ok(count(&union))
+    }
+
+    // PFMERGE, the destination is dense if any of the inputs is
+    pub fn pfmerge(&self, dest: &str, keys: &[String]) -> Result<(), String> {
+        let mut union = vec![0; HLL_REGISTERS];
+        let mut dense = false;
+        for key in keys {
+            if let Some(data) = self.get_bytes(key)? {
+                merge(&mut union, &decode(&data)?);
+                dense |= data[4] == HLL_DENSE;
+            }
+        }
+        self.modify_bytes(dest, "pfadd", |data, exists| {
+            if exists {
+                merge(&mut union, &decode(data)?);
+                dense |= data[4] == HLL_DENSE;
+            }
+            *data = encode(&union, dense);
+            Ok(((), true))
+        })?;
+        Ok(())
+    }
+}
+
+// the register index and the number of leading zeros + 1 of the element hash
+fn pattern_len(element: &[u8]) -> (usize, u8) {
+    let hash = murmurhash64a(element, HLL_HASH_SEED);
+    let index = (hash & (HLL_REGISTERS as u64 - 1)) as usize;
+    // the sentinel bit bounds the count to Q + 1
+    let hash = (hash >> HLL_P) | (1 << HLL_Q);
+    (index, hash.trailing_zeros() as u8 + 1)
+}
+
+fn merge(union: &mut [u8], registers: &[u8]) {
+    for (max, &r) in union.iter_mut().zip(registers) {
+        *max = (*max).max(r);
+    }
+}
+
+// validates a HLL value without decoding its registers
+fn check(data: &[u8]) -> Result<(), String> {
+    if data.len() < HLL_HDR_SIZE || &data[..4] != b"HYLL" {
+        return Err(HLL_INVALID.to_string());
+    }
+    match data[4] {
+        HLL_DENSE if data.len() == HLL_DENSE_SIZE => Ok(()),
+        HLL_SPARSE => {
+            let mut total = 0;
+            for run in sparse_runs(&data[HLL_HDR_SIZE..]) {
+                total += run?.1;
+                if total > HLL_REGISTERS {
+                    return Err(HLL_CORRUPTED.to_string());
+                }
+            }
+            match total {
+                HLL_REGISTERS => Ok(()),
+                _ => Err(HLL_CORRUPTED.to_string()),
+            }
+        }
+        _ => Err(HLL_INVALID.to_string()),
+    }
+}
+
+// the registers of a HLL value
+fn decode(data: &[u8]) -> Result<Vec<u8>, String> {
+    check(data)?;
+    if data[4] == HLL_DENSE {
+        return Ok((0..HLL_REGISTERS)
+            .map(|i| dense_get(&data[HLL_HDR_SIZE..], i))
+            .collect());
+    }
+    let mut registers = Vec::with_capacity(HLL_REGISTERS);
+    for run in sparse_runs(&data[HLL_HDR_SIZE..]) {
+        let (value, len, _) = run?;
+        registers.resize(registers.len() + len, value);
+    }
+    Ok(registers)
+}
+
+// the runs of a sparse value: the register value, the number of registers and the size
+// of the opcode
+fn sparse_runs(mut ops: &[u8]) -> impl Iterator<Item = Result<(u8, usize, usize), String>> + '_ {
+    std::iter::from_fn(move || {
+        let &op = ops.first()?;
+        let run = match op >> 6 {
+            0b00 => (0, (op & 0x3f) as usize + 1, 1),
+            0b01 => {
+                let Some(&next) = ops.get(1) else {
+                    ops = &[];
+                    return Some(Err(HLL_CORRUPTED.to_string()));
+                };
+                (0, ((((op & 0x3f) as usize) << 8) | next as usize) + 1, 2)
+            }
+            _ => (((op >> 2) & 0x1f) + 1, (op & 0x03) as usize + 1, 1),
+        };
+        ops = &ops[run.2..];
+        Some(Ok(run))
+    })
+}
+
+// raises a register of a valid value to `count`, true if it was lower
+fn set_register(data: &mut Vec<u8>, index: usize, count: u8) -> bool {
+    if data[4] == HLL_DENSE {
+        let regs = &mut data[HLL_HDR_SIZE..];
+        if dense_get(regs, index) >= count {
+            return false;
+        }
+        dense_set(regs, index, count);
+        return true;
+    }
+
+    // the opcode covering the register: its offset, first register, value, length and size
+    let mut offset = HLL_HDR_SIZE;
+    let mut first = 0;
+    let mut found = None;
+    for run in sparse_runs(&data[HLL_HDR_SIZE..]) {
+        let Ok((value, len, size)) = run else {
+            return false;
+        };
+        if index < first + len {
+            found = Some((value, len, size));
+            break;
+        }
+        offset += size;
+        first += len;
+    }
+    let Some((value, len, size)) = found else {
+        return false;
+    };
+    if value >= count {
+        return false;
+    }
+    if count > HLL_SPARSE_VAL_MAX_VALUE {
+        promote(data);
+        return set_register(data, index, count);
+    }
+    // the run is split around the register
+    let mut ops = Vec::with_capacity(5);
+    push_run(&mut ops, value, index - first);
+    push_run(&mut ops, count, 1);
+    push_run(&mut ops, value, first + len - 1 - index);
+    data.splice(offset..offset + size, ops);
+    if data.len() - HLL_HDR_SIZE > HLL_SPARSE_MAX_BYTES {
+        promote(data);
+    }
+    true
+}
+
+fn promote(data: &mut Vec<u8>) {
+    if let Ok(registers) = decode(data) {
+        *data = encode(&registers, true);
+    }
+}
+
+// a value with a stale cached cardinality, sparse unless `dense` is set or the registers
+// can't be represented compactly
+fn encode(registers: &[u8], dense: bool) -> Vec<u8> {
+    let mut data = b"HYLL".to_vec();
+    let sparse = (!dense).then(|| encode_sparse(registers)).flatten();
+    data.push(if sparse.is_some() {
+        HLL_SPARSE
+    } else {
+        HLL_DENSE
+    });
+    data.extend_from_slice(&[0; 10]);
+    data.push(0x80);
+    match sparse {
+        Some(ops) => data.extend_from_slice(&ops),
+        None => {
+            let mut regs = vec![0; HLL_DENSE_SIZE - HLL_HDR_SIZE];
+            for (i, &r) in registers.iter().enumerate() {
+                dense_set(&mut regs, i, r);
+            }
+            data.extend_from_slice(&regs);
+        }
+    }
+    data
+}
+
+fn encode_sparse(registers: &[u8]) -> Option<Vec<u8>> {
+    let mut ops = Vec::new();
+    let mut i = 0;
+    while i < registers.len() {
+        let value = registers[i];
+        if value > HLL_SPARSE_VAL_MAX_VALUE {
+            return None;
+        }
+        let run = registers[i..].iter().take_while(|&&r| r == value).count();
+        push_run(&mut ops, value, run);
+        if ops.len() > HLL_SPARSE_MAX_BYTES {
+            return None;
+        }
+        i += run;
+    }
+    Some(ops)
+}
+
+// the opcodes of `len` registers set to `value`, at most HLL_SPARSE_VAL_MAX_VALUE
+fn push_run(ops: &mut Vec<u8>, value: u8, len: usize) {
+    if value == 0 {
+        let mut rest = len;
+        while rest > 0 {
+            if rest <= HLL_SPARSE_ZERO_MAX_LEN {
+                ops.push((rest - 1) as u8);
+                rest = 0;
+            } else {
+                let len = rest.min(HLL_SPARSE_XZERO_MAX_LEN);
+                ops.push(0x40 | ((len - 1) >> 8) as u8);
+                ops.push(((len - 1) & 0xff) as u8);
+                rest -= len;
+            }
+        }
+    } else {
+        for chunk in (0..len).step_by(HLL_SPARSE_VAL_MAX_LEN) {
+            let len = (len - chunk).min(HLL_SPARSE_VAL_MAX_LEN);
+            ops.push(0x80 | ((value - 1) << 2) | (len - 1) as u8);
+        }
+    }
+}
+
+fn dense_get(regs: &[u8], i: usize) -> u8 {
+    let byte = i * HLL_BITS / 8;
+    let fb = (i * HLL_BITS % 8) as u32;
+    let b0 = regs[byte] as u16;
+    let b1 = regs.get(byte + 1).copied().unwrap_or(0) as u16;
+    (((b0 >> fb) | (b1 << (8 - fb))) as u8) & HLL_REGISTER_MAX
+}
+
+fn dense_set(regs: &mut [u8], i: usize, value: u8) {
+    let byte = i * HLL_BITS / 8;
+    let fb = (i * HLL_BITS % 8) as u32;
+    let v = value as u16;
+    regs[byte] &= !((HLL_REGISTER_MAX as u16) << fb) as u8;
+    regs[byte] |= (v << fb) as u8;
+    if let Some(next) = regs.get_mut(byte + 1) {
+        let fb8 = 8 - fb;
+        *next &= !((HLL_REGISTER_MAX as u16) >> fb8) as u8;
+        *next |= (v >> fb8) as u8;
+    }
+}
+
+// the cardinality estimator of "New cardinality estimation algorithms for HyperLogLog
+// sketches" (Otmar Ertl), the one redis uses
+fn count(registers: &[u8]) -> u64 {
+    let m = HLL_REGISTERS as f64;
+    let mut histogram = [0u32; 64];
+    for &r in registers {
+        histogram[r as usize] += 1;
+    }
+    let mut z = m * tau((m - histogram[HLL_Q + 1] as f64) / m);
+    for &n in histogram[1..=HLL_Q].iter().rev() {
+        z += n as f64;
+        z *= 0.5;
+    }
+    z += m * sigma(histogram[0] as f64 / m);
+    (HLL_ALPHA_INF * m * m / z).round() as u64
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let prev = z;
+        z += x * y;
+        y += y;
+        if prev == z {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let prev = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if prev == z {
+            return z / 3.0;
+        }
+    }
+}
+
+// MurmurHash64A, the hash function of the redis HyperLogLog implementation
+fn murmurhash64a(key: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+    let mut h = seed ^ (key.len() as u64).wrapping_mul(M);
+
+    let mut chunks = key.chunks_exact(8);
+    for chunk in chunks.by_ref() {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap_or_default());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        for (i, &b) in rest.iter().enumerate() {
+            h ^= (b as u64) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_cardinality() {
+        let backend = Backend::new();
+        let elements: Vec<Vec<u8>> = ["a", "b", "c", "d", "e", "f", "g"]
+            .iter()
+            .map(|e| e.as_bytes().to_vec())
+            .collect();
+        assert_eq!(backend.pfadd("hll", &elements), Ok(true));
+        assert_eq!(backend.pfcount(&["hll".to_string()]), Ok(7));
+        // an empty PFADD creates the key
+        assert_eq!(backend.pfadd("empty", &[]), Ok(true));
+        assert_eq!(backend.pfcount(&["empty".to_string()]), Ok(0));
+    }
+
+    #[test]
+    fn test_empty_hll_is_sparse() {
+        let data = encode(&[0; HLL_REGISTERS], false);
+        // one XZERO opcode covers every register
+        assert_eq!(data.len(), HLL_HDR_SIZE + 2);
+        assert_eq!(&data[16..], &[0x7f, 0xff]);
+        assert_eq!(decode(&data).unwrap(), vec![0; HLL_REGISTERS]);
+        assert_eq!(count(&decode(&data).unwrap()), 0);
+    }
+
+    #[test]
+    fn test_dense_registers_roundtrip() {
+        let registers: Vec<u8> = (0..HLL_REGISTERS).map(|i| (i % 64) as u8).collect();
+        let data = encode(&registers, false);
+        // values over 32 can't be sparse
+        assert_eq!(data[4], HLL_DENSE);
+        assert_eq!(data.len(), HLL_DENSE_SIZE);
+        assert_eq!(decode(&data).unwrap(), registers);
+    }
+
+    #[test]
+    fn test_set_register_in_place() {
+        let mut data = encode(&[0; HLL_REGISTERS], false);
+        let mut expected = vec![0; HLL_REGISTERS];
+        for (index, count) in [
+            (0, 3),
+            (16383, 1),
+            (5000, 32),
+            (5001, 32),
+            (4999, 2),
+            (0, 1),
+        ] {
+            let raised = count > expected[index];
+            assert_eq!(set_register(&mut data, index, count), raised);
+            expected[index] = expected[index].max(count);
+            assert_eq!(data[4], HLL_SPARSE);
+            assert_eq!(decode(&data).unwrap(), expected);
+        }
+        // a value over 32 can't be sparse
+        assert!(set_register(&mut data, 7, 33));
+        expected[7] = 33;
+        assert_eq!(data[4], HLL_DENSE);
+        assert_eq!(decode(&data).unwrap(), expected);
+        assert!(!set_register(&mut data, 7, 2));
+    }
+
+    #[test]
+    fn test_pfadd_pfcount_pfmerge() {
+        let backend = Backend::new();
+        let elements = |range: std::ops::Range<u32>| -> Vec<Vec<u8>> {
+            range.map(|i| format!("user:{}", i).into_bytes()).collect()
+        };
+        assert_eq!(backend.pfadd("a", &elements(0..1000)), Ok(true));
+        assert_eq!(backend.pfadd("a", &elements(0..1000)), Ok(false));
+        let n = backend.pfcount(&["a".to_string()]).unwrap();
+        assert!((980..=1020).contains(&n), "{}", n);
+        // cached
+        assert_eq!(backend.pfcount(&["a".to_string()]), Ok(n));
+
+        backend.pfadd("b", &elements(500..20000)).unwrap();
+        assert_eq!(backend.get_bytes("b").unwrap().unwrap()[4], HLL_DENSE);
+        let n = backend
+            .pfcount(&["a".to_string(), "b".to_string()])
+            .unwrap();
+        assert!((19600..=20400).contains(&n), "{}", n);
+
+        backend
+            .pfmerge("c", &["a".to_string(), "b".to_string()])
+            .unwrap();
+        assert_eq!(backend.pfcount(&["c".to_string()]), Ok(n));
+
+        backend.set("s".to_string(), crate::RespFrame::Integer(1));
+        assert_eq!(backend.pfadd("s", &[]), Err(HLL_INVALID.to_string()));
+        assert_eq!(backend.pfcount(&["missing".to_string()]), Ok(0));
+    }
+}
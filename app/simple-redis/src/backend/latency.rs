@@ -0,0 +1,222 @@
+use super::{unix_time, Backend};
+use dashmap::DashMap;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const LATENCY_HISTORY_LEN: usize = 160;
+// bucket i counts latencies up to 2^i microseconds
+const HISTOGRAM_BUCKETS: usize = 40;
+
+// latency monitor: samples of events slower than latency-monitor-threshold, plus
+// a per command histogram of every execution
+#[derive(Debug, Default)]
+pub struct Latency {
+    events: Mutex<BTreeMap<String, LatencyEvent>>,
+    histograms: DashMap<String, Histogram>,
+}
+
+#[derive(Debug, Default)]
+struct LatencyEvent {
+    // (unix time in seconds, latency in milliseconds), oldest first
+    samples: VecDeque<(u64, u64)>,
+    max: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    pub calls: u64,
+    // total time spent, in microseconds
+    pub sum: u64,
+    buckets: [u64; HISTOGRAM_BUCKETS],
+}
+
+// LATENCY LATEST entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyLatest {
+    pub event: String,
+    pub timestamp: u64,
+    pub latest: u64,
+    pub max: u64,
+}
+
+impl Latency {
+    fn add_sample(&self, event: &str, timestamp: u64, latency: u64) {
+        let mut events = self.events.lock().unwrap();
+        let event = events.entry(event.to_string()).or_default();
+        // one sample per second, keep the worst
+        match event.samples.back_mut() {
+            Some(last) if last.0 == timestamp => last.1 = last.1.max(latency),
+            _ => event.samples.push_back((timestamp, latency)),
+        }
+        if event.samples.len() > LATENCY_HISTORY_LEN {
+            event.samples.pop_front();
+        }
+        event.max = event.max.max(latency);
+    }
+
+    pub fn latest(&self) -> Vec<LatencyLatest> {
+        let events = self.events.lock().unwrap();
+        events
+            .iter()
+            .filter_map(|(name, event)| {
+                let &(timestamp, latest) = event.samples.back()?;
+                Some(LatencyLatest {
+                    event: name.clone(),
+                    timestamp,
+                    latest,
+                    max: event.max,
+                })
+            })
+            .collect()
+    }
+
+    pub fn history(&self, event: &str) -> Vec<(u64, u64)> {
+        let events = self.events.lock().unwrap();
+        events
+            .get(event)
+            .map(|e| e.samples.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    // reset the given events, or all of them; returns the number of events reset
+    pub fn reset(&self, names: &[String]) -> usize {
+        let mut events = self.events.lock().unwrap();
+        if names.is_empty() {
+            let n = events.len();
+            events.clear();
+            return n;
+        }
+        names.iter().filter(|&n| events.remove(n).is_some()).count()
+    }
+
+    // histograms of the given commands, or all of them, sorted by command name
+    pub fn histograms(&self, commands: &[String]) -> Vec<(String, Histogram)> {
+        let mut ret: Vec<_> = self
+            .histograms
+            .iter()
+            .filter(|h| commands.is_empty() || commands.iter().any(|c| c == h.key()))
+            .map(|h| (h.key().clone(), h.value().clone()))
+            .collect();
+        ret.sort_by(|a, b| a.0.cmp(&b.0));
+        ret
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            calls: 0,
+            sum: 0,
+            buckets: [0; HISTOGRAM_BUCKETS],
+        }
+    }
+}
+
+impl Histogram {
+    fn record(&mut self, micros: u64) {
+        let bucket = (u64::BITS - micros.saturating_sub(1).leading_zeros()) as usize;
+        self.buckets[bucket.min(HISTOGRAM_BUCKETS - 1)] += 1;
+        self.calls += 1;
+        self.sum += micros;
+    }
+
+    // number of calls that took at most `micros`, exact for powers of 2
+    pub fn count_le(&self, micros: u64) -> u64 {
+        self.buckets
+            .iter()
+            .enumerate()
+            .take_while(|&(i, _)| 1u64 << i <= micros)
+            .map(|(_, n)| n)
+            .sum()
+    }
+
+    // (upper bound in microseconds, cumulative count) from the first to the last used bucket
+    pub fn cumulative(&self) -> Vec<(u64, u64)> {
+        let first = self.buckets.iter().position(|&n| n > 0);
+        let last = self.buckets.iter().rposition(|&n| n > 0);
+        let (Some(first), Some(last)) = (first, last) else {
+            return vec![];
+        };
+        let mut total = self.buckets[..first].iter().sum::<u64>();
+        (first..=last)
+            .map(|i| {
+                total += self.buckets[i];
+                (1 << i, total)
+            })
+            .collect()
+    }
+}
+
+impl Backend {
+    pub(crate) fn latency_record(&self, name: &str, elapsed: Duration) {
+        self.latency
+            .histograms
+            .entry(name.to_string())
+            .or_default()
+            .record(elapsed.as_micros() as u64);
+
+        // 0 disables the latency monitor
+        let threshold = self
+            .config
+            .latency_monitor_threshold
+            .load(Ordering::Relaxed);
+        let millis = elapsed.as_millis() as u64;
+        if threshold > 0 && millis >= threshold {
+            self.latency
+                .add_sample("command", unix_time().as_secs(), millis);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let mut h = Histogram::default();
+        for micros in [1, 3, 4, 100] {
+            h.record(micros);
+        }
+        assert_eq!(h.calls, 4);
+        assert_eq!(h.sum, 108);
+        assert_eq!(h.count_le(4), 3);
+        assert_eq!(h.count_le(1 << 20), 4);
+        assert_eq!(
+            h.cumulative(),
+            vec![
+                (1, 1),
+                (2, 1),
+                (4, 3),
+                (8, 3),
+                (16, 3),
+                (32, 3),
+                (64, 3),
+                (128, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_latency_events() {
+        let latency = Latency::default();
+        latency.add_sample("command", 10, 5);
+        latency.add_sample("command", 10, 3);
+        latency.add_sample("command", 11, 2);
+        assert_eq!(latency.history("command"), vec![(10, 5), (11, 2)]);
+        assert_eq!(
+            latency.latest(),
+            vec![LatencyLatest {
+                event: "command".to_string(),
+                timestamp: 11,
+                latest: 2,
+                max: 5,
+            }]
+        );
+        assert_eq!(latency.reset(&["other".to_string()]), 0);
+        assert_eq!(latency.reset(&[]), 1);
+        assert!(latency.latest().is_empty());
+    }
+}
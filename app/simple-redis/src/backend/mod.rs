@@ -1,38 +1,277 @@
-use crate::RespFrame;
-use dashmap::DashMap;
+mod bitmap;
+mod cluster;
+mod config;
+mod dump;
+mod geo;
+mod hash;
+mod hyperloglog;
+mod latency;
+mod monitor;
+mod object;
+mod pubsub;
+mod slowlog;
+mod snapshot;
+mod stats;
+mod tracking;
+mod zset;
+
+use crate::{BulkString, RespEncode, RespFrame};
+use dashmap::{mapref::entry::Entry, DashMap};
+use std::collections::BTreeSet;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+pub(crate) use self::bitmap::MAX_BIT_OFFSET;
+use self::config::*;
+use self::object::{encode_string, Object};
+pub use self::{
+    bitmap::{BitFieldOp, BitFieldType, BitOpKind, BitUnit, Overflow},
+    cluster::{
+        crc16, key_hash_slot, parse_nodes, Cluster, ClusterNode, NodeInfo, SetSlot, CLUSTER_SLOTS,
+    },
+    config::Config,
+    dump::{crc64, DUMP_VERSION},
+    geo::{
+        geo_distance, geohash_decode, geohash_encode, GeoMatch, GeoOrder, GeoOrigin, GeoQuery,
+        GeoShape, GeoUnit, GEO_LAT_MAX, GEO_LAT_MIN, GEO_LONG_MAX, GEO_LONG_MIN,
+    },
+    hash::{Hash, ListPackLimits},
+    latency::{Histogram, Latency, LatencyLatest},
+    monitor::Monitors,
+    object::DatasetMemory,
+    pubsub::PubSub,
+    slowlog::{SlowLog, SlowLogEntry},
+    stats::Stats,
+    tracking::Tracking,
+    zset::SortedSet,
+};
+
+const DEFAULT_DATABASES: usize = 16;
+
+// push messages queued for a connection before it's closed for not reading them, like
+// redis' client-output-buffer-limit for pubsub clients
+const PUSH_BUFFER_LEN: usize = 10_000;
+
+pub(crate) const WRONGTYPE: &str =
+    "WRONGTYPE Operation against a key holding the wrong kind of value";
 
+// frames the server pushes to a connection outside of the request/response cycle
 #[derive(Debug, Clone)]
-pub struct Backend(Arc<BackendInner>);
+pub struct PushSender {
+    sender: mpsc::Sender<RespFrame>,
+    // cancelled once the buffer is full, the connection is then closed
+    overflow: CancellationToken,
+}
+
+pub type PushReceiver = mpsc::Receiver<RespFrame>;
+
+// A backend handle is bound to one client session: clones share the session, and
+// `new_session` creates a handle on the same databases with fresh per-connection state.
+#[derive(Debug, Clone)]
+pub struct Backend {
+    inner: Arc<BackendInner>,
+    session: Arc<Session>,
+}
 
 #[derive(Debug)]
 pub struct BackendInner {
-    pub(crate) map: DashMap<String, RespFrame>,
-    pub(crate) hmap: DashMap<String, DashMap<String, RespFrame>>,
+    dbs: RwLock<Vec<Arc<Db>>>,
+    pub(crate) config: Config,
+    pub(crate) pubsub: PubSub,
+    pub(crate) tracking: Tracking,
+    pub(crate) slowlog: SlowLog,
+    pub(crate) latency: Latency,
+    pub(crate) monitors: Monitors,
+    pub(crate) stats: Stats,
+    // set once on startup when cluster mode is enabled
+    pub(crate) cluster: OnceLock<Cluster>,
+    next_session_id: AtomicU64,
+    clients: AtomicUsize,
+    shutdown: CancellationToken,
+    save_on_shutdown: AtomicBool,
+}
+
+#[derive(Debug, Default)]
+pub struct Db {
+    pub(crate) map: DashMap<String, Object<RespFrame>>,
+    pub(crate) hmap: DashMap<String, Object<Hash>>,
+    pub(crate) zset: DashMap<String, Object<SortedSet>>,
+}
+
+#[derive(Debug)]
+pub struct Session {
+    id: u64,
+    // peer address of the connection, ip:port or path:0 for unix sockets
+    addr: OnceLock<String>,
+    db: AtomicUsize,
+    // set by ASKING, see `Backend::cluster_redirect`
+    asking: AtomicBool,
+    sender: PushSender,
+    receiver: Mutex<Option<PushReceiver>>,
+    pub(crate) channels: Mutex<BTreeSet<String>>,
+    pub(crate) patterns: Mutex<BTreeSet<String>>,
+}
+
+impl PushSender {
+    pub(crate) fn channel(capacity: usize) -> (Self, PushReceiver) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let overflow = CancellationToken::new();
+        (Self { sender, overflow }, receiver)
+    }
+
+    // never waits: a client too slow to read its messages is disconnected rather than
+    // let them pile up in memory
+    pub(crate) fn send(
+        &self,
+        frame: RespFrame,
+    ) -> Result<(), mpsc::error::TrySendError<RespFrame>> {
+        self.sender.try_send(frame).inspect_err(|e| {
+            if let mpsc::error::TrySendError::Full(_) = e {
+                self.overflow.cancel();
+            }
+        })
+    }
 }
 
 impl Deref for Backend {
     type Target = BackendInner;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.inner
     }
 }
 
 impl Default for Backend {
     fn default() -> Self {
-        Self(Arc::new(BackendInner::default()))
+        Self::with_databases(DEFAULT_DATABASES)
     }
 }
 
 impl Default for BackendInner {
     fn default() -> Self {
+        Self::new(DEFAULT_DATABASES)
+    }
+}
+
+impl BackendInner {
+    fn new(databases: usize) -> Self {
+        let dbs = (0..databases.max(1)).map(|_| Arc::default()).collect();
         Self {
-            map: DashMap::new(),
-            hmap: DashMap::new(),
+            dbs: RwLock::new(dbs),
+            config: Config::default(),
+            pubsub: PubSub::default(),
+            tracking: Tracking::default(),
+            slowlog: SlowLog::default(),
+            latency: Latency::default(),
+            monitors: Monitors::default(),
+            stats: Stats::default(),
+            cluster: OnceLock::new(),
+            next_session_id: AtomicU64::new(1),
+            clients: AtomicUsize::new(0),
+            shutdown: CancellationToken::new(),
+            save_on_shutdown: AtomicBool::new(false),
         }
     }
+
+    fn new_session(&self) -> Arc<Session> {
+        let (sender, receiver) = PushSender::channel(PUSH_BUFFER_LEN);
+        Arc::new(Session {
+            id: self.next_session_id.fetch_add(1, Ordering::Relaxed),
+            addr: OnceLock::new(),
+            db: AtomicUsize::new(0),
+            asking: AtomicBool::new(false),
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            channels: Mutex::default(),
+            patterns: Mutex::default(),
+        })
+    }
+
+    pub fn databases(&self) -> usize {
+        self.dbs.read().unwrap().len()
+    }
+
+    fn db_at(&self, index: usize) -> Arc<Db> {
+        self.dbs.read().unwrap()[index].clone()
+    }
+
+    // register a new client connection, fails once maxclients is reached
+    pub fn connect_client(&self) -> bool {
+        let max = self.config.maxclients.load(Ordering::Relaxed);
+        self.clients
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .inspect(|_| {
+                self.stats
+                    .connections_received
+                    .fetch_add(1, Ordering::Relaxed);
+            })
+            .is_ok()
+    }
+
+    pub fn disconnect_client(&self) {
+        self.clients.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn connected_clients(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
+    }
+
+    // SHUTDOWN [SAVE|NOSAVE]: without a flag the dataset is saved if persistence is enabled;
+    // listeners stop accepting and connections close once their current command is done
+    pub fn shutdown(&self, save: Option<bool>) -> Result<(), String> {
+        let persistent = self.persistence_enabled();
+        if save == Some(true) && !persistent {
+            return Err("ERR no dbfilename configured, can't SAVE on SHUTDOWN".to_string());
+        }
+        self.save_on_shutdown
+            .store(save.unwrap_or(persistent), Ordering::Relaxed);
+        self.shutdown.cancel();
+        Ok(())
+    }
+
+    pub fn persistence_enabled(&self) -> bool {
+        !self.config.dbfilename.read().unwrap().is_empty()
+    }
+
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    pub fn save_on_shutdown(&self) -> bool {
+        self.save_on_shutdown.load(Ordering::Relaxed)
+    }
+
+    // idle seconds before a client gets disconnected, if enabled
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        match self.config.timeout.load(Ordering::Relaxed) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    pub fn slowlog(&self) -> &SlowLog {
+        &self.slowlog
+    }
+
+    pub fn latency(&self) -> &Latency {
+        &self.latency
+    }
+}
+
+impl Db {
+    fn contains_key(&self, key: &str) -> bool {
+        self.map.contains_key(key) || self.hmap.contains_key(key) || self.zset.contains_key(key)
+    }
 }
 
 impl Backend {
@@ -40,43 +279,451 @@ impl Backend {
         Self::default()
     }
 
+    pub fn with_databases(databases: usize) -> Self {
+        let inner = BackendInner::new(databases);
+        let session = inner.new_session();
+        Self {
+            inner: Arc::new(inner),
+            session,
+        }
+    }
+
+    // a handle on the same databases, with its own selected db
+    pub fn new_session(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            session: self.inner.new_session(),
+        }
+    }
+
+    // release everything the session registered on the shared state
+    pub fn end_session(&self) {
+        self.pubsub.remove_session(self.session.id);
+        self.tracking.disable(self.session.id);
+        self.stop_monitor();
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn session_id(&self) -> u64 {
+        self.session.id
+    }
+
+    pub fn set_client_addr(&self, addr: impl Into<String>) {
+        let _ = self.session.addr.set(addr.into());
+    }
+
+    pub fn client_addr(&self) -> &str {
+        self.session
+            .addr
+            .get()
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    // the receiving end of the session's push messages, can only be taken once
+    pub fn take_push_receiver(&self) -> Option<PushReceiver> {
+        self.session.receiver.lock().unwrap().take()
+    }
+
+    pub(crate) fn push_sender(&self) -> &PushSender {
+        &self.session.sender
+    }
+
+    // cancelled when the session's push messages overflow, see `PushSender::send`
+    pub fn push_overflow_token(&self) -> CancellationToken {
+        self.session.sender.overflow.clone()
+    }
+
+    pub fn selected_db(&self) -> usize {
+        self.session.db.load(Ordering::Relaxed)
+    }
+
+    pub fn select(&self, index: usize) -> bool {
+        if index >= self.databases() {
+            return false;
+        }
+        self.session.db.store(index, Ordering::Relaxed);
+        true
+    }
+
+    pub(crate) fn db(&self) -> Arc<Db> {
+        self.db_at(self.selected_db())
+    }
+
     pub fn get(&self, key: &str) -> Option<RespFrame> {
-        self.map.get(key).map(|v| v.value().clone())
+        self.read_key(key);
+        self.db().map.get(key).map(|v| (**v).clone())
     }
 
     pub fn set(&self, key: String, value: RespFrame) {
-        self.map.insert(key, value);
+        // an overwritten key keeps its access statistics
+        let is_new = match self.db().map.entry(key.clone()) {
+            Entry::Occupied(mut entry) => {
+                **entry.get_mut() = encode_string(value);
+                false
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(Object::new(encode_string(value)));
+                true
+            }
+        };
+        self.touch(&key, NOTIFY_STRING, "set", is_new);
+    }
+
+    // the value of a string key as bytes, fails if the key holds another type
+    pub fn get_bytes(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        self.read_bytes(key, |data| data.map(<[u8]>::to_vec))
+    }
+
+    // runs `f` on the bytes of a string key (None if it doesn't exist) under a read lock,
+    // without copying them; `f` must not access the keyspace
+    pub(crate) fn read_bytes<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(Option<&[u8]>) -> T,
+    ) -> Result<T, String> {
+        self.read_key(key);
+        let db = self.db();
+        if db.hmap.contains_key(key) || db.zset.contains_key(key) {
+            return Err(WRONGTYPE.to_string());
+        }
+        let Some(value) = db.map.get(key) else {
+            return Ok(f(None));
+        };
+        match &**value {
+            RespFrame::BulkString(s) => Ok(f(Some(s))),
+            RespFrame::SimpleString(s) => Ok(f(Some(s.as_bytes()))),
+            RespFrame::Integer(n) => Ok(f(Some(n.to_string().as_bytes()))),
+            _ => Err(WRONGTYPE.to_string()),
+        }
+    }
+
+    // read-modify-write of a string value in place, atomic with respect to other writers of
+    // the key. `f` gets the current bytes (empty if the key doesn't exist) and whether the
+    // key exists, and returns its result along with whether it modified the bytes: if not,
+    // nothing is notified and a missing key isn't created. `f` must leave the bytes as they
+    // were when it fails.
+    pub(crate) fn modify_bytes<T>(
+        &self,
+        key: &str,
+        event: &str,
+        f: impl FnOnce(&mut Vec<u8>, bool) -> Result<(T, bool), String>,
+    ) -> Result<T, String> {
+        let db = self.db();
+        if db.hmap.contains_key(key) || db.zset.contains_key(key) {
+            return Err(WRONGTYPE.to_string());
+        }
+        let (ret, is_new) = match db.map.entry(key.to_string()) {
+            Entry::Occupied(mut entry) => {
+                let data =
+                    string_bytes_mut(entry.get_mut()).ok_or_else(|| WRONGTYPE.to_string())?;
+                let (ret, modified) = f(data, true)?;
+                if !modified {
+                    return Ok(ret);
+                }
+                (ret, false)
+            }
+            Entry::Vacant(entry) => {
+                let mut data = Vec::new();
+                let (ret, modified) = f(&mut data, false)?;
+                if !modified {
+                    return Ok(ret);
+                }
+                entry.insert(Object::new(BulkString::new(data).into()));
+                (ret, true)
+            }
+        };
+        self.touch(key, NOTIFY_STRING, event, is_new);
+        Ok(ret)
     }
 
     pub fn hget(&self, key: &str, field: &str) -> Option<RespFrame> {
-        self.hmap
-            .get(key)
-            .and_then(|v| v.get(field).map(|v| v.value().clone()))
+        self.read_key(key);
+        self.db().hmap.get(key).and_then(|v| v.get(field).cloned())
     }
 
-    pub fn hset(&self, key: String, field: String, value: RespFrame) {
-        let hmap = self.hmap.entry(key).or_default();
-        hmap.insert(field, value);
+    // returns true if the field is new
+    pub fn hset(&self, key: String, field: String, value: RespFrame) -> bool {
+        let db = self.db();
+        let is_new = !db.hmap.contains_key(&key);
+        let limits = self.listpack_limits();
+        let is_new_field = db
+            .hmap
+            .entry(key.clone())
+            .or_default()
+            .insert(field, value, limits);
+        self.touch(&key, NOTIFY_HASH, "hset", is_new);
+        is_new_field
     }
 
-    pub fn hgetall(&self, key: &str) -> Option<DashMap<String, RespFrame>> {
-        self.hmap.get(key).map(|v| v.clone())
+    pub fn hgetall(&self, key: &str) -> Option<Vec<(String, RespFrame)>> {
+        self.read_key(key);
+        self.db().hmap.get(key).map(|v| {
+            v.iter()
+                .map(|(field, value)| (field.clone(), value.clone()))
+                .collect()
+        })
     }
 
     // TODO: return k-v pairs?
     pub fn del(&self, keys: &[&str]) -> Vec<String> {
+        let db = self.db();
         let map_removed = keys
             .iter()
-            .filter_map(|&k| self.map.remove(k).map(|(k, _v)| k))
+            .filter_map(|&k| db.map.remove(k).map(|(k, _v)| k))
             .collect::<Vec<String>>();
         let hmap_removed = keys
             .iter()
-            .filter_map(|&k| self.hmap.remove(k).map(|(k, _v)| k))
+            .filter_map(|&k| db.hmap.remove(k).map(|(k, _v)| k))
+            .collect::<Vec<String>>();
+        let zset_removed = keys
+            .iter()
+            .filter_map(|&k| db.zset.remove(k).map(|(k, _v)| k))
             .collect::<Vec<String>>();
 
         let mut combined = map_removed;
         combined.extend(hmap_removed);
+        combined.extend(zset_removed);
 
+        for key in &combined {
+            self.touch(key, NOTIFY_GENERIC, "del", false);
+        }
         combined
     }
+
+    // move a key from the selected db to `target`; fails if the key is missing
+    // or already exists in the target db
+    pub fn move_key(&self, key: &str, target: usize) -> bool {
+        let (src, dst) = (self.db(), self.db_at(target));
+        if Arc::ptr_eq(&src, &dst) || dst.contains_key(key) {
+            return false;
+        }
+        let moved = move_entry(&src.map, &dst.map, key)
+            .or_else(|| move_entry(&src.hmap, &dst.hmap, key))
+            .or_else(|| move_entry(&src.zset, &dst.zset, key))
+            .unwrap_or(false);
+        if moved {
+            self.notify(NOTIFY_GENERIC, "move_from", key, self.selected_db());
+            self.notify(NOTIFY_GENERIC, "move_to", key, target);
+            self.tracking.invalidate(key);
+            self.stats.dirty.fetch_add(1, Ordering::Relaxed);
+        }
+        moved
+    }
+
+    pub fn swap_db(&self, a: usize, b: usize) {
+        self.dbs.write().unwrap().swap(a, b);
+        self.tracking.invalidate_all();
+        self.stats.dirty.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // replace the selected db with an empty one and return the old content, so the
+    // caller decides where it gets dropped
+    pub fn flush_db(&self) -> Arc<Db> {
+        let index = self.selected_db();
+        let old: Arc<Db> = std::mem::take(&mut self.dbs.write().unwrap()[index]);
+        self.tracking.invalidate_all();
+        self.stats
+            .dirty
+            .fetch_add(old.len() as u64, Ordering::Relaxed);
+        old
+    }
+
+    pub fn flush_all(&self) -> Vec<Arc<Db>> {
+        let old = self
+            .dbs
+            .write()
+            .unwrap()
+            .iter_mut()
+            .map(std::mem::take)
+            .collect::<Vec<Arc<Db>>>();
+        self.tracking.invalidate_all();
+        let keys = old.iter().map(|db| db.len() as u64).sum();
+        self.stats.dirty.fetch_add(keys, Ordering::Relaxed);
+        old
+    }
+
+    // called with the command arguments and execution time of every command
+    pub fn record_command(&self, args: &[Vec<u8>], elapsed: Duration) {
+        let Some(name) = args.first() else {
+            return;
+        };
+        let name = String::from_utf8_lossy(name).to_ascii_lowercase();
+        self.latency_record(&name, elapsed);
+        self.slowlog_record(args, elapsed);
+    }
+
+    pub fn publish(&self, channel: &str, message: &[u8]) -> usize {
+        self.pubsub.publish(channel, message)
+    }
+
+    // a key in the selected db was modified
+    fn touch(&self, key: &str, class: u32, event: &str, is_new: bool) {
+        let db = self.selected_db();
+        if is_new {
+            self.notify(NOTIFY_NEW, "new", key, db);
+        }
+        self.notify(class, event, key, db);
+        self.write_key(key, is_new);
+        self.tracking.invalidate(key);
+        self.stats.dirty.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn notify(&self, class: u32, event: &str, key: &str, db: usize) {
+        let flags = self.config.notify_keyspace_events.load(Ordering::Relaxed);
+        if flags & class == 0 {
+            return;
+        }
+        if flags & NOTIFY_KEYSPACE != 0 {
+            let channel = format!("__keyspace@{}__:{}", db, key);
+            self.publish(&channel, event.as_bytes());
+        }
+        if flags & NOTIFY_KEYEVENT != 0 {
+            let channel = format!("__keyevent@{}__:{}", db, event);
+            self.publish(&channel, key.as_bytes());
+        }
+    }
+}
+
+// string values are stored as the frame they were SET with
+fn string_bytes(value: &RespFrame) -> Option<Vec<u8>> {
+    match value {
+        RespFrame::BulkString(s) => Some(s.to_vec()),
+        RespFrame::SimpleString(s) => Some(s.as_bytes().to_vec()),
+        RespFrame::Integer(n) => Some(n.to_string().into_bytes()),
+        _ => None,
+    }
+}
+
+// the bytes of a string value to modify in place, an integer is converted first as redis
+// does for the bit commands
+fn string_bytes_mut(value: &mut RespFrame) -> Option<&mut Vec<u8>> {
+    if !matches!(value, RespFrame::BulkString(_)) {
+        *value = BulkString::new(string_bytes(value)?).into();
+    }
+    match value {
+        RespFrame::BulkString(s) => Some(&mut s.0),
+        _ => None,
+    }
+}
+
+// length of a value as stored, without the protocol framing of strings
+fn frame_len(value: &RespFrame) -> usize {
+    match value {
+        RespFrame::BulkString(s) => s.len(),
+        RespFrame::SimpleString(s) => s.len(),
+        RespFrame::Integer(n) => n.to_string().len(),
+        v => v.clone().encode().len(),
+    }
+}
+
+// moves a key between the same table of two dbs, None if src doesn't hold it; when dst got
+// the key in the meantime the value goes back to src, unless src was written to as well
+fn move_entry<V>(src: &DashMap<String, V>, dst: &DashMap<String, V>, key: &str) -> Option<bool> {
+    let (k, v) = src.remove(key)?;
+    // the dst shard is unlocked before src is touched again
+    let occupied = match dst.entry(k) {
+        Entry::Occupied(entry) => Some((entry.key().clone(), v)),
+        Entry::Vacant(entry) => {
+            entry.insert(v);
+            None
+        }
+    };
+    match occupied {
+        Some((k, v)) => {
+            src.entry(k).or_insert(v);
+            Some(false)
+        }
+        None => Some(true),
+    }
+}
+
+pub(crate) fn unix_time() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions_select_db_independently() {
+        let backend = Backend::new();
+        let other = backend.new_session();
+        assert!(backend.select(1));
+        assert!(!backend.select(16));
+
+        backend.set("a".to_string(), RespFrame::Integer(1));
+        assert_eq!(backend.get("a"), Some(RespFrame::Integer(1)));
+        assert_eq!(other.get("a"), None);
+        assert_eq!(backend.clone().selected_db(), 1);
+        assert_eq!(other.selected_db(), 0);
+
+        assert!(backend.move_key("a", 0));
+        assert_eq!(other.get("a"), Some(RespFrame::Integer(1)));
+        assert!(!other.move_key("a", 0));
+
+        // the target got the key after the check, the value stays where it was
+        let (src, dst) = (DashMap::new(), DashMap::new());
+        src.insert("k".to_string(), 1);
+        dst.insert("k".to_string(), 2);
+        assert_eq!(move_entry(&src, &dst, "k"), Some(false));
+        assert_eq!(*src.get("k").unwrap(), 1);
+        assert_eq!(*dst.get("k").unwrap(), 2);
+        assert_eq!(move_entry(&dst, &DashMap::new(), "k"), Some(true));
+        assert_eq!(move_entry(&dst, &src, "k"), None);
+
+        other.swap_db(0, 1);
+        assert_eq!(backend.get("a"), Some(RespFrame::Integer(1)));
+        assert_eq!(other.get("a"), None);
+    }
+
+    #[test]
+    fn test_push_overflow() {
+        let backend = Backend::new();
+        let mut rx = backend.take_push_receiver().unwrap();
+        let overflow = backend.push_overflow_token();
+        for _ in 0..PUSH_BUFFER_LEN {
+            assert!(backend.push_sender().send(RespFrame::Integer(1)).is_ok());
+        }
+        assert!(!overflow.is_cancelled());
+        assert!(backend.push_sender().send(RespFrame::Integer(1)).is_err());
+        assert!(overflow.is_cancelled());
+        assert_eq!(rx.try_recv().unwrap(), RespFrame::Integer(1));
+    }
+
+    #[test]
+    fn test_keyspace_notifications() {
+        let backend = Backend::new();
+        let mut rx = backend.take_push_receiver().unwrap();
+        assert_eq!(backend.psubscribe("__key*__:*"), 1);
+
+        backend.set("a".to_string(), RespFrame::Integer(1));
+        assert!(rx.try_recv().is_err());
+
+        backend.config_set("notify-keyspace-events", "KE$").unwrap();
+        backend.set("a".to_string(), RespFrame::Integer(2));
+        let channels: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|frame| match frame {
+                RespFrame::Push(push) => push[2].clone(),
+                frame => panic!("unexpected frame: {:?}", frame),
+            })
+            .collect();
+        assert_eq!(
+            channels,
+            vec![
+                RespFrame::BulkString("__keyspace@0__:a".into()),
+                RespFrame::BulkString("__keyevent@0__:set".into())
+            ]
+        );
+
+        // generic events are not enabled
+        backend.del(&["a"]);
+        assert!(rx.try_recv().is_err());
+    }
 }
@@ -0,0 +1,75 @@
+use super::{unix_time, Backend, PushSender};
+use crate::{quote_bytes, RespFrame, SimpleString};
+use dashmap::DashMap;
+
+// connections in MONITOR mode, keyed by session id
+#[derive(Debug, Default)]
+pub struct Monitors {
+    senders: DashMap<u64, PushSender>,
+}
+
+impl Backend {
+    pub fn monitor(&self) {
+        self.monitors
+            .senders
+            .insert(self.session_id(), self.push_sender().clone());
+    }
+
+    pub fn is_monitor(&self) -> bool {
+        self.monitors.senders.contains_key(&self.session_id())
+    }
+
+    pub(crate) fn stop_monitor(&self) {
+        self.monitors.senders.remove(&self.session_id());
+    }
+
+    // send a command about to be executed to every monitor, formatted like redis:
+    // 1339518083.107412 [0 127.0.0.1:60866] "set" "key" "value"
+    pub fn feed_monitors(&self, args: &[Vec<u8>]) {
+        if self.monitors.senders.is_empty() {
+            return;
+        }
+        let now = unix_time();
+        let mut line = format!(
+            "{}.{:06} [{} {}]",
+            now.as_secs(),
+            now.subsec_micros(),
+            self.selected_db(),
+            self.client_addr()
+        );
+        for arg in args {
+            line.push(' ');
+            line.push_str(&quote_bytes(arg));
+        }
+        let frame: RespFrame = SimpleString::new(line).into();
+        for sender in self.monitors.senders.iter() {
+            let _ = sender.send(frame.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed_monitors() {
+        let backend = Backend::new();
+        let mut rx = backend.take_push_receiver().unwrap();
+        backend.monitor();
+        assert!(backend.is_monitor());
+
+        let client = backend.new_session();
+        client.set_client_addr("127.0.0.1:6000");
+        client.select(2);
+        client.feed_monitors(&[b"set".to_vec(), b"k".to_vec(), b"a b".to_vec()]);
+        let RespFrame::SimpleString(line) = rx.try_recv().unwrap() else {
+            panic!("expected a simple string");
+        };
+        assert!(line.ends_with(r#" [2 127.0.0.1:6000] "set" "k" "a b""#));
+
+        backend.stop_monitor();
+        client.feed_monitors(&[b"get".to_vec()]);
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,406 @@
+use super::{frame_len, unix_time, Backend, Db, Hash, SortedSet};
+use crate::RespFrame;
+use dashmap::DashMap;
+use rand::Rng;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+// rough per entry overhead of the hash tables: the entry, the object header and pointers
+pub(crate) const ENTRY_OVERHEAD: usize = 64;
+// a listpack is a header, then per entry the data with its encoding and back length
+const LISTPACK_HEADER: usize = 7;
+const LISTPACK_ENTRY_OVERHEAD: usize = 2;
+// strings up to this length are allocated along with their object
+const EMBSTR_MAX_LEN: usize = 44;
+// integers below this are shared objects in redis, never freed
+const SHARED_INTEGERS: i64 = 10000;
+const SHARED_REFCOUNT: i64 = i32::MAX as i64;
+// larger tables, and hashes and sorted sets with more fields, are sized from this many of
+// their entries: used_memory then costs the same whatever the size of the dataset
+const MEMORY_SAMPLES: usize = 1000;
+
+// the logarithmic access counter of redis' LFU: a new key starts at LFU_INIT_VAL, the
+// counter is less likely to grow the higher it is, and decays by one per LFU_DECAY_TIME
+// minutes without access
+const LFU_INIT_VAL: u8 = 5;
+const LFU_LOG_FACTOR: f64 = 10.0;
+const LFU_DECAY_TIME: u64 = 1;
+
+// a value stored along with the access statistics of its key
+#[derive(Debug)]
+pub(crate) struct Object<V> {
+    value: V,
+    meta: KeyMeta,
+}
+
+// access statistics of a key, for OBJECT IDLETIME / FREQ; there is no eviction, so both
+// the access time and the frequency are always tracked. They are atomics, a read updates
+// them under the read lock of the entry.
+#[derive(Debug)]
+pub(crate) struct KeyMeta {
+    // unix time in milliseconds
+    access: AtomicU64,
+    freq: AtomicU8,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetMemory {
+    // the estimate of used_memory
+    pub total: usize,
+    // the values
+    pub dataset: usize,
+    // the keys, and the table entries holding them
+    pub overhead: usize,
+    pub keys: usize,
+    // (db index, overhead of its main table) for every non empty db
+    pub dbs: Vec<(usize, usize)>,
+}
+
+impl<V> Object<V> {
+    pub(crate) fn new(value: V) -> Self {
+        Self {
+            value,
+            meta: KeyMeta::new(now()),
+        }
+    }
+}
+
+impl<V: Default> Default for Object<V> {
+    fn default() -> Self {
+        Self::new(V::default())
+    }
+}
+
+impl<V> Deref for Object<V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.value
+    }
+}
+
+impl<V> DerefMut for Object<V> {
+    fn deref_mut(&mut self) -> &mut V {
+        &mut self.value
+    }
+}
+
+impl KeyMeta {
+    fn new(now: u64) -> Self {
+        Self {
+            access: AtomicU64::new(now),
+            freq: AtomicU8::new(LFU_INIT_VAL),
+        }
+    }
+
+    fn freq(&self, now: u64) -> u8 {
+        let access = self.access.load(Ordering::Relaxed);
+        let periods = now.saturating_sub(access) / 60_000 / LFU_DECAY_TIME;
+        let freq = self.freq.load(Ordering::Relaxed);
+        freq.saturating_sub(periods.min(u8::MAX as u64) as u8)
+    }
+
+    // concurrent accesses may count once, as with the approximate counter of redis
+    fn access(&self, now: u64) {
+        let freq = lfu_log_incr(self.freq(now));
+        self.freq.store(freq, Ordering::Relaxed);
+        self.access.store(now, Ordering::Relaxed);
+    }
+
+    fn idle(&self, now: u64) -> u64 {
+        now.saturating_sub(self.access.load(Ordering::Relaxed))
+    }
+}
+
+fn lfu_log_incr(counter: u8) -> u8 {
+    if counter == u8::MAX {
+        return counter;
+    }
+    let r: f64 = rand::thread_rng().gen();
+    let base = counter.saturating_sub(LFU_INIT_VAL) as f64;
+    if r < 1.0 / (base * LFU_LOG_FACTOR + 1.0) {
+        counter + 1
+    } else {
+        counter
+    }
+}
+
+// strings holding a small integer are stored as the integer, the "int" encoding
+pub(crate) fn encode_string(value: RespFrame) -> RespFrame {
+    let n = match &value {
+        RespFrame::BulkString(s) if s.len() <= 20 => std::str::from_utf8(s)
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok().filter(|n| n.to_string() == s)),
+        _ => None,
+    };
+    n.map_or(value, RespFrame::Integer)
+}
+
+impl Backend {
+    // a client read a key: it gets tracked, and its access recorded
+    pub(crate) fn read_key(&self, key: &str) {
+        self.tracking.track(self.session.id, key);
+        self.db().with_meta(key, |meta| meta.access(now()));
+    }
+
+    // a key of the selected db was written, a new one starts with fresh statistics
+    pub(crate) fn write_key(&self, key: &str, is_new: bool) {
+        if !is_new {
+            self.db().with_meta(key, |meta| meta.access(now()));
+        }
+    }
+
+    pub fn object_encoding(&self, key: &str) -> Option<&'static str> {
+        let db = self.db();
+        if let Some(v) = db.map.get(key) {
+            return Some(match &**v {
+                RespFrame::Integer(_) => "int",
+                v if frame_len(v) <= EMBSTR_MAX_LEN => "embstr",
+                _ => "raw",
+            });
+        }
+        if let Some(v) = db.hmap.get(key) {
+            return Some(v.encoding());
+        }
+        db.zset.contains_key(key).then_some("skiplist")
+    }
+
+    // the LFU counter, decayed to now
+    pub fn object_freq(&self, key: &str) -> Option<u8> {
+        self.db().with_meta(key, |meta| meta.freq(now()))
+    }
+
+    // seconds since the last access
+    pub fn object_idletime(&self, key: &str) -> Option<u64> {
+        self.db().with_meta(key, |meta| meta.idle(now()) / 1000)
+    }
+
+    pub fn object_refcount(&self, key: &str) -> Option<i64> {
+        let db = self.db();
+        if let Some(v) = db.map.get(key) {
+            return Some(match &**v {
+                RespFrame::Integer(n) if (0..SHARED_INTEGERS).contains(n) => SHARED_REFCOUNT,
+                _ => 1,
+            });
+        }
+        db.contains_key(key).then_some(1)
+    }
+
+    // estimate of the bytes used by a key and its value
+    pub fn memory_usage(&self, key: &str) -> Option<usize> {
+        let db = self.db();
+        let value = if let Some(v) = db.map.get(key) {
+            string_size(v.value())
+        } else if let Some(v) = db.hmap.get(key) {
+            hash_size(v.value())
+        } else if let Some(v) = db.zset.get(key) {
+            zset_size(v.value())
+        } else {
+            return None;
+        };
+        Some(key.len() + ENTRY_OVERHEAD + value)
+    }
+
+    pub fn memory_stats(&self) -> DatasetMemory {
+        let mut stats = DatasetMemory {
+            total: 0,
+            dataset: 0,
+            overhead: 0,
+            keys: 0,
+            dbs: Vec::new(),
+        };
+        for index in 0..self.databases() {
+            let db = self.db_at(index);
+            if db.is_empty() {
+                continue;
+            }
+            let (dataset, keys) = db_size(&db);
+            stats.dataset += dataset;
+            stats.overhead += keys;
+            stats.keys += db.len();
+            stats.dbs.push((index, db.len() * ENTRY_OVERHEAD));
+        }
+        stats.total = stats.dataset + stats.overhead;
+        stats
+    }
+}
+
+impl Db {
+    // runs `f` on the statistics of a key, under the read lock of its entry
+    fn with_meta<T>(&self, key: &str, f: impl FnOnce(&KeyMeta) -> T) -> Option<T> {
+        if let Some(v) = self.map.get(key) {
+            return Some(f(&v.meta));
+        }
+        if let Some(v) = self.hmap.get(key) {
+            return Some(f(&v.meta));
+        }
+        self.zset.get(key).map(|v| f(&v.meta))
+    }
+}
+
+// estimate of (bytes of the values, bytes of the keys with their table entries) of a db
+pub(crate) fn db_size(db: &Db) -> (usize, usize) {
+    [
+        table_size(&db.map, string_size),
+        table_size(&db.hmap, hash_size),
+        table_size(&db.zset, zset_size),
+    ]
+    .into_iter()
+    .fold((0, 0), |(values, keys), (value, key)| {
+        (values + value, keys + key)
+    })
+}
+
+// (bytes of the values, bytes of the keys) of a table, extrapolated from the first
+// MEMORY_SAMPLES entries
+fn table_size<V>(table: &DashMap<String, Object<V>>, size: impl Fn(&V) -> usize) -> (usize, usize) {
+    let (values, keys, n) =
+        table
+            .iter()
+            .take(MEMORY_SAMPLES)
+            .fold((0, 0, 0), |(values, keys, n), e| {
+                let key = e.key().len() + ENTRY_OVERHEAD;
+                (values + size(e.value()), keys + key, n + 1)
+            });
+    match n {
+        0 => (0, 0),
+        n => (values * table.len() / n, keys * table.len() / n),
+    }
+}
+
+// the total size of the `len` items, extrapolated from the first MEMORY_SAMPLES of them;
+// exact for smaller collections
+fn sampled_size<T>(items: impl Iterator<Item = T>, len: usize, size: impl Fn(T) -> usize) -> usize {
+    let (total, n) = items
+        .take(MEMORY_SAMPLES)
+        .fold((0, 0), |(total, n), item| (total + size(item), n + 1));
+    match n {
+        0 => 0,
+        n => total * len / n,
+    }
+}
+
+// an integer lives in the object itself
+fn string_size(value: &RespFrame) -> usize {
+    match value {
+        RespFrame::Integer(_) => 0,
+        v => frame_len(v),
+    }
+}
+
+fn hash_size(hash: &Hash) -> usize {
+    match hash {
+        Hash::ListPack(pairs) => {
+            let entries: usize = pairs
+                .iter()
+                .map(|(f, v)| f.len() + frame_len(v) + 2 * LISTPACK_ENTRY_OVERHEAD)
+                .sum();
+            LISTPACK_HEADER + entries
+        }
+        Hash::Table(table) => sampled_size(table.iter(), table.len(), |(f, v)| {
+            f.len() + frame_len(v) + ENTRY_OVERHEAD
+        }),
+    }
+}
+
+// a member is in the dict and the skiplist, along with its score
+fn zset_size(zset: &SortedSet) -> usize {
+    sampled_size(zset.iter(), zset.len(), |(member, _)| {
+        member.len() + 8 + ENTRY_OVERHEAD
+    })
+}
+
+fn now() -> u64 {
+    unix_time().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    #[test]
+    fn test_encode_string() {
+        let bulk = |s: &str| RespFrame::BulkString(BulkString::from(s));
+        assert_eq!(encode_string(bulk("12")), RespFrame::Integer(12));
+        assert_eq!(encode_string(bulk("-7")), RespFrame::Integer(-7));
+        // not the canonical form of the integer, it would not read back the same
+        assert_eq!(encode_string(bulk("012")), bulk("012"));
+        assert_eq!(encode_string(bulk("+1")), bulk("+1"));
+        assert_eq!(
+            encode_string(bulk("99999999999999999999")),
+            bulk("99999999999999999999")
+        );
+        assert_eq!(encode_string(bulk("abc")), bulk("abc"));
+    }
+
+    #[test]
+    fn test_object_introspection() {
+        let backend = Backend::new();
+        backend.set("n".to_string(), RespFrame::BulkString("42".into()));
+        backend.set("s".to_string(), RespFrame::BulkString("hello".into()));
+        backend.set(
+            "r".to_string(),
+            RespFrame::BulkString("x".repeat(45).into()),
+        );
+        backend.set("big".to_string(), RespFrame::BulkString("123456".into()));
+        backend.hset("h".to_string(), "f".to_string(), RespFrame::Integer(1));
+        assert_eq!(backend.object_encoding("n"), Some("int"));
+        assert_eq!(backend.object_encoding("s"), Some("embstr"));
+        assert_eq!(backend.object_encoding("r"), Some("raw"));
+        assert_eq!(backend.object_encoding("h"), Some("listpack"));
+        assert_eq!(backend.object_encoding("missing"), None);
+        assert_eq!(backend.object_refcount("n"), Some(SHARED_REFCOUNT));
+        assert_eq!(backend.object_refcount("big"), Some(1));
+        assert_eq!(backend.object_refcount("missing"), None);
+
+        // the first accesses always count
+        assert_eq!(backend.object_freq("s"), Some(LFU_INIT_VAL));
+        backend.get("s");
+        assert_eq!(backend.object_freq("s"), Some(LFU_INIT_VAL + 1));
+        for _ in 0..1000 {
+            backend.get("s");
+        }
+        assert!(backend.object_freq("s") > Some(LFU_INIT_VAL + 1));
+
+        // a key accessed 3 minutes ago
+        let then = now() - 180_000;
+        let meta = KeyMeta::new(then);
+        meta.freq.store(10, Ordering::Relaxed);
+        backend.db().map.get_mut("n").unwrap().meta = meta;
+        assert_eq!(backend.object_idletime("n"), Some(180));
+        assert_eq!(backend.object_freq("n"), Some(7));
+        backend.get("n");
+        assert_eq!(backend.object_idletime("n"), Some(0));
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let backend = Backend::new();
+        backend.set("n".to_string(), RespFrame::BulkString("42".into()));
+        backend.set("s".to_string(), RespFrame::BulkString("hello".into()));
+        assert_eq!(backend.memory_usage("n"), Some(1 + ENTRY_OVERHEAD));
+        assert_eq!(backend.memory_usage("s"), Some(1 + ENTRY_OVERHEAD + 5));
+        assert_eq!(backend.memory_usage("missing"), None);
+
+        // the same fields take less room as a listpack than as a table
+        backend.hset("small".to_string(), "f".to_string(), RespFrame::Integer(1));
+        backend
+            .config_set("hash-max-listpack-entries", "0")
+            .unwrap();
+        backend.hset("large".to_string(), "f".to_string(), RespFrame::Integer(1));
+        assert_eq!(backend.object_encoding("large"), Some("hashtable"));
+        let small = backend.memory_usage("small").unwrap();
+        assert_eq!(
+            small,
+            5 + ENTRY_OVERHEAD + LISTPACK_HEADER + 1 + 1 + 2 * LISTPACK_ENTRY_OVERHEAD
+        );
+        assert!(backend.memory_usage("large").unwrap() > small);
+
+        let stats = backend.memory_stats();
+        assert_eq!(stats.keys, 4);
+        assert_eq!(stats.dbs, vec![(0, 4 * ENTRY_OVERHEAD)]);
+        assert_eq!(stats.total, backend.used_memory());
+        assert_eq!(stats.total, stats.dataset + stats.overhead);
+    }
+}
@@ -0,0 +1,171 @@
+use super::{Backend, PushSender};
+use crate::{glob_match, BulkString, RespFrame, RespPush};
+use dashmap::DashMap;
+use std::collections::HashMap;
+
+// subscribers per channel and per pattern, keyed by session id
+#[derive(Debug, Default)]
+pub struct PubSub {
+    channels: DashMap<String, HashMap<u64, PushSender>>,
+    patterns: DashMap<String, HashMap<u64, PushSender>>,
+}
+
+impl PubSub {
+    pub fn subscribe(&self, id: u64, sender: &PushSender, channel: &str) {
+        self.channels
+            .entry(channel.to_string())
+            .or_default()
+            .insert(id, sender.clone());
+    }
+
+    pub fn unsubscribe(&self, id: u64, channel: &str) {
+        self.channels.remove_if_mut(channel, |_, subscribers| {
+            subscribers.remove(&id);
+            subscribers.is_empty()
+        });
+    }
+
+    pub fn psubscribe(&self, id: u64, sender: &PushSender, pattern: &str) {
+        self.patterns
+            .entry(pattern.to_string())
+            .or_default()
+            .insert(id, sender.clone());
+    }
+
+    pub fn punsubscribe(&self, id: u64, pattern: &str) {
+        self.patterns.remove_if_mut(pattern, |_, subscribers| {
+            subscribers.remove(&id);
+            subscribers.is_empty()
+        });
+    }
+
+    // deliver the message to channel and pattern subscribers, returns the number of receivers
+    pub fn publish(&self, channel: &str, message: &[u8]) -> usize {
+        let mut receivers = 0;
+        if let Some(subscribers) = self.channels.get(channel) {
+            let push = RespPush::with_kind(
+                "message",
+                [BulkString::from(channel).into(), message.into()],
+            );
+            for sender in subscribers.values() {
+                receivers += sender.send(push.clone().into()).is_ok() as usize;
+            }
+        }
+        for entry in self.patterns.iter() {
+            if !glob_match(entry.key().as_bytes(), channel.as_bytes()) {
+                continue;
+            }
+            let push: RespFrame = RespPush::with_kind(
+                "pmessage",
+                [
+                    BulkString::from(entry.key().as_str()).into(),
+                    BulkString::from(channel).into(),
+                    message.into(),
+                ],
+            )
+            .into();
+            for sender in entry.value().values() {
+                receivers += sender.send(push.clone()).is_ok() as usize;
+            }
+        }
+        receivers
+    }
+
+    pub fn remove_session(&self, id: u64) {
+        for map in [&self.channels, &self.patterns] {
+            map.retain(|_, subscribers| {
+                subscribers.remove(&id);
+                !subscribers.is_empty()
+            });
+        }
+    }
+}
+
+// session side of pub/sub, every call returns the number of subscriptions the session
+// holds afterwards, which is what the confirmation replies carry
+impl Backend {
+    pub fn subscribe(&self, channel: &str) -> usize {
+        let session = self.session();
+        if session.channels.lock().unwrap().insert(channel.to_string()) {
+            self.pubsub
+                .subscribe(self.session_id(), self.push_sender(), channel);
+        }
+        self.subscription_count()
+    }
+
+    pub fn unsubscribe(&self, channel: &str) -> usize {
+        if self.session().channels.lock().unwrap().remove(channel) {
+            self.pubsub.unsubscribe(self.session_id(), channel);
+        }
+        self.subscription_count()
+    }
+
+    pub fn psubscribe(&self, pattern: &str) -> usize {
+        let session = self.session();
+        if session.patterns.lock().unwrap().insert(pattern.to_string()) {
+            self.pubsub
+                .psubscribe(self.session_id(), self.push_sender(), pattern);
+        }
+        self.subscription_count()
+    }
+
+    pub fn punsubscribe(&self, pattern: &str) -> usize {
+        if self.session().patterns.lock().unwrap().remove(pattern) {
+            self.pubsub.punsubscribe(self.session_id(), pattern);
+        }
+        self.subscription_count()
+    }
+
+    pub fn subscribed_channels(&self) -> Vec<String> {
+        self.session()
+            .channels
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    pub fn subscribed_patterns(&self) -> Vec<String> {
+        self.session()
+            .patterns
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    fn subscription_count(&self) -> usize {
+        let session = self.session();
+        session.channels.lock().unwrap().len() + session.patterns.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish() {
+        let pubsub = PubSub::default();
+        let (tx, mut rx) = PushSender::channel(16);
+        pubsub.subscribe(1, &tx, "news");
+        pubsub.psubscribe(1, &tx, "n*");
+
+        assert_eq!(pubsub.publish("news", b"hi"), 2);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            RespPush::with_kind("message", [b"news".into(), b"hi".into()]).into()
+        );
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            RespPush::with_kind("pmessage", [b"n*".into(), b"news".into(), b"hi".into()]).into()
+        );
+
+        pubsub.unsubscribe(1, "news");
+        assert_eq!(pubsub.publish("news", b"hi"), 1);
+        pubsub.remove_session(1);
+        assert_eq!(pubsub.publish("news", b"hi"), 0);
+    }
+}
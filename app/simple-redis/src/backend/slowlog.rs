@@ -0,0 +1,133 @@
+use super::{unix_time, Backend};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// same limits as redis, so a huge MSET does not blow up the log
+const SLOWLOG_ENTRY_MAX_ARGC: usize = 32;
+const SLOWLOG_ENTRY_MAX_STRING: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlowLogEntry {
+    pub id: u64,
+    // unix time in seconds
+    pub timestamp: u64,
+    // execution time in microseconds
+    pub duration: u64,
+    pub args: Vec<Vec<u8>>,
+    pub addr: String,
+}
+
+// commands slower than slowlog-log-slower-than, newest first, at most slowlog-max-len entries
+#[derive(Debug, Default)]
+pub struct SlowLog {
+    entries: Mutex<VecDeque<SlowLogEntry>>,
+    next_id: AtomicU64,
+}
+
+impl SlowLog {
+    fn push(&self, mut entry: SlowLogEntry, max_len: usize) {
+        entry.id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock().unwrap();
+        entries.push_front(entry);
+        entries.truncate(max_len);
+    }
+
+    pub fn get(&self, count: usize) -> Vec<SlowLogEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().take(count).cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn reset(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+impl Backend {
+    pub(crate) fn slowlog_record(&self, args: &[Vec<u8>], elapsed: Duration) {
+        let threshold = self.config.slowlog_log_slower_than.load(Ordering::Relaxed);
+        let duration = elapsed.as_micros() as u64;
+        // a negative threshold disables the slow log, zero logs every command
+        if threshold < 0 || duration < threshold as u64 {
+            return;
+        }
+        let entry = SlowLogEntry {
+            id: 0,
+            timestamp: unix_time().as_secs(),
+            duration,
+            args: truncate_args(args),
+            addr: self.client_addr().to_string(),
+        };
+        let max_len = self.config.slowlog_max_len.load(Ordering::Relaxed);
+        self.slowlog.push(entry, max_len);
+    }
+}
+
+fn truncate_args(args: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let mut ret: Vec<Vec<u8>> = args
+        .iter()
+        .take(SLOWLOG_ENTRY_MAX_ARGC)
+        .map(|arg| {
+            if arg.len() <= SLOWLOG_ENTRY_MAX_STRING {
+                return arg.clone();
+            }
+            let mut s = arg[..SLOWLOG_ENTRY_MAX_STRING].to_vec();
+            let more = format!("... ({} more bytes)", arg.len() - SLOWLOG_ENTRY_MAX_STRING);
+            s.extend_from_slice(more.as_bytes());
+            s
+        })
+        .collect();
+    if args.len() > SLOWLOG_ENTRY_MAX_ARGC {
+        let more = args.len() - SLOWLOG_ENTRY_MAX_ARGC + 1;
+        ret[SLOWLOG_ENTRY_MAX_ARGC - 1] = format!("... ({} more arguments)", more).into_bytes();
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slowlog() {
+        let backend = Backend::new();
+        backend
+            .config_set("slowlog-log-slower-than", "1000")
+            .unwrap();
+        backend.config_set("slowlog-max-len", "2").unwrap();
+
+        let args = vec![b"get".to_vec(), b"a".to_vec()];
+        backend.slowlog_record(&args, Duration::from_micros(10));
+        assert!(backend.slowlog.is_empty());
+
+        for _ in 0..3 {
+            backend.slowlog_record(&args, Duration::from_millis(2));
+        }
+        let entries = backend.slowlog.get(10);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, 2);
+        assert_eq!(entries[0].duration, 2000);
+        assert_eq!(entries[0].args, args);
+
+        backend.slowlog.reset();
+        assert_eq!(backend.slowlog.len(), 0);
+    }
+
+    #[test]
+    fn test_truncate_args() {
+        let args: Vec<Vec<u8>> = (0..40).map(|_| vec![b'x'; 130]).collect();
+        let ret = truncate_args(&args);
+        assert_eq!(ret.len(), 32);
+        assert!(ret[0].ends_with(b"... (2 more bytes)"));
+        assert_eq!(ret[31], b"... (9 more arguments)");
+    }
+}
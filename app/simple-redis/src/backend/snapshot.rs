@@ -0,0 +1,142 @@
+use super::{unix_time, Backend};
+use crate::{
+    cmd::{Command, CommandExecutor},
+    geohash_decode, BulkString, RespArray, RespDecodeV2, RespEncode, RespError, RespFrame,
+};
+use anyhow::{anyhow, Result};
+use bytes::BytesMut;
+use std::{fs, path::Path, sync::atomic::Ordering};
+
+// There is no rdb support: a snapshot is the list of commands rebuilding the dataset
+// (SELECT, SET, HSET and GEOADD), encoded as RESP so it can be replayed through the command layer.
+impl Backend {
+    // save to the configured dbfilename
+    pub fn save(&self) -> Result<()> {
+        let path = self.config.dbfilename.read().unwrap().clone();
+        if path.is_empty() {
+            return Err(anyhow!("no dbfilename configured"));
+        }
+        self.save_to(path)
+    }
+
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let ret = self.write_snapshot(path.as_ref());
+        let stats = &self.stats;
+        stats
+            .last_save
+            .store(unix_time().as_secs(), Ordering::Relaxed);
+        stats.last_save_ok.store(ret.is_ok(), Ordering::Relaxed);
+        if ret.is_ok() {
+            stats.dirty.store(0, Ordering::Relaxed);
+        }
+        ret
+    }
+
+    fn write_snapshot(&self, path: &Path) -> Result<()> {
+        let mut buf = Vec::new();
+        for index in 0..self.databases() {
+            let db = self.db_at(index);
+            if db.is_empty() {
+                continue;
+            }
+            let index = BulkString::from(index.to_string()).into();
+            append(&mut buf, "select", [index]);
+            for entry in db.map.iter() {
+                let key = BulkString::from(entry.key().as_str()).into();
+                append(&mut buf, "set", [key, (**entry).clone()]);
+            }
+            for entry in db.hmap.iter() {
+                for (field, value) in entry.value().iter() {
+                    let key = BulkString::from(entry.key().as_str()).into();
+                    let name = BulkString::from(field.as_str()).into();
+                    append(&mut buf, "hset", [key, name, value.clone()]);
+                }
+            }
+            // the only sorted sets are geo indexes, a score decodes to a position encoding
+            // back to the very same score
+            for entry in db.zset.iter() {
+                for (member, score) in entry.value().iter() {
+                    let (lon, lat) = geohash_decode(score as u64);
+                    let key = BulkString::from(entry.key().as_str()).into();
+                    let lon = BulkString::from(lon.to_string()).into();
+                    let lat = BulkString::from(lat.to_string()).into();
+                    let member = BulkString::from(member).into();
+                    append(&mut buf, "geoadd", [key, lon, lat, member]);
+                }
+            }
+        }
+
+        // write aside and rename, so a crash never leaves a truncated snapshot behind
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, buf)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    // replay a snapshot, returns the number of commands executed
+    pub fn load_from(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let mut buf = BytesMut::from(fs::read(path)?.as_slice());
+        let session = self.new_session();
+        let mut n = 0;
+        while !buf.is_empty() {
+            let frame = match RespFrame::decode(&mut buf) {
+                Ok(frame) => frame,
+                Err(RespError::NotComplete) => return Err(anyhow!("truncated snapshot")),
+                Err(e) => return Err(e.into()),
+            };
+            if let RespFrame::Error(e) = Command::try_from(frame)?.execute(&session) {
+                return Err(anyhow!("failed to load snapshot: {}", e.0));
+            }
+            n += 1;
+        }
+        Ok(n)
+    }
+}
+
+fn append(buf: &mut Vec<u8>, name: &str, args: impl IntoIterator<Item = RespFrame>) {
+    let mut frames = vec![BulkString::from(name).into()];
+    frames.extend(args);
+    buf.extend_from_slice(&RespFrame::from(RespArray::new(frames)).encode());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespNull;
+
+    #[test]
+    fn test_save_load() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("dump.resp");
+
+        let backend = Backend::new();
+        backend.set("a".to_string(), RespFrame::BulkString("1".into()));
+        backend.select(2);
+        backend.set("b".to_string(), RespNull.into());
+        backend.hset("h".to_string(), "f".to_string(), RespFrame::Integer(7));
+        let place = vec![(13.361389, 38.115556, "Palermo".to_string())];
+        backend
+            .geoadd("g", place, false, false, false)
+            .map_err(|e| anyhow!(e))?;
+        backend.save_to(&path)?;
+        assert_eq!(backend.stats().changes_since_last_save(), 0);
+        assert!(backend.stats().last_save() > 0);
+
+        let restored = Backend::new();
+        assert_eq!(restored.load_from(&path)?, 6);
+        assert_eq!(restored.get("a"), Some(RespFrame::Integer(1)));
+        restored.select(2);
+        assert_eq!(restored.get("b"), Some(RespNull.into()));
+        assert_eq!(restored.hget("h", "f"), Some(RespFrame::Integer(7)));
+        let palermo = ["Palermo".to_string()];
+        assert_eq!(
+            restored.geopos("g", &palermo),
+            backend.geopos("g", &palermo)
+        );
+        assert_eq!(
+            restored.db().zset.get("g").and_then(|g| g.score("Palermo")),
+            Some(3479099956230698.0)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use super::{object::db_size, Backend, Db};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+// server wide counters, as reported by INFO stats / persistence in redis
+#[derive(Debug)]
+pub struct Stats {
+    pub(crate) connections_received: AtomicU64,
+    // writes since the last successful save
+    pub(crate) dirty: AtomicU64,
+    // unix time of the last save attempt, 0 if never saved
+    pub(crate) last_save: AtomicU64,
+    pub(crate) last_save_ok: AtomicBool,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            connections_received: AtomicU64::default(),
+            dirty: AtomicU64::default(),
+            last_save: AtomicU64::default(),
+            last_save_ok: AtomicBool::new(true),
+        }
+    }
+}
+
+impl Stats {
+    pub fn connections_received(&self) -> u64 {
+        self.connections_received.load(Ordering::Relaxed)
+    }
+
+    pub fn changes_since_last_save(&self) -> u64 {
+        self.dirty.load(Ordering::Relaxed)
+    }
+
+    pub fn last_save(&self) -> u64 {
+        self.last_save.load(Ordering::Relaxed)
+    }
+
+    pub fn last_save_ok(&self) -> bool {
+        self.last_save_ok.load(Ordering::Relaxed)
+    }
+}
+
+impl Backend {
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    // (db index, number of keys) for every non empty db
+    pub fn keyspace(&self) -> Vec<(usize, usize)> {
+        (0..self.databases())
+            .map(|index| (index, self.db_at(index).len()))
+            .filter(|(_, keys)| *keys > 0)
+            .collect()
+    }
+
+    // estimate of the memory used by the dataset: keys, encoded values and table overhead,
+    // sampled so that a metrics scrape doesn't walk the whole dataset
+    pub fn used_memory(&self) -> usize {
+        (0..self.databases())
+            .map(|index| {
+                let (values, keys) = db_size(&self.db_at(index));
+                values + keys
+            })
+            .sum()
+    }
+}
+
+impl Db {
+    pub fn len(&self) -> usize {
+        self.map.len() + self.hmap.len() + self.zset.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{backend::object::ENTRY_OVERHEAD, RespFrame};
+
+    #[test]
+    fn test_keyspace_and_memory() {
+        let backend = Backend::new();
+        assert_eq!(backend.used_memory(), 0);
+        backend.set("a".to_string(), RespFrame::BulkString("1".into()));
+        backend.select(3);
+        backend.hset("h".to_string(), "f".to_string(), RespFrame::Integer(1));
+        backend.hset("h".to_string(), "g".to_string(), RespFrame::Integer(2));
+
+        assert_eq!(backend.keyspace(), vec![(0, 1), (3, 1)]);
+        assert_eq!(backend.stats().changes_since_last_save(), 3);
+        // "a" holding an int, "h" + a listpack of 7 + 2 * ("f" + "1" + 4)
+        assert_eq!(backend.used_memory(), 1 + 1 + 7 + 12 + ENTRY_OVERHEAD * 2);
+    }
+
+    #[test]
+    fn test_used_memory_sampled() {
+        // keys and values of the same size, a sample of them tells the total
+        let backend = Backend::new();
+        for i in 0..5000 {
+            let value = RespFrame::BulkString("value".into());
+            backend.set(format!("key:{:04}", i), value);
+        }
+        assert_eq!(backend.used_memory(), 5000 * (8 + ENTRY_OVERHEAD + 5));
+    }
+}
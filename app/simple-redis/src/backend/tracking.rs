@@ -0,0 +1,134 @@
+use super::{Backend, PushSender};
+use crate::{BulkString, RespArray, RespFrame, RespNull, RespPush};
+use dashmap::DashMap;
+use std::collections::HashSet;
+
+// server assisted client side caching, see https://redis.io/docs/latest/develop/reference/client-side-caching/
+// - default mode: remember which clients read a key, invalidate once when it changes
+// - broadcasting mode: invalidate every key matching one of the client's prefixes
+#[derive(Debug, Default)]
+pub struct Tracking {
+    clients: DashMap<u64, TrackingClient>,
+    table: DashMap<String, HashSet<u64>>,
+}
+
+#[derive(Debug)]
+struct TrackingClient {
+    sender: PushSender,
+    bcast: bool,
+    prefixes: Vec<String>,
+}
+
+impl Tracking {
+    pub fn enable(&self, id: u64, sender: &PushSender, bcast: bool, prefixes: Vec<String>) {
+        let client = TrackingClient {
+            sender: sender.clone(),
+            bcast,
+            prefixes,
+        };
+        self.clients.insert(id, client);
+    }
+
+    pub fn disable(&self, id: u64) {
+        self.clients.remove(&id);
+        self.table.retain(|_, ids| {
+            ids.remove(&id);
+            !ids.is_empty()
+        });
+    }
+
+    pub fn is_enabled(&self, id: u64) -> bool {
+        self.clients.contains_key(&id)
+    }
+
+    // remember that a client in default mode has read the key
+    pub fn track(&self, id: u64, key: &str) {
+        if self.clients.get(&id).is_some_and(|c| !c.bcast) {
+            self.table.entry(key.to_string()).or_default().insert(id);
+        }
+    }
+
+    pub fn invalidate(&self, key: &str) {
+        if self.clients.is_empty() {
+            return;
+        }
+        let mut ids = self
+            .table
+            .remove(key)
+            .map(|(_, ids)| ids)
+            .unwrap_or_default();
+        for client in self.clients.iter() {
+            let matched = client.prefixes.is_empty()
+                || client.prefixes.iter().any(|p| key.starts_with(p.as_str()));
+            if client.bcast && matched {
+                ids.insert(*client.key());
+            }
+        }
+
+        let keys: RespFrame = RespArray::new([BulkString::from(key).into()]).into();
+        self.send(ids, keys);
+    }
+
+    // flushing a db invalidates everything, which is signaled with a null key list
+    pub fn invalidate_all(&self) {
+        self.table.clear();
+        let ids = self.clients.iter().map(|c| *c.key()).collect();
+        self.send(ids, RespNull.into());
+    }
+
+    fn send(&self, ids: HashSet<u64>, keys: RespFrame) {
+        for id in ids {
+            if let Some(client) = self.clients.get(&id) {
+                let push = RespPush::with_kind("invalidate", [keys.clone()]);
+                let _ = client.sender.send(push.into());
+            }
+        }
+    }
+}
+
+impl Backend {
+    // CLIENT TRACKING ON|OFF, invalidations are pushed to the session's own connection
+    pub fn client_tracking(&self, on: bool, bcast: bool, prefixes: Vec<String>) {
+        if on {
+            self.tracking
+                .enable(self.session_id(), self.push_sender(), bcast, prefixes);
+        } else {
+            self.tracking.disable(self.session_id());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracking_invalidate() {
+        let tracking = Tracking::default();
+        let (tx1, mut rx1) = PushSender::channel(16);
+        let (tx2, mut rx2) = PushSender::channel(16);
+        tracking.enable(1, &tx1, false, vec![]);
+        tracking.enable(2, &tx2, true, vec!["user:".to_string()]);
+
+        tracking.track(1, "user:1");
+        tracking.invalidate("user:1");
+        let expected: RespFrame =
+            RespPush::with_kind("invalidate", [RespArray::new([b"user:1".into()]).into()]).into();
+        assert_eq!(rx1.try_recv().unwrap(), expected);
+        assert_eq!(rx2.try_recv().unwrap(), expected);
+
+        // default mode is one-shot until the key is read again
+        tracking.invalidate("user:1");
+        assert!(rx1.try_recv().is_err());
+        assert!(rx2.try_recv().is_ok());
+
+        tracking.invalidate("other");
+        assert!(rx2.try_recv().is_err());
+
+        tracking.invalidate_all();
+        assert!(rx1.try_recv().is_ok());
+        tracking.disable(1);
+        tracking.invalidate_all();
+        assert!(rx1.try_recv().is_err());
+    }
+}
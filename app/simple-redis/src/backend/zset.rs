@@ -0,0 +1,122 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+
+// members ordered by (score, member), as in a redis sorted set: the hash map answers
+// score lookups, the ordered index answers range queries
+#[derive(Debug, Default, Clone)]
+pub struct SortedSet {
+    scores: HashMap<String, f64>,
+    index: BTreeSet<(Score, String)>,
+}
+
+// scores are never NaN, total_cmp gives them a total order
+#[derive(Debug, Clone, Copy)]
+struct Score(f64);
+
+impl SortedSet {
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn score(&self, member: &str) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    // add or update a member, returns true if it's a new member
+    pub fn insert(&mut self, member: String, score: f64) -> bool {
+        match self.scores.insert(member.clone(), score) {
+            Some(old) => {
+                self.index.remove(&(Score(old), member.clone()));
+                self.index.insert((Score(score), member));
+                false
+            }
+            None => {
+                self.index.insert((Score(score), member));
+                true
+            }
+        }
+    }
+
+    pub fn remove(&mut self, member: &str) -> Option<f64> {
+        let score = self.scores.remove(member)?;
+        self.index.remove(&(Score(score), member.to_string()));
+        Some(score)
+    }
+
+    // members with min <= score < max, in order
+    pub fn range_by_score(&self, min: f64, max: f64) -> impl Iterator<Item = (&str, f64)> {
+        let range = (
+            Bound::Included((Score(min), String::new())),
+            Bound::Excluded((Score(max), String::new())),
+        );
+        let range = if min < max {
+            Some(self.index.range(range))
+        } else {
+            None
+        };
+        range
+            .into_iter()
+            .flatten()
+            .map(|(score, member)| (member.as_str(), score.0))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.index
+            .iter()
+            .map(|(score, member)| (member.as_str(), score.0))
+    }
+}
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorted_set() {
+        let mut set = SortedSet::default();
+        assert!(set.insert("c".to_string(), 3.0));
+        assert!(set.insert("a".to_string(), 1.0));
+        assert!(set.insert("b".to_string(), 1.0));
+        assert!(!set.insert("c".to_string(), 0.5));
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.score("c"), Some(0.5));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            [("c", 0.5), ("a", 1.0), ("b", 1.0)]
+        );
+        assert_eq!(
+            set.range_by_score(1.0, 3.0).collect::<Vec<_>>(),
+            [("a", 1.0), ("b", 1.0)]
+        );
+        assert_eq!(set.range_by_score(0.0, 0.5).count(), 0);
+        assert_eq!(set.range_by_score(3.0, 1.0).count(), 0);
+        assert_eq!(set.remove("a"), Some(1.0));
+        assert_eq!(set.remove("a"), None);
+        assert_eq!(set.len(), 2);
+    }
+}
@@ -0,0 +1,176 @@
+use simple_redis::{quote_bytes, RespFrame};
+
+// format a reply the way redis-cli does for a terminal, e.g.
+// 1) "a"
+// 2) 1) (integer) 1
+//    2) (nil)
+pub fn format_tty(frame: &RespFrame) -> String {
+    let mut out = String::new();
+    write_tty(&mut out, frame, "");
+    out
+}
+
+// format a reply with no type hints or quoting, suitable for scripts
+pub fn format_raw(frame: &RespFrame) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_raw(&mut out, frame);
+    out
+}
+
+fn write_tty(out: &mut String, frame: &RespFrame, prefix: &str) {
+    match frame {
+        RespFrame::SimpleString(s) => out.push_str(s),
+        RespFrame::Error(e) => {
+            out.push_str("(error) ");
+            out.push_str(e);
+        }
+        RespFrame::BulkError(e) => {
+            out.push_str("(error) ");
+            out.push_str(&String::from_utf8_lossy(e));
+        }
+        RespFrame::Integer(n) => out.push_str(&format!("(integer) {}", n)),
+        RespFrame::Double(n) => out.push_str(&format!("(double) {}", n)),
+        RespFrame::BigNumber(n) => out.push_str(&format!("(big number) {}", n.as_str())),
+        RespFrame::Boolean(b) => out.push_str(if *b { "(true)" } else { "(false)" }),
+        RespFrame::Null(_) | RespFrame::NullBulkString(_) => out.push_str("(nil)"),
+        RespFrame::BulkString(s) => out.push_str(&quote_bytes(s)),
+        RespFrame::VerbatimString(s) => out.push_str(&String::from_utf8_lossy(s.data())),
+        RespFrame::Array(items) => write_tty_items(out, items, ")", "(empty array)", prefix),
+        RespFrame::Set(items) => write_tty_items(out, items, "~", "(empty set)", prefix),
+        RespFrame::Push(items) => write_tty_items(out, items, ")", "(empty push)", prefix),
+        RespFrame::Map(map) => write_tty_pairs(out, map.iter(), map.len(), prefix),
+        RespFrame::Attribute(map) => write_tty_pairs(out, map.iter(), map.len(), prefix),
+    }
+}
+
+fn write_tty_items(out: &mut String, items: &[RespFrame], sep: &str, empty: &str, prefix: &str) {
+    if items.is_empty() {
+        out.push_str(empty);
+        return;
+    }
+
+    let width = items.len().to_string().len();
+    let nested = format!("{}{}", prefix, " ".repeat(width + sep.len() + 1));
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+            out.push_str(prefix);
+        }
+        out.push_str(&format!("{:>width$}{} ", i + 1, sep, width = width));
+        write_tty(out, item, &nested);
+    }
+}
+
+fn write_tty_pairs<'a>(
+    out: &mut String,
+    pairs: impl Iterator<Item = (&'a String, &'a RespFrame)>,
+    len: usize,
+    prefix: &str,
+) {
+    if len == 0 {
+        out.push_str("(empty hash)");
+        return;
+    }
+
+    let width = len.to_string().len();
+    let nested = format!("{}{}", prefix, " ".repeat(width + 2));
+    for (i, (key, value)) in pairs.enumerate() {
+        if i > 0 {
+            out.push('\n');
+            out.push_str(prefix);
+        }
+        out.push_str(&format!("{:>width$}# {} => ", i + 1, key, width = width));
+        write_tty(out, value, &nested);
+    }
+}
+
+fn write_raw(out: &mut Vec<u8>, frame: &RespFrame) {
+    match frame {
+        RespFrame::SimpleString(s) => out.extend_from_slice(s.as_bytes()),
+        RespFrame::Error(e) => out.extend_from_slice(e.as_bytes()),
+        RespFrame::BulkError(e) => out.extend_from_slice(e),
+        RespFrame::Integer(n) => out.extend_from_slice(n.to_string().as_bytes()),
+        RespFrame::Double(n) => out.extend_from_slice(n.to_string().as_bytes()),
+        RespFrame::BigNumber(n) => out.extend_from_slice(n.as_bytes()),
+        RespFrame::Boolean(b) => out.push(if *b { b'1' } else { b'0' }),
+        RespFrame::Null(_) | RespFrame::NullBulkString(_) => {}
+        RespFrame::BulkString(s) => out.extend_from_slice(s),
+        RespFrame::VerbatimString(s) => out.extend_from_slice(s.data()),
+        RespFrame::Array(items) => write_raw_items(out, items),
+        RespFrame::Set(items) => write_raw_items(out, items),
+        RespFrame::Push(items) => write_raw_items(out, items),
+        RespFrame::Map(map) => write_raw_pairs(out, map.iter()),
+        RespFrame::Attribute(map) => write_raw_pairs(out, map.iter()),
+    }
+}
+
+fn write_raw_items(out: &mut Vec<u8>, items: &[RespFrame]) {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push(b'\n');
+        }
+        write_raw(out, item);
+    }
+}
+
+fn write_raw_pairs<'a>(
+    out: &mut Vec<u8>,
+    pairs: impl Iterator<Item = (&'a String, &'a RespFrame)>,
+) {
+    for (i, (key, value)) in pairs.enumerate() {
+        if i > 0 {
+            out.push(b'\n');
+        }
+        out.extend_from_slice(key.as_bytes());
+        out.push(b'\n');
+        write_raw(out, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simple_redis::{RespArray, RespMap, RespNull, SimpleError, SimpleString};
+
+    #[test]
+    fn test_format_tty() {
+        assert_eq!(format_tty(&SimpleString::new("OK").into()), "OK");
+        assert_eq!(
+            format_tty(&SimpleError::new("ERR unknown").into()),
+            "(error) ERR unknown"
+        );
+        assert_eq!(format_tty(&1.5.into()), "(double) 1.5");
+        assert_eq!(
+            format_tty(&RespFrame::BulkString("a\"b\n\x01".into())),
+            r#""a\"b\n\x01""#
+        );
+        assert_eq!(format_tty(&RespArray::new([]).into()), "(empty array)");
+
+        let nested: Vec<RespFrame> = (0..10)
+            .map(|i| match i {
+                1 => RespArray::new([1.into(), RespNull.into()]).into(),
+                _ => RespFrame::BulkString("a".into()),
+            })
+            .collect();
+        let out = format_tty(&RespArray::new(nested).into());
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], " 1) \"a\"");
+        assert_eq!(lines[1], " 2) 1) (integer) 1");
+        assert_eq!(lines[2], "    2) (nil)");
+        assert_eq!(lines[10], "10) \"a\"");
+
+        let mut map = RespMap::new();
+        map.insert("k".to_string(), RespFrame::BulkString("v".into()));
+        assert_eq!(format_tty(&map.into()), r#"1# k => "v""#);
+    }
+
+    #[test]
+    fn test_format_raw() {
+        assert_eq!(format_raw(&RespFrame::BulkString("a\nb".into())), b"a\nb");
+        assert_eq!(format_raw(&RespNull.into()), b"");
+        assert_eq!(
+            format_raw(&RespArray::new([1.into(), RespFrame::BulkString("x".into())]).into()),
+            b"1\nx"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+mod format;
+
+use anyhow::Result;
+use clap::{ArgAction, Parser};
+use futures::SinkExt;
+use rand::{distributions::Alphanumeric, Rng};
+use simple_redis::{
+    client::{Client, ClientError},
+    network::RespFrameCodec,
+    split_args, BulkString, RespArray, RespFrame, SimpleString,
+};
+use std::time::Duration;
+use tokio::io::{self, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio_stream::StreamExt;
+use tokio_util::codec::FramedRead;
+
+use crate::format::{format_raw, format_tty};
+
+#[derive(Debug, Parser)]
+#[command(name = "simple-redis-cli", version, about, disable_help_flag = true)]
+struct Opts {
+    #[arg(
+        short = 'h',
+        long,
+        default_value = "127.0.0.1",
+        help = "Server hostname"
+    )]
+    host: String,
+    #[arg(short, long, default_value_t = 6379, help = "Server port")]
+    port: u16,
+    #[arg(
+        short,
+        long,
+        default_value_t = 1,
+        allow_negative_numbers = true,
+        help = "Execute the command N times, negative means forever"
+    )]
+    repeat: i64,
+    #[arg(
+        short,
+        long,
+        default_value_t = 0.0,
+        help = "Wait <interval> seconds between repeated commands"
+    )]
+    interval: f64,
+    #[arg(long, help = "Use raw formatting for replies")]
+    raw: bool,
+    #[arg(
+        long,
+        help = "Transfer raw RESP or inline commands from stdin to the server"
+    )]
+    pipe: bool,
+    #[arg(long, action = ArgAction::Help, help = "Print help")]
+    help: Option<bool>,
+    #[arg(
+        trailing_var_arg = true,
+        help = "Command to execute, interactive mode if omitted"
+    )]
+    command: Vec<String>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let mut client = Client::connect((opts.host.as_str(), opts.port)).await?;
+
+    if opts.pipe {
+        pipe(client).await
+    } else if !opts.command.is_empty() {
+        let args = opts.command.iter().map(|s| s.as_bytes().to_vec()).collect();
+        repeat(&mut client, &opts, args).await
+    } else {
+        interactive(&mut client, &opts).await
+    }
+}
+
+async fn repeat(client: &mut Client, opts: &Opts, args: Vec<Vec<u8>>) -> Result<()> {
+    if args[0].eq_ignore_ascii_case(b"monitor") {
+        return monitor(client).await;
+    }
+    let mut n = 0;
+    while opts.repeat < 0 || n < opts.repeat {
+        let Some(reply) = execute(client, args.clone()).await? else {
+            return Ok(());
+        };
+        print_reply(&reply, opts.raw).await?;
+        n += 1;
+        if opts.interval > 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(opts.interval)).await;
+        }
+    }
+    Ok(())
+}
+
+async fn interactive(client: &mut Client, opts: &Opts) -> Result<()> {
+    let prompt = format!("{}:{}> ", opts.host, opts.port);
+    let mut stdout = io::stdout();
+    let mut lines = BufReader::new(io::stdin()).lines();
+    loop {
+        stdout.write_all(prompt.as_bytes()).await?;
+        stdout.flush().await?;
+
+        let Some(line) = lines.next_line().await? else {
+            return Ok(());
+        };
+        let args = match split_args(line.as_bytes()) {
+            Ok(args) if args.is_empty() => continue,
+            Ok(args) => args,
+            Err(_) => {
+                stdout.write_all(b"Invalid argument(s)\n").await?;
+                continue;
+            }
+        };
+        if args[0].eq_ignore_ascii_case(b"quit") || args[0].eq_ignore_ascii_case(b"exit") {
+            return Ok(());
+        }
+        if args[0].eq_ignore_ascii_case(b"monitor") {
+            return monitor(client).await;
+        }
+
+        let Some(reply) = execute(client, args).await? else {
+            return Ok(());
+        };
+        print_reply(&reply, opts.raw).await?;
+    }
+}
+
+// None once a SHUTDOWN closed the connection, the server doesn't reply to it
+async fn execute(client: &mut Client, args: Vec<Vec<u8>>) -> Result<Option<RespFrame>> {
+    let shutdown = args[0].eq_ignore_ascii_case(b"shutdown");
+    match client.execute(to_command(args)).await {
+        Ok(reply) => Ok(Some(reply)),
+        Err(ClientError::ConnectionClosed) if shutdown => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// print the command feed until the connection is closed
+async fn monitor(client: &mut Client) -> Result<()> {
+    client.monitor().await?;
+    print_reply(&SimpleString::new("OK").into(), false).await?;
+    let mut stdout = io::stdout();
+    loop {
+        let line = client.next_monitor_line().await?;
+        stdout.write_all(format!("{}\n", line).as_bytes()).await?;
+        stdout.flush().await?;
+    }
+}
+
+// mass insertion as redis-cli does it: stdin, raw RESP (as produced by scripts for
+// redis-cli --pipe) or inline commands, is streamed to the server while the replies are read,
+// so neither side waits on the other however large the input; an ECHO of a random marker
+// tells when the last reply has arrived
+async fn pipe(client: Client) -> Result<()> {
+    let (mut commands, mut replies) = client.into_split();
+    let marker: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(20)
+        .map(char::from)
+        .collect();
+
+    // a last line without a newline is still a command
+    let input = io::stdin().chain(&b"\n"[..]);
+    let mut input = FramedRead::new(input, RespFrameCodec);
+    let echo = to_command(vec![b"echo".to_vec(), marker.as_bytes().to_vec()]);
+    let write = async move {
+        while let Some(frame) = input.next().await {
+            match frame {
+                Ok(RespFrame::Array(cmd)) => commands.feed(cmd.into()).await?,
+                Ok(frame) => anyhow::bail!("expect a command array, got: {:?}", frame),
+                Err(e) => anyhow::bail!("invalid or incomplete input: {}", e),
+            }
+        }
+        commands.send(echo.into()).await?;
+        eprintln!("All data transferred. Waiting for the last reply...");
+        Ok(())
+    };
+
+    let read = async move {
+        let (mut errors, mut n) = (0, 0);
+        loop {
+            let reply = match replies.next().await {
+                Some(reply) => reply?,
+                None => anyhow::bail!("connection closed by server"),
+            };
+            match reply {
+                RespFrame::BulkString(s) if s.as_ref() == marker.as_bytes() => break,
+                RespFrame::Push(_) | RespFrame::Attribute(_) => continue,
+                RespFrame::Error(_) | RespFrame::BulkError(_) => {
+                    eprintln!("{}", format_tty(&reply));
+                    errors += 1;
+                }
+                _ => {}
+            }
+            n += 1;
+        }
+        Ok((errors, n))
+    };
+
+    let ((), (errors, n)) = tokio::try_join!(write, read)?;
+    eprintln!("Last reply received from server.");
+    eprintln!("errors: {}, replies: {}", errors, n);
+    Ok(())
+}
+
+fn to_command(args: Vec<Vec<u8>>) -> RespArray {
+    RespArray::new(
+        args.into_iter()
+            .map(|arg| BulkString::new(arg).into())
+            .collect::<Vec<RespFrame>>(),
+    )
+}
+
+async fn print_reply(reply: &RespFrame, raw: bool) -> Result<()> {
+    let mut out = if raw {
+        format_raw(reply)
+    } else {
+        format_tty(reply).into_bytes()
+    };
+    out.push(b'\n');
+
+    let mut stdout = io::stdout();
+    stdout.write_all(&out).await?;
+    stdout.flush().await?;
+    Ok(())
+}
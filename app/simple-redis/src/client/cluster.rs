@@ -0,0 +1,152 @@
+use super::{command, into_bytes, into_ok, into_reply, Client, ClientError, Pipeline};
+use crate::{cmd::command_keys, key_hash_slot, RespArray, RespFrame, CLUSTER_SLOTS};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const MAX_REDIRECTS: usize = 5;
+
+/// A cluster aware client: commands are sent to the node serving their keys.
+///
+/// The slot map is loaded with `CLUSTER SLOTS` from the seed node, `-MOVED` replies update
+/// it and `-ASK` replies are followed once with `ASKING`; a connection is kept per node.
+#[derive(Debug)]
+pub struct ClusterClient {
+    slots: Vec<Option<String>>,
+    seed: String,
+    clients: HashMap<String, Client>,
+}
+
+impl ClusterClient {
+    pub async fn connect(seed: impl Into<String>) -> Result<Self, ClientError> {
+        let mut client = Self {
+            slots: vec![None; CLUSTER_SLOTS],
+            seed: seed.into(),
+            clients: HashMap::new(),
+        };
+        client.refresh_slots().await?;
+        Ok(client)
+    }
+
+    /// Reload the slot map from the seed node.
+    pub async fn refresh_slots(&mut self) -> Result<(), ClientError> {
+        let seed = self.seed.clone();
+        let reply = self
+            .client(&seed)
+            .await?
+            .execute(command("cluster", ["slots".into()]))
+            .await?;
+        let RespFrame::Array(ranges) = reply else {
+            return Err(ClientError::UnexpectedReply(reply));
+        };
+        self.slots = vec![None; CLUSTER_SLOTS];
+        for range in ranges.0 {
+            let (start, end, addr) = parse_slot_range(&range)
+                .ok_or_else(|| ClientError::UnexpectedReply(range.clone()))?;
+            for slot in start..=end.min(CLUSTER_SLOTS - 1) {
+                self.slots[slot] = Some(addr.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a command to the node serving its keys, following redirections; commands
+    /// without keys go to the seed node.
+    pub async fn execute(&mut self, cmd: RespArray) -> Result<RespFrame, ClientError> {
+        let args: Vec<Vec<u8>> = cmd
+            .iter()
+            .map(|arg| match arg {
+                RespFrame::BulkString(s) => s.to_vec(),
+                _ => vec![],
+            })
+            .collect();
+        let mut addr = command_keys(&args)
+            .first()
+            .and_then(|key| self.slots[key_hash_slot(key) as usize].clone())
+            .unwrap_or_else(|| self.seed.clone());
+        let mut asking = false;
+
+        for _ in 0..MAX_REDIRECTS {
+            let client = self.client(&addr).await?;
+            let reply = if asking {
+                let mut pipeline = Pipeline::new();
+                pipeline.cmd(command("asking", [])).cmd(cmd.clone());
+                client
+                    .pipeline(pipeline)
+                    .await?
+                    .pop()
+                    .expect("one reply per command")
+            } else {
+                client.execute(cmd.clone()).await?
+            };
+            let RespFrame::Error(ref e) = reply else {
+                return Ok(reply);
+            };
+            let mut parts = e.0.split_ascii_whitespace();
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some("MOVED"), Some(slot), Some(target)) => {
+                    if let Ok(slot) = slot.parse::<usize>() {
+                        if slot < CLUSTER_SLOTS {
+                            self.slots[slot] = Some(target.to_string());
+                        }
+                    }
+                    addr = target.to_string();
+                    asking = false;
+                }
+                (Some("ASK"), Some(_), Some(target)) => {
+                    addr = target.to_string();
+                    asking = true;
+                }
+                (Some("TRYAGAIN"), _, _) => tokio::time::sleep(Duration::from_millis(10)).await,
+                _ => return Ok(reply),
+            }
+        }
+        Err(ClientError::Server(
+            "too many cluster redirections".to_string(),
+        ))
+    }
+
+    pub async fn get(&mut self, key: &str) -> Result<Option<Vec<u8>>, ClientError> {
+        let reply = self.execute(command("get", [key.into()])).await?;
+        into_bytes(into_reply(reply)?)
+    }
+
+    pub async fn set(&mut self, key: &str, value: &str) -> Result<(), ClientError> {
+        let reply = self
+            .execute(command("set", [key.into(), value.into()]))
+            .await?;
+        into_ok(into_reply(reply)?)
+    }
+
+    async fn client(&mut self, addr: &str) -> Result<&mut Client, ClientError> {
+        // connections broken by an io error are reopened
+        if self.clients.get(addr).is_none_or(Client::is_broken) {
+            let client = Client::connect(addr).await?;
+            self.clients.insert(addr.to_string(), client);
+        }
+        Ok(self
+            .clients
+            .get_mut(addr)
+            .expect("connection was just opened"))
+    }
+}
+
+// [start, end, [ip, port, ...], ...]
+fn parse_slot_range(frame: &RespFrame) -> Option<(usize, usize, String)> {
+    let RespFrame::Array(range) = frame else {
+        return None;
+    };
+    let (RespFrame::Integer(start), RespFrame::Integer(end), RespFrame::Array(node)) =
+        (range.first()?, range.get(1)?, range.get(2)?)
+    else {
+        return None;
+    };
+    let (RespFrame::BulkString(ip), RespFrame::Integer(port)) = (node.first()?, node.get(1)?)
+    else {
+        return None;
+    };
+    Some((
+        *start as usize,
+        *end as usize,
+        format!("{}:{}", String::from_utf8_lossy(ip), port),
+    ))
+}
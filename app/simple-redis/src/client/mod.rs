@@ -0,0 +1,454 @@
+mod cluster;
+mod pipeline;
+mod pool;
+
+use crate::{network::RespFrameCodec, BulkString, RespArray, RespFrame, RespPush};
+use futures::SinkExt;
+use std::collections::VecDeque;
+use thiserror::Error;
+use tokio::net::{
+    tcp::{OwnedReadHalf, OwnedWriteHalf},
+    TcpStream, ToSocketAddrs,
+};
+use tokio_stream::StreamExt;
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+pub use self::{
+    cluster::ClusterClient,
+    pipeline::Pipeline,
+    pool::{Pool, PooledClient},
+};
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Codec error: {0}")]
+    Codec(#[from] anyhow::Error),
+    #[error("Server error: {0}")]
+    Server(String),
+    #[error("Unexpected reply: {0:?}")]
+    UnexpectedReply(RespFrame),
+    #[error("Connection closed by server")]
+    ConnectionClosed,
+}
+
+/// A single connection to a RESP server, speaking the same codec as `network::stream_handler`.
+///
+/// Push frames received while waiting for a reply are buffered and can be consumed with
+/// [`Client::next_push`]; attribute frames preceding a reply are skipped.
+#[derive(Debug)]
+pub struct Client {
+    // the halves are apart so that a pipeline can write and read at the same time
+    reader: FramedRead<OwnedReadHalf, RespFrameCodec>,
+    writer: FramedWrite<OwnedWriteHalf, RespFrameCodec>,
+    pushes: VecDeque<RespPush>,
+    broken: bool,
+    // set from the write of a request until its reply is read: a future dropped in between
+    // leaves the reply on the wire, and the next request would read it
+    in_flight: bool,
+}
+
+impl Client {
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<Self, ClientError> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        let (reader, writer) = stream.into_split();
+        Ok(Self {
+            reader: FramedRead::new(reader, RespFrameCodec),
+            writer: FramedWrite::new(writer, RespFrameCodec),
+            pushes: VecDeque::new(),
+            broken: false,
+            in_flight: false,
+        })
+    }
+
+    /// Send a raw command and return the raw reply, including error frames.
+    pub async fn execute(&mut self, cmd: RespArray) -> Result<RespFrame, ClientError> {
+        self.in_flight = true;
+        self.send(cmd).await?;
+        let reply = self.read_reply().await?;
+        self.in_flight = false;
+        Ok(reply)
+    }
+
+    /// Send all the commands without waiting for their replies, and read one reply for each
+    /// of them.
+    pub async fn pipeline(&mut self, pipeline: Pipeline) -> Result<Vec<RespFrame>, ClientError> {
+        let n = pipeline.len();
+        self.in_flight = true;
+        // the replies are read while the commands are written: the server answers each
+        // command as it reads it, and stops reading once its replies fill the socket
+        let (writer, reader, pushes) = (&mut self.writer, &mut self.reader, &mut self.pushes);
+        let write = async move {
+            for cmd in pipeline.into_commands() {
+                writer.feed(cmd.into()).await?;
+            }
+            writer.flush().await?;
+            Ok(())
+        };
+        let read = async move {
+            let mut replies = Vec::with_capacity(n);
+            for _ in 0..n {
+                replies.push(read_reply(reader, pushes).await?);
+            }
+            Ok(replies)
+        };
+        match tokio::try_join!(write, read) {
+            Ok(((), replies)) => {
+                self.in_flight = false;
+                Ok(replies)
+            }
+            Err(e) => {
+                self.broken = true;
+                Err(e)
+            }
+        }
+    }
+
+    /// Split the connection into a sink of commands and a stream of replies, to write and
+    /// read from different tasks; push messages buffered so far are dropped.
+    pub fn into_split(
+        self,
+    ) -> (
+        FramedWrite<OwnedWriteHalf, RespFrameCodec>,
+        FramedRead<OwnedReadHalf, RespFrameCodec>,
+    ) {
+        (self.writer, self.reader)
+    }
+
+    /// Return the next push message, waiting for one to arrive if none is buffered. On a
+    /// RESP2 connection, pub/sub messages arrive as arrays and are returned as pushes too.
+    pub async fn next_push(&mut self) -> Result<RespPush, ClientError> {
+        if let Some(push) = self.pushes.pop_front() {
+            return Ok(push);
+        }
+        loop {
+            match pubsub_message(self.read_frame().await?) {
+                Ok(push) => return Ok(push),
+                Err(RespFrame::Attribute(_)) => continue,
+                Err(frame) => return Err(ClientError::UnexpectedReply(frame)),
+            }
+        }
+    }
+
+    /// Drain the push messages buffered so far without waiting.
+    pub fn take_pushes(&mut self) -> Vec<RespPush> {
+        self.pushes.drain(..).collect()
+    }
+
+    /// Whether the connection can't be used anymore, after an io or protocol error or a
+    /// request cancelled before its reply was read.
+    pub fn is_broken(&self) -> bool {
+        self.broken || self.in_flight
+    }
+
+    pub async fn echo(&mut self, message: impl Into<BulkString>) -> Result<Vec<u8>, ClientError> {
+        let reply = self.call(command("echo", [message.into()])).await?;
+        Ok(into_bytes(reply)?.unwrap_or_default())
+    }
+
+    pub async fn get(&mut self, key: &str) -> Result<Option<Vec<u8>>, ClientError> {
+        let reply = self.call(command("get", [key.into()])).await?;
+        into_bytes(reply)
+    }
+
+    pub async fn set(
+        &mut self,
+        key: &str,
+        value: impl Into<BulkString>,
+    ) -> Result<(), ClientError> {
+        let reply = self
+            .call(command("set", [key.into(), value.into()]))
+            .await?;
+        into_ok(reply)
+    }
+
+    pub async fn hget(&mut self, key: &str, field: &str) -> Result<Option<Vec<u8>>, ClientError> {
+        let reply = self
+            .call(command("hget", [key.into(), field.into()]))
+            .await?;
+        into_bytes(reply)
+    }
+
+    pub async fn hset(
+        &mut self,
+        key: &str,
+        field: &str,
+        value: impl Into<BulkString>,
+    ) -> Result<(), ClientError> {
+        let reply = self
+            .call(command("hset", [key.into(), field.into(), value.into()]))
+            .await?;
+        match reply {
+            // real redis answers with the number of fields added
+            RespFrame::Integer(_) => Ok(()),
+            reply => into_ok(reply),
+        }
+    }
+
+    pub async fn hgetall(&mut self, key: &str) -> Result<Vec<(String, Vec<u8>)>, ClientError> {
+        let reply = self.call(command("hgetall", [key.into()])).await?;
+        let pairs = match reply {
+            RespFrame::Array(array) => {
+                let mut iter = array.0.into_iter();
+                let mut pairs = Vec::with_capacity(iter.len() / 2);
+                while let (Some(field), Some(value)) = (iter.next(), iter.next()) {
+                    pairs.push((field, value));
+                }
+                pairs
+            }
+            RespFrame::Map(map) => map
+                .0
+                .into_iter()
+                .map(|(k, v)| (BulkString::from(k).into(), v))
+                .collect(),
+            RespFrame::Null(_) | RespFrame::NullBulkString(_) => vec![],
+            reply => return Err(ClientError::UnexpectedReply(reply)),
+        };
+
+        pairs
+            .into_iter()
+            .map(|(field, value)| {
+                let field = into_bytes(field)?.unwrap_or_default();
+                let value = into_bytes(value)?.unwrap_or_default();
+                Ok((String::from_utf8_lossy(&field).into_owned(), value))
+            })
+            .collect()
+    }
+
+    pub async fn del(&mut self, keys: &[&str]) -> Result<i64, ClientError> {
+        let reply = self
+            .call(command("del", keys.iter().map(|&k| k.into())))
+            .await?;
+        into_integer(reply)
+    }
+
+    pub async fn select(&mut self, db: usize) -> Result<(), ClientError> {
+        let reply = self
+            .call(command("select", [db.to_string().into()]))
+            .await?;
+        into_ok(reply)
+    }
+
+    pub async fn flushdb(&mut self) -> Result<(), ClientError> {
+        let reply = self.call(command("flushdb", [])).await?;
+        into_ok(reply)
+    }
+
+    /// Subscribe to the channels and wait for their confirmations; messages are then
+    /// received with [`Client::next_push`].
+    pub async fn subscribe(&mut self, channels: &[&str]) -> Result<(), ClientError> {
+        self.in_flight = true;
+        self.send(command("subscribe", channels.iter().map(|&c| c.into())))
+            .await?;
+        let mut confirmed = 0;
+        while confirmed < channels.len() {
+            match pubsub_message(self.read_frame().await?) {
+                Ok(push) if push.kind() == Some(b"subscribe") => confirmed += 1,
+                Ok(push) => self.pushes.push_back(push),
+                Err(RespFrame::Attribute(_)) => continue,
+                Err(RespFrame::Error(e)) => {
+                    self.in_flight = false;
+                    return Err(ClientError::Server(e.0));
+                }
+                Err(frame) => return Err(ClientError::UnexpectedReply(frame)),
+            }
+        }
+        self.in_flight = false;
+        Ok(())
+    }
+
+    pub async fn publish(&mut self, channel: &str, message: &str) -> Result<i64, ClientError> {
+        let reply = self
+            .call(command("publish", [channel.into(), message.into()]))
+            .await?;
+        into_integer(reply)
+    }
+
+    /// Turn on server assisted client side caching, invalidations arrive as push messages.
+    pub async fn client_tracking(
+        &mut self,
+        on: bool,
+        bcast: bool,
+        prefixes: &[&str],
+    ) -> Result<(), ClientError> {
+        let mut args: Vec<BulkString> =
+            vec!["tracking".into(), if on { "on" } else { "off" }.into()];
+        if bcast {
+            args.push("bcast".into());
+        }
+        for &prefix in prefixes {
+            args.push("prefix".into());
+            args.push(prefix.into());
+        }
+        let reply = self.call(command("client", args)).await?;
+        into_ok(reply)
+    }
+
+    pub async fn config_set(&mut self, name: &str, value: &str) -> Result<(), ClientError> {
+        let reply = self
+            .call(command("config", ["set".into(), name.into(), value.into()]))
+            .await?;
+        into_ok(reply)
+    }
+
+    /// Switch the connection to MONITOR mode, commands processed by the server are then
+    /// received with [`Client::next_monitor_line`]; the connection can't be used otherwise.
+    pub async fn monitor(&mut self) -> Result<(), ClientError> {
+        let reply = self.call(command("monitor", [])).await?;
+        into_ok(reply)
+    }
+
+    pub async fn next_monitor_line(&mut self) -> Result<String, ClientError> {
+        match self.read_reply().await? {
+            RespFrame::SimpleString(line) => Ok(line.0),
+            frame => Err(ClientError::UnexpectedReply(frame)),
+        }
+    }
+
+    // like execute, but error replies are turned into `ClientError::Server`
+    async fn call(&mut self, cmd: RespArray) -> Result<RespFrame, ClientError> {
+        into_reply(self.execute(cmd).await?)
+    }
+
+    async fn send(&mut self, cmd: RespArray) -> Result<(), ClientError> {
+        if let Err(e) = self.writer.send(cmd.into()).await {
+            self.broken = true;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    async fn read_reply(&mut self) -> Result<RespFrame, ClientError> {
+        let reply = read_reply(&mut self.reader, &mut self.pushes).await;
+        self.broken |= reply.is_err();
+        reply
+    }
+
+    async fn read_frame(&mut self) -> Result<RespFrame, ClientError> {
+        let frame = read_frame(&mut self.reader).await;
+        self.broken |= frame.is_err();
+        frame
+    }
+}
+
+async fn read_reply(
+    reader: &mut FramedRead<OwnedReadHalf, RespFrameCodec>,
+    pushes: &mut VecDeque<RespPush>,
+) -> Result<RespFrame, ClientError> {
+    loop {
+        match read_frame(reader).await? {
+            RespFrame::Push(push) => pushes.push_back(push),
+            // attributes carry auxiliary data for the reply that follows
+            RespFrame::Attribute(_) => continue,
+            frame => return Ok(frame),
+        }
+    }
+}
+
+async fn read_frame(
+    reader: &mut FramedRead<OwnedReadHalf, RespFrameCodec>,
+) -> Result<RespFrame, ClientError> {
+    match reader.next().await {
+        Some(Ok(frame)) => Ok(frame),
+        Some(Err(e)) => Err(e.into()),
+        None => Err(ClientError::ConnectionClosed),
+    }
+}
+
+// a pub/sub message: a push on RESP3, an array starting with its kind on RESP2
+fn pubsub_message(frame: RespFrame) -> Result<RespPush, RespFrame> {
+    const KINDS: [&[u8]; 6] = [
+        b"subscribe",
+        b"unsubscribe",
+        b"psubscribe",
+        b"punsubscribe",
+        b"message",
+        b"pmessage",
+    ];
+    match frame {
+        RespFrame::Push(push) => Ok(push),
+        RespFrame::Array(array) => {
+            let push = RespPush(array.0);
+            match push.kind() {
+                Some(kind) if KINDS.contains(&kind) => Ok(push),
+                _ => Err(RespArray(push.0).into()),
+            }
+        }
+        frame => Err(frame),
+    }
+}
+
+/// Build a command array from its name and arguments, all sent as bulk strings.
+pub fn command(name: &str, args: impl IntoIterator<Item = BulkString>) -> RespArray {
+    let mut frames = vec![BulkString::from(name).into()];
+    frames.extend(args.into_iter().map(RespFrame::from));
+    RespArray::new(frames)
+}
+
+fn into_reply(reply: RespFrame) -> Result<RespFrame, ClientError> {
+    match reply {
+        RespFrame::Error(e) => Err(ClientError::Server(e.0)),
+        RespFrame::BulkError(e) => Err(ClientError::Server(
+            String::from_utf8_lossy(e.as_ref()).into_owned(),
+        )),
+        reply => Ok(reply),
+    }
+}
+
+fn into_ok(reply: RespFrame) -> Result<(), ClientError> {
+    match reply {
+        RespFrame::SimpleString(s) if s.as_str() == "OK" => Ok(()),
+        reply => Err(ClientError::UnexpectedReply(reply)),
+    }
+}
+
+fn into_integer(reply: RespFrame) -> Result<i64, ClientError> {
+    match reply {
+        RespFrame::Integer(n) => Ok(n),
+        reply => Err(ClientError::UnexpectedReply(reply)),
+    }
+}
+
+fn into_bytes(reply: RespFrame) -> Result<Option<Vec<u8>>, ClientError> {
+    match reply {
+        RespFrame::BulkString(s) => Ok(Some(s.0)),
+        RespFrame::SimpleString(s) => Ok(Some(s.0.into_bytes())),
+        RespFrame::VerbatimString(s) => Ok(Some(s.data)),
+        RespFrame::Null(_) | RespFrame::NullBulkString(_) => Ok(None),
+        reply => Err(ClientError::UnexpectedReply(reply)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RespEncode, RespNullBulkString, SimpleString};
+    use anyhow::Result;
+
+    #[test]
+    fn test_command_encode() {
+        let cmd = command("set", ["hello".into(), "world".into()]);
+        assert_eq!(
+            RespFrame::from(cmd).encode(),
+            b"*3\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n"
+        );
+    }
+
+    #[test]
+    fn test_reply_conversion() -> Result<()> {
+        assert_eq!(
+            into_bytes(RespFrame::BulkString("v".into()))?,
+            Some(b"v".to_vec())
+        );
+        assert_eq!(into_bytes(RespNullBulkString.into())?, None);
+        assert!(into_ok(SimpleString::new("OK").into()).is_ok());
+        assert!(matches!(
+            into_integer(SimpleString::new("OK").into()),
+            Err(ClientError::UnexpectedReply(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use super::command;
+use crate::{BulkString, RespArray};
+
+/// A batch of commands sent in one write; replies come back in the same order.
+#[derive(Debug, Default, Clone)]
+pub struct Pipeline {
+    commands: Vec<RespArray>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cmd(&mut self, cmd: RespArray) -> &mut Self {
+        self.commands.push(cmd);
+        self
+    }
+
+    pub fn echo(&mut self, message: impl Into<BulkString>) -> &mut Self {
+        self.cmd(command("echo", [message.into()]))
+    }
+
+    pub fn get(&mut self, key: &str) -> &mut Self {
+        self.cmd(command("get", [key.into()]))
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<BulkString>) -> &mut Self {
+        self.cmd(command("set", [key.into(), value.into()]))
+    }
+
+    pub fn hget(&mut self, key: &str, field: &str) -> &mut Self {
+        self.cmd(command("hget", [key.into(), field.into()]))
+    }
+
+    pub fn hset(&mut self, key: &str, field: &str, value: impl Into<BulkString>) -> &mut Self {
+        self.cmd(command("hset", [key.into(), field.into(), value.into()]))
+    }
+
+    pub fn hgetall(&mut self, key: &str) -> &mut Self {
+        self.cmd(command("hgetall", [key.into()]))
+    }
+
+    pub fn del(&mut self, keys: &[&str]) -> &mut Self {
+        self.cmd(command("del", keys.iter().map(|&k| k.into())))
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub(super) fn into_commands(self) -> Vec<RespArray> {
+        self.commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespFrame;
+
+    #[test]
+    fn test_pipeline_builder() {
+        let mut pipeline = Pipeline::new();
+        pipeline.set("a", "1").get("a").del(&["a", "b"]);
+
+        assert_eq!(pipeline.len(), 3);
+        let cmds = pipeline.into_commands();
+        assert_eq!(
+            cmds[2],
+            RespArray::new([
+                RespFrame::BulkString("del".into()),
+                RespFrame::BulkString("a".into()),
+                RespFrame::BulkString("b".into()),
+            ])
+        );
+    }
+}
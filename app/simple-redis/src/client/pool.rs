@@ -0,0 +1,89 @@
+use super::{Client, ClientError};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A bounded pool of connections to a single server.
+///
+/// At most `max_size` connections are checked out at once; idle connections are reused and
+/// connections that hit an io or protocol error, or that were dropped while waiting for a
+/// reply, are closed instead of being returned.
+#[derive(Debug, Clone)]
+pub struct Pool(Arc<PoolInner>);
+
+#[derive(Debug)]
+struct PoolInner {
+    addr: String,
+    idle: Mutex<Vec<Client>>,
+    permits: Arc<Semaphore>,
+}
+
+#[derive(Debug)]
+pub struct PooledClient {
+    client: Option<Client>,
+    pool: Pool,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Pool {
+    pub fn new(addr: impl Into<String>, max_size: usize) -> Self {
+        Self(Arc::new(PoolInner {
+            addr: addr.into(),
+            idle: Mutex::new(Vec::with_capacity(max_size)),
+            permits: Arc::new(Semaphore::new(max_size)),
+        }))
+    }
+
+    /// Check out a connection, waiting if `max_size` connections are already in use.
+    pub async fn get(&self) -> Result<PooledClient, ClientError> {
+        let permit = self
+            .0
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("pool semaphore is never closed");
+
+        let idle = self.0.idle.lock().unwrap().pop();
+        let client = match idle {
+            Some(client) => client,
+            None => Client::connect(self.0.addr.as_str()).await?,
+        };
+
+        Ok(PooledClient {
+            client: Some(client),
+            pool: self.clone(),
+            _permit: permit,
+        })
+    }
+
+    pub fn idle_count(&self) -> usize {
+        self.0.idle.lock().unwrap().len()
+    }
+}
+
+impl Deref for PooledClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Self::Target {
+        self.client.as_ref().expect("client is only taken on drop")
+    }
+}
+
+impl DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.client.as_mut().expect("client is only taken on drop")
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            if !client.is_broken() {
+                self.pool.0.idle.lock().unwrap().push(client);
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{
+    client::{command, Client, ClientError},
+    parse_nodes, Backend, RespFrame,
+};
+use std::{collections::HashMap, time::Duration};
+use tokio::task::JoinSet;
+use tracing::warn;
+
+// the cluster bus: every `period`, pull `CLUSTER NODES` from every known peer (and from
+// the addresses given to CLUSTER MEET) and merge it. Peers are also asked to MEET this
+// node, so a single CLUSTER MEET is enough for both sides to know each other.
+//
+// Peers are queried concurrently, each within `period` so that an unresponsive one can't
+// hold up the next rounds, and the connections are kept open between rounds.
+pub async fn gossip(backend: Backend, period: Duration) {
+    let Some(cluster) = backend.cluster() else {
+        return;
+    };
+    let shutdown = backend.shutdown_token();
+    let mut interval = tokio::time::interval(period);
+    let mut clients: HashMap<(String, u16), Client> = HashMap::new();
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => return,
+            _ = interval.tick() => {}
+        }
+        let myself = cluster.myself();
+        let peers = cluster.peers();
+        // nodes that were forgotten
+        clients.retain(|addr, _| peers.contains(addr));
+
+        let mut pulls = JoinSet::new();
+        for addr in peers {
+            let client = clients.remove(&addr);
+            let me = (myself.ip.clone(), myself.port);
+            pulls.spawn(async move {
+                let ret = tokio::time::timeout(period, pull(client, &addr, &me)).await;
+                (addr, ret)
+            });
+        }
+        while let Some(ret) = pulls.join_next().await {
+            let Ok(((ip, port), ret)) = ret else {
+                continue;
+            };
+            match ret {
+                Ok(Ok((client, nodes))) => {
+                    cluster.merge(&parse_nodes(&nodes));
+                    clients.insert((ip, port), client);
+                }
+                Ok(Err(e)) => warn!("cluster gossip with {}:{} failed: {}", ip, port, e),
+                Err(_) => warn!("cluster gossip with {}:{} timed out", ip, port),
+            }
+        }
+    }
+}
+
+// a failed connection is dropped, the next round opens a new one
+async fn pull(
+    client: Option<Client>,
+    (ip, port): &(String, u16),
+    (my_ip, my_port): &(String, u16),
+) -> Result<(Client, String), ClientError> {
+    let mut client = match client {
+        Some(client) => client,
+        None => Client::connect((ip.as_str(), *port)).await?,
+    };
+    let meet = command(
+        "cluster",
+        [
+            "meet".into(),
+            my_ip.as_str().into(),
+            my_port.to_string().into(),
+        ],
+    );
+    client.execute(meet).await?;
+    match client.execute(command("cluster", ["nodes".into()])).await? {
+        RespFrame::BulkString(nodes) => Ok((client, String::from_utf8_lossy(&nodes).into_owned())),
+        reply => Err(ClientError::UnexpectedReply(reply)),
+    }
+}
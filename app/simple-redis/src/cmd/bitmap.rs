@@ -0,0 +1,355 @@
+use super::{
+    extract_args, validate_command, BitCount, BitField, BitOp, BitPos, CommandError,
+    CommandExecutor, GetBit, SetBit,
+};
+use crate::{
+    Backend, BitFieldOp, BitFieldType, BitOpKind, BitUnit, Overflow, RespArray, RespFrame,
+    RespNull, SimpleError, MAX_BIT_OFFSET,
+};
+
+impl CommandExecutor for SetBit {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.setbit(&self.key, self.offset, self.bit) {
+            Ok(old) => RespFrame::Integer(old as i64),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl CommandExecutor for GetBit {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.getbit(&self.key, self.offset) {
+            Ok(bit) => RespFrame::Integer(bit as i64),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl CommandExecutor for BitCount {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitcount(&self.key, self.range) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl CommandExecutor for BitPos {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitpos(&self.key, self.bit, self.start, self.end, self.unit) {
+            Ok(pos) => RespFrame::Integer(pos),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl CommandExecutor for BitOp {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitop(self.op, &self.dest, &self.keys) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl CommandExecutor for BitField {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitfield(&self.key, &self.ops) {
+            Ok(replies) => {
+                let replies = replies
+                    .into_iter()
+                    .map(|reply| match reply {
+                        Some(n) => RespFrame::Integer(n),
+                        None => RespFrame::Null(RespNull),
+                    })
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(replies).into()
+            }
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+// - setbit key offset 0|1
+impl TryFrom<RespArray> for SetBit {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["setbit"])?;
+        let mut args = parse_strings(value)?.into_iter();
+        let key = args.next().unwrap_or_default();
+        let offset = parse_offset(args.next())?;
+        let bit = parse_bit(args.next()).map_err(|_| {
+            CommandError::InvalidArgument("bit is not an integer or out of range".to_string())
+        })?;
+        Ok(SetBit { key, offset, bit })
+    }
+}
+
+// - getbit key offset
+impl TryFrom<RespArray> for GetBit {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getbit"])?;
+        let mut args = parse_strings(value)?.into_iter();
+        let key = args.next().unwrap_or_default();
+        let offset = parse_offset(args.next())?;
+        Ok(GetBit { key, offset })
+    }
+}
+
+// - bitcount key [start end [BYTE|BIT]]
+impl TryFrom<RespArray> for BitCount {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["bitcount"])?;
+        let n_args = value.len() - 1;
+        if !matches!(n_args, 1 | 3 | 4) {
+            return Err(CommandError::InvalidArgument("syntax error".to_string()));
+        }
+        let mut args = parse_strings(value)?.into_iter();
+        let key = args.next().unwrap_or_default();
+        let range = match args.next() {
+            Some(start) => Some((
+                parse_integer(Some(start))?,
+                parse_integer(args.next())?,
+                parse_unit(args.next())?,
+            )),
+            None => None,
+        };
+        Ok(BitCount { key, range })
+    }
+}
+
+// - bitpos key 0|1 [start [end [BYTE|BIT]]]
+impl TryFrom<RespArray> for BitPos {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["bitpos"])?;
+        let n_args = value.len() - 1;
+        if !(2..=5).contains(&n_args) {
+            return Err(CommandError::InvalidArgument("syntax error".to_string()));
+        }
+        let mut args = parse_strings(value)?.into_iter();
+        let key = args.next().unwrap_or_default();
+        let bit = parse_bit(args.next())?;
+        let start = args.next().map(|s| parse_integer(Some(s))).transpose()?;
+        let end = args.next().map(|s| parse_integer(Some(s))).transpose()?;
+        let unit = parse_unit(args.next())?;
+        Ok(BitPos {
+            key,
+            bit,
+            start,
+            end,
+            unit,
+        })
+    }
+}
+
+// - bitop AND|OR|XOR|NOT destkey key [key ...]
+impl TryFrom<RespArray> for BitOp {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["bitop"])?;
+        let mut args = parse_strings(value)?.into_iter();
+        let op = match args
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "and" => BitOpKind::And,
+            "or" => BitOpKind::Or,
+            "xor" => BitOpKind::Xor,
+            "not" => BitOpKind::Not,
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+        let dest = args.next().unwrap_or_default();
+        let keys: Vec<String> = args.collect();
+        if op == BitOpKind::Not && keys.len() != 1 {
+            return Err(CommandError::InvalidArgument(
+                "BITOP NOT must be called with a single source key.".to_string(),
+            ));
+        }
+        Ok(BitOp { op, dest, keys })
+    }
+}
+
+// - bitfield key [GET type offset] [SET type offset value] [INCRBY type offset increment]
+//   [OVERFLOW WRAP|SAT|FAIL] ...
+impl TryFrom<RespArray> for BitField {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["bitfield"])?;
+        let mut args = parse_strings(value)?.into_iter();
+        let key = args.next().unwrap_or_default();
+        let mut ops = Vec::new();
+        while let Some(op) = args.next() {
+            let op = match op.to_ascii_lowercase().as_str() {
+                "get" => {
+                    let ty = parse_type(args.next())?;
+                    BitFieldOp::Get(ty, parse_field_offset(args.next(), ty)?)
+                }
+                "set" => {
+                    let ty = parse_type(args.next())?;
+                    let offset = parse_field_offset(args.next(), ty)?;
+                    BitFieldOp::Set(ty, offset, parse_integer(args.next())?)
+                }
+                "incrby" => {
+                    let ty = parse_type(args.next())?;
+                    let offset = parse_field_offset(args.next(), ty)?;
+                    BitFieldOp::IncrBy(ty, offset, parse_integer(args.next())?)
+                }
+                "overflow" => {
+                    let overflow = match args.next().map(|s| s.to_ascii_lowercase()).as_deref() {
+                        Some("wrap") => Overflow::Wrap,
+                        Some("sat") => Overflow::Sat,
+                        Some("fail") => Overflow::Fail,
+                        _ => {
+                            return Err(CommandError::InvalidArgument(
+                                "Invalid OVERFLOW type specified".to_string(),
+                            ))
+                        }
+                    };
+                    BitFieldOp::Overflow(overflow)
+                }
+                _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            };
+            ops.push(op);
+        }
+        Ok(BitField { key, ops })
+    }
+}
+
+fn parse_strings(value: RespArray) -> Result<Vec<String>, CommandError> {
+    extract_args(value, 1)?
+        .into_iter()
+        .map(|v| match v {
+            RespFrame::BulkString(arg) => Ok(String::from_utf8(arg.0)?),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid argument".to_string(),
+            )),
+        })
+        .collect()
+}
+
+fn parse_integer(arg: Option<String>) -> Result<i64, CommandError> {
+    arg.and_then(|arg| arg.parse().ok()).ok_or_else(|| {
+        CommandError::InvalidArgument("value is not an integer or out of range".to_string())
+    })
+}
+
+fn parse_offset(arg: Option<String>) -> Result<u64, CommandError> {
+    arg.and_then(|arg| arg.parse().ok())
+        .filter(|&offset| offset <= MAX_BIT_OFFSET)
+        .ok_or_else(|| {
+            CommandError::InvalidArgument(
+                "bit offset is not an integer or out of range".to_string(),
+            )
+        })
+}
+
+// BITFIELD offsets prefixed with # are multiplied by the type width
+fn parse_field_offset(arg: Option<String>, ty: BitFieldType) -> Result<u64, CommandError> {
+    match arg {
+        Some(arg) if arg.starts_with('#') => parse_offset(Some(arg[1..].to_string()))?
+            .checked_mul(ty.bits as u64)
+            .filter(|&offset| offset <= MAX_BIT_OFFSET)
+            .ok_or_else(|| {
+                CommandError::InvalidArgument(
+                    "bit offset is not an integer or out of range".to_string(),
+                )
+            }),
+        arg => parse_offset(arg),
+    }
+}
+
+fn parse_bit(arg: Option<String>) -> Result<bool, CommandError> {
+    match arg.as_deref() {
+        Some("0") => Ok(false),
+        Some("1") => Ok(true),
+        _ => Err(CommandError::InvalidArgument(
+            "The bit argument must be 1 or 0.".to_string(),
+        )),
+    }
+}
+
+fn parse_unit(arg: Option<String>) -> Result<BitUnit, CommandError> {
+    match arg.map(|s| s.to_ascii_lowercase()).as_deref() {
+        None | Some("byte") => Ok(BitUnit::Byte),
+        Some("bit") => Ok(BitUnit::Bit),
+        _ => Err(CommandError::InvalidArgument("syntax error".to_string())),
+    }
+}
+
+fn parse_type(arg: Option<String>) -> Result<BitFieldType, CommandError> {
+    arg.as_deref().and_then(BitFieldType::parse).ok_or_else(|| {
+        CommandError::InvalidArgument(
+            "Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is."
+                .to_string(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::command, cmd::Command};
+    use anyhow::Result;
+
+    fn run(backend: &Backend, name: &str, args: &[&str]) -> Result<RespFrame> {
+        let cmd: Command = command(name, args.iter().map(|&arg| arg.into())).try_into()?;
+        Ok(cmd.execute(backend))
+    }
+
+    #[test]
+    fn test_bitmap_commands() -> Result<()> {
+        let backend = Backend::new();
+        assert_eq!(
+            run(&backend, "setbit", &["k", "7", "1"])?,
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            run(&backend, "setbit", &["k", "7", "1"])?,
+            RespFrame::Integer(1)
+        );
+        assert_eq!(run(&backend, "getbit", &["k", "7"])?, RespFrame::Integer(1));
+        assert_eq!(
+            backend.get("k"),
+            Some(RespFrame::BulkString((&[1u8][..]).into()))
+        );
+        assert!(run(&backend, "setbit", &["k", "7", "2"]).is_err());
+        assert!(run(&backend, "setbit", &["k", "4294967296", "1"]).is_err());
+
+        run(&backend, "set", &["s", "foobar"])?;
+        assert_eq!(run(&backend, "bitcount", &["s"])?, RespFrame::Integer(26));
+        assert_eq!(
+            run(&backend, "bitcount", &["s", "5", "30", "BIT"])?,
+            RespFrame::Integer(17)
+        );
+        assert_eq!(run(&backend, "bitpos", &["s", "1"])?, RespFrame::Integer(1));
+        assert_eq!(
+            run(&backend, "bitop", &["and", "d", "s", "k"])?,
+            RespFrame::Integer(6)
+        );
+        assert!(run(&backend, "bitop", &["not", "d", "s", "k"]).is_err());
+
+        assert_eq!(
+            run(
+                &backend,
+                "bitfield",
+                &[
+                    "bf", "set", "u8", "#1", "200", "get", "u8", "8", "overflow", "fail", "incrby",
+                    "u8", "#1", "100"
+                ]
+            )?,
+            RespArray::new([
+                RespFrame::Integer(0),
+                RespFrame::Integer(200),
+                RespFrame::Null(RespNull)
+            ])
+            .into()
+        );
+        assert!(run(&backend, "bitfield", &["bf", "get", "u64", "0"]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,507 @@
+use super::{
+    extract_args, validate_command, Asking, ClusterAddSlots, ClusterCountKeysInSlot,
+    ClusterDelSlots, ClusterGetKeysInSlot, ClusterInfo, ClusterKeySlot, ClusterMeet, ClusterMyId,
+    ClusterNodes, ClusterSetSlot, ClusterShards, ClusterSlots, CommandError, CommandExecutor,
+    RESP_OK,
+};
+use crate::{
+    key_hash_slot, Backend, BulkString, Cluster, ClusterNode, RespArray, RespFrame, RespMap,
+    SetSlot, SimpleError, CLUSTER_SLOTS,
+};
+use std::collections::BTreeMap;
+
+impl CommandExecutor for ClusterKeySlot {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |_| {
+            RespFrame::Integer(key_hash_slot(self.key.as_bytes()) as i64)
+        })
+    }
+}
+
+impl CommandExecutor for ClusterMyId {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |cluster| {
+            BulkString::from(cluster.myself().id.clone()).into()
+        })
+    }
+}
+
+impl CommandExecutor for ClusterNodes {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |cluster| {
+            BulkString::from(cluster.nodes_text()).into()
+        })
+    }
+}
+
+impl CommandExecutor for ClusterInfo {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |cluster| {
+            BulkString::from(cluster.info_text()).into()
+        })
+    }
+}
+
+// [start, end, [ip, port, id, []]] for every slot range
+impl CommandExecutor for ClusterSlots {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |cluster| {
+            let ranges = cluster
+                .slot_ranges()
+                .into_iter()
+                .map(|(start, end, node)| {
+                    RespArray::new([
+                        RespFrame::Integer(start as i64),
+                        RespFrame::Integer(end as i64),
+                        RespArray::new([
+                            BulkString::from(node.ip).into(),
+                            RespFrame::Integer(node.port as i64),
+                            BulkString::from(node.id).into(),
+                            RespArray::new([]).into(),
+                        ])
+                        .into(),
+                    ])
+                    .into()
+                })
+                .collect::<Vec<RespFrame>>();
+            RespArray::new(ranges).into()
+        })
+    }
+}
+
+// one shard per node, every node is a master without replicas
+impl CommandExecutor for ClusterShards {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |cluster| {
+            let mut slots: BTreeMap<String, Vec<RespFrame>> = BTreeMap::new();
+            for (start, end, node) in cluster.slot_ranges() {
+                slots.entry(node.id).or_default().extend([
+                    RespFrame::Integer(start as i64),
+                    RespFrame::Integer(end as i64),
+                ]);
+            }
+            let shards = cluster
+                .nodes()
+                .into_iter()
+                .map(|node| {
+                    let mut shard = RespMap::new();
+                    let ranges = slots.remove(&node.id).unwrap_or_default();
+                    shard.insert("slots".to_string(), RespArray::new(ranges).into());
+                    shard.insert(
+                        "nodes".to_string(),
+                        RespArray::new([shard_node(node)]).into(),
+                    );
+                    shard.into()
+                })
+                .collect::<Vec<RespFrame>>();
+            RespArray::new(shards).into()
+        })
+    }
+}
+
+impl CommandExecutor for ClusterMeet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |cluster| {
+            cluster.meet(&self.ip, self.port);
+            RESP_OK.clone()
+        })
+    }
+}
+
+impl CommandExecutor for ClusterAddSlots {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |cluster| {
+            reply(to_slots(&self.slots).and_then(|slots| cluster.add_slots(&slots)))
+        })
+    }
+}
+
+impl CommandExecutor for ClusterDelSlots {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |cluster| {
+            reply(to_slots(&self.slots).and_then(|slots| cluster.del_slots(&slots)))
+        })
+    }
+}
+
+impl CommandExecutor for ClusterSetSlot {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |cluster| {
+            reply(to_slots(&[self.slot]).and_then(|slots| cluster.set_slot(slots[0], self.action)))
+        })
+    }
+}
+
+impl CommandExecutor for ClusterCountKeysInSlot {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |_| match to_slots(&[self.slot]) {
+            Ok(slots) => RespFrame::Integer(backend.count_keys_in_slot(slots[0]) as i64),
+            Err(e) => SimpleError::new(e).into(),
+        })
+    }
+}
+
+impl CommandExecutor for ClusterGetKeysInSlot {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |_| match to_slots(&[self.slot]) {
+            Ok(slots) => {
+                let keys = backend
+                    .keys_in_slot(slots[0], self.count)
+                    .into_iter()
+                    .map(|key| BulkString::from(key).into())
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(keys).into()
+            }
+            Err(e) => SimpleError::new(e).into(),
+        })
+    }
+}
+
+impl CommandExecutor for Asking {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        with_cluster(backend, |_| {
+            backend.asking();
+            RESP_OK.clone()
+        })
+    }
+}
+
+fn with_cluster(backend: &Backend, f: impl FnOnce(&Cluster) -> RespFrame) -> RespFrame {
+    match backend.cluster() {
+        Some(cluster) => f(cluster),
+        None => SimpleError::new("ERR This instance has cluster support disabled").into(),
+    }
+}
+
+fn reply(ret: Result<(), String>) -> RespFrame {
+    match ret {
+        Ok(()) => RESP_OK.clone(),
+        Err(e) => SimpleError::new(e).into(),
+    }
+}
+
+fn to_slots(slots: &[i64]) -> Result<Vec<u16>, String> {
+    slots
+        .iter()
+        .map(|&slot| {
+            u16::try_from(slot)
+                .ok()
+                .filter(|&slot| (slot as usize) < CLUSTER_SLOTS)
+                .ok_or_else(|| "ERR Invalid or out of range slot".to_string())
+        })
+        .collect()
+}
+
+fn shard_node(node: ClusterNode) -> RespFrame {
+    let mut map = RespMap::new();
+    map.insert("id".to_string(), BulkString::from(node.id).into());
+    map.insert("port".to_string(), RespFrame::Integer(node.port as i64));
+    map.insert("ip".to_string(), BulkString::from(node.ip.clone()).into());
+    map.insert("endpoint".to_string(), BulkString::from(node.ip).into());
+    map.insert("role".to_string(), BulkString::from("master").into());
+    map.insert("replication-offset".to_string(), RespFrame::Integer(0));
+    map.insert("health".to_string(), BulkString::from("online").into());
+    map.into()
+}
+
+impl TryFrom<RespArray> for ClusterKeySlot {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["cluster", "keyslot"])?;
+        let mut args = parse_strings(value, 2)?;
+        Ok(ClusterKeySlot {
+            key: args.remove(0),
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ClusterMyId {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["cluster", "myid"])?;
+        Ok(ClusterMyId)
+    }
+}
+
+impl TryFrom<RespArray> for ClusterNodes {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["cluster", "nodes"])?;
+        Ok(ClusterNodes)
+    }
+}
+
+impl TryFrom<RespArray> for ClusterInfo {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["cluster", "info"])?;
+        Ok(ClusterInfo)
+    }
+}
+
+impl TryFrom<RespArray> for ClusterSlots {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["cluster", "slots"])?;
+        Ok(ClusterSlots)
+    }
+}
+
+impl TryFrom<RespArray> for ClusterShards {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["cluster", "shards"])?;
+        Ok(ClusterShards)
+    }
+}
+
+// - cluster meet ip port
+impl TryFrom<RespArray> for ClusterMeet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["cluster", "meet"])?;
+        let mut args = parse_strings(value, 2)?.into_iter();
+        let ip = args.next().unwrap_or_default();
+        let port = parse_number(args.next())?;
+        Ok(ClusterMeet { ip, port })
+    }
+}
+
+// - cluster addslots slot [slot ...]
+// - cluster addslotsrange start end [start end ...]
+impl TryFrom<RespArray> for ClusterAddSlots {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(ClusterAddSlots {
+            slots: parse_slots(value, "addslots")?,
+        })
+    }
+}
+
+// - cluster delslots slot [slot ...]
+// - cluster delslotsrange start end [start end ...]
+impl TryFrom<RespArray> for ClusterDelSlots {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(ClusterDelSlots {
+            slots: parse_slots(value, "delslots")?,
+        })
+    }
+}
+
+// - cluster setslot slot importing|migrating|node id
+// - cluster setslot slot stable
+impl TryFrom<RespArray> for ClusterSetSlot {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["cluster", "setslot"])?;
+        let n_args = value.len() - 2;
+        if !(2..=3).contains(&n_args) {
+            return Err(CommandError::InvalidArgument(
+                "cluster setslot command must have 2 or 3 arguments".to_string(),
+            ));
+        }
+
+        let mut args = parse_strings(value, 2)?.into_iter();
+        let slot = parse_number(args.next())?;
+        let action = args.next().unwrap_or_default().to_ascii_lowercase();
+        let action = match (action.as_str(), args.next()) {
+            ("importing", Some(id)) => SetSlot::Importing(id),
+            ("migrating", Some(id)) => SetSlot::Migrating(id),
+            ("node", Some(id)) => SetSlot::Node(id),
+            ("stable", None) => SetSlot::Stable,
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+        Ok(ClusterSetSlot { slot, action })
+    }
+}
+
+impl TryFrom<RespArray> for ClusterCountKeysInSlot {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["cluster", "countkeysinslot"])?;
+        let mut args = parse_strings(value, 2)?.into_iter();
+        Ok(ClusterCountKeysInSlot {
+            slot: parse_number(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ClusterGetKeysInSlot {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["cluster", "getkeysinslot"])?;
+        let mut args = parse_strings(value, 2)?.into_iter();
+        Ok(ClusterGetKeysInSlot {
+            slot: parse_number(args.next())?,
+            count: parse_number(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Asking {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["asking"])?;
+        Ok(Asking)
+    }
+}
+
+// the slots of ADDSLOTS / DELSLOTS, or the expanded ranges of their *RANGE variants
+fn parse_slots(value: RespArray, name: &'static str) -> Result<Vec<i64>, CommandError> {
+    let is_range = match value.get(1) {
+        Some(RespFrame::BulkString(sub)) => sub.to_ascii_lowercase().ends_with(b"range"),
+        _ => false,
+    };
+    let sub = if is_range {
+        if name == "addslots" {
+            "addslotsrange"
+        } else {
+            "delslotsrange"
+        }
+    } else {
+        name
+    };
+    validate_command(&value, &["cluster", sub])?;
+    if is_range && !(value.len() - 2).is_multiple_of(2) {
+        return Err(CommandError::WrongArity(format!("cluster|{}", sub)));
+    }
+
+    let numbers = parse_strings(value, 2)?
+        .into_iter()
+        .map(|arg| parse_number(Some(arg)))
+        .collect::<Result<Vec<i64>, CommandError>>()?;
+    if !is_range {
+        return Ok(numbers);
+    }
+    let mut slots = Vec::new();
+    for range in numbers.chunks(2) {
+        if range[0] > range[1] {
+            return Err(CommandError::InvalidArgument(format!(
+                "start slot number {} is greater than end slot number {}",
+                range[0], range[1]
+            )));
+        }
+        slots.extend(range[0]..=range[1].min(CLUSTER_SLOTS as i64));
+    }
+    Ok(slots)
+}
+
+fn parse_strings(value: RespArray, start: usize) -> Result<Vec<String>, CommandError> {
+    extract_args(value, start)?
+        .into_iter()
+        .map(|v| match v {
+            RespFrame::BulkString(arg) => Ok(String::from_utf8(arg.0)?),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid argument".to_string(),
+            )),
+        })
+        .collect()
+}
+
+fn parse_number<T: std::str::FromStr>(arg: Option<String>) -> Result<T, CommandError> {
+    arg.and_then(|arg| arg.parse().ok())
+        .ok_or_else(|| CommandError::InvalidArgument("value is not an integer".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::command, cmd::Command};
+    use anyhow::Result;
+
+    fn run(backend: &Backend, args: &[&str]) -> Result<RespFrame> {
+        let cmd: Command = command("cluster", args.iter().map(|&arg| arg.into())).try_into()?;
+        Ok(cmd.execute(backend))
+    }
+
+    #[test]
+    fn test_cluster_disabled() -> Result<()> {
+        let backend = Backend::new();
+        assert_eq!(
+            run(&backend, &["keyslot", "foo"])?,
+            SimpleError::new("ERR This instance has cluster support disabled").into()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cluster_slots_commands() -> Result<()> {
+        let backend = Backend::new();
+        backend.enable_cluster("127.0.0.1", 7000);
+        let id = backend.cluster().unwrap().myself().id.clone();
+
+        assert_eq!(
+            run(&backend, &["KEYSLOT", "foo"])?,
+            RespFrame::Integer(12182)
+        );
+        assert_eq!(
+            run(&backend, &["addslotsrange", "0", "99", "200", "200"])?,
+            RESP_OK.clone()
+        );
+        assert_eq!(run(&backend, &["addslots", "100"])?, RESP_OK.clone());
+        assert_eq!(
+            run(&backend, &["addslots", "16384"])?,
+            SimpleError::new("ERR Invalid or out of range slot").into()
+        );
+        assert_eq!(run(&backend, &["delslots", "200"])?, RESP_OK.clone());
+        assert!(run(&backend, &["addslotsrange", "2", "1"]).is_err());
+
+        let node = RespArray::new([
+            BulkString::from("127.0.0.1").into(),
+            RespFrame::Integer(7000),
+            BulkString::from(id.clone()).into(),
+            RespArray::new([]).into(),
+        ]);
+        assert_eq!(
+            run(&backend, &["slots"])?,
+            RespArray::new([RespArray::new([
+                RespFrame::Integer(0),
+                RespFrame::Integer(100),
+                node.into()
+            ])
+            .into()])
+            .into()
+        );
+
+        let RespFrame::Array(shards) = run(&backend, &["shards"])? else {
+            panic!("expected an array");
+        };
+        let RespFrame::Map(ref shard) = shards[0] else {
+            panic!("expected a map");
+        };
+        assert_eq!(
+            shard.get("slots"),
+            Some(&RespArray::new([RespFrame::Integer(0), RespFrame::Integer(100)]).into())
+        );
+
+        assert_eq!(
+            run(&backend, &["setslot", "1", "migrating", "unknown"])?,
+            SimpleError::new("ERR I don't know about node unknown").into()
+        );
+        assert_eq!(run(&backend, &["setslot", "1", "stable"])?, RESP_OK.clone());
+        assert!(run(&backend, &["setslot", "1", "importing"]).is_err());
+        assert_eq!(run(&backend, &["myid"])?, RespFrame::BulkString(id.into()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_in_slot() -> Result<()> {
+        let backend = Backend::new();
+        backend.enable_cluster("127.0.0.1", 7000);
+        backend.set("{a}1".to_string(), RespFrame::Integer(1));
+        backend.hset("{a}2".to_string(), "f".to_string(), RespFrame::Integer(2));
+        backend.set("b".to_string(), RespFrame::Integer(3));
+
+        let slot = key_hash_slot(b"a").to_string();
+        assert_eq!(
+            run(&backend, &["countkeysinslot", &slot])?,
+            RespFrame::Integer(2)
+        );
+        assert_eq!(
+            run(&backend, &["getkeysinslot", &slot, "1"])?,
+            RespArray::new([BulkString::from("{a}1").into()]).into()
+        );
+        Ok(())
+    }
+}
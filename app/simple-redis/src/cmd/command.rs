@@ -0,0 +1,192 @@
+use super::{
+    extract_args, table, validate_command, CommandCount, CommandError, CommandExecutor,
+    CommandGetKeys, CommandInfo, CommandList,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, SimpleError};
+
+impl CommandExecutor for CommandList {
+    fn execute(self, _: &Backend) -> RespFrame {
+        let commands = table::commands().iter().map(|spec| spec.info());
+        RespArray::new(commands.collect::<Vec<RespFrame>>()).into()
+    }
+}
+
+impl CommandExecutor for CommandCount {
+    fn execute(self, _: &Backend) -> RespFrame {
+        RespFrame::Integer(table::commands().len() as i64)
+    }
+}
+
+impl CommandExecutor for CommandInfo {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.names.is_empty() {
+            return CommandList.execute(backend);
+        }
+        let infos = self.names.iter().map(|name| table::command_info(name));
+        RespArray::new(infos.collect::<Vec<RespFrame>>()).into()
+    }
+}
+
+impl CommandExecutor for CommandGetKeys {
+    fn execute(self, _: &Backend) -> RespFrame {
+        let args = self.args.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let spec = match table::lookup(&args) {
+            Ok(spec) => spec,
+            Err(CommandError::WrongArity(_)) => {
+                return SimpleError::new("ERR Invalid number of arguments specified for command")
+                    .into()
+            }
+            Err(_) => return SimpleError::new("ERR Invalid command specified").into(),
+        };
+        let keys = spec.key_positions(&args);
+        if keys.is_empty() {
+            return SimpleError::new("ERR The command has no key arguments").into();
+        }
+        let keys = keys
+            .into_iter()
+            .map(|i| BulkString::from(args[i]).into())
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(keys).into()
+    }
+}
+
+// - command
+impl TryFrom<RespArray> for CommandList {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["command"])?;
+        if value.len() > 1 {
+            return Err(CommandError::InvalidArgument("syntax error".to_string()));
+        }
+        Ok(CommandList)
+    }
+}
+
+impl TryFrom<RespArray> for CommandCount {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["command", "count"])?;
+        Ok(CommandCount)
+    }
+}
+
+// - command info [command-name ...]
+impl TryFrom<RespArray> for CommandInfo {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["command", "info"])?;
+        let names = extract_args(value, 2)?
+            .into_iter()
+            .map(|name| match name {
+                RespFrame::BulkString(name) => Ok(String::from_utf8(name.0)?),
+                _ => Err(CommandError::InvalidArgument(
+                    "Invalid command name".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<String>, CommandError>>()?;
+        Ok(CommandInfo { names })
+    }
+}
+
+// - command getkeys command [arg ...]
+impl TryFrom<RespArray> for CommandGetKeys {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["command", "getkeys"])?;
+        let args = extract_args(value, 2)?
+            .into_iter()
+            .map(|arg| match arg {
+                RespFrame::BulkString(arg) => Ok(arg.0),
+                _ => Err(CommandError::InvalidArgument(
+                    "Invalid argument".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<Vec<u8>>, CommandError>>()?;
+        Ok(CommandGetKeys { args })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::command, cmd::Command, RespNull, SimpleString};
+    use anyhow::Result;
+
+    fn run(name: &str, args: &[&str]) -> Result<RespFrame> {
+        let cmd: Command = command(name, args.iter().map(|&arg| arg.into())).try_into()?;
+        Ok(cmd.execute(&Backend::new()))
+    }
+
+    #[test]
+    fn test_command_info() -> Result<()> {
+        let RespFrame::Array(infos) = run("COMMAND", &["INFO", "get", "nope", "client|id"])? else {
+            panic!("expected an array");
+        };
+        assert_eq!(infos[1], RespFrame::Null(RespNull));
+        let RespFrame::Array(get) = &infos[0] else {
+            panic!("expected an array");
+        };
+        assert_eq!(get[0], RespFrame::BulkString("get".into()));
+        assert_eq!(get[1], RespFrame::Integer(2));
+        assert_eq!(
+            get[2],
+            RespArray::new([
+                SimpleString::new("readonly").into(),
+                SimpleString::new("fast").into()
+            ])
+            .into()
+        );
+        assert_eq!(get[3..6], [1.into(), 1.into(), 1.into()]);
+        assert_eq!(
+            get[6],
+            RespArray::new([
+                SimpleString::new("@read").into(),
+                SimpleString::new("@string").into(),
+                SimpleString::new("@fast").into()
+            ])
+            .into()
+        );
+        assert!(matches!(&infos[2], RespFrame::Array(id) if id[1] == RespFrame::Integer(2)));
+
+        let RespFrame::Integer(count) = run("command", &["count"])? else {
+            panic!("expected an integer");
+        };
+        assert!(matches!(run("command", &[])?, RespFrame::Array(all) if all.len() as i64 == count));
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_getkeys() -> Result<()> {
+        let keys = |args: &[&str]| {
+            let mut all = vec!["getkeys"];
+            all.extend(args);
+            run("command", &all)
+        };
+        assert_eq!(
+            keys(&["bitop", "and", "dest", "a", "b"])?,
+            RespArray::new([
+                BulkString::from("dest").into(),
+                BulkString::from("a").into(),
+                BulkString::from("b").into()
+            ])
+            .into()
+        );
+        assert_eq!(
+            keys(&["MIGRATE", "h", "1", "", "0", "0", "KEYS", "x"])?,
+            RespArray::new([BulkString::from("x").into()]).into()
+        );
+        assert_eq!(
+            keys(&["get"])?,
+            SimpleError::new("ERR Invalid number of arguments specified for command").into()
+        );
+        assert_eq!(
+            keys(&["nope", "a"])?,
+            SimpleError::new("ERR Invalid command specified").into()
+        );
+        assert_eq!(
+            keys(&["echo", "a"])?,
+            SimpleError::new("ERR The command has no key arguments").into()
+        );
+        Ok(())
+    }
+}
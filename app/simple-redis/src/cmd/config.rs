@@ -0,0 +1,64 @@
+use super::{
+    extract_args, validate_command, CommandError, CommandExecutor, ConfigGet, ConfigSet, RESP_OK,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, SimpleError};
+
+impl CommandExecutor for ConfigGet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let params = backend
+            .config_get(&self.pattern)
+            .into_iter()
+            .flat_map(|(name, value)| {
+                [
+                    BulkString::from(name).into(),
+                    BulkString::from(value).into(),
+                ]
+            })
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(params).into()
+    }
+}
+
+impl CommandExecutor for ConfigSet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.config_set(&self.name, &self.value) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ConfigGet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["config", "get"])?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(pattern)) => Ok(ConfigGet {
+                pattern: String::from_utf8(pattern.0)?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid pattern".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ConfigSet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["config", "set"])?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(name)), Some(RespFrame::BulkString(value))) => {
+                Ok(ConfigSet {
+                    name: String::from_utf8(name.0)?,
+                    value: String::from_utf8(value.0)?,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid parameter or value".to_string(),
+            )),
+        }
+    }
+}
@@ -1,6 +1,9 @@
-use crate::{RespArray, RespFrame};
+use crate::{Backend, RespArray, RespFrame, SimpleError};
 
-use super::{extract_args, validate_command, CommandError, CommandExecutor, Echo};
+use super::{
+    extract_args, validate_command, ClientId, ClientTracking, CommandError, CommandExecutor, Echo,
+    RESP_OK,
+};
 
 impl CommandExecutor for Echo {
     fn execute(self, _backend: &crate::Backend) -> RespFrame {
@@ -11,7 +14,7 @@ impl CommandExecutor for Echo {
 impl TryFrom<RespArray> for Echo {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["echo"], 1)?;
+        validate_command(&value, &["echo"])?;
 
         let mut args = extract_args(value, 1)?.into_iter();
         match args.next() {
@@ -23,13 +26,74 @@ impl TryFrom<RespArray> for Echo {
     }
 }
 
+impl CommandExecutor for ClientId {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.session_id() as i64)
+    }
+}
+
+impl CommandExecutor for ClientTracking {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if !self.prefixes.is_empty() && !self.bcast {
+            return SimpleError::new("ERR PREFIX option requires BCAST mode to be enabled").into();
+        }
+        backend.client_tracking(self.on, self.bcast, self.prefixes);
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for ClientId {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["client", "id"])?;
+        Ok(ClientId)
+    }
+}
+
+// - client tracking on|off [bcast] [prefix p1 [prefix p2 ...]]
+impl TryFrom<RespArray> for ClientTracking {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["client", "tracking"])?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        let on = match args.next() {
+            Some(RespFrame::BulkString(mode)) if mode.eq_ignore_ascii_case(b"on") => true,
+            Some(RespFrame::BulkString(mode)) if mode.eq_ignore_ascii_case(b"off") => false,
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+        let mut cmd = ClientTracking {
+            on,
+            bcast: false,
+            prefixes: Vec::new(),
+        };
+        while let Some(arg) = args.next() {
+            match arg {
+                RespFrame::BulkString(opt) if opt.eq_ignore_ascii_case(b"bcast") => {
+                    cmd.bcast = true
+                }
+                RespFrame::BulkString(opt) if opt.eq_ignore_ascii_case(b"prefix") => {
+                    match args.next() {
+                        Some(RespFrame::BulkString(prefix)) => {
+                            cmd.prefixes.push(String::from_utf8(prefix.0)?)
+                        }
+                        _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+                    }
+                }
+                _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            }
+        }
+        Ok(cmd)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::{Ok, Result};
 
     use crate::{
-        cmd::{CommandExecutor as _, Echo},
-        Backend, RespFrame,
+        cmd::{ClientTracking, CommandExecutor as _, Echo},
+        Backend, RespArray, RespFrame, RespPush, SimpleError,
     };
 
     #[test]
@@ -43,4 +107,33 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_client_tracking_command() -> Result<()> {
+        let backend = Backend::new();
+        let mut rx = backend.take_push_receiver().unwrap();
+        let writer = backend.new_session();
+
+        let cmd = ClientTracking {
+            on: true,
+            bcast: false,
+            prefixes: vec!["user:".to_string()],
+        };
+        let err = SimpleError::new("ERR PREFIX option requires BCAST mode to be enabled");
+        assert_eq!(cmd.execute(&backend), err.into());
+
+        let cmd = ClientTracking {
+            on: true,
+            bcast: false,
+            prefixes: vec![],
+        };
+        cmd.execute(&backend);
+        backend.get("a");
+        writer.set("a".to_string(), RespFrame::Integer(1));
+        let expected: RespFrame =
+            RespPush::with_kind("invalidate", [RespArray::new([b"a".into()]).into()]).into();
+        assert_eq!(rx.try_recv()?, expected);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,218 @@
+use super::{
+    extract_args, validate_command, CommandError, CommandExecutor, FlushAll, FlushDb, Move, Select,
+    SwapDb, RESP_OK,
+};
+use crate::{Backend, RespArray, RespFrame, SimpleError};
+
+impl CommandExecutor for Select {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.index != 0 && backend.cluster().is_some() {
+            return SimpleError::new("ERR SELECT is not allowed in cluster mode").into();
+        }
+        if backend.select(self.index) {
+            RESP_OK.clone()
+        } else {
+            SimpleError::new("ERR DB index is out of range").into()
+        }
+    }
+}
+
+impl CommandExecutor for Move {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.db >= backend.databases() {
+            return SimpleError::new("ERR DB index is out of range").into();
+        }
+        if self.db == backend.selected_db() {
+            return SimpleError::new("ERR source and destination objects are the same").into();
+        }
+        RespFrame::Integer(backend.move_key(&self.key, self.db) as i64)
+    }
+}
+
+impl CommandExecutor for SwapDb {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.a >= backend.databases() {
+            return SimpleError::new("ERR invalid first DB index").into();
+        }
+        if self.b >= backend.databases() {
+            return SimpleError::new("ERR invalid second DB index").into();
+        }
+        backend.swap_db(self.a, self.b);
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for FlushDb {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let old = backend.flush_db();
+        drop_db(old, self.is_async);
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for FlushAll {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let old = backend.flush_all();
+        drop_db(old, self.is_async);
+        RESP_OK.clone()
+    }
+}
+
+// freeing a large keyspace can take a while, ASYNC hands it over to another thread
+fn drop_db<T: Send + 'static>(db: T, is_async: bool) {
+    if is_async {
+        std::thread::spawn(move || drop(db));
+    }
+}
+
+impl TryFrom<RespArray> for Select {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["select"])?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(Select {
+            index: parse_db_index(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Move {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["move"])?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(Move {
+                key: String::from_utf8(key.0)?,
+                db: parse_db_index(args.next())?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for SwapDb {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["swapdb"])?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(SwapDb {
+            a: parse_db_index(args.next())?,
+            b: parse_db_index(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for FlushDb {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(FlushDb {
+            is_async: parse_flush_mode(value, "flushdb")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for FlushAll {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(FlushAll {
+            is_async: parse_flush_mode(value, "flushall")?,
+        })
+    }
+}
+
+fn parse_db_index(arg: Option<RespFrame>) -> Result<usize, CommandError> {
+    match arg {
+        // a negative index is a valid integer, it fails as out of range like in redis
+        Some(RespFrame::BulkString(index)) => String::from_utf8(index.0)?
+            .parse::<i64>()
+            .map(|index| usize::try_from(index).unwrap_or(usize::MAX))
+            .map_err(|_| CommandError::InvalidArgument("invalid DB index".to_string())),
+        _ => Err(CommandError::InvalidArgument(
+            "invalid DB index".to_string(),
+        )),
+    }
+}
+
+// - flushdb / flushall [ASYNC|SYNC]
+fn parse_flush_mode(value: RespArray, name: &'static str) -> Result<bool, CommandError> {
+    validate_command(&value, &[name])?;
+    let n_args = value.len() - 1;
+    if n_args > 1 {
+        return Err(CommandError::InvalidArgument(format!(
+            "{} command must have at most 1 argument",
+            name
+        )));
+    }
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    match args.next() {
+        None => Ok(false),
+        Some(RespFrame::BulkString(mode)) if mode.eq_ignore_ascii_case(b"async") => Ok(true),
+        Some(RespFrame::BulkString(mode)) if mode.eq_ignore_ascii_case(b"sync") => Ok(false),
+        _ => Err(CommandError::InvalidArgument("syntax error".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cmd::Command, RespDecode, RespNull};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn run(backend: &Backend, cmd: &[u8]) -> Result<RespFrame> {
+        let mut buf = BytesMut::from(cmd);
+        let cmd: Command = RespArray::decode(&mut buf)?.try_into()?;
+        Ok(cmd.execute(backend))
+    }
+
+    #[test]
+    fn test_select_move_swapdb() -> Result<()> {
+        let backend = Backend::new();
+        run(&backend, b"*3\r\n$3\r\nset\r\n$1\r\na\r\n$1\r\n1\r\n")?;
+
+        let ret = run(&backend, b"*3\r\n$4\r\nmove\r\n$1\r\na\r\n$1\r\n3\r\n")?;
+        assert_eq!(ret, RespFrame::Integer(1));
+        assert_eq!(backend.get("a"), None);
+
+        let ret = run(&backend, b"*2\r\n$6\r\nselect\r\n$2\r\n16\r\n")?;
+        assert_eq!(ret, SimpleError::new("ERR DB index is out of range").into());
+
+        let ret = run(&backend, b"*2\r\n$6\r\nselect\r\n$1\r\n3\r\n")?;
+        assert_eq!(ret, RESP_OK.clone());
+        assert_eq!(backend.get("a"), Some(RespFrame::Integer(1)));
+
+        let ret = run(&backend, b"*3\r\n$6\r\nswapdb\r\n$1\r\n3\r\n$1\r\n0\r\n")?;
+        assert_eq!(ret, RESP_OK.clone());
+        assert_eq!(backend.get("a"), None);
+        backend.select(0);
+        assert_eq!(backend.get("a"), Some(RespFrame::Integer(1)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_flushdb_flushall() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("a".to_string(), RespNull.into());
+        backend.select(1);
+        backend.set("b".to_string(), RespNull.into());
+
+        let ret = run(&backend, b"*2\r\n$7\r\nflushdb\r\n$5\r\nASYNC\r\n")?;
+        assert_eq!(ret, RESP_OK.clone());
+        assert_eq!(backend.get("b"), None);
+        backend.select(0);
+        assert_eq!(backend.get("a"), Some(RespNull.into()));
+
+        run(&backend, b"*1\r\n$8\r\nflushall\r\n")?;
+        assert_eq!(backend.get("a"), None);
+
+        assert!(run(&backend, b"*2\r\n$7\r\nflushdb\r\n$4\r\nLAZY\r\n").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+use super::{
+    extract_args, validate_command, CommandError, CommandExecutor, Dump, Migrate, Restore, RESP_OK,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull, SimpleError, SimpleString};
+use std::time::Duration;
+
+// the timeout of MIGRATE when 0 is given
+const DEFAULT_MIGRATE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+impl CommandExecutor for Dump {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.dump(&self.key) {
+            Some(payload) => RespFrame::BulkString(BulkString::new(payload)),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl CommandExecutor for Restore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.restore(&self.key, &self.payload, self.replace) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+// MIGRATE waits for the target instance, connections run it with `Migrate::run`
+impl CommandExecutor for Migrate {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR MIGRATE is only supported from a client connection").into()
+    }
+}
+
+impl Migrate {
+    pub async fn run(self, backend: &Backend) -> RespFrame {
+        let addr = if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        };
+        let timeout = if self.timeout.is_zero() {
+            DEFAULT_MIGRATE_TIMEOUT
+        } else {
+            self.timeout
+        };
+        match backend
+            .migrate(&addr, &self.keys, self.db, timeout, self.copy, self.replace)
+            .await
+        {
+            Ok(true) => RESP_OK.clone(),
+            Ok(false) => SimpleString::new("NOKEY").into(),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+// - dump key
+impl TryFrom<RespArray> for Dump {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["dump"])?;
+        let mut args = parse_bytes(value)?.into_iter();
+        let key = String::from_utf8(args.next().unwrap_or_default())?;
+        Ok(Dump { key })
+    }
+}
+
+// - restore key ttl serialized-value [REPLACE]
+impl TryFrom<RespArray> for Restore {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["restore"])?;
+        let n_args = value.len() - 1;
+        if !matches!(n_args, 3 | 4) {
+            return Err(CommandError::InvalidArgument("syntax error".to_string()));
+        }
+        let mut args = parse_bytes(value)?.into_iter();
+        let key = String::from_utf8(args.next().unwrap_or_default())?;
+        let ttl = String::from_utf8(args.next().unwrap_or_default())?;
+        // there is no key expiration, the value lives forever
+        match ttl.parse::<i64>() {
+            Ok(0) => {}
+            Ok(ttl) if ttl < 0 => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid TTL value, must be >= 0".to_string(),
+                ))
+            }
+            Ok(_) => {
+                return Err(CommandError::InvalidArgument(
+                    "key expiration is not supported, the TTL must be 0".to_string(),
+                ))
+            }
+            Err(_) => {
+                return Err(CommandError::InvalidArgument(
+                    "value is not an integer or out of range".to_string(),
+                ))
+            }
+        }
+        let payload = args.next().unwrap_or_default();
+        let replace = match args.next() {
+            Some(arg) if arg.eq_ignore_ascii_case(b"replace") => true,
+            Some(_) => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            None => false,
+        };
+        Ok(Restore {
+            key,
+            payload,
+            replace,
+        })
+    }
+}
+
+// - migrate host port key|"" destination-db timeout [COPY] [REPLACE] [KEYS key [key ...]]
+impl TryFrom<RespArray> for Migrate {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["migrate"])?;
+        let mut args = parse_bytes(value)?
+            .into_iter()
+            .map(String::from_utf8)
+            .collect::<Result<Vec<String>, _>>()?
+            .into_iter();
+        let host = args.next().unwrap_or_default();
+        let port = parse_number(args.next())?;
+        let key = args.next().unwrap_or_default();
+        let db = parse_number(args.next())?;
+        let timeout = Duration::from_millis(parse_number(args.next())?);
+        let (mut copy, mut replace, mut keys) = (false, false, None);
+        while let Some(arg) = args.next() {
+            match arg.to_ascii_lowercase().as_str() {
+                "copy" => copy = true,
+                "replace" => replace = true,
+                // KEYS takes all the remaining arguments
+                "keys" if key.is_empty() => keys = Some(args.by_ref().collect::<Vec<String>>()),
+                "keys" => {
+                    return Err(CommandError::InvalidArgument(
+                        "When using MIGRATE KEYS option, the key argument must be set to the empty string".to_string(),
+                    ))
+                }
+                _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            }
+        }
+        let keys = match keys {
+            Some(keys) => keys,
+            None if key.is_empty() => {
+                return Err(CommandError::InvalidArgument("syntax error".to_string()))
+            }
+            None => vec![key],
+        };
+        Ok(Migrate {
+            host,
+            port,
+            keys,
+            db,
+            timeout,
+            copy,
+            replace,
+        })
+    }
+}
+
+fn parse_bytes(value: RespArray) -> Result<Vec<Vec<u8>>, CommandError> {
+    extract_args(value, 1)?
+        .into_iter()
+        .map(|v| match v {
+            RespFrame::BulkString(arg) => Ok(arg.0),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid argument".to_string(),
+            )),
+        })
+        .collect()
+}
+
+fn parse_number<T: std::str::FromStr>(arg: Option<String>) -> Result<T, CommandError> {
+    arg.and_then(|arg| arg.parse().ok()).ok_or_else(|| {
+        CommandError::InvalidArgument("value is not an integer or out of range".to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::command, cmd::Command};
+    use anyhow::Result;
+
+    fn run(backend: &Backend, name: &str, args: Vec<BulkString>) -> Result<RespFrame> {
+        let cmd: Command = command(name, args).try_into()?;
+        Ok(cmd.execute(backend))
+    }
+
+    #[test]
+    fn test_dump_restore_commands() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("a".to_string(), RespFrame::BulkString("1".into()));
+        assert_eq!(
+            run(&backend, "dump", vec!["b".into()])?,
+            RespFrame::Null(RespNull)
+        );
+        let RespFrame::BulkString(payload) = run(&backend, "dump", vec!["a".into()])? else {
+            panic!("expected a bulk string");
+        };
+
+        let restore = |key: &str, ttl: &str, replace: bool| {
+            let mut args = vec![key.into(), ttl.into(), payload.clone()];
+            if replace {
+                args.push("REPLACE".into());
+            }
+            run(&backend, "restore", args)
+        };
+        assert_eq!(restore("b", "0", false)?, RESP_OK.clone());
+        assert_eq!(backend.get("b"), Some(RespFrame::Integer(1)));
+        assert_eq!(
+            restore("b", "0", false)?,
+            SimpleError::new("BUSYKEY Target key name already exists.").into()
+        );
+        assert_eq!(restore("b", "0", true)?, RESP_OK.clone());
+        assert!(restore("c", "-1", false).is_err());
+        assert!(restore("c", "1000", false).is_err());
+
+        let garbage = vec!["c".into(), "0".into(), "garbage".into()];
+        assert_eq!(
+            run(&backend, "restore", garbage)?,
+            SimpleError::new("ERR DUMP payload version or checksum are wrong").into()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_parsing() -> Result<()> {
+        let migrate = |args: &[&str]| -> Result<Migrate> {
+            Ok(command("migrate", args.iter().map(|&arg| arg.into())).try_into()?)
+        };
+        let cmd = migrate(&["127.0.0.1", "6380", "k", "2", "100", "COPY"])?;
+        assert_eq!(cmd.keys, ["k"]);
+        assert_eq!((cmd.db, cmd.copy, cmd.replace), (2, true, false));
+        let cmd = migrate(&["::1", "6380", "", "0", "0", "REPLACE", "KEYS", "a", "b"])?;
+        assert_eq!(cmd.keys, ["a", "b"]);
+        assert!(cmd.replace);
+        assert!(migrate(&["127.0.0.1", "6380", "k", "0", "0", "KEYS", "a"]).is_err());
+        assert!(migrate(&["127.0.0.1", "6380", "", "0", "0"]).is_err());
+        assert!(migrate(&["127.0.0.1", "port", "k", "0", "0"]).is_err());
+
+        // no key to send, the target is never contacted
+        let backend = Backend::new();
+        let cmd = migrate(&["127.0.0.1", "1", "missing", "0", "0"])?;
+        assert_eq!(cmd.run(&backend).await, SimpleString::new("NOKEY").into());
+        Ok(())
+    }
+}
@@ -0,0 +1,424 @@
+use super::{
+    extract_args, validate_command, CommandError, CommandExecutor, GeoAdd, GeoDist, GeoPos,
+    GeoSearch,
+};
+use crate::{
+    Backend, BulkString, GeoOrder, GeoOrigin, GeoQuery, GeoShape, GeoUnit, RespArray, RespFrame,
+    RespNull, SimpleError,
+};
+
+impl CommandExecutor for GeoAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.geoadd(&self.key, self.items, self.nx, self.xx, self.ch) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl CommandExecutor for GeoPos {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.geopos(&self.key, &self.members) {
+            Ok(positions) => {
+                let positions = positions
+                    .into_iter()
+                    .map(|pos| match pos {
+                        Some((lon, lat)) => coord_frame(lon, lat),
+                        None => RespFrame::Null(RespNull),
+                    })
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(positions).into()
+            }
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl CommandExecutor for GeoDist {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.geodist(&self.key, &self.a, &self.b, self.unit) {
+            Ok(Some(dist)) => dist_frame(dist),
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl CommandExecutor for GeoSearch {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let matches = match backend.geosearch(&self.key, &self.query) {
+            Ok(matches) => matches,
+            Err(e) => return SimpleError::new(e).into(),
+        };
+        let plain = !(self.with_dist || self.with_hash || self.with_coord);
+        let matches = matches
+            .into_iter()
+            .map(|m| {
+                let member = RespFrame::BulkString(m.member.into());
+                if plain {
+                    return member;
+                }
+                // the order is fixed whatever the order of the options
+                let mut item = vec![member];
+                if self.with_dist {
+                    item.push(dist_frame(m.dist));
+                }
+                if self.with_hash {
+                    item.push(RespFrame::Integer(m.hash as i64));
+                }
+                if self.with_coord {
+                    item.push(coord_frame(m.lon, m.lat));
+                }
+                RespArray::new(item).into()
+            })
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(matches).into()
+    }
+}
+
+// - geoadd key [NX|XX] [CH] longitude latitude member [longitude latitude member ...]
+impl TryFrom<RespArray> for GeoAdd {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["geoadd"])?;
+        let mut args = parse_strings(value)?.into_iter().peekable();
+        let key = args.next().unwrap_or_default();
+        let (mut nx, mut xx, mut ch) = (false, false, false);
+        while let Some(arg) =
+            args.next_if(|arg| matches!(arg.to_ascii_lowercase().as_str(), "nx" | "xx" | "ch"))
+        {
+            match arg.to_ascii_lowercase().as_str() {
+                "nx" => nx = true,
+                "xx" => xx = true,
+                _ => ch = true,
+            }
+        }
+        if nx && xx {
+            return Err(CommandError::InvalidArgument(
+                "XX and NX options at the same time are not compatible".to_string(),
+            ));
+        }
+        let args: Vec<String> = args.collect();
+        if args.is_empty() || !args.len().is_multiple_of(3) {
+            return Err(CommandError::InvalidArgument("syntax error".to_string()));
+        }
+        let items = args
+            .chunks(3)
+            .map(|item| {
+                let lon = parse_float(Some(&item[0]))?;
+                let lat = parse_float(Some(&item[1]))?;
+                Ok((lon, lat, item[2].clone()))
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        Ok(GeoAdd {
+            key,
+            items,
+            nx,
+            xx,
+            ch,
+        })
+    }
+}
+
+// - geopos key [member ...]
+impl TryFrom<RespArray> for GeoPos {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["geopos"])?;
+        let mut args = parse_strings(value)?.into_iter();
+        let key = args.next().unwrap_or_default();
+        Ok(GeoPos {
+            key,
+            members: args.collect(),
+        })
+    }
+}
+
+// - geodist key member1 member2 [M|KM|FT|MI]
+impl TryFrom<RespArray> for GeoDist {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["geodist"])?;
+        let n_args = value.len() - 1;
+        if !matches!(n_args, 3 | 4) {
+            return Err(CommandError::InvalidArgument("syntax error".to_string()));
+        }
+        let mut args = parse_strings(value)?.into_iter();
+        let key = args.next().unwrap_or_default();
+        let a = args.next().unwrap_or_default();
+        let b = args.next().unwrap_or_default();
+        let unit = match args.next() {
+            Some(unit) => parse_unit(Some(&unit))?,
+            None => GeoUnit::Meters,
+        };
+        Ok(GeoDist { key, a, b, unit })
+    }
+}
+
+// - geosearch key FROMMEMBER member|FROMLONLAT longitude latitude
+//   BYRADIUS radius M|KM|FT|MI|BYBOX width height M|KM|FT|MI
+//   [ASC|DESC] [COUNT count [ANY]] [WITHCOORD] [WITHDIST] [WITHHASH]
+impl TryFrom<RespArray> for GeoSearch {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["geosearch"])?;
+        let mut args = parse_strings(value)?.into_iter().peekable();
+        let key = args.next().unwrap_or_default();
+        let (mut origin, mut shape, mut unit) = (None, None, GeoUnit::Meters);
+        let (mut order, mut count) = (None, None);
+        let (mut with_coord, mut with_dist, mut with_hash) = (false, false, false);
+        while let Some(arg) = args.next() {
+            match arg.to_ascii_lowercase().as_str() {
+                "frommember" if origin.is_none() => {
+                    let member = args.next().ok_or_else(syntax_error)?;
+                    origin = Some(GeoOrigin::Member(member));
+                }
+                "fromlonlat" if origin.is_none() => {
+                    let lon = parse_float(args.next().as_ref())?;
+                    let lat = parse_float(args.next().as_ref())?;
+                    origin = Some(GeoOrigin::LonLat(lon, lat));
+                }
+                "byradius" if shape.is_none() => {
+                    let radius = parse_float(args.next().as_ref())?;
+                    shape = Some(GeoShape::Radius(radius));
+                    unit = parse_unit(args.next().as_ref())?;
+                }
+                "bybox" if shape.is_none() => {
+                    let width = parse_float(args.next().as_ref())?;
+                    let height = parse_float(args.next().as_ref())?;
+                    shape = Some(GeoShape::Box(width, height));
+                    unit = parse_unit(args.next().as_ref())?;
+                }
+                "asc" => order = Some(GeoOrder::Asc),
+                "desc" => order = Some(GeoOrder::Desc),
+                "count" => {
+                    let n = args
+                        .next()
+                        .and_then(|n| n.parse::<i64>().ok())
+                        .ok_or_else(|| {
+                            CommandError::InvalidArgument(
+                                "value is not an integer or out of range".to_string(),
+                            )
+                        })?;
+                    if n <= 0 {
+                        return Err(CommandError::InvalidArgument(
+                            "COUNT must be > 0".to_string(),
+                        ));
+                    }
+                    let any = args
+                        .next_if(|arg| arg.eq_ignore_ascii_case("any"))
+                        .is_some();
+                    count = Some((n as usize, any));
+                }
+                "withcoord" => with_coord = true,
+                "withdist" => with_dist = true,
+                "withhash" => with_hash = true,
+                _ => return Err(syntax_error()),
+            }
+        }
+        let origin = origin.ok_or_else(|| {
+            CommandError::InvalidArgument(
+                "exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH"
+                    .to_string(),
+            )
+        })?;
+        let shape = shape.ok_or_else(|| {
+            CommandError::InvalidArgument(
+                "exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH".to_string(),
+            )
+        })?;
+        let out_of_range = match shape {
+            GeoShape::Radius(r) => r < 0.0,
+            GeoShape::Box(w, h) => w < 0.0 || h < 0.0,
+        };
+        if out_of_range {
+            return Err(CommandError::InvalidArgument(
+                "radius, width and height cannot be negative".to_string(),
+            ));
+        }
+        Ok(GeoSearch {
+            key,
+            query: GeoQuery {
+                origin,
+                shape,
+                unit,
+                order,
+                count,
+            },
+            with_coord,
+            with_dist,
+            with_hash,
+        })
+    }
+}
+
+fn coord_frame(lon: f64, lat: f64) -> RespFrame {
+    RespArray::new([RespFrame::Double(lon), RespFrame::Double(lat)]).into()
+}
+
+// distances are replied with a precision of 4 decimals, as redis does
+// distances are strings with 4 decimals, even in RESP3
+fn dist_frame(dist: f64) -> RespFrame {
+    BulkString::from(format!("{:.4}", dist)).into()
+}
+
+fn syntax_error() -> CommandError {
+    CommandError::InvalidArgument("syntax error".to_string())
+}
+
+fn parse_strings(value: RespArray) -> Result<Vec<String>, CommandError> {
+    extract_args(value, 1)?
+        .into_iter()
+        .map(|v| match v {
+            RespFrame::BulkString(arg) => Ok(String::from_utf8(arg.0)?),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid argument".to_string(),
+            )),
+        })
+        .collect()
+}
+
+fn parse_float(arg: Option<&String>) -> Result<f64, CommandError> {
+    arg.and_then(|arg| arg.parse::<f64>().ok())
+        .filter(|n| n.is_finite())
+        .ok_or_else(|| CommandError::InvalidArgument("value is not a valid float".to_string()))
+}
+
+fn parse_unit(arg: Option<&String>) -> Result<GeoUnit, CommandError> {
+    arg.and_then(|arg| GeoUnit::parse(arg)).ok_or_else(|| {
+        CommandError::InvalidArgument(
+            "unsupported unit provided. please use M, KM, FT, MI".to_string(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::command, cmd::Command};
+    use anyhow::Result;
+
+    fn run(backend: &Backend, name: &str, args: &[&str]) -> Result<RespFrame> {
+        let cmd: Command = command(name, args.iter().map(|&arg| arg.into())).try_into()?;
+        Ok(cmd.execute(backend))
+    }
+
+    #[test]
+    fn test_geo_commands() -> Result<()> {
+        let backend = Backend::new();
+        let sicily = [
+            "Sicily",
+            "13.361389",
+            "38.115556",
+            "Palermo",
+            "15.087269",
+            "37.502669",
+            "Catania",
+        ];
+        assert_eq!(run(&backend, "geoadd", &sicily)?, RespFrame::Integer(2));
+        assert_eq!(
+            run(&backend, "geoadd", &["Sicily", "CH", "13", "38", "Palermo"])?,
+            RespFrame::Integer(1)
+        );
+        assert!(run(&backend, "geoadd", &["Sicily", "NX", "XX", "13", "38", "x"]).is_err());
+        assert!(run(&backend, "geoadd", &["Sicily", "13", "38"]).is_err());
+        run(&backend, "geoadd", &sicily)?;
+
+        assert_eq!(
+            run(&backend, "geodist", &["Sicily", "Palermo", "Catania", "km"])?,
+            RespFrame::BulkString("166.2742".into())
+        );
+        assert_eq!(
+            run(&backend, "geodist", &["Sicily", "Palermo", "Rome"])?,
+            RespFrame::Null(RespNull)
+        );
+        assert!(run(&backend, "geodist", &["Sicily", "Palermo", "Catania", "yd"]).is_err());
+
+        let RespFrame::Array(pos) = run(&backend, "geopos", &["Sicily", "Palermo", "Rome"])? else {
+            panic!("expected an array");
+        };
+        assert_eq!(pos.len(), 2);
+        assert!(matches!(&pos[0], RespFrame::Array(coord)
+            if matches!(coord[0], RespFrame::Double(lon) if (lon - 13.361389).abs() < 1e-5)));
+        assert_eq!(pos[1], RespFrame::Null(RespNull));
+
+        assert_eq!(
+            run(
+                &backend,
+                "geosearch",
+                &[
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "200",
+                    "km",
+                    "ASC"
+                ]
+            )?,
+            RespArray::new([
+                RespFrame::BulkString("Catania".into()),
+                RespFrame::BulkString("Palermo".into()),
+            ])
+            .into()
+        );
+        assert_eq!(
+            run(
+                &backend,
+                "geosearch",
+                &[
+                    "Sicily",
+                    "FROMMEMBER",
+                    "Palermo",
+                    "BYBOX",
+                    "400",
+                    "400",
+                    "km",
+                    "COUNT",
+                    "1",
+                    "DESC",
+                    "WITHDIST",
+                    "WITHHASH"
+                ]
+            )?,
+            RespArray::new([RespArray::new([
+                RespFrame::BulkString("Catania".into()),
+                RespFrame::BulkString("166.2742".into()),
+                RespFrame::Integer(3479447370796909),
+            ])
+            .into()])
+            .into()
+        );
+        assert!(run(
+            &backend,
+            "geosearch",
+            &["Sicily", "BYRADIUS", "1", "m", "ASC"]
+        )
+        .is_err());
+        assert!(run(
+            &backend,
+            "geosearch",
+            &[
+                "Sicily",
+                "FROMMEMBER",
+                "Palermo",
+                "BYRADIUS",
+                "1",
+                "m",
+                "COUNT",
+                "0"
+            ]
+        )
+        .is_err());
+        assert_eq!(
+            run(
+                &backend,
+                "geosearch",
+                &["Sicily", "FROMMEMBER", "Rome", "BYRADIUS", "1", "m"]
+            )?,
+            SimpleError::new("ERR could not decode requested zset member").into()
+        );
+        Ok(())
+    }
+}
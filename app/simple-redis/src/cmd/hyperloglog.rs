@@ -0,0 +1,121 @@
+use super::{
+    extract_args, validate_command, CommandError, CommandExecutor, PfAdd, PfCount, PfMerge, RESP_OK,
+};
+use crate::{Backend, RespArray, RespFrame, SimpleError};
+
+impl CommandExecutor for PfAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfadd(&self.key, &self.elements) {
+            Ok(updated) => RespFrame::Integer(updated as i64),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl CommandExecutor for PfCount {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfcount(&self.keys) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl CommandExecutor for PfMerge {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfmerge(&self.dest, &self.keys) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+// - pfadd key [element ...]
+impl TryFrom<RespArray> for PfAdd {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["pfadd"])?;
+        let mut args = parse_bytes(value)?.into_iter();
+        let key = String::from_utf8(args.next().unwrap_or_default())?;
+        Ok(PfAdd {
+            key,
+            elements: args.collect(),
+        })
+    }
+}
+
+// - pfcount key [key ...]
+impl TryFrom<RespArray> for PfCount {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["pfcount"])?;
+        let keys = parse_bytes(value)?
+            .into_iter()
+            .map(String::from_utf8)
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(PfCount { keys })
+    }
+}
+
+// - pfmerge destkey [sourcekey ...]
+impl TryFrom<RespArray> for PfMerge {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["pfmerge"])?;
+        let mut keys = parse_bytes(value)?
+            .into_iter()
+            .map(String::from_utf8)
+            .collect::<Result<Vec<String>, _>>()?;
+        let dest = keys.remove(0);
+        Ok(PfMerge { dest, keys })
+    }
+}
+
+fn parse_bytes(value: RespArray) -> Result<Vec<Vec<u8>>, CommandError> {
+    extract_args(value, 1)?
+        .into_iter()
+        .map(|v| match v {
+            RespFrame::BulkString(arg) => Ok(arg.0),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid argument".to_string(),
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::command, cmd::Command};
+    use anyhow::Result;
+
+    fn run(backend: &Backend, name: &str, args: &[&str]) -> Result<RespFrame> {
+        let cmd: Command = command(name, args.iter().map(|&arg| arg.into())).try_into()?;
+        Ok(cmd.execute(backend))
+    }
+
+    #[test]
+    fn test_hyperloglog_commands() -> Result<()> {
+        let backend = Backend::new();
+        assert_eq!(
+            run(&backend, "pfadd", &["a", "x", "y", "z"])?,
+            RespFrame::Integer(1)
+        );
+        assert_eq!(run(&backend, "pfadd", &["a", "x"])?, RespFrame::Integer(0));
+        run(&backend, "pfadd", &["b", "z", "w"])?;
+        assert_eq!(run(&backend, "pfcount", &["a"])?, RespFrame::Integer(3));
+        assert_eq!(
+            run(&backend, "pfcount", &["a", "b"])?,
+            RespFrame::Integer(4)
+        );
+        assert_eq!(run(&backend, "pfmerge", &["c", "a", "b"])?, RESP_OK.clone());
+        assert_eq!(run(&backend, "pfcount", &["c"])?, RespFrame::Integer(4));
+
+        run(&backend, "set", &["s", "not a hll"])?;
+        assert_eq!(
+            run(&backend, "pfcount", &["s"])?,
+            SimpleError::new("WRONGTYPE Key is not a valid HyperLogLog string value.").into()
+        );
+        Ok(())
+    }
+}
@@ -1,12 +1,14 @@
 use super::{extract_args, validate_command, CommandExecutor, Set, RESP_OK};
 use crate::{
     cmd::{CommandError, Get},
-    RespArray, RespFrame, RespNull,
+    BulkString, RespArray, RespFrame, RespNull,
 };
 
 impl CommandExecutor for Get {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
         match backend.get(&self.key) {
+            // integer encoded strings are still strings to the client
+            Some(RespFrame::Integer(n)) => BulkString::from(n.to_string()).into(),
             Some(value) => value,
             None => RespFrame::Null(RespNull),
         }
@@ -23,7 +25,7 @@ impl CommandExecutor for Set {
 impl TryFrom<RespArray> for Get {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["get"], 1)?;
+        validate_command(&value, &["get"])?;
 
         let mut args = extract_args(value, 1)?.into_iter();
         match args.next() {
@@ -38,7 +40,7 @@ impl TryFrom<RespArray> for Get {
 impl TryFrom<RespArray> for Set {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["set"], 2)?;
+        validate_command(&value, &["set"])?;
 
         let mut args = extract_args(value, 1)?.into_iter();
         match (args.next(), args.next()) {
@@ -1,13 +1,31 @@
+mod bitmap;
+mod cluster;
+mod command;
+mod config;
 mod conn;
+mod db;
+mod dump;
+mod geo;
 mod hmap;
+mod hyperloglog;
 mod map;
+mod object;
+mod pubsub;
 mod removal;
+mod server;
+mod table;
 
-use crate::{Backend, RespArray, RespError, RespFrame, SimpleString};
+use crate::{
+    Backend, BitFieldOp, BitOpKind, BitUnit, GeoQuery, GeoUnit, RespArray, RespError, RespFrame,
+    SetSlot, SimpleError, SimpleString,
+};
 use enum_dispatch::enum_dispatch;
 use lazy_static::lazy_static;
+use std::time::Duration;
 use thiserror::Error;
 
+pub use self::table::{arg_bytes, command_keys, lookup, CommandSpec};
+
 // you could also use once_cell instead of lazy_static
 lazy_static! {
     static ref RESP_OK: RespFrame = SimpleString::new("OK").into();
@@ -19,6 +37,12 @@ pub enum CommandError {
     InvalidCommand(String),
     #[error("Invalid argument: {0}")]
     InvalidArgument(String),
+    #[error("unknown command '{0}', with args beginning with: {1}")]
+    UnknownCommand(String, String),
+    #[error("unknown subcommand '{0}'. Try {1} HELP.")]
+    UnknownSubcommand(String, String),
+    #[error("wrong number of arguments for '{0}' command")]
+    WrongArity(String),
 
     #[error("{0}")]
     RespError(#[from] RespError),
@@ -41,9 +65,68 @@ pub enum Command {
     HSet(HSet),
     HGetAll(HGetAll),
     Del(Del),
-
-    // unrecognized command
-    Unrecognized(Unrecognized),
+    Select(Select),
+    Move(Move),
+    SwapDb(SwapDb),
+    FlushDb(FlushDb),
+    FlushAll(FlushAll),
+    Subscribe(Subscribe),
+    Unsubscribe(Unsubscribe),
+    PSubscribe(PSubscribe),
+    PUnsubscribe(PUnsubscribe),
+    Publish(Publish),
+    ClientId(ClientId),
+    ClientTracking(ClientTracking),
+    ConfigGet(ConfigGet),
+    ConfigSet(ConfigSet),
+    Shutdown(Shutdown),
+    SlowLogGet(SlowLogGet),
+    SlowLogLen(SlowLogLen),
+    SlowLogReset(SlowLogReset),
+    LatencyLatest(LatencyLatest),
+    LatencyHistory(LatencyHistory),
+    LatencyHistogram(LatencyHistogram),
+    LatencyReset(LatencyReset),
+    Monitor(Monitor),
+    ClusterKeySlot(ClusterKeySlot),
+    ClusterMyId(ClusterMyId),
+    ClusterNodes(ClusterNodes),
+    ClusterInfo(ClusterInfo),
+    ClusterSlots(ClusterSlots),
+    ClusterShards(ClusterShards),
+    ClusterMeet(ClusterMeet),
+    ClusterAddSlots(ClusterAddSlots),
+    ClusterDelSlots(ClusterDelSlots),
+    ClusterSetSlot(ClusterSetSlot),
+    ClusterCountKeysInSlot(ClusterCountKeysInSlot),
+    ClusterGetKeysInSlot(ClusterGetKeysInSlot),
+    Asking(Asking),
+    SetBit(SetBit),
+    GetBit(GetBit),
+    BitCount(BitCount),
+    BitPos(BitPos),
+    BitOp(BitOp),
+    BitField(BitField),
+    PfAdd(PfAdd),
+    PfCount(PfCount),
+    PfMerge(PfMerge),
+    GeoAdd(GeoAdd),
+    GeoPos(GeoPos),
+    GeoDist(GeoDist),
+    GeoSearch(GeoSearch),
+    Dump(Dump),
+    Restore(Restore),
+    Migrate(Migrate),
+    CommandList(CommandList),
+    CommandCount(CommandCount),
+    CommandInfo(CommandInfo),
+    CommandGetKeys(CommandGetKeys),
+    ObjectEncoding(ObjectEncoding),
+    ObjectFreq(ObjectFreq),
+    ObjectIdleTime(ObjectIdleTime),
+    ObjectRefCount(ObjectRefCount),
+    MemoryUsage(MemoryUsage),
+    MemoryStats(MemoryStats),
 }
 
 #[derive(Debug)]
@@ -87,7 +170,328 @@ pub struct Del {
 }
 
 #[derive(Debug)]
-pub struct Unrecognized;
+pub struct Select {
+    index: usize,
+}
+
+#[derive(Debug)]
+pub struct Move {
+    key: String,
+    db: usize,
+}
+
+#[derive(Debug)]
+pub struct SwapDb {
+    a: usize,
+    b: usize,
+}
+
+#[derive(Debug)]
+pub struct FlushDb {
+    is_async: bool,
+}
+
+#[derive(Debug)]
+pub struct FlushAll {
+    is_async: bool,
+}
+
+#[derive(Debug)]
+pub struct Subscribe {
+    channels: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Unsubscribe {
+    channels: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct PSubscribe {
+    patterns: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct PUnsubscribe {
+    patterns: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Publish {
+    channel: String,
+    message: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct ClientId;
+
+#[derive(Debug)]
+pub struct ClientTracking {
+    on: bool,
+    bcast: bool,
+    prefixes: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ConfigGet {
+    pattern: String,
+}
+
+#[derive(Debug)]
+pub struct ConfigSet {
+    name: String,
+    value: String,
+}
+
+#[derive(Debug)]
+pub struct Shutdown {
+    save: Option<bool>,
+}
+
+#[derive(Debug)]
+pub struct SlowLogGet {
+    count: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct SlowLogLen;
+
+#[derive(Debug)]
+pub struct SlowLogReset;
+
+#[derive(Debug)]
+pub struct LatencyLatest;
+
+#[derive(Debug)]
+pub struct LatencyHistory {
+    event: String,
+}
+
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    commands: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct LatencyReset {
+    events: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Monitor;
+
+#[derive(Debug)]
+pub struct ClusterKeySlot {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct ClusterMyId;
+
+#[derive(Debug)]
+pub struct ClusterNodes;
+
+#[derive(Debug)]
+pub struct ClusterInfo;
+
+#[derive(Debug)]
+pub struct ClusterSlots;
+
+#[derive(Debug)]
+pub struct ClusterShards;
+
+#[derive(Debug)]
+pub struct ClusterMeet {
+    ip: String,
+    port: u16,
+}
+
+#[derive(Debug)]
+pub struct ClusterAddSlots {
+    slots: Vec<i64>,
+}
+
+#[derive(Debug)]
+pub struct ClusterDelSlots {
+    slots: Vec<i64>,
+}
+
+#[derive(Debug)]
+pub struct ClusterSetSlot {
+    slot: i64,
+    action: SetSlot,
+}
+
+#[derive(Debug)]
+pub struct ClusterCountKeysInSlot {
+    slot: i64,
+}
+
+#[derive(Debug)]
+pub struct ClusterGetKeysInSlot {
+    slot: i64,
+    count: usize,
+}
+
+#[derive(Debug)]
+pub struct Asking;
+
+#[derive(Debug)]
+pub struct SetBit {
+    key: String,
+    offset: u64,
+    bit: bool,
+}
+
+#[derive(Debug)]
+pub struct GetBit {
+    key: String,
+    offset: u64,
+}
+
+#[derive(Debug)]
+pub struct BitCount {
+    key: String,
+    range: Option<(i64, i64, BitUnit)>,
+}
+
+#[derive(Debug)]
+pub struct BitPos {
+    key: String,
+    bit: bool,
+    start: Option<i64>,
+    end: Option<i64>,
+    unit: BitUnit,
+}
+
+#[derive(Debug)]
+pub struct BitOp {
+    op: BitOpKind,
+    dest: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct BitField {
+    key: String,
+    ops: Vec<BitFieldOp>,
+}
+
+#[derive(Debug)]
+pub struct PfAdd {
+    key: String,
+    elements: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct PfCount {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct PfMerge {
+    dest: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct GeoAdd {
+    key: String,
+    items: Vec<(f64, f64, String)>,
+    nx: bool,
+    xx: bool,
+    ch: bool,
+}
+
+#[derive(Debug)]
+pub struct GeoPos {
+    key: String,
+    members: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct GeoDist {
+    key: String,
+    a: String,
+    b: String,
+    unit: GeoUnit,
+}
+
+#[derive(Debug)]
+pub struct GeoSearch {
+    key: String,
+    query: GeoQuery,
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+}
+
+#[derive(Debug)]
+pub struct Dump {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct Restore {
+    key: String,
+    payload: Vec<u8>,
+    replace: bool,
+}
+
+#[derive(Debug)]
+pub struct Migrate {
+    host: String,
+    port: u16,
+    keys: Vec<String>,
+    db: usize,
+    timeout: Duration,
+    copy: bool,
+    replace: bool,
+}
+
+#[derive(Debug)]
+pub struct CommandList;
+
+#[derive(Debug)]
+pub struct CommandCount;
+
+#[derive(Debug)]
+pub struct CommandInfo {
+    names: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct CommandGetKeys {
+    args: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct ObjectEncoding {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct ObjectFreq {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct ObjectIdleTime {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct ObjectRefCount {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct MemoryUsage {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct MemoryStats;
 
 impl TryFrom<RespFrame> for Command {
     type Error = CommandError;
@@ -101,20 +505,12 @@ impl TryFrom<RespFrame> for Command {
     }
 }
 
+// the name is looked up in the command table, case-insensitively
 impl TryFrom<RespArray> for Command {
     type Error = CommandError;
     fn try_from(v: RespArray) -> Result<Self, Self::Error> {
         match v.first() {
-            Some(RespFrame::BulkString(ref cmd)) => match cmd.as_ref() {
-                b"echo" => Ok(Echo::try_from(v)?.into()),
-                b"get" => Ok(Get::try_from(v)?.into()),
-                b"set" => Ok(Set::try_from(v)?.into()),
-                b"hget" => Ok(HGet::try_from(v)?.into()),
-                b"hset" => Ok(HSet::try_from(v)?.into()),
-                b"hgetall" => Ok(HGetAll::try_from(v)?.into()),
-                b"del" => Ok(Del::try_from(v)?.into()),
-                _ => Ok(Unrecognized.into()),
-            },
+            Some(RespFrame::BulkString(_)) => table::parse_command(v),
             _ => Err(CommandError::InvalidCommand(
                 "Command must have a BulkString as the first argument".to_string(),
             )),
@@ -122,28 +518,22 @@ impl TryFrom<RespArray> for Command {
     }
 }
 
-impl CommandExecutor for Unrecognized {
-    fn execute(self, _: &Backend) -> RespFrame {
-        RESP_OK.clone()
+// the error reply of a command that can't be parsed
+impl From<CommandError> for RespFrame {
+    fn from(e: CommandError) -> Self {
+        let msg = match e {
+            CommandError::InvalidArgument(msg) => msg,
+            e => e.to_string(),
+        };
+        SimpleError::new(format!("ERR {}", msg)).into()
     }
 }
 
-fn validate_command(
-    value: &RespArray,
-    names: &[&'static str],
-    n_args: usize,
-) -> Result<(), CommandError> {
-    if value.len() != n_args + names.len() {
-        return Err(CommandError::InvalidArgument(format!(
-            "{} command must have exactly {} argument",
-            names.join(" "),
-            n_args
-        )));
-    }
-
+// checks the command and subcommand names, and the arity from the command table
+fn validate_command(value: &RespArray, names: &[&'static str]) -> Result<(), CommandError> {
     for (i, name) in names.iter().enumerate() {
-        match value[i] {
-            RespFrame::BulkString(ref cmd) => {
+        match value.get(i) {
+            Some(RespFrame::BulkString(ref cmd)) => {
                 if cmd.as_ref().to_ascii_lowercase() != name.as_bytes() {
                     return Err(CommandError::InvalidCommand(format!(
                         "Invalid command: expected {}, got {}",
@@ -159,7 +549,7 @@ fn validate_command(
             }
         }
     }
-    Ok(())
+    table::check_arity(names, value.len())
 }
 
 fn extract_args(value: RespArray, start: usize) -> Result<Vec<RespFrame>, CommandError> {
@@ -169,7 +559,7 @@ fn extract_args(value: RespArray, start: usize) -> Result<Vec<RespFrame>, Comman
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{RespDecode, RespNull};
+    use crate::{client::command, RespDecode, RespNull};
     use anyhow::Result;
     use bytes::BytesMut;
 
@@ -189,4 +579,46 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_command_dispatch_is_case_insensitive() -> Result<()> {
+        let backend = Backend::new();
+        let cmd: Command = command("SET", ["k".into(), "v".into()]).try_into()?;
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        let cmd: Command = command("gEt", ["k".into()]).try_into()?;
+        assert_eq!(cmd.execute(&backend), RespFrame::BulkString("v".into()));
+        let cmd: Command = command("Client", ["ID".into()]).try_into()?;
+        assert!(matches!(cmd, Command::ClientId(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_errors() {
+        let reply = |name: &str, args: &[&str]| -> RespFrame {
+            match Command::try_from(command(name, args.iter().map(|&arg| arg.into()))) {
+                Ok(_) => panic!("expected an error"),
+                Err(e) => e.into(),
+            }
+        };
+        assert_eq!(
+            reply("GET", &[]),
+            SimpleError::new("ERR wrong number of arguments for 'get' command").into()
+        );
+        assert_eq!(
+            reply("hset", &["h", "f"]),
+            SimpleError::new("ERR wrong number of arguments for 'hset' command").into()
+        );
+        assert_eq!(
+            reply("config", &["get"]),
+            SimpleError::new("ERR wrong number of arguments for 'config|get' command").into()
+        );
+        assert_eq!(
+            reply("nope", &["a"]),
+            SimpleError::new("ERR unknown command 'nope', with args beginning with: 'a' ").into()
+        );
+        assert_eq!(
+            reply("bitcount", &["k", "0"]),
+            SimpleError::new("ERR syntax error").into()
+        );
+    }
 }
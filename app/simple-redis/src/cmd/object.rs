@@ -0,0 +1,215 @@
+use super::{
+    extract_args, validate_command, CommandError, CommandExecutor, MemoryStats, MemoryUsage,
+    ObjectEncoding, ObjectFreq, ObjectIdleTime, ObjectRefCount,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespMap, RespNull};
+
+impl CommandExecutor for ObjectEncoding {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.object_encoding(&self.key) {
+            Some(encoding) => BulkString::from(encoding).into(),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl CommandExecutor for ObjectFreq {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        integer_or_null(backend.object_freq(&self.key).map(i64::from))
+    }
+}
+
+impl CommandExecutor for ObjectIdleTime {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        integer_or_null(backend.object_idletime(&self.key).map(|secs| secs as i64))
+    }
+}
+
+impl CommandExecutor for ObjectRefCount {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        integer_or_null(backend.object_refcount(&self.key))
+    }
+}
+
+impl CommandExecutor for MemoryUsage {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        integer_or_null(backend.memory_usage(&self.key).map(|bytes| bytes as i64))
+    }
+}
+
+impl CommandExecutor for MemoryStats {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let stats = backend.memory_stats();
+        let mut map = RespMap::new();
+        let mut insert = |name: &str, value: usize| {
+            map.insert(name.to_string(), RespFrame::Integer(value as i64));
+        };
+        insert("total.allocated", stats.total);
+        insert("dataset.bytes", stats.dataset);
+        insert("overhead.total", stats.overhead);
+        insert("keys.count", stats.keys);
+        insert(
+            "keys.bytes-per-key",
+            stats.total.checked_div(stats.keys).unwrap_or(0),
+        );
+        for (index, overhead) in stats.dbs {
+            let mut db = RespMap::new();
+            db.insert(
+                "overhead.hashtable.main".to_string(),
+                RespFrame::Integer(overhead as i64),
+            );
+            // there is no key expiration
+            db.insert(
+                "overhead.hashtable.expires".to_string(),
+                RespFrame::Integer(0),
+            );
+            map.insert(format!("db.{}", index), db.into());
+        }
+        map.into()
+    }
+}
+
+// - object encoding|freq|idletime|refcount key
+impl TryFrom<RespArray> for ObjectEncoding {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["object", "encoding"])?;
+        Ok(ObjectEncoding {
+            key: parse_key(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ObjectFreq {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["object", "freq"])?;
+        Ok(ObjectFreq {
+            key: parse_key(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ObjectIdleTime {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["object", "idletime"])?;
+        Ok(ObjectIdleTime {
+            key: parse_key(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ObjectRefCount {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["object", "refcount"])?;
+        Ok(ObjectRefCount {
+            key: parse_key(value)?,
+        })
+    }
+}
+
+// - memory usage key [SAMPLES count]
+impl TryFrom<RespArray> for MemoryUsage {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["memory", "usage"])?;
+        let mut args = extract_args(value, 2)?.into_iter();
+        let key = match args.next() {
+            Some(RespFrame::BulkString(key)) => String::from_utf8(key.0)?,
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+        // every field is counted, the number of samples makes no difference
+        match (args.next(), args.next(), args.next()) {
+            (None, None, None) => {}
+            (Some(RespFrame::BulkString(option)), Some(RespFrame::BulkString(count)), None)
+                if option.eq_ignore_ascii_case(b"samples") =>
+            {
+                String::from_utf8(count.0)?.parse::<u64>().map_err(|_| {
+                    CommandError::InvalidArgument(
+                        "value is not an integer or out of range".to_string(),
+                    )
+                })?;
+            }
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        }
+        Ok(MemoryUsage { key })
+    }
+}
+
+impl TryFrom<RespArray> for MemoryStats {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["memory", "stats"])?;
+        Ok(MemoryStats)
+    }
+}
+
+fn parse_key(value: RespArray) -> Result<String, CommandError> {
+    match extract_args(value, 2)?.into_iter().next() {
+        Some(RespFrame::BulkString(key)) => Ok(String::from_utf8(key.0)?),
+        _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    }
+}
+
+fn integer_or_null(n: Option<i64>) -> RespFrame {
+    match n {
+        Some(n) => RespFrame::Integer(n),
+        None => RespFrame::Null(RespNull),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::command, cmd::Command};
+    use anyhow::Result;
+
+    fn run(backend: &Backend, name: &str, args: &[&str]) -> Result<RespFrame> {
+        let cmd: Command = command(name, args.iter().map(|&arg| arg.into())).try_into()?;
+        Ok(cmd.execute(backend))
+    }
+
+    #[test]
+    fn test_object_and_memory_commands() -> Result<()> {
+        let backend = Backend::new();
+        run(&backend, "set", &["n", "12"])?;
+        run(&backend, "hset", &["h", "f", "v"])?;
+        let encoding = |key| run(&backend, "object", &["encoding", key]);
+        assert_eq!(encoding("n")?, RespFrame::BulkString("int".into()));
+        assert_eq!(encoding("h")?, RespFrame::BulkString("listpack".into()));
+        assert_eq!(encoding("missing")?, RespFrame::Null(RespNull));
+        // an integer is still read back as a string
+        assert_eq!(
+            run(&backend, "get", &["n"])?,
+            RespFrame::BulkString("12".into())
+        );
+
+        assert_eq!(
+            run(&backend, "object", &["idletime", "n"])?,
+            RespFrame::Integer(0)
+        );
+        assert!(matches!(
+            run(&backend, "object", &["freq", "n"])?,
+            RespFrame::Integer(n) if n > 0
+        ));
+        assert_eq!(
+            run(&backend, "object", &["refcount", "h"])?,
+            RespFrame::Integer(1)
+        );
+
+        let usage = run(&backend, "memory", &["usage", "n", "SAMPLES", "5"])?;
+        assert_eq!(
+            usage,
+            RespFrame::Integer(backend.memory_usage("n").unwrap() as i64)
+        );
+        assert!(run(&backend, "memory", &["usage", "n", "SAMPLES"]).is_err());
+        let RespFrame::Map(stats) = run(&backend, "memory", &["stats"])? else {
+            panic!("expected a map");
+        };
+        assert_eq!(stats["keys.count"], RespFrame::Integer(2));
+        assert!(stats.contains_key("db.0"));
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use super::{
+    extract_args, validate_command, CommandError, CommandExecutor, PSubscribe, PUnsubscribe,
+    Publish, Subscribe, Unsubscribe,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull, RespPush};
+
+impl CommandExecutor for Subscribe {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        confirm(backend, "subscribe", self.channels, |ch| {
+            backend.subscribe(ch)
+        })
+    }
+}
+
+impl CommandExecutor for Unsubscribe {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let channels = if self.channels.is_empty() {
+            backend.subscribed_channels()
+        } else {
+            self.channels
+        };
+        confirm(backend, "unsubscribe", channels, |ch| {
+            backend.unsubscribe(ch)
+        })
+    }
+}
+
+impl CommandExecutor for PSubscribe {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        confirm(backend, "psubscribe", self.patterns, |p| {
+            backend.psubscribe(p)
+        })
+    }
+}
+
+impl CommandExecutor for PUnsubscribe {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let patterns = if self.patterns.is_empty() {
+            backend.subscribed_patterns()
+        } else {
+            self.patterns
+        };
+        confirm(backend, "punsubscribe", patterns, |p| {
+            backend.punsubscribe(p)
+        })
+    }
+}
+
+impl CommandExecutor for Publish {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.publish(&self.channel, &self.message) as i64)
+    }
+}
+
+// (un)subscribing to n channels produces n confirmations: all but the last one go
+// through the session's push channel, so they reach the client before the reply
+fn confirm(
+    backend: &Backend,
+    kind: &str,
+    names: Vec<String>,
+    f: impl Fn(&str) -> usize,
+) -> RespFrame {
+    if names.is_empty() {
+        return RespPush::with_kind(kind, [RespNull.into(), RespFrame::Integer(0)]).into();
+    }
+    let mut replies = names.into_iter().map(|name| {
+        let count = f(&name);
+        RespFrame::from(RespPush::with_kind(
+            kind,
+            [
+                BulkString::from(name).into(),
+                RespFrame::Integer(count as i64),
+            ],
+        ))
+    });
+    let mut last = replies.next().unwrap_or_else(|| RespNull.into());
+    for reply in replies {
+        let _ = backend
+            .push_sender()
+            .send(std::mem::replace(&mut last, reply));
+    }
+    last
+}
+
+impl TryFrom<RespArray> for Subscribe {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let channels = parse_names(value, "subscribe")?;
+        Ok(Subscribe { channels })
+    }
+}
+
+impl TryFrom<RespArray> for Unsubscribe {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let channels = parse_names(value, "unsubscribe")?;
+        Ok(Unsubscribe { channels })
+    }
+}
+
+impl TryFrom<RespArray> for PSubscribe {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let patterns = parse_names(value, "psubscribe")?;
+        Ok(PSubscribe { patterns })
+    }
+}
+
+impl TryFrom<RespArray> for PUnsubscribe {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let patterns = parse_names(value, "punsubscribe")?;
+        Ok(PUnsubscribe { patterns })
+    }
+}
+
+impl TryFrom<RespArray> for Publish {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["publish"])?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(channel)), Some(RespFrame::BulkString(message))) => {
+                Ok(Publish {
+                    channel: String::from_utf8(channel.0)?,
+                    message: message.0,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid channel or message".to_string(),
+            )),
+        }
+    }
+}
+
+fn parse_names(value: RespArray, name: &'static str) -> Result<Vec<String>, CommandError> {
+    validate_command(&value, &[name])?;
+
+    extract_args(value, 1)?
+        .into_iter()
+        .map(|v| match v {
+            RespFrame::BulkString(name) => Ok(String::from_utf8(name.0)?),
+            _ => Err(CommandError::InvalidArgument("Invalid channel".to_string())),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cmd::Command, RespDecode};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn run(backend: &Backend, cmd: &[u8]) -> Result<RespFrame> {
+        let mut buf = BytesMut::from(cmd);
+        let cmd: Command = RespArray::decode(&mut buf)?.try_into()?;
+        Ok(cmd.execute(backend))
+    }
+
+    fn confirmation(kind: &str, name: &str, count: i64) -> RespFrame {
+        RespPush::with_kind(kind, [name.as_bytes().into(), RespFrame::Integer(count)]).into()
+    }
+
+    #[test]
+    fn test_subscribe_publish() -> Result<()> {
+        let backend = Backend::new();
+        let mut rx = backend.take_push_receiver().unwrap();
+
+        let ret = run(&backend, b"*3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n$1\r\nb\r\n")?;
+        assert_eq!(rx.try_recv()?, confirmation("subscribe", "a", 1));
+        assert_eq!(ret, confirmation("subscribe", "b", 2));
+
+        let publisher = backend.new_session();
+        let ret = run(
+            &publisher,
+            b"*3\r\n$7\r\npublish\r\n$1\r\na\r\n$2\r\nhi\r\n",
+        )?;
+        assert_eq!(ret, RespFrame::Integer(1));
+        assert_eq!(
+            rx.try_recv()?,
+            RespPush::with_kind("message", [b"a".into(), b"hi".into()]).into()
+        );
+
+        let ret = run(&backend, b"*1\r\n$11\r\nunsubscribe\r\n")?;
+        assert_eq!(rx.try_recv()?, confirmation("unsubscribe", "a", 1));
+        assert_eq!(ret, confirmation("unsubscribe", "b", 0));
+        let ret = run(
+            &publisher,
+            b"*3\r\n$7\r\npublish\r\n$1\r\na\r\n$2\r\nhi\r\n",
+        )?;
+        assert_eq!(ret, RespFrame::Integer(0));
+
+        Ok(())
+    }
+}
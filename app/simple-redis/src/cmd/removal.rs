@@ -13,8 +13,7 @@ impl CommandExecutor for Del {
 impl TryFrom<RespArray> for Del {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        let n_args = value.len() - 1;
-        validate_command(&value, &["del"], n_args)?;
+        validate_command(&value, &["del"])?;
 
         let args = extract_args(value, 1)?.into_iter();
         let mut keys = Vec::new();
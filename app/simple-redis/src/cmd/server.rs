@@ -0,0 +1,345 @@
+use super::{
+    extract_args, validate_command, CommandError, CommandExecutor, LatencyHistogram,
+    LatencyHistory, LatencyLatest, LatencyReset, Monitor, Shutdown, SlowLogGet, SlowLogLen,
+    SlowLogReset, RESP_OK,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespMap, SimpleError};
+
+const SLOWLOG_DEFAULT_COUNT: usize = 10;
+
+// on success the connection is closed instead of sending the reply, see network.rs
+impl CommandExecutor for Shutdown {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.shutdown(self.save) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl CommandExecutor for SlowLogGet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let count = match self.count {
+            None => SLOWLOG_DEFAULT_COUNT,
+            Some(-1) => usize::MAX,
+            Some(n) if n >= 0 => n as usize,
+            Some(_) => {
+                return SimpleError::new("ERR count should be greater than or equal to -1").into()
+            }
+        };
+        let entries = backend
+            .slowlog()
+            .get(count)
+            .into_iter()
+            .map(|entry| {
+                let args = entry
+                    .args
+                    .into_iter()
+                    .map(|arg| BulkString::new(arg).into())
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new([
+                    RespFrame::Integer(entry.id as i64),
+                    RespFrame::Integer(entry.timestamp as i64),
+                    RespFrame::Integer(entry.duration as i64),
+                    RespArray::new(args).into(),
+                    BulkString::from(entry.addr).into(),
+                    // client name, CLIENT SETNAME is not supported
+                    BulkString::from("").into(),
+                ])
+                .into()
+            })
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(entries).into()
+    }
+}
+
+impl CommandExecutor for SlowLogLen {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.slowlog().len() as i64)
+    }
+}
+
+impl CommandExecutor for SlowLogReset {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend.slowlog().reset();
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for LatencyLatest {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let events = backend
+            .latency()
+            .latest()
+            .into_iter()
+            .map(|e| {
+                RespArray::new([
+                    BulkString::from(e.event).into(),
+                    RespFrame::Integer(e.timestamp as i64),
+                    RespFrame::Integer(e.latest as i64),
+                    RespFrame::Integer(e.max as i64),
+                ])
+                .into()
+            })
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(events).into()
+    }
+}
+
+impl CommandExecutor for LatencyHistory {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let samples = backend
+            .latency()
+            .history(&self.event)
+            .into_iter()
+            .map(|(timestamp, latency)| {
+                RespArray::new([
+                    RespFrame::Integer(timestamp as i64),
+                    RespFrame::Integer(latency as i64),
+                ])
+                .into()
+            })
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(samples).into()
+    }
+}
+
+// command name => {calls, histogram_usec: [bucket upper bound, cumulative count, ...]}
+impl CommandExecutor for LatencyHistogram {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let mut map = RespMap::new();
+        for (name, histogram) in backend.latency().histograms(&self.commands) {
+            let buckets = histogram
+                .cumulative()
+                .into_iter()
+                .flat_map(|(usec, count)| {
+                    [
+                        RespFrame::Integer(usec as i64),
+                        RespFrame::Integer(count as i64),
+                    ]
+                })
+                .collect::<Vec<RespFrame>>();
+            let mut entry = RespMap::new();
+            entry.insert(
+                "calls".to_string(),
+                RespFrame::Integer(histogram.calls as i64),
+            );
+            entry.insert("histogram_usec".to_string(), RespArray::new(buckets).into());
+            map.insert(name, entry.into());
+        }
+        map.into()
+    }
+}
+
+impl CommandExecutor for LatencyReset {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.latency().reset(&self.events) as i64)
+    }
+}
+
+// the connection only receives the command feed from now on, see network::stream_handler
+impl CommandExecutor for Monitor {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend.monitor();
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for Monitor {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["monitor"])?;
+        Ok(Monitor)
+    }
+}
+
+// - shutdown [save|nosave]
+impl TryFrom<RespArray> for Shutdown {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["shutdown"])?;
+        let n_args = value.len() - 1;
+        if n_args > 1 {
+            return Err(CommandError::InvalidArgument(
+                "shutdown command must have at most 1 argument".to_string(),
+            ));
+        }
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let save = match args.next() {
+            None => None,
+            Some(RespFrame::BulkString(mode)) if mode.eq_ignore_ascii_case(b"save") => Some(true),
+            Some(RespFrame::BulkString(mode)) if mode.eq_ignore_ascii_case(b"nosave") => {
+                Some(false)
+            }
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+        Ok(Shutdown { save })
+    }
+}
+
+// - slowlog get [count]
+impl TryFrom<RespArray> for SlowLogGet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["slowlog", "get"])?;
+        let n_args = value.len() - 2;
+        if n_args > 1 {
+            return Err(CommandError::InvalidArgument(
+                "slowlog get command must have at most 1 argument".to_string(),
+            ));
+        }
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        let count = match args.next() {
+            None => None,
+            Some(RespFrame::BulkString(count)) => Some(
+                String::from_utf8(count.0)?
+                    .parse()
+                    .map_err(|_| CommandError::InvalidArgument("invalid count".to_string()))?,
+            ),
+            _ => return Err(CommandError::InvalidArgument("invalid count".to_string())),
+        };
+        Ok(SlowLogGet { count })
+    }
+}
+
+impl TryFrom<RespArray> for SlowLogLen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["slowlog", "len"])?;
+        Ok(SlowLogLen)
+    }
+}
+
+impl TryFrom<RespArray> for SlowLogReset {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["slowlog", "reset"])?;
+        Ok(SlowLogReset)
+    }
+}
+
+impl TryFrom<RespArray> for LatencyLatest {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["latency", "latest"])?;
+        Ok(LatencyLatest)
+    }
+}
+
+impl TryFrom<RespArray> for LatencyHistory {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["latency", "history"])?;
+        let mut names = parse_names(value, 2)?;
+        Ok(LatencyHistory {
+            event: names.remove(0),
+        })
+    }
+}
+
+// - latency histogram [command ...]
+impl TryFrom<RespArray> for LatencyHistogram {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["latency", "histogram"])?;
+        let commands = parse_names(value, 2)?
+            .into_iter()
+            .map(|name| name.to_ascii_lowercase())
+            .collect();
+        Ok(LatencyHistogram { commands })
+    }
+}
+
+// - latency reset [event ...]
+impl TryFrom<RespArray> for LatencyReset {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["latency", "reset"])?;
+        Ok(LatencyReset {
+            events: parse_names(value, 2)?,
+        })
+    }
+}
+
+fn parse_names(value: RespArray, start: usize) -> Result<Vec<String>, CommandError> {
+    extract_args(value, start)?
+        .into_iter()
+        .map(|v| match v {
+            RespFrame::BulkString(name) => Ok(String::from_utf8(name.0)?),
+            _ => Err(CommandError::InvalidArgument("Invalid name".to_string())),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_shutdown_command() {
+        let backend = Backend::new();
+        let ret = Shutdown { save: Some(true) }.execute(&backend);
+        assert!(matches!(ret, RespFrame::Error(_)));
+        assert!(!backend.is_shutting_down());
+
+        backend.config_set("dbfilename", "dump.resp").unwrap();
+        let ret = Shutdown { save: None }.execute(&backend);
+        assert_eq!(ret, RESP_OK.clone());
+        assert!(backend.is_shutting_down());
+        assert!(backend.save_on_shutdown());
+    }
+
+    #[test]
+    fn test_slowlog_commands() {
+        let backend = Backend::new();
+        backend.set_client_addr("127.0.0.1:1234");
+        backend.config_set("slowlog-log-slower-than", "0").unwrap();
+        let args = vec![b"get".to_vec(), b"a".to_vec()];
+        backend.record_command(&args, Duration::from_micros(5));
+        backend.record_command(&args, Duration::from_micros(7));
+
+        assert_eq!(SlowLogLen.execute(&backend), RespFrame::Integer(2));
+        let ret = SlowLogGet { count: Some(1) }.execute(&backend);
+        let RespFrame::Array(entries) = ret else {
+            panic!("expected an array");
+        };
+        assert_eq!(entries.len(), 1);
+        let RespFrame::Array(ref entry) = entries[0] else {
+            panic!("expected an array");
+        };
+        assert_eq!(entry[0], RespFrame::Integer(1));
+        assert_eq!(entry[2], RespFrame::Integer(7));
+        assert_eq!(
+            entry[3],
+            RespArray::new([b"get".into(), b"a".into()]).into()
+        );
+        assert_eq!(entry[4], RespFrame::BulkString("127.0.0.1:1234".into()));
+
+        assert_eq!(SlowLogReset.execute(&backend), RESP_OK.clone());
+        assert_eq!(SlowLogLen.execute(&backend), RespFrame::Integer(0));
+    }
+
+    #[test]
+    fn test_latency_histogram_command() {
+        let backend = Backend::new();
+        backend.record_command(&[b"GET".to_vec()], Duration::from_micros(3));
+        backend.record_command(&[b"set".to_vec()], Duration::from_micros(1));
+
+        let ret = LatencyHistogram {
+            commands: vec!["get".to_string()],
+        }
+        .execute(&backend);
+        let mut entry = RespMap::new();
+        entry.insert("calls".to_string(), RespFrame::Integer(1));
+        entry.insert(
+            "histogram_usec".to_string(),
+            RespArray::new([RespFrame::Integer(4), RespFrame::Integer(1)]).into(),
+        );
+        let mut expected = RespMap::new();
+        expected.insert("get".to_string(), entry.into());
+        assert_eq!(ret, expected.into());
+    }
+}
@@ -0,0 +1,631 @@
+use super::*;
+use crate::{BulkString, RespNull};
+use std::collections::HashMap;
+
+// command flags, as reported by COMMAND INFO
+pub const CMD_WRITE: u32 = 1 << 0;
+pub const CMD_READONLY: u32 = 1 << 1;
+pub const CMD_DENYOOM: u32 = 1 << 2;
+pub const CMD_ADMIN: u32 = 1 << 3;
+pub const CMD_PUBSUB: u32 = 1 << 4;
+pub const CMD_NOSCRIPT: u32 = 1 << 5;
+pub const CMD_BLOCKING: u32 = 1 << 6;
+pub const CMD_LOADING: u32 = 1 << 7;
+pub const CMD_STALE: u32 = 1 << 8;
+pub const CMD_FAST: u32 = 1 << 9;
+
+const FLAG_NAMES: [(u32, &str); 10] = [
+    (CMD_WRITE, "write"),
+    (CMD_READONLY, "readonly"),
+    (CMD_DENYOOM, "denyoom"),
+    (CMD_ADMIN, "admin"),
+    (CMD_PUBSUB, "pubsub"),
+    (CMD_NOSCRIPT, "noscript"),
+    (CMD_BLOCKING, "blocking"),
+    (CMD_LOADING, "loading"),
+    (CMD_STALE, "stale"),
+    (CMD_FAST, "fast"),
+];
+
+type Parser = fn(RespArray) -> Result<Command, CommandError>;
+// positions of the keys, for the commands where they depend on the arguments
+type KeysFn = fn(&[&[u8]]) -> Vec<usize>;
+
+// the description of a command: the table drives dispatch, arity checks, key extraction
+// (cluster redirects, sharding) and the COMMAND replies
+#[derive(Debug)]
+pub struct CommandSpec {
+    // lowercase, "container|sub" for subcommands
+    name: &'static str,
+    // exact number of arguments including the name, or the minimum when negative
+    arity: i64,
+    flags: u32,
+    first_key: i64,
+    // negative counts from the end
+    last_key: i64,
+    step: i64,
+    categories: &'static [&'static str],
+    subcommands: &'static [CommandSpec],
+    parse: Option<Parser>,
+    keys: Option<KeysFn>,
+}
+
+const fn spec(
+    name: &'static str,
+    arity: i64,
+    flags: u32,
+    categories: &'static [&'static str],
+) -> CommandSpec {
+    CommandSpec {
+        name,
+        arity,
+        flags,
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        categories,
+        subcommands: &[],
+        parse: None,
+        keys: None,
+    }
+}
+
+fn parse<T>(value: RespArray) -> Result<Command, CommandError>
+where
+    T: TryFrom<RespArray, Error = CommandError> + Into<Command>,
+{
+    Ok(T::try_from(value)?.into())
+}
+
+impl CommandSpec {
+    const fn parser(self, parse: Parser) -> Self {
+        Self {
+            parse: Some(parse),
+            ..self
+        }
+    }
+
+    const fn keys(self, first_key: i64, last_key: i64, step: i64) -> Self {
+        Self {
+            first_key,
+            last_key,
+            step,
+            ..self
+        }
+    }
+
+    const fn movable_keys(self, keys: KeysFn) -> Self {
+        Self {
+            keys: Some(keys),
+            ..self
+        }
+    }
+
+    const fn subcommands(self, subcommands: &'static [CommandSpec]) -> Self {
+        Self {
+            subcommands,
+            ..self
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    // a single key, always the first argument
+    pub fn has_single_key(&self) -> bool {
+        self.keys.is_none() && self.first_key == 1 && self.last_key == 1
+    }
+
+    fn check_arity(&self, len: usize) -> Result<(), CommandError> {
+        let len = len as i64;
+        if (self.arity >= 0 && len != self.arity) || len < -self.arity {
+            return Err(CommandError::WrongArity(self.name.to_string()));
+        }
+        Ok(())
+    }
+
+    fn subcommand(&self, name: &[u8]) -> Option<&'static CommandSpec> {
+        self.subcommands.iter().find(|sub| {
+            sub.name
+                .split_once('|')
+                .is_some_and(|(_, sub)| sub.as_bytes().eq_ignore_ascii_case(name))
+        })
+    }
+
+    pub fn key_positions(&self, args: &[&[u8]]) -> Vec<usize> {
+        if let Some(keys) = self.keys {
+            return keys(args);
+        }
+        if self.first_key <= 0 {
+            return vec![];
+        }
+        let last = if self.last_key < 0 {
+            args.len() as i64 + self.last_key
+        } else {
+            self.last_key.min(args.len() as i64 - 1)
+        };
+        (self.first_key..=last)
+            .step_by(self.step.max(1) as usize)
+            .map(|i| i as usize)
+            .collect()
+    }
+
+    // the COMMAND INFO entry
+    pub fn info(&self) -> RespFrame {
+        let mut flags: Vec<RespFrame> = FLAG_NAMES
+            .iter()
+            .filter(|(flag, _)| self.flags & flag != 0)
+            .map(|(_, name)| SimpleString::new(*name).into())
+            .collect();
+        if self.keys.is_some() {
+            flags.push(SimpleString::new("movablekeys").into());
+        }
+        let categories = self
+            .acl_categories()
+            .into_iter()
+            .map(|category| SimpleString::new(category).into())
+            .collect::<Vec<RespFrame>>();
+        let subcommands = self
+            .subcommands
+            .iter()
+            .map(CommandSpec::info)
+            .collect::<Vec<RespFrame>>();
+        RespArray::new([
+            BulkString::from(self.name).into(),
+            RespFrame::Integer(self.arity),
+            RespArray::new(flags).into(),
+            RespFrame::Integer(self.first_key),
+            RespFrame::Integer(self.last_key),
+            RespFrame::Integer(self.step),
+            RespArray::new(categories).into(),
+            // no command tips nor key specs
+            RespArray::new([]).into(),
+            RespArray::new([]).into(),
+            RespArray::new(subcommands).into(),
+        ])
+        .into()
+    }
+
+    // the listed categories, plus the ones implied by the flags
+    fn acl_categories(&self) -> Vec<&'static str> {
+        let mut categories = Vec::new();
+        if self.flags & CMD_WRITE != 0 {
+            categories.push("@write");
+        }
+        if self.flags & CMD_READONLY != 0 {
+            categories.push("@read");
+        }
+        categories.extend(self.categories);
+        if self.flags & CMD_PUBSUB != 0 {
+            categories.push("@pubsub");
+        }
+        if self.flags & CMD_BLOCKING != 0 {
+            categories.push("@blocking");
+        }
+        if self.flags & CMD_ADMIN != 0 {
+            categories.extend(["@admin", "@dangerous"]);
+        }
+        categories.push(if self.flags & CMD_FAST != 0 {
+            "@fast"
+        } else {
+            "@slow"
+        });
+        let mut seen = Vec::new();
+        categories.retain(|category| {
+            let new = !seen.contains(category);
+            seen.push(category);
+            new
+        });
+        categories
+    }
+}
+
+// migrate host port key|"" db timeout [COPY] [REPLACE] [KEYS key ...]
+fn migrate_keys(args: &[&[u8]]) -> Vec<usize> {
+    let keys = args
+        .iter()
+        .skip(6)
+        .position(|arg| arg.eq_ignore_ascii_case(b"keys"));
+    match keys {
+        Some(i) if args.get(3).is_some_and(|key| key.is_empty()) => (i + 7..args.len()).collect(),
+        _ => vec![3],
+    }
+}
+
+const ADMIN: u32 = CMD_ADMIN | CMD_NOSCRIPT | CMD_LOADING | CMD_STALE;
+const PUBSUB: u32 = CMD_PUBSUB | CMD_NOSCRIPT | CMD_LOADING | CMD_STALE;
+
+static CLIENT: &[CommandSpec] = &[
+    spec(
+        "client|id",
+        2,
+        CMD_NOSCRIPT | CMD_LOADING | CMD_STALE,
+        &["@connection"],
+    )
+    .parser(parse::<ClientId>),
+    spec(
+        "client|tracking",
+        -3,
+        CMD_NOSCRIPT | CMD_LOADING | CMD_STALE,
+        &["@connection"],
+    )
+    .parser(parse::<ClientTracking>),
+];
+
+static CONFIG: &[CommandSpec] = &[
+    spec("config|get", 3, ADMIN, &[]).parser(parse::<ConfigGet>),
+    spec("config|set", 4, ADMIN, &[]).parser(parse::<ConfigSet>),
+];
+
+static SLOWLOG: &[CommandSpec] = &[
+    spec("slowlog|get", -2, ADMIN, &[]).parser(parse::<SlowLogGet>),
+    spec("slowlog|len", 2, ADMIN, &[]).parser(parse::<SlowLogLen>),
+    spec("slowlog|reset", 2, ADMIN, &[]).parser(parse::<SlowLogReset>),
+];
+
+static LATENCY: &[CommandSpec] = &[
+    spec("latency|latest", 2, ADMIN, &[]).parser(parse::<LatencyLatest>),
+    spec("latency|history", 3, ADMIN, &[]).parser(parse::<LatencyHistory>),
+    spec("latency|histogram", -2, ADMIN, &[]).parser(parse::<LatencyHistogram>),
+    spec("latency|reset", -2, ADMIN, &[]).parser(parse::<LatencyReset>),
+];
+
+static CLUSTER: &[CommandSpec] = &[
+    spec("cluster|keyslot", 3, CMD_STALE, &[]).parser(parse::<ClusterKeySlot>),
+    spec("cluster|myid", 2, CMD_STALE, &[]).parser(parse::<ClusterMyId>),
+    spec("cluster|nodes", 2, CMD_STALE, &[]).parser(parse::<ClusterNodes>),
+    spec("cluster|info", 2, CMD_STALE, &[]).parser(parse::<ClusterInfo>),
+    spec("cluster|slots", 2, CMD_LOADING | CMD_STALE, &[]).parser(parse::<ClusterSlots>),
+    spec("cluster|shards", 2, CMD_LOADING | CMD_STALE, &[]).parser(parse::<ClusterShards>),
+    spec("cluster|meet", 4, ADMIN, &[]).parser(parse::<ClusterMeet>),
+    spec("cluster|addslots", -3, ADMIN, &[]).parser(parse::<ClusterAddSlots>),
+    spec("cluster|addslotsrange", -4, ADMIN, &[]).parser(parse::<ClusterAddSlots>),
+    spec("cluster|delslots", -3, ADMIN, &[]).parser(parse::<ClusterDelSlots>),
+    spec("cluster|delslotsrange", -4, ADMIN, &[]).parser(parse::<ClusterDelSlots>),
+    spec("cluster|setslot", -4, ADMIN, &[]).parser(parse::<ClusterSetSlot>),
+    spec("cluster|countkeysinslot", 3, CMD_STALE, &[]).parser(parse::<ClusterCountKeysInSlot>),
+    spec("cluster|getkeysinslot", 4, CMD_STALE, &[]).parser(parse::<ClusterGetKeysInSlot>),
+];
+
+static COMMAND: &[CommandSpec] = &[
+    spec(
+        "command|count",
+        2,
+        CMD_LOADING | CMD_STALE,
+        &["@connection"],
+    )
+    .parser(parse::<CommandCount>),
+    spec(
+        "command|info",
+        -2,
+        CMD_LOADING | CMD_STALE,
+        &["@connection"],
+    )
+    .parser(parse::<CommandInfo>),
+    spec(
+        "command|getkeys",
+        -3,
+        CMD_LOADING | CMD_STALE,
+        &["@connection"],
+    )
+    .parser(parse::<CommandGetKeys>),
+];
+
+static OBJECT: &[CommandSpec] = &[
+    spec("object|encoding", 3, CMD_READONLY, &["@keyspace"])
+        .keys(2, 2, 1)
+        .parser(parse::<ObjectEncoding>),
+    spec("object|freq", 3, CMD_READONLY, &["@keyspace"])
+        .keys(2, 2, 1)
+        .parser(parse::<ObjectFreq>),
+    spec("object|idletime", 3, CMD_READONLY, &["@keyspace"])
+        .keys(2, 2, 1)
+        .parser(parse::<ObjectIdleTime>),
+    spec("object|refcount", 3, CMD_READONLY, &["@keyspace"])
+        .keys(2, 2, 1)
+        .parser(parse::<ObjectRefCount>),
+];
+
+static MEMORY: &[CommandSpec] = &[
+    spec("memory|usage", -3, CMD_READONLY, &[])
+        .keys(2, 2, 1)
+        .parser(parse::<MemoryUsage>),
+    spec("memory|stats", 2, 0, &[]).parser(parse::<MemoryStats>),
+];
+
+static COMMANDS: &[CommandSpec] = &[
+    spec("echo", 2, CMD_FAST, &["@connection"]).parser(parse::<Echo>),
+    spec("get", 2, CMD_READONLY | CMD_FAST, &["@string"])
+        .keys(1, 1, 1)
+        .parser(parse::<Get>),
+    spec("set", 3, CMD_WRITE | CMD_DENYOOM, &["@string"])
+        .keys(1, 1, 1)
+        .parser(parse::<Set>),
+    spec("hget", 3, CMD_READONLY | CMD_FAST, &["@hash"])
+        .keys(1, 1, 1)
+        .parser(parse::<HGet>),
+    spec("hset", 4, CMD_WRITE | CMD_DENYOOM | CMD_FAST, &["@hash"])
+        .keys(1, 1, 1)
+        .parser(parse::<HSet>),
+    spec("hgetall", 2, CMD_READONLY, &["@hash"])
+        .keys(1, 1, 1)
+        .parser(parse::<HGetAll>),
+    spec("del", -2, CMD_WRITE, &["@keyspace"])
+        .keys(1, -1, 1)
+        .parser(parse::<Del>),
+    spec(
+        "select",
+        2,
+        CMD_LOADING | CMD_STALE | CMD_FAST,
+        &["@connection"],
+    )
+    .parser(parse::<Select>),
+    spec("move", 3, CMD_WRITE | CMD_FAST, &["@keyspace"])
+        .keys(1, 1, 1)
+        .parser(parse::<Move>),
+    spec(
+        "swapdb",
+        3,
+        CMD_WRITE | CMD_FAST,
+        &["@keyspace", "@dangerous"],
+    )
+    .parser(parse::<SwapDb>),
+    spec("flushdb", -1, CMD_WRITE, &["@keyspace", "@dangerous"]).parser(parse::<FlushDb>),
+    spec("flushall", -1, CMD_WRITE, &["@keyspace", "@dangerous"]).parser(parse::<FlushAll>),
+    spec("subscribe", -2, PUBSUB, &[]).parser(parse::<Subscribe>),
+    spec("unsubscribe", -1, PUBSUB, &[]).parser(parse::<Unsubscribe>),
+    spec("psubscribe", -2, PUBSUB, &[]).parser(parse::<PSubscribe>),
+    spec("punsubscribe", -1, PUBSUB, &[]).parser(parse::<PUnsubscribe>),
+    spec(
+        "publish",
+        3,
+        CMD_PUBSUB | CMD_LOADING | CMD_STALE | CMD_FAST,
+        &[],
+    )
+    .parser(parse::<Publish>),
+    spec("client", -2, 0, &[]).subcommands(CLIENT),
+    spec("config", -2, 0, &[]).subcommands(CONFIG),
+    spec("monitor", 1, ADMIN, &[]).parser(parse::<Monitor>),
+    spec("shutdown", -1, ADMIN, &[]).parser(parse::<Shutdown>),
+    spec("slowlog", -2, 0, &[]).subcommands(SLOWLOG),
+    spec("latency", -2, 0, &[]).subcommands(LATENCY),
+    spec("cluster", -2, 0, &[]).subcommands(CLUSTER),
+    spec("asking", 1, CMD_FAST, &["@connection"]).parser(parse::<Asking>),
+    spec("setbit", 4, CMD_WRITE | CMD_DENYOOM, &["@bitmap"])
+        .keys(1, 1, 1)
+        .parser(parse::<SetBit>),
+    spec("getbit", 3, CMD_READONLY | CMD_FAST, &["@bitmap"])
+        .keys(1, 1, 1)
+        .parser(parse::<GetBit>),
+    spec("bitcount", -2, CMD_READONLY, &["@bitmap"])
+        .keys(1, 1, 1)
+        .parser(parse::<BitCount>),
+    spec("bitpos", -3, CMD_READONLY, &["@bitmap"])
+        .keys(1, 1, 1)
+        .parser(parse::<BitPos>),
+    spec("bitop", -4, CMD_WRITE | CMD_DENYOOM, &["@bitmap"])
+        .keys(2, -1, 1)
+        .parser(parse::<BitOp>),
+    spec("bitfield", -2, CMD_WRITE | CMD_DENYOOM, &["@bitmap"])
+        .keys(1, 1, 1)
+        .parser(parse::<BitField>),
+    spec(
+        "pfadd",
+        -2,
+        CMD_WRITE | CMD_DENYOOM | CMD_FAST,
+        &["@hyperloglog"],
+    )
+    .keys(1, 1, 1)
+    .parser(parse::<PfAdd>),
+    spec("pfcount", -2, CMD_READONLY, &["@hyperloglog"])
+        .keys(1, -1, 1)
+        .parser(parse::<PfCount>),
+    spec("pfmerge", -2, CMD_WRITE | CMD_DENYOOM, &["@hyperloglog"])
+        .keys(1, -1, 1)
+        .parser(parse::<PfMerge>),
+    spec("geoadd", -5, CMD_WRITE | CMD_DENYOOM, &["@geo"])
+        .keys(1, 1, 1)
+        .parser(parse::<GeoAdd>),
+    spec("geopos", -2, CMD_READONLY, &["@geo"])
+        .keys(1, 1, 1)
+        .parser(parse::<GeoPos>),
+    spec("geodist", -4, CMD_READONLY, &["@geo"])
+        .keys(1, 1, 1)
+        .parser(parse::<GeoDist>),
+    spec("geosearch", -7, CMD_READONLY, &["@geo"])
+        .keys(1, 1, 1)
+        .parser(parse::<GeoSearch>),
+    spec("dump", 2, CMD_READONLY, &["@keyspace"])
+        .keys(1, 1, 1)
+        .parser(parse::<Dump>),
+    spec(
+        "restore",
+        -4,
+        CMD_WRITE | CMD_DENYOOM,
+        &["@keyspace", "@dangerous"],
+    )
+    .keys(1, 1, 1)
+    .parser(parse::<Restore>),
+    spec("migrate", -6, CMD_WRITE, &["@keyspace", "@dangerous"])
+        .keys(3, 3, 1)
+        .movable_keys(migrate_keys)
+        .parser(parse::<Migrate>),
+    spec("object", -2, 0, &[]).subcommands(OBJECT),
+    spec("memory", -2, 0, &[]).subcommands(MEMORY),
+    spec("command", -1, CMD_LOADING | CMD_STALE, &["@connection"])
+        .subcommands(COMMAND)
+        .parser(parse::<CommandList>),
+];
+
+lazy_static! {
+    static ref TABLE: HashMap<&'static str, &'static CommandSpec> =
+        COMMANDS.iter().map(|spec| (spec.name, spec)).collect();
+}
+
+pub fn commands() -> &'static [CommandSpec] {
+    COMMANDS
+}
+
+// by name, "container|sub" for a subcommand
+pub fn find(name: &str) -> Option<&'static CommandSpec> {
+    let name = name.to_ascii_lowercase();
+    match name.split_once('|') {
+        Some((container, sub)) => TABLE.get(container)?.subcommand(sub.as_bytes()),
+        None => TABLE.get(name.as_str()).copied(),
+    }
+}
+
+// the spec of the command invoked by `args`, subcommand resolved and arity checked
+pub fn lookup(args: &[&[u8]]) -> Result<&'static CommandSpec, CommandError> {
+    let Some(name) = args.first() else {
+        return Err(CommandError::InvalidCommand(
+            "Command must have a BulkString as the first argument".to_string(),
+        ));
+    };
+    let lossy = |arg: &[u8]| String::from_utf8_lossy(arg).to_string();
+    let Some(spec) = TABLE.get(lossy(name).to_ascii_lowercase().as_str()) else {
+        let preview = args[1..]
+            .iter()
+            .map(|arg| format!("'{}' ", lossy(arg)))
+            .collect();
+        return Err(CommandError::UnknownCommand(lossy(name), preview));
+    };
+    let spec = match args.get(1) {
+        Some(sub) if !spec.subcommands.is_empty() => spec.subcommand(sub).ok_or_else(|| {
+            CommandError::UnknownSubcommand(lossy(sub), spec.name.to_ascii_uppercase())
+        })?,
+        _ => spec,
+    };
+    spec.check_arity(args.len())?;
+    Ok(spec)
+}
+
+// the arguments of a command as bytes, anything but a bulk string counts as empty
+pub fn arg_bytes(value: &RespArray) -> Vec<&[u8]> {
+    value
+        .iter()
+        .map(|arg| match arg {
+            RespFrame::BulkString(arg) => arg.as_ref(),
+            _ => &[][..],
+        })
+        .collect()
+}
+
+pub fn parse_command(value: RespArray) -> Result<Command, CommandError> {
+    let spec = lookup(&arg_bytes(&value))?;
+    match spec.parse {
+        Some(parse) => parse(value),
+        None => Err(CommandError::WrongArity(spec.name.to_string())),
+    }
+}
+
+// the keys of a command, used to route it in cluster and sharded mode
+pub fn command_keys(args: &[Vec<u8>]) -> Vec<&[u8]> {
+    let args = args.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    match lookup(&args) {
+        Ok(spec) => spec
+            .key_positions(&args)
+            .into_iter()
+            .filter_map(|i| args.get(i).copied())
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+pub(super) fn check_arity(names: &[&str], len: usize) -> Result<(), CommandError> {
+    match find(&names.join("|")) {
+        Some(spec) => spec.check_arity(len),
+        None => Err(CommandError::InvalidCommand(format!(
+            "unknown command {}",
+            names.join(" ")
+        ))),
+    }
+}
+
+// nothing to report for an unknown command
+pub fn command_info(name: &str) -> RespFrame {
+    match find(name) {
+        Some(spec) => spec.info(),
+        None => RespFrame::Null(RespNull),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup_args(args: &[&str]) -> Result<&'static str, String> {
+        let args = args.iter().map(|arg| arg.as_bytes()).collect::<Vec<_>>();
+        lookup(&args)
+            .map(CommandSpec::name)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup_args(&["GeT", "k"]), Ok("get"));
+        assert_eq!(lookup_args(&["CLIENT", "Id"]), Ok("client|id"));
+        assert_eq!(lookup_args(&["command"]), Ok("command"));
+        assert_eq!(
+            lookup_args(&["get"]),
+            Err("wrong number of arguments for 'get' command".to_string())
+        );
+        assert_eq!(
+            lookup_args(&["client"]),
+            Err("wrong number of arguments for 'client' command".to_string())
+        );
+        assert_eq!(
+            lookup_args(&["foo", "a", "b"]),
+            Err("unknown command 'foo', with args beginning with: 'a' 'b' ".to_string())
+        );
+        assert_eq!(
+            lookup_args(&["client", "foo"]),
+            Err("unknown subcommand 'foo'. Try CLIENT HELP.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_key_positions() {
+        let keys = |args: &[&str]| {
+            let args = args
+                .iter()
+                .map(|arg| arg.as_bytes().to_vec())
+                .collect::<Vec<_>>();
+            command_keys(&args)
+                .into_iter()
+                .map(|key| String::from_utf8_lossy(key).to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&["get", "a"]), ["a"]);
+        assert_eq!(keys(&["DEL", "a", "b"]), ["a", "b"]);
+        assert_eq!(keys(&["bitop", "and", "d", "a", "b"]), ["d", "a", "b"]);
+        assert_eq!(keys(&["migrate", "h", "1", "k", "0", "0"]), ["k"]);
+        assert_eq!(
+            keys(&["migrate", "h", "1", "", "0", "0", "COPY", "KEYS", "a", "b"]),
+            ["a", "b"]
+        );
+        assert!(keys(&["echo", "a"]).is_empty());
+        assert!(keys(&["get"]).is_empty());
+    }
+
+    #[test]
+    fn test_table_is_consistent() {
+        for spec in COMMANDS
+            .iter()
+            .chain(COMMANDS.iter().flat_map(|s| s.subcommands))
+        {
+            assert!(spec.arity != 0, "{}", spec.name);
+            assert!(
+                spec.parse.is_some() || !spec.subcommands.is_empty(),
+                "{}",
+                spec.name
+            );
+            assert!(
+                spec.flags & (CMD_WRITE | CMD_READONLY) != CMD_WRITE | CMD_READONLY,
+                "{}",
+                spec.name
+            );
+        }
+    }
+}
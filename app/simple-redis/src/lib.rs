@@ -1,10 +1,17 @@
 mod backend;
 mod resp;
 mod respv2;
+mod utils;
 
+pub mod client;
+pub mod cluster;
 pub mod cmd;
+pub mod metrics;
 pub mod network;
+pub mod shard;
+pub mod tls;
 
 pub use backend::*;
 pub use resp::*;
 pub use respv2::*;
+pub use utils::{glob_match, quote_bytes, split_args};
@@ -1,30 +1,327 @@
-use anyhow::Result;
-use simple_redis::{network, Backend};
-use tokio::net::TcpListener;
+use anyhow::{Context, Result};
+use clap::Parser;
+use simple_redis::{cluster, metrics, network, shard::Shards, tls::tls_acceptor, Backend};
+use std::{
+    fmt::Display,
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, UnixListener},
+    signal::unix::{signal, SignalKind},
+    task::JoinSet,
+};
+use tokio_rustls::TlsAcceptor;
+use tokio_util::task::TaskTracker;
 use tracing::{info, warn};
 
+const CLUSTER_GOSSIP_PERIOD: Duration = Duration::from_secs(1);
+// clients that don't complete the TLS handshake in time are dropped
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Parser)]
+#[command(name = "simple-redis", version, about)]
+struct Opts {
+    #[arg(long, default_value = "0.0.0.0", help = "Address to listen on")]
+    bind: String,
+    #[arg(long, default_value_t = 6379, help = "Plain TCP port, 0 disables it")]
+    port: u16,
+    #[arg(
+        long,
+        requires_all = ["tls_cert_file", "tls_key_file"],
+        help = "TLS port, disabled unless set"
+    )]
+    tls_port: Option<u16>,
+    #[arg(long, help = "Server certificate (PEM)")]
+    tls_cert_file: Option<PathBuf>,
+    #[arg(long, help = "Server private key (PEM)")]
+    tls_key_file: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "CA certificate (PEM), when set TLS clients must present a certificate signed by it"
+    )]
+    tls_ca_cert_file: Option<PathBuf>,
+    #[arg(long, help = "Unix socket path, disabled unless set")]
+    unixsocket: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 10000,
+        help = "Max number of connected clients"
+    )]
+    maxclients: usize,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Close a connection after it is idle for N seconds, 0 disables it"
+    )]
+    timeout: u64,
+    #[arg(
+        long,
+        help = "Snapshot file, loaded on startup and saved on shutdown; no persistence unless set"
+    )]
+    dbfilename: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Serve prometheus metrics over http on this port, disabled unless set"
+    )]
+    metrics_port: Option<u16>,
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "tls_port", "unixsocket", "maxclients", "timeout", "dbfilename",
+            "metrics_port", "cluster_enabled",
+        ],
+        help = "Experimental: partition the keyspace into N single threaded shards, \
+                only data commands and transactions on the plain TCP port are supported"
+    )]
+    shards: Option<usize>,
+    #[arg(long, help = "Run as a node of a redis cluster, on the plain TCP port")]
+    cluster_enabled: bool,
+    #[arg(
+        long,
+        help = "IP address advertised to clients and other nodes, defaults to --bind \
+                or 127.0.0.1 when bound to all interfaces"
+    )]
+    cluster_announce_ip: Option<String>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
-
-    let addr = "0.0.0.0:6379";
-    info!("Simple-Redis-Server is listening on {}", addr);
-    let listener = TcpListener::bind(addr).await?;
+    let opts = Opts::parse();
+    if let Some(n) = opts.shards {
+        return run_sharded(&opts, n).await;
+    }
 
     let backend = Backend::new();
+    backend
+        .config_set("maxclients", &opts.maxclients.to_string())
+        .map_err(anyhow::Error::msg)?;
+    backend
+        .config_set("timeout", &opts.timeout.to_string())
+        .map_err(anyhow::Error::msg)?;
+    if let Some(path) = &opts.dbfilename {
+        backend
+            .config_set("dbfilename", &path.to_string_lossy())
+            .map_err(anyhow::Error::msg)?;
+        if path.exists() {
+            let n = backend.load_from(path)?;
+            info!("Loaded {} commands from {}", n, path.display());
+        }
+    }
+
+    if opts.cluster_enabled {
+        if opts.port == 0 {
+            anyhow::bail!("cluster mode needs the plain TCP port");
+        }
+        let ip = opts
+            .cluster_announce_ip
+            .clone()
+            .unwrap_or_else(|| match opts.bind.as_str() {
+                "0.0.0.0" | "::" => "127.0.0.1".to_string(),
+                bind => bind.to_string(),
+            });
+        backend.enable_cluster(ip, opts.port);
+        info!(
+            "Cluster mode enabled, node id {}",
+            backend
+                .cluster()
+                .map(|c| c.myself().id.as_str())
+                .unwrap_or_default()
+        );
+        tokio::spawn(cluster::gossip(backend.clone(), CLUSTER_GOSSIP_PERIOD));
+    }
+
+    let connections = TaskTracker::new();
+    let mut listeners = JoinSet::new();
+
+    if opts.port != 0 {
+        let addr = format!("{}:{}", opts.bind, opts.port);
+        info!("Simple-Redis-Server is listening on {}", addr);
+        let listener = TcpListener::bind(addr).await?;
+        listeners.spawn(serve_tcp(
+            listener,
+            None,
+            backend.clone(),
+            connections.clone(),
+        ));
+    }
+
+    if let (Some(port), Some(cert), Some(key)) =
+        (opts.tls_port, &opts.tls_cert_file, &opts.tls_key_file)
+    {
+        let acceptor = tls_acceptor(cert, key, opts.tls_ca_cert_file.as_ref())?;
+        let addr = format!("{}:{}", opts.bind, port);
+        info!("Simple-Redis-Server is listening on {} (tls)", addr);
+        let listener = TcpListener::bind(addr).await?;
+        listeners.spawn(serve_tcp(
+            listener,
+            Some(acceptor),
+            backend.clone(),
+            connections.clone(),
+        ));
+    }
+
+    if let Some(path) = opts.unixsocket {
+        remove_stale_socket(&path)?;
+        info!("Simple-Redis-Server is listening on {}", path.display());
+        let listener = UnixListener::bind(&path)?;
+        listeners.spawn(serve_unix(
+            listener,
+            path,
+            backend.clone(),
+            connections.clone(),
+        ));
+    }
+
+    if let Some(port) = opts.metrics_port {
+        let listener = TcpListener::bind(format!("{}:{}", opts.bind, port)).await?;
+        // not part of `listeners`, it serves until the process exits
+        tokio::spawn(metrics::serve(listener, backend.clone()));
+    }
+
+    if listeners.is_empty() {
+        anyhow::bail!("no listener configured");
+    }
+    tokio::spawn(shutdown_on_signal(backend.clone()));
+
+    // listeners return once shutdown starts, or when accepting fails
+    while let Some(ret) = listeners.join_next().await {
+        ret??;
+    }
+    info!(
+        "Shutting down, waiting for {} connections",
+        connections.len()
+    );
+    connections.close();
+    connections.wait().await;
+
+    // a failed save exits with an error, the dataset is lost otherwise
+    if backend.save_on_shutdown() {
+        backend
+            .save()
+            .context("failed to save the dataset before exiting")?;
+        info!("Saved the dataset before exiting");
+    }
+    Ok(())
+}
+
+// the other listeners and server wide options are rejected when parsing the arguments
+async fn run_sharded(opts: &Opts, n: usize) -> Result<()> {
+    if opts.port == 0 {
+        anyhow::bail!("sharded mode needs the plain TCP port");
+    }
+    let shards = Shards::new(n);
+    let addr = format!("{}:{}", opts.bind, opts.port);
+    info!(
+        "Simple-Redis-Server is listening on {} with {} shards",
+        addr,
+        shards.len()
+    );
+    let listener = TcpListener::bind(addr).await?;
+    let mut sigterm = signal(SignalKind::terminate())?;
     loop {
-        let (stream, raddr) = listener.accept().await?;
-        info!("Accepted connection from: {}", raddr);
-        let cloned_backend = backend.clone();
+        let (stream, raddr) = tokio::select! {
+            ret = listener.accept() => ret?,
+            _ = sigterm.recv() => return Ok(()),
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        };
+        stream.set_nodelay(true)?;
+        let shards = shards.clone();
         tokio::spawn(async move {
-            match network::stream_handler(stream, cloned_backend).await {
-                Ok(_) => {
-                    info!("Connection from {} exited", raddr);
-                }
-                Err(e) => {
-                    warn!("handle error for {}: {:?}", raddr, e);
-                }
+            if let Err(e) = network::sharded_stream_handler(stream, shards).await {
+                warn!("handle error for {}: {:?}", raddr, e);
             }
         });
     }
 }
+
+async fn shutdown_on_signal(backend: Backend) -> Result<()> {
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let shutdown = backend.shutdown_token();
+    tokio::select! {
+        _ = sigterm.recv() => info!("Received SIGTERM"),
+        _ = tokio::signal::ctrl_c() => info!("Received SIGINT"),
+        // SHUTDOWN command
+        _ = shutdown.cancelled() => return Ok(()),
+    }
+    backend.shutdown(None).map_err(anyhow::Error::msg)
+}
+
+async fn serve_tcp(
+    listener: TcpListener,
+    acceptor: Option<TlsAcceptor>,
+    backend: Backend,
+    connections: TaskTracker,
+) -> Result<()> {
+    let shutdown = backend.shutdown_token();
+    loop {
+        let (stream, raddr) = tokio::select! {
+            ret = listener.accept() => ret?,
+            _ = shutdown.cancelled() => return Ok(()),
+        };
+        info!("Accepted connection from: {}", raddr);
+        let backend = backend.clone();
+        match acceptor.clone() {
+            Some(acceptor) => {
+                connections.spawn(async move {
+                    match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await
+                    {
+                        Ok(Ok(stream)) => handle(stream, backend, raddr).await,
+                        Ok(Err(e)) => warn!("tls handshake error for {}: {:?}", raddr, e),
+                        Err(_) => warn!("tls handshake timeout for {}", raddr),
+                    }
+                });
+            }
+            None => {
+                connections.spawn(handle(stream, backend, raddr));
+            }
+        }
+    }
+}
+
+// a socket file from a previous run would make bind fail; any other file at the path is
+// left alone, bind then reports it
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => Ok(std::fs::remove_file(path)?),
+        _ => Ok(()),
+    }
+}
+
+async fn serve_unix(
+    listener: UnixListener,
+    path: PathBuf,
+    backend: Backend,
+    connections: TaskTracker,
+) -> Result<()> {
+    let shutdown = backend.shutdown_token();
+    loop {
+        let (stream, _) = tokio::select! {
+            ret = listener.accept() => ret?,
+            _ = shutdown.cancelled() => return Ok(()),
+        };
+        info!("Accepted connection on unix socket");
+        connections.spawn(handle(
+            stream,
+            backend.clone(),
+            format!("{}:0", path.display()),
+        ));
+    }
+}
+
+async fn handle<S>(stream: S, backend: Backend, raddr: impl Display)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    match network::stream_handler_with_addr(stream, backend, raddr.to_string()).await {
+        Ok(_) => {
+            info!("Connection from {} exited", raddr);
+        }
+        Err(e) => {
+            warn!("handle error for {}: {:?}", raddr, e);
+        }
+    }
+}
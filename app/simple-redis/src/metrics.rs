@@ -0,0 +1,215 @@
+use crate::Backend;
+use anyhow::Result;
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+use std::fmt::Write;
+use tokio::net::TcpListener;
+use tracing::info;
+
+// histogram buckets exposed for command latencies, powers of 4 in microseconds (1us .. ~1s)
+const LATENCY_BUCKETS: [u32; 11] = [0, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20];
+
+// serve the metrics in prometheus text format on /metrics
+pub async fn serve(listener: TcpListener, backend: Backend) -> Result<()> {
+    info!("Serving metrics on {}", listener.local_addr()?);
+    let router = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .with_state(backend);
+    axum::serve(listener, router).await?;
+    Ok(())
+}
+
+async fn metrics_handler(State(backend): State<Backend>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render(&backend),
+    )
+}
+
+pub fn render(backend: &Backend) -> String {
+    let mut out = String::new();
+    let stats = backend.stats();
+
+    metric(
+        &mut out,
+        "connected_clients",
+        "gauge",
+        "Number of client connections",
+    );
+    sample(
+        &mut out,
+        "connected_clients",
+        "",
+        backend.connected_clients(),
+    );
+    metric(
+        &mut out,
+        "connections_received_total",
+        "counter",
+        "Connections accepted by the server",
+    );
+    sample(
+        &mut out,
+        "connections_received_total",
+        "",
+        stats.connections_received(),
+    );
+
+    metric(
+        &mut out,
+        "commands_total",
+        "counter",
+        "Commands processed, by command",
+    );
+    metric(
+        &mut out,
+        "command_duration_seconds",
+        "histogram",
+        "Command execution time, by command",
+    );
+    let histograms = backend.latency().histograms(&[]);
+    for (name, histogram) in &histograms {
+        let cmd = format!("cmd=\"{}\"", escape(name));
+        sample(&mut out, "commands_total", &cmd, histogram.calls);
+    }
+    for (name, histogram) in &histograms {
+        let cmd = format!("cmd=\"{}\"", escape(name));
+        for bucket in LATENCY_BUCKETS {
+            let le = (1u64 << bucket) as f64 / 1e6;
+            let labels = format!("{},le=\"{}\"", cmd, le);
+            sample(
+                &mut out,
+                "command_duration_seconds_bucket",
+                &labels,
+                histogram.count_le(1 << bucket),
+            );
+        }
+        let labels = format!("{},le=\"+Inf\"", cmd);
+        sample(
+            &mut out,
+            "command_duration_seconds_bucket",
+            &labels,
+            histogram.calls,
+        );
+        sample(
+            &mut out,
+            "command_duration_seconds_sum",
+            &cmd,
+            histogram.sum as f64 / 1e6,
+        );
+        sample(
+            &mut out,
+            "command_duration_seconds_count",
+            &cmd,
+            histogram.calls,
+        );
+    }
+
+    metric(&mut out, "db_keys", "gauge", "Number of keys, by db");
+    for (db, keys) in backend.keyspace() {
+        sample(&mut out, "db_keys", &format!("db=\"db{}\"", db), keys);
+    }
+    metric(
+        &mut out,
+        "memory_used_bytes",
+        "gauge",
+        "Estimated memory used by the dataset",
+    );
+    sample(&mut out, "memory_used_bytes", "", backend.used_memory());
+
+    metric(
+        &mut out,
+        "persistence_enabled",
+        "gauge",
+        "Whether a dbfilename is configured",
+    );
+    sample(
+        &mut out,
+        "persistence_enabled",
+        "",
+        backend.persistence_enabled() as u8,
+    );
+    metric(
+        &mut out,
+        "changes_since_last_save",
+        "gauge",
+        "Writes since the last successful save",
+    );
+    sample(
+        &mut out,
+        "changes_since_last_save",
+        "",
+        stats.changes_since_last_save(),
+    );
+    metric(
+        &mut out,
+        "last_save_timestamp_seconds",
+        "gauge",
+        "Time of the last save, 0 if never saved",
+    );
+    sample(
+        &mut out,
+        "last_save_timestamp_seconds",
+        "",
+        stats.last_save(),
+    );
+    metric(
+        &mut out,
+        "last_save_status",
+        "gauge",
+        "Whether the last save succeeded",
+    );
+    sample(&mut out, "last_save_status", "", stats.last_save_ok() as u8);
+    out
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP simple_redis_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE simple_redis_{} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: impl std::fmt::Display) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "simple_redis_{} {}", name, value);
+    } else {
+        let _ = writeln!(out, "simple_redis_{}{{{}}} {}", name, labels, value);
+    }
+}
+
+// label values escape backslash, double quote and line feed
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespFrame;
+    use std::time::Duration;
+
+    #[test]
+    fn test_render() {
+        let backend = Backend::new();
+        backend.set("a".to_string(), RespFrame::Integer(1));
+        backend.record_command(&[b"get".to_vec(), b"a".to_vec()], Duration::from_micros(3));
+
+        let out = render(&backend);
+        assert!(out.contains("# TYPE simple_redis_commands_total counter\n"));
+        assert!(out.contains("simple_redis_commands_total{cmd=\"get\"} 1\n"));
+        assert!(out.contains(
+            "simple_redis_command_duration_seconds_bucket{cmd=\"get\",le=\"0.000001\"} 0\n"
+        ));
+        assert!(out.contains(
+            "simple_redis_command_duration_seconds_bucket{cmd=\"get\",le=\"0.000004\"} 1\n"
+        ));
+        assert!(out.contains("simple_redis_command_duration_seconds_sum{cmd=\"get\"} 0.000003\n"));
+        assert!(out.contains("simple_redis_db_keys{db=\"db0\"} 1\n"));
+        assert!(out.contains("simple_redis_persistence_enabled 0\n"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a\"b\\c\n"), "a\\\"b\\\\c\\n");
+    }
+}
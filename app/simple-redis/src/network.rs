@@ -1,16 +1,22 @@
 use crate::{
     cmd::{Command, CommandExecutor},
-    Backend, RespDecodeV2, RespEncode, RespError, RespFrame,
+    shard::Shards,
+    split_args, Backend, BulkString, PushReceiver, RespArray, RespDecodeV2, RespEncode, RespError,
+    RespFrame, SimpleError,
 };
-use anyhow::Result;
+use anyhow::{bail, Result};
 use futures::SinkExt;
-use tokio::net::TcpStream;
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_stream::StreamExt;
 use tokio_util::codec::{Decoder, Encoder, Framed};
-use tracing::info;
+use tracing::{info, warn};
 
-#[derive(Debug)]
-struct RespFrameCodec;
+// an inline command line longer than this, without its newline, is a protocol error
+const MAX_INLINE_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Default)]
+pub struct RespFrameCodec;
 
 #[derive(Debug)]
 struct RedisRequest {
@@ -21,35 +27,209 @@ struct RedisRequest {
 #[derive(Debug)]
 struct RedisResponse {
     frame: RespFrame,
+    // a successful SHUTDOWN closes the connection without a reply, as redis does
+    close: bool,
+}
+
+// serves a single connection: plain tcp, tls or unix socket
+pub async fn stream_handler<S>(stream: S, backend: Backend) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream_handler_with_addr(stream, backend, String::new()).await
+}
+
+// same as stream_handler, `addr` identifies the client in SLOWLOG and MONITOR output
+pub async fn stream_handler_with_addr<S>(stream: S, backend: Backend, addr: String) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    if !backend.connect_client() {
+        let mut framed = Framed::new(stream, RespFrameCodec);
+        let err = SimpleError::new("ERR max number of clients reached");
+        framed.send(err.into()).await?;
+        return Ok(());
+    }
+    // each connection selects its own db
+    let backend = backend.new_session();
+    backend.set_client_addr(addr);
+    let ret = handle_session(stream, &backend).await;
+    backend.end_session();
+    backend.disconnect_client();
+    ret
 }
 
-pub async fn stream_handler(stream: TcpStream, backend: Backend) -> Result<()> {
+async fn handle_session<S>(stream: S, backend: &Backend) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut pushes = backend
+        .take_push_receiver()
+        .expect("push receiver of a new session");
+    let shutdown = backend.shutdown_token();
+    let overflow = backend.push_overflow_token();
     // how to get a frame from the stream?
     let mut framed = Framed::new(stream, RespFrameCodec);
     loop {
-        match framed.next().await {
-            Some(Ok(frame)) => {
-                info!("Received frame: {:?}", frame);
-                let request = RedisRequest {
-                    frame,
-                    backend: backend.clone(),
-                };
-                let response = request_handler(request).await?;
-                info!("Sending response: {:?}", response.frame);
-                framed.send(response.frame).await?;
+        tokio::select! {
+            // a closing connection stops taking commands even while frames keep arriving
+            biased;
+            // commands are executed one at a time, so nothing is in flight here
+            _ = shutdown.cancelled() => return Ok(()),
+            _ = overflow.cancelled() => {
+                warn!("Closing connection, too many pending push messages");
+                return Ok(());
+            }
+            _ = idle(backend) => {
+                info!("Closing idle connection");
+                return Ok(());
+            }
+            frame = framed.next() => match frame {
+                Some(Ok(frame)) => {
+                    info!("Received frame: {:?}", frame);
+                    let request = RedisRequest {
+                        frame,
+                        backend: backend.clone(),
+                    };
+                    let response = request_handler(request).await?;
+                    if response.close {
+                        return Ok(());
+                    }
+                    // pushes emitted while executing (e.g. subscribe confirmations) go first
+                    while let Ok(push) = pushes.try_recv() {
+                        framed.feed(push).await?;
+                    }
+                    info!("Sending response: {:?}", response.frame);
+                    framed.send(response.frame).await?;
+                    if backend.is_monitor() {
+                        return monitor_session(framed, pushes, backend).await;
+                    }
+                }
+                Some(Err(e)) => return Err(e),
+                None => return Ok(()),
+            },
+            Some(push) = pushes.recv() => {
+                info!("Sending push: {:?}", push);
+                framed.send(push).await?;
+            }
+        }
+    }
+}
+
+// connection loop of the sharded execution mode, see `shard::Shards`
+pub async fn sharded_stream_handler<S>(stream: S, shards: Shards) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut session = shards.connect();
+    let mut framed = Framed::new(stream, RespFrameCodec);
+    while let Some(frame) = framed.next().await {
+        let frame = session.execute(frame?).await;
+        framed.send(frame).await?;
+    }
+    Ok(())
+}
+
+// MONITOR mode: the connection only streams the command feed, anything it sends is
+// ignored except QUIT
+async fn monitor_session<S>(
+    mut framed: Framed<S, RespFrameCodec>,
+    mut pushes: PushReceiver,
+    backend: &Backend,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let shutdown = backend.shutdown_token();
+    let overflow = backend.push_overflow_token();
+    loop {
+        tokio::select! {
+            biased;
+            _ = shutdown.cancelled() => return Ok(()),
+            _ = overflow.cancelled() => {
+                warn!("Closing monitor connection, too many pending lines");
+                return Ok(());
             }
-            Some(Err(e)) => return Err(e),
-            None => return Ok(()),
+            frame = framed.next() => match frame {
+                Some(Ok(frame)) => {
+                    if is_quit(&frame) {
+                        return Ok(());
+                    }
+                }
+                Some(Err(e)) => return Err(e),
+                None => return Ok(()),
+            },
+            Some(line) = pushes.recv() => framed.send(line).await?,
         }
     }
 }
 
+fn is_quit(frame: &RespFrame) -> bool {
+    matches!(frame, RespFrame::Array(args)
+        if matches!(args.first(), Some(RespFrame::BulkString(cmd)) if cmd.eq_ignore_ascii_case(b"quit")))
+}
+
+// resolves once the connection has been idle for longer than the configured timeout;
+// subscribers are only waiting for messages and never time out
+async fn idle(backend: &Backend) {
+    match backend.idle_timeout() {
+        Some(timeout)
+            if backend.subscribed_channels().is_empty()
+                && backend.subscribed_patterns().is_empty() =>
+        {
+            tokio::time::sleep(timeout).await
+        }
+        _ => std::future::pending().await,
+    }
+}
+
 async fn request_handler(request: RedisRequest) -> Result<RedisResponse> {
     let (frame, backend) = (request.frame, request.backend);
-    let cmd = Command::try_from(frame)?;
+    // the command consumes the frame, keep its arguments for the slow log
+    let args = command_args(&frame);
+    // malformed and unknown commands are answered with an error, the connection stays open
+    let cmd = match Command::try_from(frame) {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            return Ok(RedisResponse {
+                frame: e.into(),
+                close: false,
+            })
+        }
+    };
     info!("Executing command: {:?}", cmd);
-    let frame = cmd.execute(&backend);
-    Ok(RedisResponse { frame })
+    backend.feed_monitors(&args);
+    // keys served by another node are redirected without executing the command
+    if let Some(frame) = backend.cluster_redirect(&args) {
+        return Ok(RedisResponse {
+            frame,
+            close: false,
+        });
+    }
+    let shutdown = matches!(cmd, Command::Shutdown(_));
+    let start = Instant::now();
+    let frame = match cmd {
+        // waits for the target instance without blocking the worker
+        Command::Migrate(migrate) => migrate.run(&backend).await,
+        cmd => cmd.execute(&backend),
+    };
+    backend.record_command(&args, start.elapsed());
+    let close = shutdown && !matches!(frame, RespFrame::Error(_));
+    Ok(RedisResponse { frame, close })
+}
+
+fn command_args(frame: &RespFrame) -> Vec<Vec<u8>> {
+    let RespFrame::Array(args) = frame else {
+        return vec![];
+    };
+    args.iter()
+        .map(|arg| match arg {
+            RespFrame::BulkString(s) => s.to_vec(),
+            RespFrame::SimpleString(s) => s.as_bytes().to_vec(),
+            RespFrame::Integer(n) => n.to_string().into_bytes(),
+            frame => frame.clone().encode(),
+        })
+        .collect()
 }
 
 impl Encoder<RespFrame> for RespFrameCodec {
@@ -67,6 +247,19 @@ impl Decoder for RespFrameCodec {
     type Error = anyhow::Error;
 
     fn decode(&mut self, src: &mut bytes::BytesMut) -> Result<Option<RespFrame>> {
+        // an inline command becomes the same array a RESP client would send
+        while src.first().is_some_and(|&c| !is_resp_type(c)) {
+            let Some(args) = decode_inline(src)? else {
+                return Ok(None);
+            };
+            // empty lines are ignored
+            if !args.is_empty() {
+                let args = args.into_iter().map(|arg| BulkString::new(arg).into());
+                return Ok(Some(
+                    RespArray::new(args.collect::<Vec<RespFrame>>()).into(),
+                ));
+            }
+        }
         match RespFrame::decode(src) {
             Ok(frame) => Ok(Some(frame)),
             Err(RespError::NotComplete) => Ok(None),
@@ -74,3 +267,39 @@ impl Decoder for RespFrameCodec {
         }
     }
 }
+
+fn is_resp_type(c: u8) -> bool {
+    matches!(
+        c,
+        b'+' | b'-'
+            | b':'
+            | b'$'
+            | b'*'
+            | b'_'
+            | b'#'
+            | b','
+            | b'('
+            | b'!'
+            | b'='
+            | b'%'
+            | b'|'
+            | b'~'
+            | b'>'
+    )
+}
+
+// inline commands, as typed in telnet or nc: the arguments of a line, quoted the way
+// redis-cli does. None until the line is complete, an empty line has no arguments
+fn decode_inline(src: &mut bytes::BytesMut) -> Result<Option<Vec<Vec<u8>>>> {
+    let Some(end) = src.iter().position(|&c| c == b'\n') else {
+        if src.len() > MAX_INLINE_SIZE {
+            bail!("Protocol error: too big inline request");
+        }
+        return Ok(None);
+    };
+    let line = src.split_to(end + 1);
+    match split_args(&line) {
+        Ok(args) => Ok(Some(args)),
+        Err(_) => bail!("Protocol error: unbalanced quotes in request"),
+    }
+}
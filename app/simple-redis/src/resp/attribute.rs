@@ -0,0 +1,126 @@
+use bytes::{Buf, BytesMut};
+
+use crate::{RespDecode, RespEncode, RespError, RespFrame, SimpleString};
+use std::{
+    collections::BTreeMap,
+    ops::{Deref, DerefMut},
+};
+
+use super::{calc_total_length, parse_length, BUF_CAP, CRLF_LEN};
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct RespAttribute(pub(crate) BTreeMap<String, RespFrame>);
+
+// - attribute: "|<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n>"
+// the attribute carries auxiliary data and is followed by the actual reply, which
+// is decoded as the next frame. Like map, we only support string keys.
+impl RespEncode for RespAttribute {
+    fn encode(self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(BUF_CAP);
+        buf.extend_from_slice(&format!("|{}\r\n", self.len()).into_bytes());
+        for (key, value) in self.0 {
+            buf.extend_from_slice(&SimpleString::new(key).encode());
+            buf.extend_from_slice(&value.encode());
+        }
+        buf
+    }
+}
+
+impl RespDecode for RespAttribute {
+    const PREFIX: &'static str = "|";
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let (end, len) = parse_length(buf, Self::PREFIX)?;
+        let total_len = calc_total_length(buf, end, len, Self::PREFIX)?;
+
+        if buf.len() < total_len {
+            return Err(RespError::NotComplete);
+        }
+
+        buf.advance(end + CRLF_LEN);
+
+        let mut frames = RespAttribute::new();
+        for _ in 0..len {
+            let key = SimpleString::decode(buf)?;
+            let value = RespFrame::decode(buf)?;
+            frames.insert(key.0, value);
+        }
+
+        Ok(frames)
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let (end, len) = parse_length(buf, Self::PREFIX)?;
+        calc_total_length(buf, end, len, Self::PREFIX)
+    }
+}
+
+impl RespAttribute {
+    pub fn new() -> Self {
+        RespAttribute(BTreeMap::new())
+    }
+}
+
+impl Default for RespAttribute {
+    fn default() -> Self {
+        RespAttribute::new()
+    }
+}
+
+impl Deref for RespAttribute {
+    type Target = BTreeMap<String, RespFrame>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for RespAttribute {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<BTreeMap<String, RespFrame>> for RespAttribute {
+    fn from(map: BTreeMap<String, RespFrame>) -> Self {
+        RespAttribute(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespArray;
+    use anyhow::Result;
+
+    #[test]
+    fn test_attribute_encode() {
+        let mut attr = RespAttribute::new();
+        attr.insert(
+            "key-popularity".to_string(),
+            RespArray::new([b"a".into(), 0.1923.into()]).into(),
+        );
+
+        let frame: RespFrame = attr.into();
+        assert_eq!(
+            &frame.encode(),
+            b"|1\r\n+key-popularity\r\n*2\r\n$1\r\na\r\n,+0.1923\r\n"
+        );
+    }
+
+    #[test]
+    fn test_attribute_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"|1\r\n+ttl\r\n:3600\r\n:2039123\r\n");
+
+        let frame = RespFrame::decode(&mut buf)?;
+        let mut attr = RespAttribute::new();
+        attr.insert("ttl".to_string(), 3600.into());
+        assert_eq!(frame, attr.into());
+
+        // the actual reply follows the attribute
+        let frame = RespFrame::decode(&mut buf)?;
+        assert_eq!(frame, 2039123.into());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use std::ops::Deref;
+
+use bytes::BytesMut;
+
+use crate::{RespDecode, RespEncode, RespError};
+
+use super::{extract_simple_frame_data, CRLF_LEN};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub struct RespBigNumber(pub(crate) String);
+
+// - big number: "([+|-]<number>\r\n"
+impl RespEncode for RespBigNumber {
+    fn encode(self) -> Vec<u8> {
+        format!("({}\r\n", self.0).into_bytes()
+    }
+}
+
+impl RespDecode for RespBigNumber {
+    const PREFIX: &'static str = "(";
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        let data = buf.split_to(end + CRLF_LEN);
+        let s = String::from_utf8_lossy(&data[Self::PREFIX.len()..end]);
+        RespBigNumber::try_new(s)
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        Ok(end + CRLF_LEN)
+    }
+}
+
+impl RespBigNumber {
+    /// Create a big number, validating that it is an optionally signed run of digits.
+    pub fn try_new(s: impl Into<String>) -> Result<Self, RespError> {
+        let s = s.into();
+        let digits = s.strip_prefix(['+', '-']).unwrap_or(&s);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(RespError::InvalidFrame(format!(
+                "invalid big number: {}",
+                s
+            )));
+        }
+        Ok(RespBigNumber(s))
+    }
+}
+
+impl Deref for RespBigNumber {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<i128> for RespBigNumber {
+    fn from(n: i128) -> Self {
+        RespBigNumber(n.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespFrame;
+    use anyhow::Result;
+
+    #[test]
+    fn test_big_number_encode() -> Result<()> {
+        let frame: RespFrame =
+            RespBigNumber::try_new("-3492890328409238509324850943850943825024385")?.into();
+        assert_eq!(
+            frame.encode(),
+            b"(-3492890328409238509324850943850943825024385\r\n"
+        );
+
+        let frame: RespFrame = RespBigNumber::from(i128::MAX).into();
+        assert_eq!(
+            frame.encode(),
+            b"(170141183460469231731687303715884105727\r\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_big_number_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"(3492890328409238509324850943850943825024385\r\n");
+
+        let frame = RespBigNumber::decode(&mut buf)?;
+        assert_eq!(
+            frame,
+            RespBigNumber::try_new("3492890328409238509324850943850943825024385")?
+        );
+
+        buf.extend_from_slice(b"(12a\r\n");
+        let ret = RespBigNumber::decode(&mut buf);
+        assert!(matches!(ret.unwrap_err(), RespError::InvalidFrame(_)));
+
+        Ok(())
+    }
+}
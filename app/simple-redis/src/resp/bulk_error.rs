@@ -0,0 +1,87 @@
+use std::ops::Deref;
+
+use bytes::BytesMut;
+
+use crate::{RespDecode, RespEncode, RespError};
+
+use super::{blob_expect_length, extract_blob_data};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub struct BulkError(pub(crate) Vec<u8>);
+
+// - blob error: "!<length>\r\n<error>\r\n"
+impl RespEncode for BulkError {
+    fn encode(self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.len() + 16);
+        buf.extend_from_slice(&format!("!{}\r\n", self.len()).into_bytes());
+        buf.extend_from_slice(&self);
+        buf.extend_from_slice(b"\r\n");
+        buf
+    }
+}
+
+impl RespDecode for BulkError {
+    const PREFIX: &'static str = "!";
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let data = extract_blob_data(buf, Self::PREFIX)?;
+        Ok(BulkError::new(data))
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        blob_expect_length(buf, Self::PREFIX)
+    }
+}
+
+impl BulkError {
+    pub fn new(s: impl Into<Vec<u8>>) -> Self {
+        BulkError(s.into())
+    }
+}
+
+impl AsRef<[u8]> for BulkError {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Deref for BulkError {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<&str> for BulkError {
+    fn from(s: &str) -> Self {
+        BulkError(s.as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespFrame;
+    use anyhow::Result;
+
+    #[test]
+    fn test_bulk_error_encode() {
+        let frame: RespFrame = BulkError::from("SYNTAX invalid syntax").into();
+        assert_eq!(frame.encode(), b"!21\r\nSYNTAX invalid syntax\r\n");
+    }
+
+    #[test]
+    fn test_bulk_error_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"!22\r\nSYNTAX invalid\r\nsyntax\r\n");
+
+        let frame = BulkError::decode(&mut buf)?;
+        assert_eq!(frame, BulkError::from("SYNTAX invalid\r\nsyntax"));
+
+        buf.extend_from_slice(b"!5\r\nERR");
+        let ret = BulkError::decode(&mut buf);
+        assert_eq!(ret.unwrap_err(), RespError::NotComplete);
+
+        Ok(())
+    }
+}
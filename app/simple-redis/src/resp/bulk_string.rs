@@ -4,21 +4,19 @@ use bytes::{Buf, BytesMut};
 
 use crate::{RespDecode, RespEncode, RespError};
 
-use super::{parse_length, CRLF_LEN};
+use super::{extract_fixed_data, parse_length, CRLF_LEN};
 
-const NULL_BULK_STRING: &[u8] = b"$-1\r\n";
+pub(crate) const NULL_BULK_STRING: &[u8] = b"$-1\r\n";
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
 pub struct BulkString(pub(crate) Vec<u8>);
 
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub struct RespNullBulkString;
+
 // - bulk string: "$<length>\r\n<data>\r\n"
-// - null bulk string: "$-1\r\n"
 impl RespEncode for BulkString {
     fn encode(self) -> Vec<u8> {
-        if self.is_empty() {
-            return NULL_BULK_STRING.to_vec();
-        }
-
         let mut buf = Vec::with_capacity(self.len() + 16);
         buf.extend_from_slice(&format!("${}\r\n", self.len()).into_bytes());
         buf.extend_from_slice(&self);
@@ -31,8 +29,9 @@ impl RespDecode for BulkString {
     const PREFIX: &'static str = "$";
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
         if buf.starts_with(NULL_BULK_STRING) {
-            buf.advance(NULL_BULK_STRING.len());
-            return Ok(BulkString::new(vec![]));
+            return Err(RespError::InvalidFrameType(
+                "expect: BulkString, got: null bulk string".to_string(),
+            ));
         }
 
         let (end, len) = parse_length(buf, Self::PREFIX)?;
@@ -53,6 +52,25 @@ impl RespDecode for BulkString {
     }
 }
 
+// - null bulk string: "$-1\r\n"
+impl RespEncode for RespNullBulkString {
+    fn encode(self) -> Vec<u8> {
+        NULL_BULK_STRING.to_vec()
+    }
+}
+
+impl RespDecode for RespNullBulkString {
+    const PREFIX: &'static str = "$";
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        extract_fixed_data(buf, "$-1\r\n", "NullBulkString")?;
+        Ok(RespNullBulkString)
+    }
+
+    fn expect_length(_buf: &[u8]) -> Result<usize, RespError> {
+        Ok(NULL_BULK_STRING.len())
+    }
+}
+
 impl BulkString {
     pub fn new(s: impl Into<Vec<u8>>) -> Self {
         BulkString(s.into())
@@ -111,8 +129,14 @@ mod tests {
     }
 
     #[test]
-    fn test_null_bulk_string_encode() {
+    fn test_empty_bulk_string_encode() {
         let frame: RespFrame = b"".into();
+        assert_eq!(frame.encode(), b"$0\r\n\r\n");
+    }
+
+    #[test]
+    fn test_null_bulk_string_encode() {
+        let frame: RespFrame = RespNullBulkString.into();
         assert_eq!(frame.encode(), NULL_BULK_STRING);
     }
 
@@ -136,12 +160,28 @@ mod tests {
     }
 
     #[test]
-    fn test_null_bulk_string_decode() -> Result<()> {
+    fn test_empty_bulk_string_decode() -> Result<()> {
         let mut buf = BytesMut::new();
-        buf.extend_from_slice(NULL_BULK_STRING);
+        buf.extend_from_slice(b"$0\r\n\r\n");
 
         let frame = BulkString::decode(&mut buf)?;
         assert_eq!(frame, BulkString::new(b""));
+        assert!(buf.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_null_bulk_string_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(NULL_BULK_STRING);
+
+        let frame = RespNullBulkString::decode(&mut buf)?;
+        assert_eq!(frame, RespNullBulkString);
+
+        buf.extend_from_slice(NULL_BULK_STRING);
+        let frame = RespFrame::decode(&mut buf)?;
+        assert_eq!(frame, RespFrame::NullBulkString(RespNullBulkString));
 
         Ok(())
     }
@@ -1,7 +1,9 @@
 use crate::{
-    BulkString, RespArray, RespDecode, RespError, RespMap, RespNull, RespSet, SimpleError,
-    SimpleString,
+    BulkError, BulkString, RespArray, RespAttribute, RespBigNumber, RespDecode, RespError, RespMap,
+    RespNull, RespNullBulkString, RespPush, RespSet, SimpleError, SimpleString, VerbatimString,
 };
+
+use super::bulk_string::NULL_BULK_STRING;
 use bytes::BytesMut;
 use enum_dispatch::enum_dispatch;
 
@@ -12,12 +14,18 @@ pub enum RespFrame {
     Error(SimpleError),
     Integer(i64),
     BulkString(BulkString),
+    NullBulkString(RespNullBulkString),
     Array(RespArray),
     Null(RespNull),
     Boolean(bool),
     Double(f64),
+    BigNumber(RespBigNumber),
+    BulkError(BulkError),
+    VerbatimString(VerbatimString),
     Map(RespMap),
+    Attribute(RespAttribute),
     Set(RespSet),
+    Push(RespPush),
 }
 
 impl RespDecode for RespFrame {
@@ -37,6 +45,10 @@ impl RespDecode for RespFrame {
                 let frame = i64::decode(buf)?;
                 Ok(frame.into())
             }
+            Some(b'$') if buf.starts_with(NULL_BULK_STRING) => {
+                let frame = RespNullBulkString::decode(buf)?;
+                Ok(frame.into())
+            }
             Some(b'$') => {
                 let frame = BulkString::decode(buf)?;
                 Ok(frame.into())
@@ -65,6 +77,26 @@ impl RespDecode for RespFrame {
                 let frame = RespSet::decode(buf)?;
                 Ok(frame.into())
             }
+            Some(b'(') => {
+                let frame = RespBigNumber::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b'!') => {
+                let frame = BulkError::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b'=') => {
+                let frame = VerbatimString::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b'|') => {
+                let frame = RespAttribute::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b'>') => {
+                let frame = RespPush::decode(buf)?;
+                Ok(frame.into())
+            }
             None => Err(RespError::NotComplete),
             _ => Err(RespError::InvalidFrameType(format!(
                 "expect_length: unknown frame type: {:?}",
@@ -79,6 +111,9 @@ impl RespDecode for RespFrame {
             Some(b'*') => RespArray::expect_length(buf),
             Some(b'~') => RespSet::expect_length(buf),
             Some(b'%') => RespMap::expect_length(buf),
+            Some(b'$') if buf.starts_with(NULL_BULK_STRING) => {
+                RespNullBulkString::expect_length(buf)
+            }
             Some(b'$') => BulkString::expect_length(buf),
             Some(b':') => i64::expect_length(buf),
             Some(b'+') => SimpleString::expect_length(buf),
@@ -86,6 +121,11 @@ impl RespDecode for RespFrame {
             Some(b'#') => bool::expect_length(buf),
             Some(b',') => f64::expect_length(buf),
             Some(b'_') => RespNull::expect_length(buf),
+            Some(b'(') => RespBigNumber::expect_length(buf),
+            Some(b'!') => BulkError::expect_length(buf),
+            Some(b'=') => VerbatimString::expect_length(buf),
+            Some(b'|') => RespAttribute::expect_length(buf),
+            Some(b'>') => RespPush::expect_length(buf),
             _ => Err(RespError::NotComplete),
         }
     }
@@ -111,5 +151,40 @@ impl<const N: usize> From<&[u8; N]> for RespFrame {
 
 #[cfg(test)]
 mod tests {
-    // TODO: Add tests
+    use super::*;
+    use crate::{RespDecodeV2, RespEncode};
+    use anyhow::Result;
+
+    #[test]
+    fn test_frame_roundtrip() -> Result<()> {
+        let mut attr = RespAttribute::new();
+        attr.insert("ttl".to_string(), 3600.into());
+        let frames: Vec<RespFrame> = vec![
+            BulkString::new(b"").into(),
+            RespNullBulkString.into(),
+            RespBigNumber::try_new("-3492890328409238509324850943850943825024385")?.into(),
+            BulkError::from("SYNTAX invalid syntax").into(),
+            VerbatimString::markdown("# title").into(),
+            attr.into(),
+            RespPush::with_kind("message", [b"chan".into(), b"".into()]).into(),
+        ];
+
+        for frame in frames {
+            let encoded = frame.clone().encode();
+
+            let mut buf = BytesMut::from(&encoded[..]);
+            assert_eq!(
+                <RespFrame as RespDecode>::expect_length(&buf)?,
+                encoded.len()
+            );
+            assert_eq!(<RespFrame as RespDecode>::decode(&mut buf)?, frame);
+            assert!(buf.is_empty());
+
+            let mut buf = BytesMut::from(&encoded[..]);
+            assert_eq!(<RespFrame as RespDecodeV2>::decode(&mut buf)?, frame);
+            assert!(buf.is_empty());
+        }
+
+        Ok(())
+    }
 }
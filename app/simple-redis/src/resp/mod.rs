@@ -1,14 +1,19 @@
 mod array;
+mod attribute;
+mod big_number;
 mod bool;
+mod bulk_error;
 mod bulk_string;
 mod double;
 mod frame;
 mod integer;
 mod map;
 mod null;
+mod push;
 mod set;
 mod simple_error;
 mod simple_string;
+mod verbatim_string;
 
 use bytes::{Buf, BytesMut};
 use enum_dispatch::enum_dispatch;
@@ -19,8 +24,19 @@ const CRLF: &[u8] = b"\r\n";
 const CRLF_LEN: usize = CRLF.len();
 
 pub use self::{
-    array::RespArray, bulk_string::BulkString, frame::RespFrame, map::RespMap, null::RespNull,
-    set::RespSet, simple_error::SimpleError, simple_string::SimpleString,
+    array::RespArray,
+    attribute::RespAttribute,
+    big_number::RespBigNumber,
+    bulk_error::BulkError,
+    bulk_string::{BulkString, RespNullBulkString},
+    frame::RespFrame,
+    map::RespMap,
+    null::RespNull,
+    push::RespPush,
+    set::RespSet,
+    simple_error::SimpleError,
+    simple_string::SimpleString,
+    verbatim_string::VerbatimString,
 };
 
 #[enum_dispatch]
@@ -112,11 +128,30 @@ fn parse_length(buf: &[u8], prefix: &str) -> Result<(usize, usize), RespError> {
     Ok((end, s.parse()?))
 }
 
+// extract the payload of a length-prefixed frame: "<prefix><length>\r\n<data>\r\n"
+fn extract_blob_data(buf: &mut BytesMut, prefix: &str) -> Result<Vec<u8>, RespError> {
+    let (end, len) = parse_length(buf, prefix)?;
+    let remained = &buf[end + CRLF_LEN..];
+    if remained.len() < len + CRLF_LEN {
+        return Err(RespError::NotComplete);
+    }
+
+    buf.advance(end + CRLF_LEN);
+
+    let data = buf.split_to(len + CRLF_LEN);
+    Ok(data[..len].to_vec())
+}
+
+fn blob_expect_length(buf: &[u8], prefix: &str) -> Result<usize, RespError> {
+    let (end, len) = parse_length(buf, prefix)?;
+    Ok(end + CRLF_LEN + len + CRLF_LEN)
+}
+
 fn calc_total_length(buf: &[u8], end: usize, len: usize, prefix: &str) -> Result<usize, RespError> {
     let mut total = end + CRLF_LEN;
     let mut data = &buf[total..];
     match prefix {
-        "*" | "~" => {
+        "*" | "~" | ">" => {
             // find nth CRLF in the buffer, for array and set, we need to find 1 CRLF for each element
             for _ in 0..len {
                 let len = RespFrame::expect_length(data)?;
@@ -125,8 +160,8 @@ fn calc_total_length(buf: &[u8], end: usize, len: usize, prefix: &str) -> Result
             }
             Ok(total)
         }
-        "%" => {
-            // find nth CRLF in the buffer. For map, we need to find 2 CRLF for each key-value pair
+        "%" | "|" => {
+            // find nth CRLF in the buffer. For map and attribute, we need to find 2 CRLF for each key-value pair
             for _ in 0..len {
                 let len = SimpleString::expect_length(data)?;
 
@@ -0,0 +1,122 @@
+use bytes::{Buf, BytesMut};
+
+use crate::{BulkString, RespDecode, RespEncode, RespError, RespFrame};
+use std::ops::Deref;
+
+use super::{calc_total_length, parse_length, BUF_CAP, CRLF_LEN};
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct RespPush(pub(crate) Vec<RespFrame>);
+
+// - push: "><number-of-elements>\r\n<kind><element-1>...<element-n>"
+// the first element is a string naming the kind of push, e.g. "message" or "invalidate"
+impl RespEncode for RespPush {
+    fn encode(self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(BUF_CAP);
+        buf.extend_from_slice(&format!(">{}\r\n", self.len()).into_bytes());
+        for frame in self.0 {
+            buf.extend_from_slice(&frame.encode());
+        }
+        buf
+    }
+}
+
+impl RespDecode for RespPush {
+    const PREFIX: &'static str = ">";
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let (end, len) = parse_length(buf, Self::PREFIX)?;
+        let total_len = calc_total_length(buf, end, len, Self::PREFIX)?;
+
+        if buf.len() < total_len {
+            return Err(RespError::NotComplete);
+        }
+
+        buf.advance(end + CRLF_LEN);
+
+        let mut frames = Vec::with_capacity(len);
+        for _ in 0..len {
+            frames.push(RespFrame::decode(buf)?);
+        }
+
+        Ok(RespPush::new(frames))
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let (end, len) = parse_length(buf, Self::PREFIX)?;
+        calc_total_length(buf, end, len, Self::PREFIX)
+    }
+}
+
+impl RespPush {
+    pub fn new(s: impl Into<Vec<RespFrame>>) -> Self {
+        RespPush(s.into())
+    }
+
+    /// Build a push message whose first element is the given kind.
+    pub fn with_kind(kind: &str, data: impl IntoIterator<Item = RespFrame>) -> Self {
+        let mut frames = vec![BulkString::from(kind).into()];
+        frames.extend(data);
+        RespPush(frames)
+    }
+
+    /// The kind of the push message, if the first element is a string.
+    pub fn kind(&self) -> Option<&[u8]> {
+        match self.first() {
+            Some(RespFrame::BulkString(s)) => Some(s.as_ref()),
+            Some(RespFrame::SimpleString(s)) => Some(s.as_bytes()),
+            _ => None,
+        }
+    }
+}
+
+impl Deref for RespPush {
+    type Target = Vec<RespFrame>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Vec<RespFrame>> for RespPush {
+    fn from(s: Vec<RespFrame>) -> Self {
+        RespPush(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_push_encode() {
+        let frame: RespFrame =
+            RespPush::with_kind("message", [b"chan".into(), b"hello".into()]).into();
+        assert_eq!(
+            &frame.encode(),
+            b">3\r\n$7\r\nmessage\r\n$4\r\nchan\r\n$5\r\nhello\r\n"
+        );
+    }
+
+    #[test]
+    fn test_push_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b">2\r\n$10\r\ninvalidate\r\n*1\r\n$3\r\nfoo\r\n");
+
+        let frame = RespPush::decode(&mut buf)?;
+        assert_eq!(frame.kind(), Some(&b"invalidate"[..]));
+        assert_eq!(
+            frame,
+            RespPush::with_kind(
+                "invalidate",
+                [crate::RespArray::new([b"foo".into()]).into()]
+            )
+        );
+
+        buf.extend_from_slice(b">2\r\n$10\r\ninvalidate\r\n");
+        let ret = RespPush::decode(&mut buf);
+        assert_eq!(ret.unwrap_err(), RespError::NotComplete);
+
+        Ok(())
+    }
+}
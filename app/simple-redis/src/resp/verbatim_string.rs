@@ -0,0 +1,106 @@
+use bytes::BytesMut;
+
+use crate::{RespDecode, RespEncode, RespError};
+
+use super::{blob_expect_length, extract_blob_data};
+
+// the encoding is always exactly three bytes followed by a colon
+const FORMAT_LEN: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub struct VerbatimString {
+    pub(crate) format: [u8; FORMAT_LEN],
+    pub(crate) data: Vec<u8>,
+}
+
+// - verbatim string: "=<length>\r\n<encoding>:<data>\r\n"
+impl RespEncode for VerbatimString {
+    fn encode(self) -> Vec<u8> {
+        let len = FORMAT_LEN + 1 + self.data.len();
+        let mut buf = Vec::with_capacity(len + 16);
+        buf.extend_from_slice(&format!("={}\r\n", len).into_bytes());
+        buf.extend_from_slice(&self.format);
+        buf.push(b':');
+        buf.extend_from_slice(&self.data);
+        buf.extend_from_slice(b"\r\n");
+        buf
+    }
+}
+
+impl RespDecode for VerbatimString {
+    const PREFIX: &'static str = "=";
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let data = extract_blob_data(buf, Self::PREFIX)?;
+        if data.len() <= FORMAT_LEN || data[FORMAT_LEN] != b':' {
+            return Err(RespError::InvalidFrame(format!(
+                "verbatim string must start with <encoding>:, got: {:?}",
+                String::from_utf8_lossy(&data)
+            )));
+        }
+        let mut format = [0u8; FORMAT_LEN];
+        format.copy_from_slice(&data[..FORMAT_LEN]);
+        Ok(VerbatimString {
+            format,
+            data: data[FORMAT_LEN + 1..].to_vec(),
+        })
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        blob_expect_length(buf, Self::PREFIX)
+    }
+}
+
+impl VerbatimString {
+    pub fn new(format: [u8; FORMAT_LEN], data: impl Into<Vec<u8>>) -> Self {
+        VerbatimString {
+            format,
+            data: data.into(),
+        }
+    }
+
+    /// Plain text payload, encoded as `txt`.
+    pub fn text(data: impl Into<Vec<u8>>) -> Self {
+        Self::new(*b"txt", data)
+    }
+
+    /// Markdown payload, encoded as `mkd`.
+    pub fn markdown(data: impl Into<Vec<u8>>) -> Self {
+        Self::new(*b"mkd", data)
+    }
+
+    pub fn format(&self) -> &[u8] {
+        &self.format
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespFrame;
+    use anyhow::Result;
+
+    #[test]
+    fn test_verbatim_string_encode() {
+        let frame: RespFrame = VerbatimString::text("Some string").into();
+        assert_eq!(frame.encode(), b"=15\r\ntxt:Some string\r\n");
+    }
+
+    #[test]
+    fn test_verbatim_string_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"=15\r\ntxt:Some string\r\n");
+
+        let frame = VerbatimString::decode(&mut buf)?;
+        assert_eq!(frame, VerbatimString::text("Some string"));
+
+        buf.extend_from_slice(b"=3\r\ntxt\r\n");
+        let ret = VerbatimString::decode(&mut buf);
+        assert!(matches!(ret.unwrap_err(), RespError::InvalidFrame(_)));
+
+        Ok(())
+    }
+}
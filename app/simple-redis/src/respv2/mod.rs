@@ -102,6 +102,17 @@ mod tests {
     fn respv2_null_bulk_string_should_work() {
         let mut buf = BytesMut::from("$-1\r\n");
         let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(frame, RespFrame::NullBulkString(crate::RespNullBulkString));
+    }
+
+    #[test]
+    fn respv2_empty_bulk_string_should_work() {
+        let buf = b"$0\r\n\r\n";
+        let len = RespFrame::expect_length(buf).unwrap();
+        assert_eq!(len, buf.len());
+
+        let mut buf = BytesMut::from("$0\r\n\r\n");
+        let frame = RespFrame::decode(&mut buf).unwrap();
         assert_eq!(frame, RespFrame::BulkString("".into()));
     }
 
@@ -172,4 +183,104 @@ mod tests {
         .collect();
         assert_eq!(frame, RespFrame::Map(items.into()));
     }
+
+    #[test]
+    fn respv2_set_should_work() {
+        let mut buf = BytesMut::from("~2\r\n+OK\r\n:1\r\n");
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::Set(crate::RespSet::new([
+                RespFrame::SimpleString("OK".into()),
+                RespFrame::Integer(1)
+            ]))
+        );
+    }
+
+    #[test]
+    fn respv2_big_number_should_work() {
+        let buf = b"(-3492890328409238509324850943850943825024385\r\n";
+        let len = RespFrame::expect_length(buf).unwrap();
+        assert_eq!(len, buf.len());
+
+        let mut buf = BytesMut::from(&buf[..]);
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::BigNumber(
+                crate::RespBigNumber::try_new("-3492890328409238509324850943850943825024385")
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn respv2_bulk_error_should_work() {
+        let buf = b"!21\r\nSYNTAX invalid syntax\r\n";
+        let len = RespFrame::expect_length(buf).unwrap();
+        assert_eq!(len, buf.len());
+
+        let mut buf = BytesMut::from(&buf[..]);
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(frame, RespFrame::BulkError("SYNTAX invalid syntax".into()));
+    }
+
+    #[test]
+    fn respv2_verbatim_string_should_work() {
+        let buf = b"=15\r\ntxt:Some string\r\n";
+        let len = RespFrame::expect_length(buf).unwrap();
+        assert_eq!(len, buf.len());
+
+        let mut buf = BytesMut::from(&buf[..]);
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::VerbatimString(crate::VerbatimString::text("Some string"))
+        );
+    }
+
+    #[test]
+    fn respv2_null_bulk_error_and_verbatim_string_should_fail() {
+        // only bulk strings have a null form
+        for buf in [&b"!-1\r\n"[..], b"=-1\r\n"] {
+            let mut buf = BytesMut::from(buf);
+            assert!(matches!(
+                RespFrame::decode(&mut buf),
+                Err(RespError::InvalidFrame(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn respv2_attribute_should_work() {
+        let buf = b"|1\r\n+ttl\r\n:3600\r\n";
+        let len = RespFrame::expect_length(buf).unwrap();
+        assert_eq!(len, buf.len());
+
+        let mut buf = BytesMut::from(&buf[..]);
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        let items: BTreeMap<String, RespFrame> = [("ttl".to_string(), RespFrame::Integer(3600))]
+            .into_iter()
+            .collect();
+        assert_eq!(frame, RespFrame::Attribute(items.into()));
+    }
+
+    #[test]
+    fn respv2_push_should_work() {
+        let buf = b">2\r\n$10\r\ninvalidate\r\n*1\r\n$3\r\nfoo\r\n";
+        let len = RespFrame::expect_length(buf).unwrap();
+        assert_eq!(len, buf.len());
+
+        let mut buf = BytesMut::from(&buf[..]);
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::Push(crate::RespPush::with_kind(
+                "invalidate",
+                [RespFrame::Array(
+                    vec![RespFrame::BulkString("foo".into())].into()
+                )]
+            ))
+        );
+    }
 }
@@ -1,5 +1,6 @@
 use crate::{
-    BulkString, RespArray, RespError, RespFrame, RespMap, RespNull, SimpleError, SimpleString,
+    BulkError, BulkString, RespArray, RespAttribute, RespBigNumber, RespError, RespFrame, RespMap,
+    RespNull, RespNullBulkString, RespPush, RespSet, SimpleError, SimpleString, VerbatimString,
 };
 use std::{collections::BTreeMap, num::NonZeroUsize};
 use winnow::{
@@ -24,6 +25,8 @@ pub fn parse_frame_length(input: &[u8]) -> Result<usize, RespError> {
             let len = end - start;
             Ok(len)
         }
+        // a malformed frame won't become valid with more data
+        Err(ErrMode::Cut(e)) => Err(RespError::InvalidFrame(e.to_string())),
         Err(_) => Err(RespError::NotComplete),
     }
 }
@@ -40,7 +43,12 @@ fn parse_frame_len(input: &mut &[u8]) -> PResult<()> {
         b'#' => simple_parser,
         b',' => simple_parser,
         b'%' => map_len,
-        // b'~' => set,
+        b'~' => array_len,
+        b'(' => simple_parser,
+        b'!' => blob_len,
+        b'=' => blob_len,
+        b'|' => map_len,
+        b'>' => array_len,
         _v => fail::<_, _, _>
     }
     .parse_next(input)
@@ -52,13 +60,18 @@ pub fn parse_frame(input: &mut &[u8]) -> PResult<RespFrame> {
         b'+' => simple_string.map(RespFrame::SimpleString),
         b'-' => error.map(RespFrame::Error),
         b':' => integer.map(RespFrame::Integer),
-        b'$' => bulk_string.map(RespFrame::BulkString),
+        b'$' => bulk_string,
         b'*' => array.map(RespFrame::Array),
         b'_' => null.map(RespFrame::Null),
         b'#' => boolean.map(RespFrame::Boolean),
         b',' => double.map(RespFrame::Double),
         b'%' => map.map(RespFrame::Map),
-        // b'~' => set,
+        b'~' => array.map(|v| RespFrame::Set(RespSet(v.0))),
+        b'(' => big_number.map(RespFrame::BigNumber),
+        b'!' => bulk_error.map(RespFrame::BulkError),
+        b'=' => verbatim_string.map(RespFrame::VerbatimString),
+        b'|' => map.map(|v| RespFrame::Attribute(RespAttribute(v.0))),
+        b'>' => array.map(|v| RespFrame::Push(RespPush(v.0))),
         _v => fail::<_, _, _>
     }
     .parse_next(input)
@@ -82,37 +95,84 @@ fn integer(input: &mut &[u8]) -> PResult<i64> {
 }
 
 // - bulk string: "$6\r\nfoobar\r\n"
+// - empty bulk string: "$0\r\n\r\n"
 // - null bulk string: "$-1\r\n"
-#[allow(clippy::comparison_chain)]
-fn bulk_string(input: &mut &[u8]) -> PResult<BulkString> {
+fn bulk_string(input: &mut &[u8]) -> PResult<RespFrame> {
     let len: i64 = integer.parse_next(input)?;
-    if len == 0 || len == -1 {
-        return Ok(BulkString(vec![]));
+    if len == -1 {
+        return Ok(RespFrame::NullBulkString(RespNullBulkString));
     } else if len < -1 {
         return Err(err_cut("bulk string length must be non-negative"));
     }
-    let data = terminated(take(len as usize), CRLF)
+    let data = blob_data(input, len as usize)?;
+    Ok(RespFrame::BulkString(BulkString(data)))
+}
+
+// - blob error: "!21\r\nSYNTAX invalid syntax\r\n"
+fn bulk_error(input: &mut &[u8]) -> PResult<BulkError> {
+    let len: i64 = integer.parse_next(input)?;
+    if len < 0 {
+        return Err(err_cut("blob error length must be non-negative"));
+    }
+    let data = blob_data(input, len as usize)?;
+    Ok(BulkError(data))
+}
+
+// - verbatim string: "=15\r\ntxt:Some string\r\n"
+fn verbatim_string(input: &mut &[u8]) -> PResult<VerbatimString> {
+    let len: i64 = integer.parse_next(input)?;
+    if len < 4 {
+        return Err(err_cut("verbatim string must have a 3 bytes encoding"));
+    }
+    let data = blob_data(input, len as usize)?;
+    if data[3] != b':' {
+        return Err(err_cut("verbatim string encoding must be followed by ':'"));
+    }
+    let mut format = [0u8; 3];
+    format.copy_from_slice(&data[..3]);
+    Ok(VerbatimString::new(format, &data[4..]))
+}
+
+// - big number: "(3492890328409238509324850943850943825024385\r\n"
+fn big_number(input: &mut &[u8]) -> PResult<RespBigNumber> {
+    let s = parse_string.parse_next(input)?;
+    RespBigNumber::try_new(s).map_err(|e| err_cut(e.to_string()))
+}
+
+fn blob_data(input: &mut &[u8], len: usize) -> PResult<Vec<u8>> {
+    terminated(take(len), CRLF)
         .map(|s: &[u8]| s.to_vec())
-        .parse_next(input)?;
-    Ok(BulkString(data))
+        .parse_next(input)
 }
 
 fn bulk_string_len(input: &mut &[u8]) -> PResult<()> {
     let len: i64 = integer.parse_next(input)?;
-    if len == 0 || len == -1 {
+    if len == -1 {
         return Ok(());
     } else if len < -1 {
         return Err(err_cut("bulk string length must be non-negative"));
     }
+    skip_blob(input, len as usize)
+}
+
+// blob errors and verbatim strings have no null form, unlike bulk strings
+fn blob_len(input: &mut &[u8]) -> PResult<()> {
+    let len: i64 = integer.parse_next(input)?;
+    if len < 0 {
+        return Err(err_cut("blob length must be non-negative"));
+    }
+    skip_blob(input, len as usize)
+}
 
+fn skip_blob(input: &mut &[u8], len: usize) -> PResult<()> {
     // we don't really need to parse the data, just skip it
     // this is a good optimization
-    let len_with_crlf = len as usize + 2;
+    let len_with_crlf = len + 2;
     if input.len() < len_with_crlf {
-        let size = NonZeroUsize::new((len_with_crlf - input.len()) as usize).unwrap();
+        let size = NonZeroUsize::new(len_with_crlf - input.len()).unwrap();
         return Err(ErrMode::Incomplete(Needed::Size(size)));
     }
-    *input = &input[(len + 2) as usize..];
+    *input = &input[len_with_crlf..];
     Ok(())
 }
 
@@ -0,0 +1,553 @@
+use crate::{
+    cmd::{self, Command, CommandExecutor},
+    Backend, BulkString, RespArray, RespFrame, RespNull, SimpleError, SimpleString,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use tokio::sync::{mpsc, oneshot};
+
+// Shared-nothing execution mode: the keyspace is split into N shards, each one owned by a
+// dedicated thread that executes its commands one at a time, so no lock is ever contended.
+// Connections route every command to the shard owning its key. A command on several shards,
+// and a MULTI/EXEC transaction, first reserves every shard it involves, in ascending order so
+// that two of them can't deadlock: a reserved shard runs nothing else until released, which
+// makes the whole command atomic.
+//
+// Only the data commands are supported, server wide features (pub/sub, tracking, config,
+// persistence, ...) need the shared `Backend`.
+#[derive(Debug, Clone)]
+pub struct Shards {
+    inner: Arc<ShardsInner>,
+}
+
+#[derive(Debug)]
+struct ShardsInner {
+    senders: Vec<ShardSender>,
+    next_conn: AtomicU64,
+}
+
+type ShardSender = mpsc::UnboundedSender<ShardRequest>;
+
+#[derive(Debug)]
+enum ShardRequest {
+    Execute {
+        conn: u64,
+        frame: RespFrame,
+        reply: oneshot::Sender<RespFrame>,
+    },
+    // the shard acknowledges, then only runs the requests of `requests` until it's closed
+    Reserve {
+        ready: oneshot::Sender<()>,
+        requests: mpsc::UnboundedReceiver<ShardRequest>,
+    },
+    Disconnect {
+        conn: u64,
+    },
+}
+
+// how a command is dispatched to the shards
+enum Route {
+    // any shard can answer, e.g. ECHO
+    Any,
+    // the shard owning the first argument
+    Key,
+    // the keys are split among their shards, the integer replies are summed (DEL)
+    Keys,
+    // per connection or per db state, executed on every shard (SELECT, FLUSHDB, ...)
+    All,
+    Unsupported,
+}
+
+// a client connection, holding a session on every shard
+#[derive(Debug)]
+pub struct ShardedSession {
+    conn: u64,
+    shards: Shards,
+    // commands queued since MULTI
+    transaction: Option<Transaction>,
+}
+
+#[derive(Debug, Default)]
+struct Transaction {
+    commands: Vec<RespArray>,
+    // a command failed to queue, EXEC discards the transaction
+    aborted: bool,
+}
+
+impl Shards {
+    pub fn new(n: usize) -> Self {
+        let senders = (0..n.max(1))
+            .map(|i| {
+                let (tx, rx) = mpsc::unbounded_channel();
+                thread::Builder::new()
+                    .name(format!("shard-{}", i))
+                    .spawn(move || run_shard(rx))
+                    .expect("failed to spawn shard thread");
+                tx
+            })
+            .collect();
+        Self {
+            inner: Arc::new(ShardsInner {
+                senders,
+                next_conn: AtomicU64::new(1),
+            }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.senders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.senders.is_empty()
+    }
+
+    pub fn connect(&self) -> ShardedSession {
+        ShardedSession {
+            conn: self.inner.next_conn.fetch_add(1, Ordering::Relaxed),
+            shards: self.clone(),
+            transaction: None,
+        }
+    }
+
+    pub fn shard_for(&self, key: &[u8]) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.len() as u64) as usize
+    }
+
+    fn shard_of(&self, arg: &RespFrame) -> usize {
+        match arg {
+            RespFrame::BulkString(key) => self.shard_for(key),
+            _ => 0,
+        }
+    }
+
+    // the shards a command runs on
+    fn involved(&self, args: &RespArray, route: &Route) -> BTreeSet<usize> {
+        match route {
+            Route::Key => args
+                .get(1)
+                .map(|key| self.shard_of(key))
+                .into_iter()
+                .collect(),
+            Route::Keys => args[1..].iter().map(|key| self.shard_of(key)).collect(),
+            Route::All => (0..self.len()).collect(),
+            Route::Any | Route::Unsupported => BTreeSet::new(),
+        }
+    }
+}
+
+impl ShardedSession {
+    pub async fn execute(&mut self, frame: RespFrame) -> RespFrame {
+        let RespFrame::Array(args) = frame else {
+            return SimpleError::new("ERR Command must be an Array").into();
+        };
+        match command_name(&args).as_str() {
+            "multi" => return self.multi(),
+            "exec" => return self.exec().await,
+            "discard" => return self.discard(),
+            _ => {}
+        }
+
+        let route = route(&args);
+        if let Some(transaction) = &mut self.transaction {
+            // as redis does, only the errors found before running a command abort the
+            // transaction
+            let error = match route {
+                Route::Unsupported => Some(unsupported(&args)),
+                _ => cmd::lookup(&cmd::arg_bytes(&args))
+                    .err()
+                    .map(RespFrame::from),
+            };
+            return match error {
+                Some(error) => {
+                    transaction.aborted = true;
+                    error
+                }
+                None => {
+                    transaction.commands.push(args);
+                    SimpleString::new("QUEUED").into()
+                }
+            };
+        }
+        if let Route::Unsupported = route {
+            return unsupported(&args);
+        }
+        let shards = self.shards.involved(&args, &route);
+        match self.reserve(shards).await {
+            Ok(senders) => self.run(&senders, args, &route).await,
+            Err(e) => e,
+        }
+    }
+
+    fn multi(&mut self) -> RespFrame {
+        if self.transaction.is_some() {
+            return SimpleError::new("ERR MULTI calls can not be nested").into();
+        }
+        self.transaction = Some(Transaction::default());
+        SimpleString::new("OK").into()
+    }
+
+    fn discard(&mut self) -> RespFrame {
+        match self.transaction.take() {
+            Some(_) => SimpleString::new("OK").into(),
+            None => SimpleError::new("ERR DISCARD without MULTI").into(),
+        }
+    }
+
+    // runs the queued commands with all their shards reserved, no other connection sees
+    // the keyspace in between
+    async fn exec(&mut self) -> RespFrame {
+        let Some(transaction) = self.transaction.take() else {
+            return SimpleError::new("ERR EXEC without MULTI").into();
+        };
+        if transaction.aborted {
+            return SimpleError::new("EXECABORT Transaction discarded because of previous errors.")
+                .into();
+        }
+        let commands = transaction
+            .commands
+            .into_iter()
+            .map(|args| {
+                let route = route(&args);
+                (args, route)
+            })
+            .collect::<Vec<_>>();
+        let shards = commands
+            .iter()
+            .flat_map(|(args, route)| self.shards.involved(args, route))
+            .collect();
+        let senders = match self.reserve(shards).await {
+            Ok(senders) => senders,
+            Err(e) => return e,
+        };
+        let mut replies = Vec::with_capacity(commands.len());
+        for (args, route) in commands {
+            replies.push(self.run(&senders, args, &route).await);
+        }
+        RespArray::new(replies).into()
+    }
+
+    // the channels to send the commands of the given shards to; when there are several of
+    // them, they are reserved until the returned map is dropped
+    async fn reserve(
+        &self,
+        mut shards: BTreeSet<usize>,
+    ) -> Result<BTreeMap<usize, ShardSender>, RespFrame> {
+        if shards.is_empty() {
+            shards.insert(0);
+        }
+        let senders = &self.shards.inner.senders;
+        if shards.len() == 1 {
+            return Ok(shards
+                .into_iter()
+                .map(|i| (i, senders[i].clone()))
+                .collect());
+        }
+        let mut reserved = BTreeMap::new();
+        for shard in shards {
+            let (tx, requests) = mpsc::unbounded_channel();
+            let (ready, acked) = oneshot::channel();
+            if senders[shard]
+                .send(ShardRequest::Reserve { ready, requests })
+                .is_err()
+                || acked.await.is_err()
+            {
+                return Err(shard_down());
+            }
+            reserved.insert(shard, tx);
+        }
+        Ok(reserved)
+    }
+
+    async fn run(
+        &self,
+        senders: &BTreeMap<usize, ShardSender>,
+        args: RespArray,
+        route: &Route,
+    ) -> RespFrame {
+        match route {
+            Route::Any | Route::Key => {
+                // any reserved shard can answer a command without a key
+                let shard = self.shards.involved(&args, route).first().copied();
+                self.send(any_sender(senders, shard), args.into()).await
+            }
+            Route::Keys => self.run_keys(senders, args).await,
+            Route::All => {
+                // the replies are all the same, unless the command fails
+                let mut ret = RespFrame::Null(RespNull);
+                for sender in senders.values() {
+                    match self.send(sender, args.clone().into()).await {
+                        RespFrame::Error(e) => return e.into(),
+                        reply => ret = reply,
+                    }
+                }
+                ret
+            }
+            Route::Unsupported => unsupported(&args),
+        }
+    }
+
+    // DEL k1 k2 ... becomes one DEL per shard, run while all of them are reserved
+    async fn run_keys(&self, senders: &BTreeMap<usize, ShardSender>, args: RespArray) -> RespFrame {
+        let mut parts: BTreeMap<usize, Vec<RespFrame>> = BTreeMap::new();
+        let mut iter = args.0.into_iter();
+        let name = iter.next().unwrap_or_else(|| BulkString::from("").into());
+        for key in iter {
+            parts
+                .entry(self.shards.shard_of(&key))
+                .or_insert_with(|| vec![name.clone()])
+                .push(key);
+        }
+        if parts.is_empty() {
+            return self
+                .send(any_sender(senders, None), RespArray::new([name]).into())
+                .await;
+        }
+
+        let mut total = 0;
+        let mut error = None;
+        for (shard, args) in parts {
+            match self
+                .send(&senders[&shard], RespArray::new(args).into())
+                .await
+            {
+                RespFrame::Integer(n) => total += n,
+                reply => {
+                    error.get_or_insert(reply);
+                }
+            }
+        }
+        error.unwrap_or(RespFrame::Integer(total))
+    }
+
+    async fn send(&self, sender: &ShardSender, frame: RespFrame) -> RespFrame {
+        let (tx, rx) = oneshot::channel();
+        let request = ShardRequest::Execute {
+            conn: self.conn,
+            frame,
+            reply: tx,
+        };
+        if sender.send(request).is_err() {
+            return shard_down();
+        }
+        rx.await.unwrap_or_else(|_| shard_down())
+    }
+}
+
+impl Drop for ShardedSession {
+    fn drop(&mut self) {
+        for sender in &self.shards.inner.senders {
+            let _ = sender.send(ShardRequest::Disconnect { conn: self.conn });
+        }
+    }
+}
+
+fn command_name(args: &RespArray) -> String {
+    match args.first() {
+        Some(RespFrame::BulkString(name)) => String::from_utf8_lossy(name).to_ascii_lowercase(),
+        _ => String::new(),
+    }
+}
+
+fn unsupported(args: &RespArray) -> RespFrame {
+    SimpleError::new(format!(
+        "ERR command '{}' is not supported in sharded mode",
+        command_name(args)
+    ))
+    .into()
+}
+
+fn any_sender(senders: &BTreeMap<usize, ShardSender>, shard: Option<usize>) -> &ShardSender {
+    shard
+        .and_then(|shard| senders.get(&shard))
+        .or_else(|| senders.values().next())
+        .expect("at least one shard is reserved")
+}
+
+fn shard_down() -> RespFrame {
+    SimpleError::new("ERR shard is down").into()
+}
+
+fn route(args: &RespArray) -> Route {
+    let spec = match cmd::lookup(&cmd::arg_bytes(args)) {
+        Ok(spec) => spec,
+        // any shard replies with the parse error
+        Err(_) => return Route::Any,
+    };
+    match spec.name() {
+        "echo" | "command" | "command|count" | "command|info" | "command|getkeys" => Route::Any,
+        "del" => Route::Keys,
+        "select" | "swapdb" | "flushdb" | "flushall" => Route::All,
+        _ if spec.has_single_key() => Route::Key,
+        _ => Route::Unsupported,
+    }
+}
+
+// the shard loop: a private backend, plus one session on it per connection
+fn run_shard(mut rx: mpsc::UnboundedReceiver<ShardRequest>) {
+    let backend = Backend::new();
+    let mut sessions: HashMap<u64, Backend> = HashMap::new();
+    while let Some(request) = rx.blocking_recv() {
+        handle(request, &backend, &mut sessions);
+    }
+}
+
+fn handle(request: ShardRequest, backend: &Backend, sessions: &mut HashMap<u64, Backend>) {
+    match request {
+        ShardRequest::Execute { conn, frame, reply } => {
+            let session = sessions
+                .entry(conn)
+                .or_insert_with(|| backend.new_session());
+            let ret = match Command::try_from(frame) {
+                Ok(cmd) => cmd.execute(session),
+                Err(e) => e.into(),
+            };
+            let _ = reply.send(ret);
+        }
+        // the reservation ends when its holder drops the sender, or gives up waiting
+        ShardRequest::Reserve {
+            ready,
+            mut requests,
+        } => {
+            if ready.send(()).is_ok() {
+                while let Some(request) = requests.blocking_recv() {
+                    handle(request, backend, sessions);
+                }
+            }
+        }
+        ShardRequest::Disconnect { conn } => {
+            if let Some(session) = sessions.remove(&conn) {
+                session.end_session();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::command;
+
+    async fn run(session: &mut ShardedSession, name: &str, args: &[&str]) -> RespFrame {
+        let cmd = command(name, args.iter().map(|&arg| arg.into()));
+        session.execute(cmd.into()).await
+    }
+
+    #[tokio::test]
+    async fn test_sharded_commands() {
+        let shards = Shards::new(4);
+        let mut session = shards.connect();
+
+        for key in ["a", "b", "c", "d", "e"] {
+            run(&mut session, "set", &[key, key]).await;
+        }
+        let mut other = shards.connect();
+        assert_eq!(
+            run(&mut other, "get", &["c"]).await,
+            RespFrame::BulkString("c".into())
+        );
+        assert_eq!(
+            run(&mut other, "del", &["a", "b", "c", "x"]).await,
+            RespFrame::Integer(3)
+        );
+
+        // selecting a db applies to the connection on every shard
+        run(&mut session, "select", &["1"]).await;
+        assert_eq!(
+            run(&mut session, "get", &["d"]).await,
+            RespFrame::Null(RespNull)
+        );
+        assert_eq!(
+            run(&mut other, "get", &["d"]).await,
+            RespFrame::BulkString("d".into())
+        );
+
+        assert!(matches!(
+            run(&mut session, "publish", &["ch", "msg"]).await,
+            RespFrame::Error(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sharded_transaction() {
+        let shards = Shards::new(4);
+        let mut session = shards.connect();
+        let ok = RespFrame::from(SimpleString::new("OK"));
+        let queued = RespFrame::from(SimpleString::new("QUEUED"));
+
+        assert_eq!(run(&mut session, "multi", &[]).await, ok);
+        for key in ["a", "b", "c"] {
+            assert_eq!(run(&mut session, "set", &[key, "1"]).await, queued);
+        }
+        assert_eq!(run(&mut session, "echo", &["hi"]).await, queued);
+        assert_eq!(run(&mut session, "del", &["a", "b", "x"]).await, queued);
+        assert_eq!(
+            run(&mut session, "setbit", &["c", "100", "1"]).await,
+            queued
+        );
+        assert_eq!(
+            run(&mut session, "exec", &[]).await,
+            RespArray::new([
+                ok.clone(),
+                ok.clone(),
+                ok.clone(),
+                RespFrame::BulkString("hi".into()),
+                RespFrame::Integer(2),
+                RespFrame::Integer(0),
+            ])
+            .into()
+        );
+
+        // an error while queueing discards the whole transaction
+        run(&mut session, "multi", &[]).await;
+        run(&mut session, "set", &["a", "2"]).await;
+        assert!(matches!(
+            run(&mut session, "get", &[]).await,
+            RespFrame::Error(_)
+        ));
+        assert!(matches!(
+            run(&mut session, "publish", &["ch", "msg"]).await,
+            RespFrame::Error(_)
+        ));
+        assert!(matches!(
+            run(&mut session, "exec", &[]).await,
+            RespFrame::Error(e) if e.starts_with("EXECABORT")
+        ));
+        assert_eq!(
+            run(&mut session, "get", &["a"]).await,
+            RespFrame::Null(RespNull)
+        );
+
+        run(&mut session, "multi", &[]).await;
+        run(&mut session, "set", &["a", "3"]).await;
+        assert_eq!(run(&mut session, "discard", &[]).await, ok);
+        assert!(matches!(
+            run(&mut session, "exec", &[]).await,
+            RespFrame::Error(_)
+        ));
+        assert_eq!(
+            run(&mut session, "get", &["a"]).await,
+            RespFrame::Null(RespNull)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reserved_shards_run_nothing_else() {
+        let shards = Shards::new(2);
+        let session = shards.connect();
+        let mut other = shards.connect();
+        let reserved = session.reserve([0, 1].into()).await.unwrap();
+
+        let get = run(&mut other, "get", &["a"]);
+        tokio::pin!(get);
+        let waited = tokio::time::timeout(std::time::Duration::from_millis(50), &mut get).await;
+        assert!(waited.is_err());
+        drop(reserved);
+        assert_eq!(get.await, RespFrame::Null(RespNull));
+    }
+}
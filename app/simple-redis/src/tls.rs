@@ -0,0 +1,53 @@
+use anyhow::{anyhow, Result};
+use std::{fs::File, io::BufReader, path::Path, sync::Arc};
+use tokio_rustls::{
+    rustls::{
+        pki_types::{CertificateDer, PrivateKeyDer},
+        server::WebPkiClientVerifier,
+        RootCertStore, ServerConfig,
+    },
+    TlsAcceptor,
+};
+
+// Build the acceptor for the TLS port from PEM files. With a CA certificate, clients
+// must present a certificate signed by it (like redis' tls-auth-clients yes).
+pub fn tls_acceptor(
+    cert_file: impl AsRef<Path>,
+    key_file: impl AsRef<Path>,
+    ca_cert_file: Option<impl AsRef<Path>>,
+) -> Result<TlsAcceptor> {
+    let certs = load_certs(cert_file)?;
+    let key = load_key(key_file)?;
+
+    let builder = ServerConfig::builder();
+    let builder = match ca_cert_file {
+        Some(ca_cert_file) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(ca_cert_file)? {
+                roots.add(cert)?;
+            }
+            let verifier = WebPkiClientVerifier::builder(Arc::new(roots)).build()?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let config = builder.with_single_cert(certs, key)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn load_certs(path: impl AsRef<Path>) -> Result<Vec<CertificateDer<'static>>> {
+    let path = path.as_ref();
+    let mut reader = BufReader::new(File::open(path)?);
+    let certs = rustls_pemfile::certs(&mut reader).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(anyhow!("no certificate found in {}", path.display()));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: impl AsRef<Path>) -> Result<PrivateKeyDer<'static>> {
+    let path = path.as_ref();
+    let mut reader = BufReader::new(File::open(path)?);
+    rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| anyhow!("no private key found in {}", path.display()))
+}
@@ -0,0 +1,218 @@
+use crate::RespError;
+
+// split a line into arguments the way redis-cli does (sdssplitargs):
+// - arguments are separated by whitespace
+// - "double quoted" arguments support \n \r \t \b \a \\ \" and \xHH escapes
+// - 'single quoted' arguments only support \'
+// - a closing quote must be followed by whitespace or the end of the line
+pub fn split_args(line: &[u8]) -> Result<Vec<Vec<u8>>, RespError> {
+    let mut args = Vec::new();
+    let mut i = 0;
+    loop {
+        while i < line.len() && line[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i == line.len() {
+            return Ok(args);
+        }
+
+        let mut current = Vec::new();
+        let mut in_dq = false;
+        let mut in_sq = false;
+        loop {
+            let c = line.get(i).copied();
+            if in_dq {
+                match c {
+                    None => return Err(unbalanced()),
+                    Some(b'\\') if line.get(i + 1) == Some(&b'x') && hex_pair(line, i + 2) => {
+                        current.push(hex_value(line[i + 2]) * 16 + hex_value(line[i + 3]));
+                        i += 3;
+                    }
+                    Some(b'\\') if i + 1 < line.len() => {
+                        i += 1;
+                        current.push(match line[i] {
+                            b'n' => b'\n',
+                            b'r' => b'\r',
+                            b't' => b'\t',
+                            b'b' => 0x08,
+                            b'a' => 0x07,
+                            c => c,
+                        });
+                    }
+                    Some(b'"') => {
+                        if line.get(i + 1).is_some_and(|c| !c.is_ascii_whitespace()) {
+                            return Err(unbalanced());
+                        }
+                        i += 1;
+                        break;
+                    }
+                    Some(c) => current.push(c),
+                }
+            } else if in_sq {
+                match c {
+                    None => return Err(unbalanced()),
+                    Some(b'\\') if line.get(i + 1) == Some(&b'\'') => {
+                        current.push(b'\'');
+                        i += 1;
+                    }
+                    Some(b'\'') => {
+                        if line.get(i + 1).is_some_and(|c| !c.is_ascii_whitespace()) {
+                            return Err(unbalanced());
+                        }
+                        i += 1;
+                        break;
+                    }
+                    Some(c) => current.push(c),
+                }
+            } else {
+                match c {
+                    None => break,
+                    Some(c) if c.is_ascii_whitespace() => break,
+                    Some(b'"') => in_dq = true,
+                    Some(b'\'') => in_sq = true,
+                    Some(c) => current.push(c),
+                }
+            }
+            i += 1;
+        }
+        args.push(current);
+    }
+}
+
+// glob-style matching like redis stringmatchlen: supports *, ?, [abc], [^a], [a-z] and \x
+pub fn glob_match(pattern: &[u8], s: &[u8]) -> bool {
+    match pattern.first() {
+        None => s.is_empty(),
+        Some(b'*') => {
+            let rest = &pattern[1..];
+            (0..=s.len()).any(|i| glob_match(rest, &s[i..]))
+        }
+        Some(b'?') => !s.is_empty() && glob_match(&pattern[1..], &s[1..]),
+        Some(b'[') => {
+            let Some(&c) = s.first() else {
+                return false;
+            };
+            let mut i = 1;
+            let negate = pattern.get(i) == Some(&b'^');
+            if negate {
+                i += 1;
+            }
+            let mut matched = false;
+            while i < pattern.len() && pattern[i] != b']' {
+                if pattern[i] == b'\\' && i + 1 < pattern.len() {
+                    i += 1;
+                    matched |= pattern[i] == c;
+                } else if i + 2 < pattern.len() && pattern[i + 1] == b'-' && pattern[i + 2] != b']'
+                {
+                    let (lo, hi) = (
+                        pattern[i].min(pattern[i + 2]),
+                        pattern[i].max(pattern[i + 2]),
+                    );
+                    matched |= (lo..=hi).contains(&c);
+                    i += 2;
+                } else {
+                    matched |= pattern[i] == c;
+                }
+                i += 1;
+            }
+            // an unterminated class matches like redis: the rest of the pattern is the class
+            let rest = if i < pattern.len() {
+                &pattern[i + 1..]
+            } else {
+                &[][..]
+            };
+            matched != negate && glob_match(rest, &s[1..])
+        }
+        Some(b'\\') if pattern.len() > 1 => {
+            s.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &s[1..])
+        }
+        Some(&p) => s.first() == Some(&p) && glob_match(&pattern[1..], &s[1..]),
+    }
+}
+
+// quote binary data like sdscatrepr
+pub fn quote_bytes(data: &[u8]) -> String {
+    let mut s = String::with_capacity(data.len() + 2);
+    s.push('"');
+    for &c in data {
+        match c {
+            b'\\' => s.push_str("\\\\"),
+            b'"' => s.push_str("\\\""),
+            b'\n' => s.push_str("\\n"),
+            b'\r' => s.push_str("\\r"),
+            b'\t' => s.push_str("\\t"),
+            0x07 => s.push_str("\\a"),
+            0x08 => s.push_str("\\b"),
+            c if c.is_ascii_graphic() || c == b' ' => s.push(c as char),
+            c => s.push_str(&format!("\\x{:02x}", c)),
+        }
+    }
+    s.push('"');
+    s
+}
+
+fn unbalanced() -> RespError {
+    RespError::InvalidFrame("unbalanced quotes in request".to_string())
+}
+
+fn hex_pair(line: &[u8], i: usize) -> bool {
+    i + 1 < line.len() && line[i].is_ascii_hexdigit() && line[i + 1].is_ascii_hexdigit()
+}
+
+fn hex_value(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        _ => c - b'A' + 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_split_args() -> Result<()> {
+        assert_eq!(
+            split_args(b"  set a  b ")?,
+            vec![b"set".to_vec(), b"a".to_vec(), b"b".to_vec()]
+        );
+        assert_eq!(
+            split_args(br#"set a "hello world\n\x41" 'it\'s'"#)?,
+            vec![
+                b"set".to_vec(),
+                b"a".to_vec(),
+                b"hello world\nA".to_vec(),
+                b"it's".to_vec()
+            ]
+        );
+        assert_eq!(split_args(br#"set a """#)?[2], b"".to_vec());
+        assert!(split_args(b"").unwrap().is_empty());
+
+        assert!(split_args(br#"set a "hello"#).is_err());
+        assert!(split_args(br#"set a "hello"world"#).is_err());
+        assert!(split_args(b"set a 'hello").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_bytes() {
+        assert_eq!(quote_bytes(b"set"), r#""set""#);
+        assert_eq!(quote_bytes(b"a \"b\"\n\x01"), r#""a \"b\"\n\x01""#);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"*", b""));
+        assert!(glob_match(b"__keyspace@*__:*", b"__keyspace@0__:foo"));
+        assert!(glob_match(b"h?llo", b"hello"));
+        assert!(!glob_match(b"h?llo", b"hllo"));
+        assert!(glob_match(b"h[ae]llo", b"hallo"));
+        assert!(!glob_match(b"h[^e]llo", b"hello"));
+        assert!(glob_match(b"h[a-b]llo", b"hbllo"));
+        assert!(glob_match(b"h\\*llo", b"h*llo"));
+        assert!(!glob_match(b"h\\*llo", b"hello"));
+    }
+}
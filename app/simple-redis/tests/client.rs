@@ -0,0 +1,294 @@
+use anyhow::Result;
+use simple_redis::{
+    client::{Client, Pipeline, Pool},
+    network, Backend, RespArray, RespFrame, RespNull, RespPush, SimpleString,
+};
+use std::{net::SocketAddr, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+async fn start_server() -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let backend = Backend::new();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let backend = backend.clone();
+            tokio::spawn(network::stream_handler(stream, backend));
+        }
+    });
+    Ok(addr)
+}
+
+#[tokio::test]
+async fn client_typed_commands_should_work() -> Result<()> {
+    let addr = start_server().await?;
+    let mut client = Client::connect(addr).await?;
+
+    assert_eq!(client.echo("hello").await?, b"hello");
+
+    client.set("key", "value").await?;
+    assert_eq!(client.get("key").await?, Some(b"value".to_vec()));
+    assert_eq!(client.get("missing").await?, None);
+
+    // empty values must not be confused with missing keys
+    client.set("empty", "").await?;
+    assert_eq!(client.get("empty").await?, Some(vec![]));
+
+    client.hset("map", "a", "1").await?;
+    client.hset("map", "b", "2").await?;
+    assert_eq!(client.hget("map", "a").await?, Some(b"1".to_vec()));
+    let mut all = client.hgetall("map").await?;
+    all.sort();
+    assert_eq!(
+        all,
+        vec![
+            ("a".to_string(), b"1".to_vec()),
+            ("b".to_string(), b"2".to_vec())
+        ]
+    );
+
+    assert_eq!(client.del(&["key", "map", "missing"]).await?, 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn connections_should_select_databases_independently() -> Result<()> {
+    let addr = start_server().await?;
+    let mut a = Client::connect(addr).await?;
+    let mut b = Client::connect(addr).await?;
+
+    a.select(1).await?;
+    a.set("key", "db1").await?;
+    b.set("key", "db0").await?;
+    assert_eq!(a.get("key").await?, Some(b"db1".to_vec()));
+    assert_eq!(b.get("key").await?, Some(b"db0".to_vec()));
+
+    a.flushdb().await?;
+    assert_eq!(a.get("key").await?, None);
+    assert_eq!(b.get("key").await?, Some(b"db0".to_vec()));
+
+    assert!(a.select(16).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_pipeline_should_work() -> Result<()> {
+    let addr = start_server().await?;
+    let mut client = Client::connect(addr).await?;
+
+    let mut pipeline = Pipeline::new();
+    pipeline.set("a", "1").set("b", "2").get("a").get("b");
+    let replies = client.pipeline(pipeline).await?;
+    assert_eq!(
+        replies,
+        vec![
+            SimpleString::new("OK").into(),
+            SimpleString::new("OK").into(),
+            RespFrame::BulkString("1".into()),
+            RespFrame::BulkString("2".into()),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn large_pipelines_should_not_deadlock() -> Result<()> {
+    let addr = start_server().await?;
+    let mut client = Client::connect(addr).await?;
+
+    // far more than the socket buffers hold both ways, the server stops reading while the
+    // client isn't reading replies
+    let message = "x".repeat(16 * 1024);
+    let mut pipeline = Pipeline::new();
+    for _ in 0..2000 {
+        pipeline.echo(message.as_str());
+    }
+    let replies =
+        tokio::time::timeout(Duration::from_secs(10), client.pipeline(pipeline)).await??;
+    assert_eq!(replies.len(), 2000);
+    assert_eq!(client.echo("hi").await?, b"hi");
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_pool_should_reuse_connections() -> Result<()> {
+    let addr = start_server().await?;
+    let pool = Pool::new(addr.to_string(), 2);
+
+    {
+        let mut a = pool.get().await?;
+        let mut b = pool.get().await?;
+        a.set("k", "v").await?;
+        assert_eq!(b.get("k").await?, Some(b"v".to_vec()));
+    }
+    assert_eq!(pool.idle_count(), 2);
+
+    let mut c = pool.get().await?;
+    assert_eq!(c.get("k").await?, Some(b"v".to_vec()));
+    assert_eq!(pool.idle_count(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn keyspace_notifications_should_be_published() -> Result<()> {
+    let addr = start_server().await?;
+    let mut subscriber = Client::connect(addr).await?;
+    let mut client = Client::connect(addr).await?;
+
+    client.config_set("notify-keyspace-events", "Kg$").await?;
+    subscriber.subscribe(&["__keyspace@0__:key"]).await?;
+
+    client.set("key", "value").await?;
+    client.del(&["key"]).await?;
+    for event in ["set", "del"] {
+        let push = subscriber.next_push().await?;
+        assert_eq!(
+            push,
+            RespPush::with_kind(
+                "message",
+                [b"__keyspace@0__:key".into(), event.as_bytes().into()]
+            )
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn resp2_pubsub_arrays_should_be_read_as_pushes() -> Result<()> {
+    // a RESP2 server replies to SUBSCRIBE with arrays instead of pushes
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = [0; 1024];
+        let _ = stream.read(&mut buf).await.unwrap();
+        stream
+            .write_all(b"*3\r\n$9\r\nsubscribe\r\n$2\r\nch\r\n:1\r\n")
+            .await
+            .unwrap();
+        stream
+            .write_all(b"*3\r\n$7\r\nmessage\r\n$2\r\nch\r\n$2\r\nhi\r\n")
+            .await
+            .unwrap();
+        // keep the connection open until the client is done
+        let _ = stream.read(&mut buf).await;
+    });
+
+    let mut subscriber = Client::connect(addr).await?;
+    subscriber.subscribe(&["ch"]).await?;
+    let push = subscriber.next_push().await?;
+    assert_eq!(
+        push,
+        RespPush::with_kind("message", [b"ch".into(), b"hi".into()])
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_tracking_should_push_invalidations() -> Result<()> {
+    let addr = start_server().await?;
+    let mut cache = Client::connect(addr).await?;
+    let mut writer = Client::connect(addr).await?;
+
+    cache.client_tracking(true, false, &[]).await?;
+    writer.set("hot", "1").await?;
+    assert_eq!(cache.get("hot").await?, Some(b"1".to_vec()));
+
+    writer.set("hot", "2").await?;
+    let push = cache.next_push().await?;
+    assert_eq!(
+        push,
+        RespPush::with_kind("invalidate", [RespArray::new([b"hot".into()]).into()])
+    );
+
+    writer.flushdb().await?;
+    let push = cache.next_push().await?;
+    assert_eq!(push, RespPush::with_kind("invalidate", [RespNull.into()]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn maxclients_should_be_enforced() -> Result<()> {
+    let addr = start_server().await?;
+    let mut admin = Client::connect(addr).await?;
+    admin.config_set("maxclients", "1").await?;
+
+    let mut rejected = Client::connect(addr).await?;
+    let err = rejected.echo("hi").await.unwrap_err();
+    assert!(err.to_string().contains("max number of clients reached"));
+
+    drop(admin);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let mut accepted = Client::connect(addr).await?;
+    assert_eq!(accepted.echo("hi").await?, b"hi");
+    Ok(())
+}
+
+#[tokio::test]
+async fn idle_connections_should_be_closed() -> Result<()> {
+    let addr = start_server().await?;
+    let mut client = Client::connect(addr).await?;
+    client.config_set("timeout", "1").await?;
+
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert!(client.echo("hi").await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn monitor_should_stream_commands() -> Result<()> {
+    let addr = start_server().await?;
+    let mut monitor = Client::connect(addr).await?;
+    let mut client = Client::connect(addr).await?;
+
+    monitor.monitor().await?;
+    client.select(1).await?;
+    client.set("key", "a b").await?;
+
+    let line = monitor.next_monitor_line().await?;
+    assert!(line.ends_with(r#""select" "1""#), "{}", line);
+    let line = monitor.next_monitor_line().await?;
+    assert!(line.contains(" [1 "), "{}", line);
+    assert!(line.ends_with(r#""set" "key" "a b""#), "{}", line);
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_pool_should_discard_cancelled_requests() -> Result<()> {
+    // a server slow to reply, the request times out before the reply is read
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0; 1024];
+                while let Ok(n) = stream.read(&mut buf).await {
+                    if n == 0 {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    stream.write_all(b"+stale\r\n").await.unwrap();
+                }
+            });
+        }
+    });
+
+    let pool = Pool::new(addr.to_string(), 1);
+    {
+        let mut client = pool.get().await?;
+        let get = client.get("key");
+        assert!(tokio::time::timeout(Duration::from_millis(10), get)
+            .await
+            .is_err());
+        assert!(client.is_broken());
+    }
+    // the stale reply would otherwise be read as the reply to the next request
+    assert_eq!(pool.idle_count(), 0);
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use anyhow::Result;
+use simple_redis::{
+    client::{command, Client, ClusterClient},
+    cluster, key_hash_slot, network, Backend, RespFrame, SimpleError, SimpleString,
+};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+async fn start_node() -> Result<(String, Backend)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+
+    let backend = Backend::new();
+    backend.enable_cluster("127.0.0.1", port);
+    tokio::spawn(cluster::gossip(backend.clone(), Duration::from_millis(20)));
+    let node = backend.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(network::stream_handler(stream, node.clone()));
+        }
+    });
+    Ok((format!("127.0.0.1:{}", port), backend))
+}
+
+async fn cluster_cmd(addr: &str, args: &[&str]) -> Result<RespFrame> {
+    let mut client = Client::connect(addr).await?;
+    Ok(client
+        .execute(command("cluster", args.iter().map(|&arg| arg.into())))
+        .await?)
+}
+
+// three nodes splitting the slots evenly, like `redis-cli --cluster create`
+async fn start_cluster() -> Result<Vec<(String, Backend)>> {
+    let mut nodes = Vec::new();
+    for range in [["0", "5460"], ["5461", "10922"], ["10923", "16383"]] {
+        let (addr, backend) = start_node().await?;
+        cluster_cmd(&addr, &["addslotsrange", range[0], range[1]]).await?;
+        nodes.push((addr, backend));
+    }
+    for (addr, _) in &nodes[1..] {
+        let (ip, port) = addr.split_once(':').unwrap();
+        cluster_cmd(&nodes[0].0, &["meet", ip, port]).await?;
+    }
+
+    // wait for every node to learn the whole slot map
+    for _ in 0..100 {
+        let mut ok = true;
+        for (addr, _) in &nodes {
+            let info = cluster_cmd(addr, &["info"]).await?;
+            ok &= matches!(info, RespFrame::BulkString(ref s)
+                if String::from_utf8_lossy(s).contains("cluster_state:ok")
+                    && String::from_utf8_lossy(s).contains("cluster_known_nodes:3"));
+        }
+        if ok {
+            return Ok(nodes);
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    anyhow::bail!("cluster didn't converge")
+}
+
+fn id(backend: &Backend) -> String {
+    backend.cluster().unwrap().myself().id.clone()
+}
+
+#[tokio::test]
+async fn cluster_should_redirect_to_the_slot_owner() -> Result<()> {
+    let nodes = start_cluster().await?;
+
+    let RespFrame::Array(slots) = cluster_cmd(&nodes[1].0, &["slots"]).await? else {
+        panic!("expected an array");
+    };
+    assert_eq!(slots.len(), 3);
+
+    // "foo" hashes to slot 12182, served by the third node
+    let mut client = Client::connect(&nodes[0].0).await?;
+    let reply = client.execute(command("get", ["foo".into()])).await?;
+    assert_eq!(
+        reply,
+        SimpleError::new(format!("MOVED 12182 {}", nodes[2].0)).into()
+    );
+    let reply = client
+        .execute(command("del", ["foo".into(), "bar".into()]))
+        .await?;
+    assert!(matches!(reply, RespFrame::Error(ref e) if e.starts_with("CROSSSLOT")));
+
+    let mut cluster = ClusterClient::connect(nodes[0].0.as_str()).await?;
+    for i in 0..50 {
+        cluster.set(&format!("key:{}", i), &i.to_string()).await?;
+    }
+    for i in 0..50 {
+        let value = cluster.get(&format!("key:{}", i)).await?;
+        assert_eq!(value, Some(i.to_string().into_bytes()));
+    }
+    // the keys are spread over all the nodes
+    for (_, backend) in &nodes {
+        assert!(!backend.keyspace().is_empty());
+    }
+    Ok(())
+}
+
+// MIGRATE blocks its worker while the target, on the same runtime, answers
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn cluster_should_migrate_a_slot_with_ask() -> Result<()> {
+    let nodes = start_cluster().await?;
+    let (source, target) = (&nodes[2], &nodes[0]);
+    let slot = key_hash_slot(b"foo").to_string();
+
+    let mut cluster = ClusterClient::connect(target.0.as_str()).await?;
+    cluster.set("foo", "1").await?;
+    cluster.set("{foo}bar", "2").await?;
+
+    let (source_id, target_id) = (id(&source.1), id(&target.1));
+    cluster_cmd(&target.0, &["setslot", &slot, "importing", &source_id]).await?;
+    cluster_cmd(&source.0, &["setslot", &slot, "migrating", &target_id]).await?;
+
+    // move "foo" by hand: keys not found on the source are asked to the target
+    let mut client = Client::connect(&target.0).await?;
+    client.execute(command("asking", [])).await?;
+    client
+        .execute(command("set", ["foo".into(), "1".into()]))
+        .await?;
+    let mut client = Client::connect(&source.0).await?;
+    client.execute(command("del", ["foo".into()])).await?;
+    let reply = client.execute(command("get", ["foo".into()])).await?;
+    assert_eq!(
+        reply,
+        SimpleError::new(format!("ASK {} {}", slot, target.0)).into()
+    );
+    // without ASKING the target still redirects to the owner
+    let mut client = Client::connect(&target.0).await?;
+    let reply = client.execute(command("get", ["foo".into()])).await?;
+    assert!(matches!(reply, RespFrame::Error(ref e) if e.starts_with("MOVED")));
+
+    assert_eq!(cluster.get("foo").await?, Some(b"1".to_vec()));
+    assert_eq!(cluster.get("{foo}bar").await?, Some(b"2".to_vec()));
+
+    // finish the migration of the remaining key with MIGRATE and hand the slot over
+    let (ip, port) = target.0.split_once(':').unwrap();
+    let mut source_client = Client::connect(&source.0).await?;
+    let args = [ip, port, "{foo}bar", "0", "1000"];
+    let reply = source_client
+        .execute(command("migrate", args.into_iter().map(|arg| arg.into())))
+        .await?;
+    assert_eq!(reply, SimpleString::new("OK").into());
+    assert_eq!(source.1.keyspace(), vec![]);
+    cluster_cmd(&target.0, &["setslot", &slot, "node", &target_id]).await?;
+    cluster_cmd(&source.0, &["setslot", &slot, "node", &target_id]).await?;
+
+    let mut client = Client::connect(&source.0).await?;
+    let reply = client.execute(command("get", ["foo".into()])).await?;
+    assert_eq!(
+        reply,
+        SimpleError::new(format!("MOVED {} {}", slot, target.0)).into()
+    );
+    assert_eq!(cluster.get("{foo}bar").await?, Some(b"2".to_vec()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn gossip_should_not_wait_for_an_unresponsive_peer() -> Result<()> {
+    // accepts connections and never replies
+    let silent = TcpListener::bind("127.0.0.1:0").await?;
+    let silent_port = silent.local_addr()?.port().to_string();
+    tokio::spawn(async move {
+        let mut streams = Vec::new();
+        while let Ok((stream, _)) = silent.accept().await {
+            streams.push(stream);
+        }
+    });
+
+    let (a, backend) = start_node().await?;
+    let (b, _) = start_node().await?;
+    cluster_cmd(&a, &["meet", "127.0.0.1", &silent_port]).await?;
+    let (ip, port) = b.split_once(':').unwrap();
+    cluster_cmd(&a, &["meet", ip, port]).await?;
+
+    for _ in 0..50 {
+        if backend.cluster().unwrap().peers().len() == 2
+            && matches!(cluster_cmd(&a, &["info"]).await?, RespFrame::BulkString(ref s)
+                if String::from_utf8_lossy(s).contains("cluster_known_nodes:2"))
+        {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    anyhow::bail!("the node wasn't learnt")
+}
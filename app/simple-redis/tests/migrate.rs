@@ -0,0 +1,117 @@
+use anyhow::Result;
+use simple_redis::{
+    client::{command, Client},
+    network, Backend, RespFrame, SimpleString,
+};
+use std::{net::SocketAddr, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+async fn start_server() -> Result<(SocketAddr, Backend)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let backend = Backend::new();
+    let server = backend.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(network::stream_handler(stream, server.clone()));
+        }
+    });
+    Ok((addr, backend))
+}
+
+async fn migrate(client: &mut Client, target: SocketAddr, args: &[&str]) -> Result<RespFrame> {
+    let port = target.port().to_string();
+    let mut all = vec!["127.0.0.1", port.as_str()];
+    all.extend(args);
+    Ok(client
+        .execute(command("migrate", all.into_iter().map(|arg| arg.into())))
+        .await?)
+}
+
+// the target is on the same single threaded runtime: MIGRATE must not block it
+#[tokio::test]
+async fn migrate_should_move_keys_between_instances() -> Result<()> {
+    let (source, _) = start_server().await?;
+    let (target, target_backend) = start_server().await?;
+    let mut client = Client::connect(source).await?;
+    client.set("a", "1").await?;
+    client.hset("h", "f", "2").await?;
+    client.set("c", "3").await?;
+
+    let ok = RespFrame::from(SimpleString::new("OK"));
+    assert_eq!(migrate(&mut client, target, &["a", "3", "1000"]).await?, ok);
+    assert_eq!(client.get("a").await?, None);
+    assert_eq!(
+        migrate(
+            &mut client,
+            target,
+            &["", "3", "1000", "COPY", "KEYS", "h", "missing"]
+        )
+        .await?,
+        ok
+    );
+    assert_eq!(client.hget("h", "f").await?, Some(b"2".to_vec()));
+    assert_eq!(
+        migrate(&mut client, target, &["missing", "0", "1000"]).await?,
+        SimpleString::new("NOKEY").into()
+    );
+
+    let mut remote = Client::connect(target).await?;
+    remote.select(3).await?;
+    assert_eq!(remote.get("a").await?, Some(b"1".to_vec()));
+    assert_eq!(remote.hget("h", "f").await?, Some(b"2".to_vec()));
+
+    // an existing key is only overwritten with REPLACE, and stays on the source otherwise
+    remote.set("c", "old").await?;
+    let reply = migrate(&mut client, target, &["c", "3", "1000"]).await?;
+    assert!(matches!(reply, RespFrame::Error(ref e) if e.contains("BUSYKEY")));
+    assert_eq!(client.get("c").await?, Some(b"3".to_vec()));
+    assert_eq!(
+        migrate(&mut client, target, &["c", "3", "1000", "REPLACE"]).await?,
+        ok
+    );
+    assert_eq!(remote.get("c").await?, Some(b"3".to_vec()));
+    assert_eq!(target_backend.keyspace().len(), 1);
+
+    // nobody listens there anymore
+    let closed = TcpListener::bind("127.0.0.1:0").await?.local_addr()?;
+    client.set("d", "4").await?;
+    let reply = migrate(&mut client, closed, &["d", "0", "100"]).await?;
+    assert!(matches!(reply, RespFrame::Error(ref e) if e.starts_with("IOERR")));
+    assert_eq!(client.get("d").await?, Some(b"4".to_vec()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn migrate_should_keep_a_key_written_while_it_is_sent() -> Result<()> {
+    let (source, _) = start_server().await?;
+    // a target that takes its time to answer SELECT and RESTORE
+    let target = TcpListener::bind("127.0.0.1:0").await?;
+    let target_addr = target.local_addr()?;
+    tokio::spawn(async move {
+        let (mut stream, _) = target.accept().await.unwrap();
+        let mut buf = [0; 4096];
+        let _ = stream.read(&mut buf).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        stream.write_all(b"+OK\r\n+OK\r\n").await.unwrap();
+        let _ = stream.read(&mut buf).await;
+    });
+
+    let mut client = Client::connect(source).await?;
+    client.set("a", "1").await?;
+    let migrating =
+        tokio::spawn(async move { migrate(&mut client, target_addr, &["a", "0", "1000"]).await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    // the server keeps serving other clients meanwhile
+    let mut other = Client::connect(source).await?;
+    other.set("a", "2").await?;
+
+    assert_eq!(migrating.await??, SimpleString::new("OK").into());
+    assert_eq!(other.get("a").await?, Some(b"2".to_vec()));
+    Ok(())
+}
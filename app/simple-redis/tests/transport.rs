@@ -0,0 +1,209 @@
+use anyhow::Result;
+use futures::SinkExt;
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa};
+use simple_redis::{
+    client::command, network, network::RespFrameCodec, tls::tls_acceptor, Backend, RespFrame,
+    SimpleString,
+};
+use std::{io::Cursor, sync::Arc};
+use tempfile::TempDir;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream, UnixListener, UnixStream},
+};
+use tokio_rustls::{
+    rustls::{pki_types::ServerName, ClientConfig, RootCertStore},
+    TlsConnector,
+};
+use tokio_stream::StreamExt;
+use tokio_util::codec::Framed;
+
+async fn echo<S>(stream: S) -> Result<RespFrame>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut framed = Framed::new(stream, RespFrameCodec);
+    framed
+        .send(command("echo", ["hello".into()]).into())
+        .await?;
+    framed
+        .next()
+        .await
+        .ok_or_else(|| anyhow::anyhow!("connection closed"))?
+}
+
+#[tokio::test]
+async fn unix_socket_should_work() -> Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("redis.sock");
+    let listener = UnixListener::bind(&path)?;
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        network::stream_handler(stream, Backend::new()).await
+    });
+
+    let reply = echo(UnixStream::connect(&path).await?).await?;
+    assert_eq!(reply, RespFrame::BulkString("hello".into()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn shutdown_should_close_the_connection_without_reply() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let backend = Backend::new();
+    let server = backend.clone();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        network::stream_handler(stream, server).await
+    });
+
+    let mut framed = Framed::new(TcpStream::connect(addr).await?, RespFrameCodec);
+    // a failed SHUTDOWN is answered
+    framed
+        .send(command("shutdown", ["save".into()]).into())
+        .await?;
+    assert!(matches!(framed.next().await, Some(Ok(RespFrame::Error(_)))));
+    framed
+        .send(command("shutdown", ["nosave".into()]).into())
+        .await?;
+    assert!(framed.next().await.is_none());
+    assert!(backend.is_shutting_down());
+    Ok(())
+}
+
+#[tokio::test]
+async fn inline_commands_should_work() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        network::stream_handler(stream, Backend::new()).await
+    });
+
+    let mut stream = TcpStream::connect(addr).await?;
+    // inline and RESP commands can be mixed, empty lines are skipped
+    stream
+        .write_all(
+            b"SET a \"hello\\x20world\"\r\n\r\n*2\r\n$3\r\nget\r\n$1\r\na\r\necho 'it\\'s'\n",
+        )
+        .await?;
+    let mut framed = Framed::new(stream, RespFrameCodec);
+    let mut next = async || -> Result<RespFrame> {
+        framed
+            .next()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("connection closed"))?
+    };
+    assert_eq!(next().await?, SimpleString::new("OK").into());
+    assert_eq!(next().await?, RespFrame::BulkString("hello world".into()));
+    assert_eq!(next().await?, RespFrame::BulkString("it's".into()));
+
+    // unbalanced quotes are a protocol error, the server hangs up
+    framed.get_mut().write_all(b"echo \"oops\r\n").await?;
+    assert!(framed.next().await.is_none());
+    Ok(())
+}
+
+struct Pki {
+    dir: TempDir,
+    ca: Certificate,
+}
+
+impl Pki {
+    fn new() -> Result<Self> {
+        let mut params = CertificateParams::new(vec![]);
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = Certificate::from_params(params)?;
+        let pki = Pki {
+            dir: TempDir::new()?,
+            ca,
+        };
+        std::fs::write(pki.dir.path().join("ca.crt"), pki.ca.serialize_pem()?)?;
+        Ok(pki)
+    }
+
+    // issue a certificate signed by the ca, written to <name>.crt and <name>.key
+    fn issue(&self, name: &str) -> Result<(String, String)> {
+        let cert = Certificate::from_params(CertificateParams::new(vec!["localhost".into()]))?;
+        let cert_pem = cert.serialize_pem_with_signer(&self.ca)?;
+        let key_pem = cert.serialize_private_key_pem();
+        std::fs::write(self.path(&format!("{}.crt", name)), &cert_pem)?;
+        std::fs::write(self.path(&format!("{}.key", name)), &key_pem)?;
+        Ok((cert_pem, key_pem))
+    }
+
+    fn path(&self, file: &str) -> std::path::PathBuf {
+        self.dir.path().join(file)
+    }
+
+    fn client_config(&self, client: Option<(String, String)>) -> Result<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        for cert in rustls_pemfile::certs(&mut Cursor::new(self.ca.serialize_pem()?)) {
+            roots.add(cert?)?;
+        }
+        let builder = ClientConfig::builder().with_root_certificates(roots);
+        let config = match client {
+            Some((cert, key)) => {
+                let certs =
+                    rustls_pemfile::certs(&mut Cursor::new(cert)).collect::<Result<Vec<_>, _>>()?;
+                let key = rustls_pemfile::private_key(&mut Cursor::new(key))?.unwrap();
+                builder.with_client_auth_cert(certs, key)?
+            }
+            None => builder.with_no_client_auth(),
+        };
+        Ok(config)
+    }
+}
+
+async fn start_tls_server(pki: &Pki, auth_clients: bool) -> Result<std::net::SocketAddr> {
+    let ca = auth_clients.then(|| pki.path("ca.crt"));
+    let acceptor = tls_acceptor(pki.path("server.crt"), pki.path("server.key"), ca)?;
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                if let Ok(stream) = acceptor.accept(stream).await {
+                    let _ = network::stream_handler(stream, Backend::new()).await;
+                }
+            });
+        }
+    });
+    Ok(addr)
+}
+
+async fn tls_echo(addr: std::net::SocketAddr, config: ClientConfig) -> Result<RespFrame> {
+    let connector = TlsConnector::from(Arc::new(config));
+    let stream = TcpStream::connect(addr).await?;
+    let stream = connector
+        .connect(ServerName::try_from("localhost")?, stream)
+        .await?;
+    echo(stream).await
+}
+
+#[tokio::test]
+async fn tls_should_work() -> Result<()> {
+    let pki = Pki::new()?;
+    pki.issue("server")?;
+    let addr = start_tls_server(&pki, false).await?;
+
+    let reply = tls_echo(addr, pki.client_config(None)?).await?;
+    assert_eq!(reply, RespFrame::BulkString("hello".into()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn tls_should_require_client_certificate() -> Result<()> {
+    let pki = Pki::new()?;
+    pki.issue("server")?;
+    let client = pki.issue("client")?;
+    let addr = start_tls_server(&pki, true).await?;
+
+    assert!(tls_echo(addr, pki.client_config(None)?).await.is_err());
+    let reply = tls_echo(addr, pki.client_config(Some(client))?).await?;
+    assert_eq!(reply, RespFrame::BulkString("hello".into()));
+    Ok(())
+}